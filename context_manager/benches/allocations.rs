@@ -0,0 +1,89 @@
+//! Heap allocations performed by the wrapping of async bodies, compared with awaiting the bare body
+//!
+//! The wrapping pins the body in place, so no allocation is expected other than the ones of the hooks,
+//! including for the nameable future returned by [`SyncWrapContext::run_future`].
+//!
+//! Run via `cargo bench --bench allocations`.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::future::Future;
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+const ITERATIONS: usize = 10_000;
+
+/// Allocator counting the allocations, delegating them to the system allocator
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+struct NoOp;
+impl SyncWrapContext<u64> for NoOp {
+    fn new() -> Self {
+        Self
+    }
+}
+impl AsyncWrapContext<u64> for NoOp {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+/// Body of the wrapped functions, holding a large state across an await point
+async fn body(value: u64) -> u64 {
+    let state = [value; 64];
+    futures::future::ready(()).await;
+    black_box(state).iter().sum()
+}
+
+fn allocations<F: Future<Output = u64>>(future: impl Fn(u64) -> F) -> usize {
+    // Warm up the executor, so that its lazily initialised state is not counted
+    futures::executor::block_on(future(0));
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for iteration in 0..ITERATIONS as u64 {
+        black_box(futures::executor::block_on(future(iteration)));
+    }
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+fn main() {
+    for (name, allocations) in [
+        ("bare body", allocations(body)),
+        (
+            "run_async",
+            allocations(|value| {
+                <NoOp as SyncWrapContext<_>>::run_async(CallerContext::new("bench"), body(value))
+            }),
+        ),
+        (
+            "run",
+            allocations(|value| {
+                <NoOp as AsyncWrapContext<_>>::run(CallerContext::new("bench"), body(value))
+            }),
+        ),
+        (
+            "run_future",
+            allocations(|value| {
+                <NoOp as SyncWrapContext<_>>::run_future(CallerContext::new("bench"), body(value))
+            }),
+        ),
+    ] {
+        println!("{name:>10}: {allocations} allocations over {ITERATIONS} iterations");
+    }
+}
@@ -0,0 +1,46 @@
+//! Cost of executing no-op hooks, compared with contexts opting out of them via `HAS_BEFORE`/`HAS_AFTER`
+//!
+//! Run via `cargo bench --bench hooks` (add `--profile dev` for a debug build, where the no-op calls are not inlined).
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use context_manager::{CallerContext, SyncWrapContext};
+
+const ITERATIONS: u32 = 1_000_000;
+
+struct NoOpHooks;
+impl SyncWrapContext<u64> for NoOpHooks {
+    fn new() -> Self {
+        Self
+    }
+}
+
+struct OptedOutHooks;
+impl SyncWrapContext<u64> for OptedOutHooks {
+    const HAS_BEFORE: bool = false;
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self
+    }
+}
+
+fn measure<C: SyncWrapContext<u64>>() -> Duration {
+    let start = Instant::now();
+    for iteration in 0..u64::from(ITERATIONS) {
+        black_box(C::run_sync(CallerContext::new("bench"), || {
+            black_box(iteration)
+        }));
+    }
+    start.elapsed()
+}
+
+fn main() {
+    for (name, elapsed) in [
+        ("no-op hooks", measure::<NoOpHooks>()),
+        ("opted out hooks", measure::<OptedOutHooks>()),
+    ] {
+        println!("{name:>16}: {:?}/iter", elapsed / ITERATIONS);
+    }
+}
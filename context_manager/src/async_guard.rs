@@ -0,0 +1,199 @@
+use core::fmt;
+use core::marker::PhantomData;
+
+use crate::platform::{self, Stopwatch};
+use crate::{AsyncWrapContext, CallerContext};
+
+/// Guard keeping an asynchronous context active until [`AsyncContextGuard::finish`] is awaited
+///
+/// It is the manual counterpart of [`AsyncWrapContext::run`], useful when the code to wrap
+/// cannot be expressed as a single future (ie. a resource acquired and released by different code paths).
+/// The guard is created via [`AsyncWrapContext::guard`], which awaits [`AsyncWrapContext::new`] and
+/// [`AsyncWrapContext::before`], and [`AsyncContextGuard::finish`] awaits [`AsyncWrapContext::after_timed`].
+///
+/// ```
+/// # use context_manager::{AsyncWrapContext, CallerContext};
+/// struct AsyncPrintDuration;
+/// impl<T> AsyncWrapContext<T> for AsyncPrintDuration {
+///   async fn new() -> Self { Self }
+/// }
+///
+/// # async fn foo() {
+/// let guard = AsyncPrintDuration::guard(CallerContext::new("manual")).await;
+/// let result = "async";
+/// guard.finish(&result).await;
+/// # }
+/// ```
+///
+/// **IMPORTANT**: as asynchronous [`Drop`] does not exist, dropping the guard without awaiting
+/// [`AsyncContextGuard::finish`] skips [`AsyncWrapContext::after_timed`] (while [`AsyncWrapContext::finally`]
+/// is still executed). Debug builds report such occurrences on the standard error.
+///
+/// Differently from [`AsyncWrapContext::run`], the guarded code is not tracked by
+/// [`current_fn_name`](crate::current_fn_name), as there is no body being polled by the context.
+#[must_use = "dropping the guard without awaiting `finish` skips the `after` hook"]
+pub struct AsyncContextGuard<C, T, I = ()>
+where
+    C: AsyncWrapContext<T, I>,
+{
+    context: Option<C>,
+    caller_context: CallerContext,
+    start: Stopwatch,
+    result: PhantomData<fn(&T, &I)>,
+}
+
+impl<C, T, I> AsyncContextGuard<C, T, I>
+where
+    C: AsyncWrapContext<T, I>,
+{
+    pub(crate) async fn enter(caller_context: CallerContext) -> Self {
+        let mut caller_context = C::build_caller_context(caller_context);
+        let context = C::new().await;
+        caller_context.map_fn_name(|fn_name| context.map_fn_name(fn_name));
+        caller_context.on_first_call(|caller_context| context.on_first_call(caller_context));
+        if C::HAS_BEFORE {
+            context.before(&mut caller_context).await;
+        }
+        Self {
+            context: Some(context),
+            caller_context,
+            start: Stopwatch::start_async::<C, T, I>(),
+            result: PhantomData,
+        }
+    }
+
+    /// Context of the caller, as enriched by [`AsyncWrapContext::before`]
+    #[must_use]
+    pub const fn caller_context(&self) -> &CallerContext {
+        &self.caller_context
+    }
+
+    /// Close the guard, executing [`AsyncWrapContext::after_timed`] with the provided result
+    ///
+    /// The elapsed time is measured from the end of [`AsyncWrapContext::before`], and
+    /// [`AsyncWrapContext::on_slow`] is executed first if it exceeds [`AsyncWrapContext::slow_threshold`].
+    pub async fn finish(mut self, result: &T) {
+        if let Some(context) = self.context.take() {
+            let elapsed = self.start.elapsed();
+            context.finally(&self.caller_context);
+            if elapsed > context.slow_threshold() {
+                context.on_slow(&self.caller_context, elapsed).await;
+            }
+            if C::HAS_AFTER {
+                context
+                    .after_timed(&self.caller_context, result, elapsed)
+                    .await;
+            }
+        }
+    }
+}
+
+impl<C, T, I> fmt::Debug for AsyncContextGuard<C, T, I>
+where
+    C: AsyncWrapContext<T, I>,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsyncContextGuard")
+            .field("context", &core::any::type_name::<C>())
+            .field("caller_context", &self.caller_context)
+            .field("finished", &self.context.is_none())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<C, T, I> Drop for AsyncContextGuard<C, T, I>
+where
+    C: AsyncWrapContext<T, I>,
+{
+    fn drop(&mut self) {
+        // The context is taken by `finish`, so it is still present only if the guard is dropped early
+        if let Some(context) = self.context.take() {
+            #[cfg(feature = "std")]
+            if cfg!(debug_assertions) && !std::thread::panicking() {
+                report_unfinished::<C>(&self.caller_context);
+            }
+            if platform::panicking() {
+                context.on_poisoned(&self.caller_context);
+            }
+            context.finally(&self.caller_context);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+std::thread_local! {
+    /// Number of guards reported as dropped without awaiting `finish` on the current thread
+    static UNFINISHED_REPORTS: core::cell::Cell<usize> = const { core::cell::Cell::new(0) };
+}
+
+/// Report, on the standard error, a guard dropped without awaiting [`AsyncContextGuard::finish`]
+#[cfg(feature = "std")]
+fn report_unfinished<C>(caller_context: &CallerContext) {
+    #[cfg(test)]
+    UNFINISHED_REPORTS.set(UNFINISHED_REPORTS.get() + 1);
+    eprintln!(
+        "AsyncContextGuard<{}> of {} dropped without awaiting `finish`: the `after` hook did not run",
+        core::any::type_name::<C>(),
+        caller_context.fn_name(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::{AsyncWrapContext, CallerContext};
+
+    static BEFORE: AtomicUsize = AtomicUsize::new(0);
+    static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+    struct Counting;
+    impl AsyncWrapContext<usize> for Counting {
+        async fn new() -> Self {
+            Self
+        }
+
+        async fn before(&self, caller_context: &mut CallerContext) {
+            BEFORE.fetch_add(1, Ordering::Relaxed);
+            caller_context.insert_extension(10_usize);
+        }
+
+        async fn after(self, caller_context: &CallerContext, result: &usize) {
+            AFTER.fetch_add(
+                *result + caller_context.extension::<usize>().unwrap(),
+                Ordering::Relaxed,
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn guard_lifecycle() {
+        let guard = Counting::guard(CallerContext::new("guarded")).await;
+        assert_eq!(BEFORE.load(Ordering::Relaxed), 1);
+        assert_eq!(guard.caller_context().extension::<usize>(), Some(&10));
+        assert_eq!(AFTER.load(Ordering::Relaxed), 0);
+
+        guard.finish(&32).await;
+        assert_eq!(AFTER.load(Ordering::Relaxed), 42);
+
+        // Dropping the guard without finishing it skips the after hook
+        drop(Counting::guard(CallerContext::new("dropped")).await);
+        assert_eq!(BEFORE.load(Ordering::Relaxed), 2);
+        assert_eq!(AFTER.load(Ordering::Relaxed), 42);
+    }
+
+    #[cfg(all(debug_assertions, feature = "std"))]
+    #[tokio::test(flavor = "current_thread")]
+    async fn unfinished_guards_are_reported() {
+        use super::UNFINISHED_REPORTS;
+
+        Counting::guard(CallerContext::new("finished"))
+            .await
+            .finish(&0)
+            .await;
+        assert_eq!(UNFINISHED_REPORTS.get(), 0);
+
+        drop(Counting::guard(CallerContext::new("dropped")).await);
+        assert_eq!(UNFINISHED_REPORTS.get(), 1);
+    }
+}
@@ -0,0 +1,70 @@
+use core::future::Future;
+
+use crate::CallerContext;
+
+/// Synchronous block of code executed by the `run_sync*` functions of [`SyncWrapContext`](crate::SyncWrapContext)
+///
+/// It is implemented by the closures not taking any argument and, via [`WithCallerContext`], by the closures
+/// taking the caller context provided to the hooks of the context.
+pub trait SyncBlock<T> {
+    /// Execute the block, with the caller context as enriched by the hooks executed before it
+    fn call(self, caller_context: &CallerContext) -> T;
+}
+
+impl<T, F: FnOnce() -> T> SyncBlock<T> for F {
+    fn call(self, _: &CallerContext) -> T {
+        self()
+    }
+}
+
+/// Asynchronous block of code executed by the `run_async*` functions of [`SyncWrapContext`](crate::SyncWrapContext)
+/// and by the `run*` functions of [`AsyncWrapContext`](crate::AsyncWrapContext)
+///
+/// It is implemented by the futures and, via [`WithCallerContext`], by the async closures taking the caller context
+/// provided to the hooks of the context.
+pub trait AsyncBlock<T> {
+    /// Create the future to execute, with the caller context as enriched by the hooks executed before it
+    fn call(self, caller_context: &CallerContext) -> impl Future<Output = T>;
+}
+
+impl<F: Future> AsyncBlock<F::Output> for F {
+    fn call(self, _: &CallerContext) -> impl Future<Output = F::Output> {
+        self
+    }
+}
+
+/// Block of code borrowing the caller context provided to the hooks of the context
+///
+/// The caller context is the one enriched by the hooks, so the block can read the extensions inserted by
+/// [`SyncWrapContext::before`](crate::SyncWrapContext::before) or the current [`CallerContext::attempt`].
+/// This is used by the `bind = "..."` macro argument.
+/// ```
+/// # use context_manager::{CallerContext, SyncWrapContext, WithCallerContext};
+/// struct Tag;
+/// impl<T> SyncWrapContext<T> for Tag {
+///     fn new() -> Self { Self }
+///     fn before(&self, caller_context: &mut CallerContext) {
+///         caller_context.insert_extension("tagged");
+///     }
+/// }
+///
+/// let tag = Tag::run_sync(
+///     CallerContext::new("manual"),
+///     WithCallerContext(|caller_context: &CallerContext| caller_context.extension::<&str>().copied()),
+/// );
+/// assert_eq!(tag, Some("tagged"));
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct WithCallerContext<F>(pub F);
+
+impl<T, F: FnOnce(&CallerContext) -> T> SyncBlock<T> for WithCallerContext<F> {
+    fn call(self, caller_context: &CallerContext) -> T {
+        (self.0)(caller_context)
+    }
+}
+
+impl<T, F: AsyncFnOnce(&CallerContext) -> T> AsyncBlock<T> for WithCallerContext<F> {
+    fn call(self, caller_context: &CallerContext) -> impl Future<Output = T> {
+        (self.0)(caller_context)
+    }
+}
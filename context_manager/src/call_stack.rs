@@ -0,0 +1,209 @@
+#[cfg(feature = "std")]
+use alloc::borrow::Cow;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+use core::any::type_name;
+#[cfg(feature = "std")]
+use core::cell::RefCell;
+#[cfg(feature = "std")]
+use core::future::poll_fn;
+use core::future::Future;
+#[cfg(feature = "std")]
+use core::pin::pin;
+
+use crate::CallerContext;
+
+/// Wrapped function whose body is currently executing
+///
+/// Frames are compared by the name of the wrapped function and by the type of the context wrapping it.
+#[derive(Debug, Clone)]
+pub(crate) struct Frame {
+    /// Name of the wrapped function
+    pub(crate) fn_name: &'static str,
+    /// Name of the wrapped function as emitted by the contexts (refer to [`CallerContext::emitted_fn_name`]),
+    /// if it differs from the name of the wrapped function
+    #[cfg(feature = "std")]
+    pub(crate) emitted_fn_name: Option<Arc<str>>,
+    /// Name of the type of the context wrapping the function
+    pub(crate) context_type: &'static str,
+}
+
+impl Frame {
+    /// Frame of the function described by `caller_context` wrapped by the context `C`
+    pub(crate) fn new<C>(caller_context: &CallerContext) -> Self {
+        Self {
+            fn_name: caller_context.fn_name(),
+            #[cfg(feature = "std")]
+            emitted_fn_name: (caller_context.emitted_fn_name() != caller_context.fn_name())
+                .then(|| caller_context.emitted_fn_name().into()),
+            context_type: type_name::<C>(),
+        }
+    }
+
+    /// Name of the wrapped function as emitted by the contexts (refer to [`CallerContext::emitted_fn_name`])
+    #[cfg(feature = "std")]
+    fn emitted_fn_name(&self) -> Cow<'static, str> {
+        self.emitted_fn_name
+            .as_deref()
+            .map_or(Cow::Borrowed(self.fn_name), |name| Cow::Owned(name.into()))
+    }
+}
+
+impl PartialEq for Frame {
+    fn eq(&self, other: &Self) -> bool {
+        self.fn_name == other.fn_name && self.context_type == other.context_type
+    }
+}
+
+impl Eq for Frame {}
+
+#[cfg(feature = "std")]
+thread_local! {
+    /// Stack of the wrapped functions whose body is currently executing on this thread
+    static CALL_STACK: RefCell<Vec<Frame>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Name of the innermost wrapped function whose body is currently executing.
+///
+/// Returns `None` if invoked outside of any wrapped body.
+///
+/// ```
+/// # use context_manager::{current_fn_name, wrap, SyncWrapContext};
+/// # struct PrintDuration;
+/// # impl<T> SyncWrapContext<T> for PrintDuration { fn new() -> Self { Self } }
+/// #[wrap(PrintDuration)]
+/// fn foo() -> Option<&'static str> {
+///     current_fn_name()
+/// }
+///
+/// assert_eq!(foo(), Some("foo"));
+/// assert_eq!(current_fn_name(), None);
+/// ```
+///
+/// The stack is tracked per thread while executing synchronous bodies, and per poll
+/// while executing asynchronous bodies. This means that the name is correctly reported
+/// even if the runtime moves the future across threads between polls.
+#[cfg(feature = "std")]
+#[must_use]
+pub fn current_fn_name() -> Option<&'static str> {
+    CALL_STACK.with_borrow(|stack| stack.last().map(|frame| frame.fn_name))
+}
+
+/// Emitted names (refer to [`CallerContext::emitted_fn_name`]) of the wrapped functions whose body
+/// is currently executing, from the outermost to the innermost
+#[cfg(feature = "std")]
+pub(crate) fn emitted_fn_names() -> Vec<Cow<'static, str>> {
+    CALL_STACK.with_borrow(|stack| stack.iter().map(Frame::emitted_fn_name).collect())
+}
+
+/// Check whether the body of `frame` is already executing on the current thread
+///
+/// Without the `std` feature the call stack is not tracked, so it is always `false`.
+pub(crate) fn is_active(frame: &Frame) -> bool {
+    #[cfg(feature = "std")]
+    return CALL_STACK.with_borrow(|stack| stack.contains(frame));
+    #[cfg(not(feature = "std"))]
+    return {
+        let _ = frame;
+        false
+    };
+}
+
+/// Guard keeping a function on the call stack until dropped.
+///
+/// Relying on [`Drop`] ensures that the stack is consistent even if the body panics.
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub(crate) struct CallStackGuard(());
+
+#[cfg(feature = "std")]
+impl CallStackGuard {
+    pub(crate) fn enter(frame: Frame) -> Self {
+        CALL_STACK.with_borrow_mut(|stack| stack.push(frame));
+        Self(())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Drop for CallStackGuard {
+    fn drop(&mut self) {
+        CALL_STACK.with_borrow_mut(Vec::pop);
+    }
+}
+
+/// Execute the synchronous `block` while keeping `frame` on the call stack
+#[cfg_attr(not(feature = "std"), allow(clippy::needless_pass_by_value))]
+pub(crate) fn in_call_stack_sync<T>(frame: Frame, block: impl FnOnce() -> T) -> T {
+    #[cfg(feature = "std")]
+    let _guard = CallStackGuard::enter(frame);
+    #[cfg(not(feature = "std"))]
+    let _ = frame;
+    block()
+}
+
+/// Await `block` while keeping `frame` on the call stack during each poll
+pub(crate) async fn in_call_stack_async<T>(frame: Frame, block: impl Future<Output = T>) -> T {
+    #[cfg(feature = "std")]
+    return {
+        let mut block = pin!(block);
+        poll_fn(|cx| {
+            let _guard = CallStackGuard::enter(frame.clone());
+            block.as_mut().poll(cx)
+        })
+        .await
+    };
+    #[cfg(not(feature = "std"))]
+    return {
+        let _ = frame;
+        block.await
+    };
+}
+
+// The tests rely on the call stack and on the timing, which require the `std` feature
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{current_fn_name, in_call_stack_async, in_call_stack_sync, is_active, Frame};
+
+    const fn frame(fn_name: &'static str) -> Frame {
+        Frame {
+            fn_name,
+            emitted_fn_name: None,
+            context_type: "Context",
+        }
+    }
+
+    #[test]
+    fn nested_sync_calls() {
+        assert_eq!(current_fn_name(), None);
+        in_call_stack_sync(frame("outer"), || {
+            assert_eq!(current_fn_name(), Some("outer"));
+            assert!(is_active(&frame("outer")));
+            assert!(!is_active(&frame("inner")));
+            in_call_stack_sync(frame("inner"), || {
+                assert_eq!(current_fn_name(), Some("inner"));
+            });
+            assert_eq!(current_fn_name(), Some("outer"));
+        });
+        assert_eq!(current_fn_name(), None);
+    }
+
+    #[test]
+    fn stack_is_restored_on_panic() {
+        let result = std::panic::catch_unwind(|| {
+            in_call_stack_sync(frame("panicking"), || panic!("expected"));
+        });
+        assert!(result.is_err());
+        assert_eq!(current_fn_name(), None);
+    }
+
+    #[tokio::test]
+    async fn async_call_across_await_points() {
+        in_call_stack_async(frame("outer"), async {
+            assert_eq!(current_fn_name(), Some("outer"));
+            tokio::task::yield_now().await;
+            assert_eq!(current_fn_name(), Some("outer"));
+        })
+        .await;
+        assert_eq!(current_fn_name(), None);
+    }
+}
@@ -0,0 +1,374 @@
+use alloc::borrow::Cow;
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::any::Any;
+
+use crate::FirstCall;
+
+/// Context about the caller propagated into the context.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct CallerContext {
+    /// Name of the wrapped function
+    fn_name: &'static str,
+    /// Name of the wrapped function as emitted by the contexts (ie. in logs or in metrics)
+    emitted_fn_name: Cow<'static, str>,
+    /// Module path of the wrapped function (as reported by [`module_path!`])
+    module_path: Option<&'static str>,
+    /// File containing the wrapped function (as reported by [`file!`])
+    file: Option<&'static str>,
+    /// Line of the wrapped function (as reported by [`line!`])
+    line: Option<u32>,
+    /// Names of the arguments of the wrapped function
+    arg_names: &'static [&'static str],
+    /// Whether the wrapped function is async
+    is_async: bool,
+    /// Static key-value metadata of the wrapped function
+    meta: &'static [(&'static str, &'static str)],
+    /// Guard of the first call of the wrapped function
+    first_call: Option<&'static FirstCall>,
+    /// Attempt of the wrapped body being executed (starting from 1)
+    attempt: u32,
+    /// Extensions added by the contexts (at most one per type)
+    extensions: Vec<Box<dyn Any + Send + Sync>>,
+}
+
+impl CallerContext {
+    /// Create a new instance of the `CallerContext`
+    ///
+    /// The function, as all the `with_*` builders, is `const`, so it can be evaluated at compile time
+    /// (ie. to precompute per-function data in a `static`).
+    /// ```
+    /// # use context_manager::CallerContext;
+    /// static CALLER_CONTEXT: CallerContext = CallerContext::new("foo").with_arg_names(&["a", "b"]);
+    /// assert_eq!(CALLER_CONTEXT.fn_name(), "foo");
+    /// ```
+    #[must_use]
+    pub const fn new(fn_name: &'static str) -> Self {
+        Self {
+            fn_name,
+            emitted_fn_name: Cow::Borrowed(fn_name),
+            module_path: None,
+            file: None,
+            line: None,
+            arg_names: &[],
+            is_async: false,
+            meta: &[],
+            first_call: None,
+            attempt: 1,
+            extensions: Vec::new(),
+        }
+    }
+
+    /// Attach the location of the wrapped function to the `CallerContext`
+    ///
+    /// The [`wrap`](crate::wrap) and [`async_wrap`](crate::async_wrap) macros populate it automatically.
+    /// ```
+    /// # use context_manager::CallerContext;
+    /// let caller_context = CallerContext::new("manual").with_location(module_path!(), file!(), line!());
+    /// assert_eq!(caller_context.module_path(), Some(module_path!()));
+    /// ```
+    #[must_use]
+    pub const fn with_location(
+        mut self,
+        module_path: &'static str,
+        file: &'static str,
+        line: u32,
+    ) -> Self {
+        self.module_path = Some(module_path);
+        self.file = Some(file);
+        self.line = Some(line);
+        self
+    }
+
+    /// Attach the names of the arguments of the wrapped function to the `CallerContext`
+    ///
+    /// The [`wrap`](crate::wrap) and [`async_wrap`](crate::async_wrap) macros populate it automatically,
+    /// reporting the identifiers bound by each argument in declaration order (ie. `(a, b): (u8, u8)` binds `a` and `b`).
+    /// Wildcard patterns (`_`) do not bind any identifier, so they are not reported.
+    /// ```
+    /// # use context_manager::CallerContext;
+    /// let caller_context = CallerContext::new("manual").with_arg_names(&["self", "a", "b"]);
+    /// assert_eq!(caller_context.arg_names(), ["self", "a", "b"]);
+    /// ```
+    #[must_use]
+    pub const fn with_arg_names(mut self, arg_names: &'static [&'static str]) -> Self {
+        self.arg_names = arg_names;
+        self
+    }
+
+    /// Mark the wrapped function as async (or not) in the `CallerContext`
+    ///
+    /// The [`wrap`](crate::wrap) and [`async_wrap`](crate::async_wrap) macros populate it automatically.
+    /// ```
+    /// # use context_manager::CallerContext;
+    /// let caller_context = CallerContext::new("manual").with_async(true);
+    /// assert!(caller_context.is_async());
+    /// ```
+    #[must_use]
+    pub const fn with_async(mut self, is_async: bool) -> Self {
+        self.is_async = is_async;
+        self
+    }
+
+    /// Attach static key-value metadata to the `CallerContext`
+    ///
+    /// The [`wrap`](crate::wrap) and [`async_wrap`](crate::async_wrap) macros populate it from the
+    /// `meta(...)` argument, like `#[wrap(Metrics, meta(service = "auth", tier = "edge"))]`.
+    /// The metadata is static, so it allows zero-allocation tagging (ie. for metrics labels).
+    /// ```
+    /// # use context_manager::CallerContext;
+    /// let caller_context = CallerContext::new("manual").with_meta(&[("service", "auth")]);
+    /// assert_eq!(caller_context.meta("service"), Some("auth"));
+    /// assert_eq!(caller_context.meta("tier"), None);
+    /// ```
+    #[must_use]
+    pub const fn with_meta(mut self, meta: &'static [(&'static str, &'static str)]) -> Self {
+        self.meta = meta;
+        self
+    }
+
+    /// Attach the guard of the first call of the wrapped function to the `CallerContext`
+    ///
+    /// The [`wrap`](crate::wrap) and [`async_wrap`](crate::async_wrap) macros populate it automatically,
+    /// with a guard per wrapped function. Without a guard [`SyncWrapContext::on_first_call`](crate::SyncWrapContext::on_first_call)
+    /// and [`AsyncWrapContext::on_first_call`](crate::AsyncWrapContext::on_first_call) are never executed.
+    #[must_use]
+    pub const fn with_first_call(mut self, first_call: &'static FirstCall) -> Self {
+        self.first_call = Some(first_call);
+        self
+    }
+
+    /// Name of the wrapped function
+    ///
+    /// The macros report raw identifiers without their prefix (ie. `r#type` is reported as `type`), as for [`CallerContext::arg_names`].
+    #[must_use]
+    pub const fn fn_name(&self) -> &'static str {
+        self.fn_name
+    }
+
+    /// Name of the wrapped function as emitted by the contexts (ie. in logs or in metrics)
+    ///
+    /// It is the name mapped by [`SyncWrapContext::map_fn_name`](crate::SyncWrapContext::map_fn_name) (or
+    /// [`AsyncWrapContext::map_fn_name`](crate::AsyncWrapContext::map_fn_name)) of the context wrapping the function,
+    /// so it differs from [`CallerContext::fn_name`] only if the context maps it (ie. prefixing it via a
+    /// `contexts::ContextChain`). The built-in contexts emitting names use it.
+    /// ```
+    /// # use context_manager::CallerContext;
+    /// let caller_context = CallerContext::new("manual");
+    /// assert_eq!(caller_context.emitted_fn_name(), "manual");
+    /// ```
+    #[must_use]
+    pub fn emitted_fn_name(&self) -> &str {
+        &self.emitted_fn_name
+    }
+
+    /// Map the name emitted by the contexts, via `map` receiving the name of the wrapped function
+    pub(crate) fn map_fn_name(&mut self, map: impl FnOnce(&'static str) -> Cow<'static, str>) {
+        self.emitted_fn_name = map(self.fn_name);
+    }
+
+    /// Module path of the wrapped function, if known
+    #[must_use]
+    pub const fn module_path(&self) -> Option<&'static str> {
+        self.module_path
+    }
+
+    /// File containing the wrapped function, if known
+    #[must_use]
+    pub const fn file(&self) -> Option<&'static str> {
+        self.file
+    }
+
+    /// Line of the wrapped function, if known
+    #[must_use]
+    pub const fn line(&self) -> Option<u32> {
+        self.line
+    }
+
+    /// Names of the arguments of the wrapped function, empty if unknown
+    #[must_use]
+    pub const fn arg_names(&self) -> &'static [&'static str] {
+        self.arg_names
+    }
+
+    /// Whether the wrapped function is async, `false` if unknown
+    #[must_use]
+    pub const fn is_async(&self) -> bool {
+        self.is_async
+    }
+
+    /// Value of the metadata `key`, if present
+    #[must_use]
+    pub fn meta(&self, key: &str) -> Option<&'static str> {
+        self.meta
+            .iter()
+            .find_map(|(meta_key, value)| (*meta_key == key).then_some(*value))
+    }
+
+    /// All the metadata entries, in declaration order
+    #[must_use]
+    pub const fn meta_entries(&self) -> &'static [(&'static str, &'static str)] {
+        self.meta
+    }
+
+    /// Attempt of the wrapped body being executed, starting from 1
+    ///
+    /// The attempt is incremented by the retrying variants of the run functions
+    /// (ie. [`SyncWrapContext::run_sync_retrying`](crate::SyncWrapContext::run_sync_retrying))
+    /// before each new execution of the body, and it is always 1 otherwise.
+    #[must_use]
+    pub const fn attempt(&self) -> u32 {
+        self.attempt
+    }
+
+    /// Execute `hook` if this is the first call of the wrapped function
+    pub(crate) fn on_first_call(&self, hook: impl FnOnce(&Self)) {
+        if let Some(first_call) = self.first_call {
+            first_call.call_once(|| hook(self));
+        }
+    }
+
+    pub(crate) const fn set_attempt(&mut self, attempt: u32) {
+        self.attempt = attempt;
+    }
+
+    /// Insert an extension into the `CallerContext`, returning the previous extension of the same type (if any)
+    ///
+    /// Extensions allow contexts to attach arbitrary data (ie. request identifiers) to the
+    /// `CallerContext`, for it to be accessed by the following hooks.
+    /// ```
+    /// # use context_manager::CallerContext;
+    /// struct RequestId(u64);
+    ///
+    /// let mut caller_context = CallerContext::new("manual");
+    /// assert!(caller_context.insert_extension(RequestId(1)).is_none());
+    /// assert_eq!(caller_context.insert_extension(RequestId(2)).map(|id| id.0), Some(1));
+    /// assert_eq!(caller_context.extension::<RequestId>().map(|id| id.0), Some(2));
+    /// ```
+    pub fn insert_extension<E: Any + Send + Sync>(&mut self, extension: E) -> Option<E> {
+        if let Some(existing) = self.extension_mut::<E>() {
+            Some(core::mem::replace(existing, extension))
+        } else {
+            self.extensions.push(Box::new(extension));
+            None
+        }
+    }
+
+    /// Reference to the extension of type `E`, if present
+    #[must_use]
+    pub fn extension<E: Any + Send + Sync>(&self) -> Option<&E> {
+        self.extensions
+            .iter()
+            .find_map(|extension| extension.downcast_ref::<E>())
+    }
+
+    /// Mutable reference to the extension of type `E`, if present
+    #[must_use]
+    pub fn extension_mut<E: Any + Send + Sync>(&mut self) -> Option<&mut E> {
+        self.extensions
+            .iter_mut()
+            .find_map(|extension| extension.downcast_mut::<E>())
+    }
+
+    /// Remove the extension of type `E` from the `CallerContext`, returning it (if present)
+    pub fn remove_extension<E: Any + Send + Sync>(&mut self) -> Option<E> {
+        let index = self
+            .extensions
+            .iter()
+            .position(|extension| extension.is::<E>())?;
+        self.extensions
+            .swap_remove(index)
+            .downcast::<E>()
+            .ok()
+            .map(|extension| *extension)
+    }
+}
+
+/// Serialization of [`CallerContext`] (requires the `serde` feature).
+///
+/// The emitted layout is stable and contains the fields identifying the wrapped function, with unknown
+/// values serialized as `null`. Extensions are not serialized, as they are not guaranteed to be serializable.
+/// ```
+/// # use context_manager::CallerContext;
+/// let caller_context = CallerContext::new("foo").with_location("my_crate::my_module", "src/my_module.rs", 42);
+/// assert_eq!(
+///     serde_json::to_value(&caller_context).unwrap(),
+///     serde_json::json!({
+///         "fn_name": "foo",
+///         "module_path": "my_crate::my_module",
+///         "file": "src/my_module.rs",
+///         "line": 42,
+///     }),
+/// );
+/// ```
+#[cfg(feature = "serde")]
+impl serde::Serialize for CallerContext {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use serde::ser::SerializeStruct;
+
+        let mut state = serializer.serialize_struct("CallerContext", 4)?;
+        state.serialize_field("fn_name", &self.fn_name)?;
+        state.serialize_field("module_path", &self.module_path)?;
+        state.serialize_field("file", &self.file)?;
+        state.serialize_field("line", &self.line)?;
+        state.end()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CallerContext;
+
+    #[test]
+    fn extensions_are_indexed_by_type() {
+        let mut caller_context = CallerContext::new("foo");
+        assert_eq!(caller_context.insert_extension(1_u8), None);
+        assert_eq!(caller_context.insert_extension("value"), None);
+        assert_eq!(caller_context.insert_extension(2_u8), Some(1));
+
+        *caller_context.extension_mut::<u8>().unwrap() += 1;
+        assert_eq!(caller_context.extension::<u8>(), Some(&3));
+        assert_eq!(caller_context.extension::<&str>(), Some(&"value"));
+        assert_eq!(caller_context.extension::<u16>(), None);
+
+        assert_eq!(caller_context.remove_extension::<u8>(), Some(3));
+        assert_eq!(caller_context.remove_extension::<u8>(), None);
+        assert_eq!(caller_context.extension::<&str>(), Some(&"value"));
+    }
+
+    #[test]
+    fn constructible_at_compile_time() {
+        const CONST_CALLER_CONTEXT: CallerContext = CallerContext::new("foo")
+            .with_location("crate::module", "src/module.rs", 7)
+            .with_async(true);
+        static STATIC_CALLER_CONTEXT: CallerContext =
+            CallerContext::new("bar").with_meta(&[("tier", "edge")]);
+
+        assert_eq!(CONST_CALLER_CONTEXT.fn_name(), "foo");
+        assert_eq!(CONST_CALLER_CONTEXT.line(), Some(7));
+        assert!(CONST_CALLER_CONTEXT.is_async());
+        assert_eq!(STATIC_CALLER_CONTEXT.fn_name(), "bar");
+        assert_eq!(STATIC_CALLER_CONTEXT.meta("tier"), Some("edge"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_without_location() {
+        assert_eq!(
+            serde_json::to_string(&CallerContext::new("foo")).unwrap(),
+            r#"{"fn_name":"foo","module_path":null,"file":null,"line":null}"#,
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_with_location() {
+        let caller_context =
+            CallerContext::new("foo").with_location("crate::module", "src/module.rs", 7);
+        assert_eq!(
+            serde_json::to_string(&caller_context).unwrap(),
+            r#"{"fn_name":"foo","module_path":"crate::module","file":"src/module.rs","line":7}"#,
+        );
+    }
+}
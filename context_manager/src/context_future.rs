@@ -0,0 +1,253 @@
+use core::fmt;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
+use core::pin::Pin;
+use core::task::{Context, Poll};
+use core::time::Duration;
+
+use pin_project_lite::pin_project;
+
+use crate::call_stack::{in_call_stack_sync, Frame};
+use crate::platform::{self, Stopwatch};
+use crate::t_sync::enter_async;
+use crate::{CallerContext, SyncWrapContext};
+
+/// Progress of a [`ContextFuture`]
+enum State<C> {
+    /// The future was not polled yet, so the context is not initialised
+    Created,
+    /// The body is executing with the hooks of the context
+    Running {
+        context: C,
+        frame: Frame,
+        start: Stopwatch,
+    },
+    /// The body is executing without the hooks of the context, as the wrapped function is re-entered
+    Reentered { frame: Frame },
+    /// The future completed
+    Done,
+}
+
+pin_project! {
+    /// Future of a body wrapped by the context `C`, as returned by [`SyncWrapContext::run_future`]
+    ///
+    /// Differently from the futures returned by the other `run*` functions, which are opaque, the type can be named
+    /// (ie. to store it in a struct). It holds the body and the context without boxing them, so it is [`Send`] if
+    /// they are, and it is [`Unpin`] if the body is, so it can be polled via `Pin::new(&mut future)` and used by
+    /// combinators requiring it (ie. `futures::future::select`).
+    /// ```
+    /// # use context_manager::{CallerContext, ContextFuture, SyncWrapContext};
+    /// # use std::future::Ready;
+    /// struct PrintDuration;
+    /// impl<T> SyncWrapContext<T> for PrintDuration {
+    ///     fn new() -> Self { Self }
+    /// }
+    ///
+    /// struct Pending {
+    ///     future: ContextFuture<PrintDuration, Ready<u8>>,
+    /// }
+    ///
+    /// # async fn foo() {
+    /// let pending = Pending {
+    ///     future: PrintDuration::run_future(CallerContext::new("manual"), std::future::ready(42)),
+    /// };
+    /// assert_eq!(pending.future.await, 42);
+    /// # }
+    /// ```
+    ///
+    /// The hooks are executed as for [`SyncWrapContext::run_async`], starting from the first poll, but the completion
+    /// of the body is handled via [`SyncWrapContext::after_timed`], as the future of [`SyncWrapContext::after_async`]
+    /// cannot be named. If the future is dropped before completing, [`SyncWrapContext::finally`] is executed.
+    ///
+    /// NOTE: The futures of the hooks of [`AsyncWrapContext`](crate::AsyncWrapContext) cannot be named either, so the
+    /// asynchronous contexts do not provide a [`ContextFuture`]: their `run` future can be boxed instead.
+    #[must_use = "futures do nothing unless you `.await` or poll them"]
+    pub struct ContextFuture<C, F, I = ()>
+    where
+        C: SyncWrapContext<F::Output, I>,
+        F: Future,
+    {
+        #[pin]
+        block: F,
+        caller_context: CallerContext,
+        state: State<C>,
+        hook: PhantomData<fn(&I)>,
+    }
+
+    impl<C, F, I> PinnedDrop for ContextFuture<C, F, I>
+    where
+        C: SyncWrapContext<F::Output, I>,
+        F: Future,
+    {
+        fn drop(this: Pin<&mut Self>) {
+            let this = this.project();
+            if let State::Running { context, .. } = core::mem::replace(this.state, State::Done) {
+                if platform::panicking() {
+                    context.on_poisoned(this.caller_context);
+                }
+                context.finally(this.caller_context);
+            }
+        }
+    }
+}
+
+impl<C, F, I> ContextFuture<C, F, I>
+where
+    C: SyncWrapContext<F::Output, I>,
+    F: Future,
+{
+    pub(crate) const fn new(caller_context: CallerContext, block: F) -> Self {
+        Self {
+            block,
+            caller_context,
+            state: State::Created,
+            hook: PhantomData,
+        }
+    }
+}
+
+impl<C, F, I> Future for ContextFuture<C, F, I>
+where
+    C: SyncWrapContext<F::Output, I>,
+    F: Future,
+{
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let caller_context = this.caller_context;
+        if matches!(this.state, State::Created) {
+            *this.state = match enter_async::<C, F::Output, I>(caller_context, C::before) {
+                (frame, None) => State::Reentered { frame },
+                (frame, Some(context)) => {
+                    if let ControlFlow::Break(result) = context.short_circuit(caller_context) {
+                        *this.state = State::Done;
+                        context.finally(caller_context);
+                        if C::HAS_AFTER {
+                            context.after_timed(caller_context, &result, Duration::ZERO);
+                        }
+                        return Poll::Ready(result);
+                    }
+                    State::Running {
+                        context,
+                        frame,
+                        start: Stopwatch::start_sync::<C, F::Output, I>(),
+                    }
+                }
+            };
+        }
+
+        let block = this.block;
+        let frame = match this.state {
+            State::Running { frame, .. } | State::Reentered { frame } => frame.clone(),
+            State::Created | State::Done => panic!("`ContextFuture` polled after completion"),
+        };
+        let Poll::Ready(result) = in_call_stack_sync(frame, || block.poll(cx)) else {
+            return Poll::Pending;
+        };
+        if let State::Running { context, start, .. } = core::mem::replace(this.state, State::Done) {
+            context.finally(caller_context);
+            let elapsed = start.elapsed();
+            if elapsed > context.slow_threshold() {
+                context.on_slow(caller_context, elapsed);
+            }
+            if C::HAS_AFTER {
+                context.after_timed(caller_context, &result, elapsed);
+            }
+        }
+        Poll::Ready(result)
+    }
+}
+
+impl<C, F, I> fmt::Debug for ContextFuture<C, F, I>
+where
+    C: SyncWrapContext<F::Output, I>,
+    F: Future,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ContextFuture")
+            .field("context", &core::any::type_name::<C>())
+            .field("caller_context", &self.caller_context)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContextFuture;
+    use crate::{CallerContext, SyncWrapContext};
+    use std::future::{pending, ready, Pending, Ready};
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    static AFTER: AtomicUsize = AtomicUsize::new(0);
+    static FINALLY: AtomicUsize = AtomicUsize::new(0);
+
+    struct CountHooks;
+    impl<T> SyncWrapContext<T> for CountHooks {
+        fn new() -> Self {
+            Self
+        }
+
+        fn finally(&self, caller_context: &CallerContext) {
+            if caller_context.fn_name() == "dropped" {
+                FINALLY.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        fn after(self, caller_context: &CallerContext, _: &T) {
+            if caller_context.fn_name() == "counted" {
+                AFTER.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    struct Stored {
+        first: ContextFuture<CountHooks, Ready<u8>>,
+        second: ContextFuture<CountHooks, Ready<u8>>,
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn futures_can_be_stored_before_awaiting() {
+        let stored = Stored {
+            first: CountHooks::run_future(CallerContext::new("counted"), ready(1)),
+            second: CountHooks::run_future(CallerContext::new("counted"), ready(2)),
+        };
+        assert_eq!(AFTER.load(Ordering::SeqCst), 0);
+
+        assert_eq!(stored.first.await, 1);
+        assert_eq!(stored.second.await, 2);
+        assert_eq!(AFTER.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn futures_can_be_polled_via_pin_new() {
+        let mut pending: ContextFuture<CountHooks, Pending<()>> =
+            CountHooks::run_future(CallerContext::new("foo"), pending());
+        let mut ready = CountHooks::run_future(CallerContext::new("foo"), ready("ready"));
+
+        match futures::future::select(Pin::new(&mut pending), Pin::new(&mut ready)).await {
+            futures::future::Either::Right((value, _)) => assert_eq!(value, "ready"),
+            futures::future::Either::Left(_) => unreachable!("the body never completes"),
+        }
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn futures_are_send_if_the_body_is() {
+        let future = CountHooks::run_future(CallerContext::new("foo"), async {
+            tokio::task::yield_now().await;
+            42
+        });
+        assert_eq!(tokio::spawn(future).await.unwrap(), 42);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn finally_is_executed_if_dropped_while_running() {
+        let mut future = CountHooks::run_future(CallerContext::new("dropped"), pending::<()>());
+        assert!(futures::poll!(Pin::new(&mut future)).is_pending());
+        assert_eq!(FINALLY.load(Ordering::SeqCst), 0);
+        drop(future);
+        assert_eq!(FINALLY.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,188 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Number of allocations performed via [`CountingAllocator`]
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+/// Number of bytes allocated via [`CountingAllocator`]
+static ALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+/// Number of deallocations performed via [`CountingAllocator`]
+static DEALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+/// Number of bytes deallocated via [`CountingAllocator`]
+static DEALLOCATED_BYTES: AtomicU64 = AtomicU64::new(0);
+
+/// Global allocator counting the allocations and the deallocations, delegating them to the wrapped allocator
+///
+/// A reallocation is counted as a deallocation of the previous memory block and an allocation of the new one.
+///
+/// It has to be installed as the global allocator for [`AllocContext`] to observe the allocations.
+/// ```
+/// # use std::alloc::System;
+/// # use context_manager::contexts::CountingAllocator;
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator::new(System);
+/// ```
+#[derive(Debug)]
+pub struct CountingAllocator<A = System> {
+    allocator: A,
+}
+
+impl<A> CountingAllocator<A> {
+    /// Create a new `CountingAllocator`, delegating the allocations to `allocator`
+    pub const fn new(allocator: A) -> Self {
+        Self { allocator }
+    }
+
+    fn record(size: usize) {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        ALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+    }
+
+    fn record_dealloc(size: usize) {
+        DEALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        DEALLOCATED_BYTES.fetch_add(size as u64, Ordering::Relaxed);
+    }
+}
+
+// SAFETY: the allocations are delegated to the wrapped allocator, with the same layouts
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        Self::record(layout.size());
+        unsafe { self.allocator.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        Self::record(layout.size());
+        unsafe { self.allocator.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        Self::record_dealloc(layout.size());
+        unsafe { self.allocator.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        Self::record_dealloc(layout.size());
+        Self::record(new_size);
+        unsafe { self.allocator.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// Configuration of [`AllocContext`]
+///
+/// ```
+/// # use context_manager::contexts::AllocConfig;
+/// struct PrintAllocations;
+/// impl AllocConfig for PrintAllocations {
+///     fn on_alloc(fn_name: &'static str, allocs: u64, bytes: u64) {
+///         eprintln!("{fn_name} performed {allocs} allocations ({bytes} bytes)");
+///     }
+/// }
+/// ```
+pub trait AllocConfig {
+    /// Execute the code after the wrapped function returns
+    ///
+    /// Parameters:
+    /// - `fn_name`: Name of the wrapped function
+    /// - `allocs`: Number of allocations (including reallocations) performed while executing the wrapped function
+    /// - `bytes`: Number of bytes allocated while executing the wrapped function (freed bytes are not subtracted)
+    fn on_alloc(fn_name: &'static str, allocs: u64, bytes: u64);
+
+    /// Execute the code after the wrapped function returns, right after [`AllocConfig::on_alloc`]
+    ///
+    /// By default it does nothing. Together with [`AllocConfig::on_alloc`] it allows to detect the memory
+    /// retained by the wrapped function, as the difference between the allocated and the deallocated bytes.
+    ///
+    /// Parameters:
+    /// - `fn_name`: Name of the wrapped function
+    /// - `deallocs`: Number of deallocations (including reallocations) performed while executing the wrapped function
+    /// - `bytes`: Number of bytes deallocated while executing the wrapped function
+    #[allow(unused_variables)]
+    fn on_dealloc(fn_name: &'static str, deallocs: u64, bytes: u64) {}
+}
+
+/// Context reporting the allocations and the deallocations performed by the wrapped function, for memory profiling
+///
+/// The allocations are counted by [`CountingAllocator`], which has to be installed as the global allocator:
+/// otherwise no allocation is observed. The counters are shared by the whole process, so the allocations
+/// performed concurrently by other threads (or by other tasks, for async functions) are reported as well.
+/// ```
+/// # use std::alloc::System;
+/// # use context_manager::contexts::{AllocConfig, AllocContext, CountingAllocator};
+/// # use context_manager::wrap;
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator::new(System);
+///
+/// struct PrintAllocations;
+/// impl AllocConfig for PrintAllocations {
+///     fn on_alloc(fn_name: &'static str, allocs: u64, bytes: u64) {
+///         eprintln!("{fn_name} performed {allocs} allocations ({bytes} bytes)");
+///     }
+/// }
+///
+/// #[wrap(AllocContext<PrintAllocations>)]
+/// fn build() -> Vec<u8> {
+///     vec![0; 1024]
+/// }
+/// ```
+#[derive(Debug)]
+pub struct AllocContext<C: AllocConfig> {
+    allocations: u64,
+    allocated_bytes: u64,
+    deallocations: u64,
+    deallocated_bytes: u64,
+    config: PhantomData<C>,
+}
+
+impl<C: AllocConfig> AllocContext<C> {
+    fn create() -> Self {
+        Self {
+            allocations: ALLOCATIONS.load(Ordering::Relaxed),
+            allocated_bytes: ALLOCATED_BYTES.load(Ordering::Relaxed),
+            deallocations: DEALLOCATIONS.load(Ordering::Relaxed),
+            deallocated_bytes: DEALLOCATED_BYTES.load(Ordering::Relaxed),
+            config: PhantomData,
+        }
+    }
+
+    fn report(&self, caller_context: &CallerContext) {
+        C::on_alloc(
+            caller_context.fn_name(),
+            ALLOCATIONS.load(Ordering::Relaxed) - self.allocations,
+            ALLOCATED_BYTES.load(Ordering::Relaxed) - self.allocated_bytes,
+        );
+        C::on_dealloc(
+            caller_context.fn_name(),
+            DEALLOCATIONS.load(Ordering::Relaxed) - self.deallocations,
+            DEALLOCATED_BYTES.load(Ordering::Relaxed) - self.deallocated_bytes,
+        );
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: AllocConfig, T> SyncWrapContext<T> for AllocContext<C> {
+    const HAS_BEFORE: bool = false;
+
+    fn new() -> Self {
+        Self::create()
+    }
+
+    fn after(self, caller_context: &CallerContext, _: &T) {
+        self.report(caller_context);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: AllocConfig, T> AsyncWrapContext<T> for AllocContext<C> {
+    const HAS_BEFORE: bool = false;
+
+    async fn new() -> Self {
+        Self::create()
+    }
+
+    async fn after(self, caller_context: &CallerContext, _: &T) {
+        self.report(caller_context);
+    }
+}
@@ -0,0 +1,176 @@
+use alloc::borrow::Cow;
+use core::ops::ControlFlow;
+use core::time::Duration;
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Adapter using the synchronous context `C` where an asynchronous one is expected
+///
+/// The adapter implements [`AsyncWrapContext`] by executing the hooks of `C` inline, so it allows using
+/// a [`SyncWrapContext`] via [`async_wrap`](crate::async_wrap) or within contexts requiring async hooks.
+/// The completion of the body is handled via [`SyncWrapContext::after_async`], as done by [`SyncWrapContext::run_async`].
+/// ```
+/// # use context_manager::contexts::AsAsync;
+/// # use context_manager::{async_wrap, CallerContext, SyncWrapContext};
+/// struct PrintCall;
+/// impl<T> SyncWrapContext<T> for PrintCall {
+///     fn new() -> Self {
+///         Self
+///     }
+///
+///     fn before(&self, caller_context: &mut CallerContext) {
+///         println!("calling {}", caller_context.fn_name());
+///     }
+/// }
+///
+/// #[async_wrap(AsAsync<PrintCall>)]
+/// async fn fetch() {}
+/// ```
+///
+/// NOTE: The hooks of `C` block the executor thread while running, so they should be quick. Hooks performing
+/// blocking I/O should be wrapped by `AsAsyncBlocking` instead (requires the `tokio` feature).
+#[derive(Debug)]
+pub struct AsAsync<C, const BLOCKING: bool = false> {
+    inner: C,
+}
+
+/// Adapter using the synchronous context `C` where an asynchronous one is expected, for hooks blocking the thread
+///
+/// This is equivalent to [`AsAsync`], but the hooks of `C` are executed via [`tokio::task::block_in_place`],
+/// so the other tasks of the executor thread are moved to other threads while the hooks run.
+/// Hence, the wrapped functions have to be executed by the multi-threaded runtime of `tokio`.
+///
+/// The completion of the body is handled via [`SyncWrapContext::after_timed`], as [`SyncWrapContext::after_async`]
+/// cannot be awaited while blocking.
+#[cfg(feature = "tokio")]
+pub type AsAsyncBlocking<C> = AsAsync<C, true>;
+
+impl<C, const BLOCKING: bool> AsAsync<C, BLOCKING> {
+    /// Execute the hook of the inner context, blocking the thread in place for the `AsAsyncBlocking` variant
+    fn run_hook<R>(hook: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "tokio")]
+        if BLOCKING {
+            return tokio::task::block_in_place(hook);
+        }
+        hook()
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: SyncWrapContext<T, I>, T, I, const BLOCKING: bool> AsyncWrapContext<T, I>
+    for AsAsync<C, BLOCKING>
+{
+    const HAS_BEFORE: bool = C::HAS_BEFORE;
+    const HAS_AFTER: bool = C::HAS_AFTER;
+
+    async fn new() -> Self {
+        Self {
+            inner: Self::run_hook(C::new),
+        }
+    }
+
+    fn build_caller_context(default: CallerContext) -> CallerContext {
+        C::build_caller_context(default)
+    }
+
+    fn on_first_call(&self, caller_context: &CallerContext) {
+        Self::run_hook(|| self.inner.on_first_call(caller_context));
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        Self::run_hook(|| self.inner.before(caller_context));
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        Self::run_hook(|| self.inner.short_circuit(caller_context))
+    }
+
+    async fn before_with_input(&self, caller_context: &mut CallerContext, input: &I) {
+        Self::run_hook(|| self.inner.before_with_input(caller_context, input));
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        Self::run_hook(|| self.inner.on_poisoned(caller_context));
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        Self::run_hook(|| self.inner.finally(caller_context));
+    }
+
+    #[cfg(feature = "std")]
+    fn now() -> std::time::Instant {
+        C::now()
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        self.inner.map_fn_name(name)
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        self.inner.slow_threshold()
+    }
+
+    async fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        Self::run_hook(|| self.inner.on_slow(caller_context, elapsed));
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        if BLOCKING {
+            Self::run_hook(|| self.inner.after_timed(caller_context, result, elapsed));
+        } else {
+            self.inner
+                .after_async(caller_context, result, elapsed)
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::AsAsync;
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+    static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct Record;
+    impl SyncWrapContext<u8> for Record {
+        fn new() -> Self {
+            Self
+        }
+
+        fn before(&self, caller_context: &mut CallerContext) {
+            EVENTS
+                .lock()
+                .unwrap()
+                .push(format!("before {}", caller_context.fn_name()));
+        }
+
+        fn finally(&self, _: &CallerContext) {
+            EVENTS.lock().unwrap().push("finally".to_string());
+        }
+
+        fn after_timed(self, _: &CallerContext, result: &u8, _: Duration) {
+            EVENTS.lock().unwrap().push(format!("after {result}"));
+        }
+    }
+
+    #[tokio::test]
+    async fn sync_hooks_are_executed_inline() {
+        let result =
+            <AsAsync<Record> as AsyncWrapContext<_>>::run(CallerContext::new("as_async"), async {
+                tokio::task::yield_now().await;
+                EVENTS.lock().unwrap().push("body".to_string());
+                42
+            })
+            .await;
+
+        assert_eq!(result, 42);
+        assert_eq!(
+            *EVENTS.lock().unwrap(),
+            ["before as_async", "body", "finally", "after 42"]
+        );
+    }
+}
@@ -0,0 +1,246 @@
+use std::marker::PhantomData;
+use std::mem;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::contexts::registry::Registry;
+use crate::contexts::Flush;
+use crate::{AsyncWrapContext, CallerContext, Clock, SyncWrapContext, SystemClock};
+
+/// Buffers of [`BatchingContext`], one per configuration (keyed by its type name)
+static BUFFERS: Registry<Mutex<Buffer>> = Registry::new();
+
+/// Record of a call of a function wrapped by [`BatchingContext`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct BatchRecord {
+    /// Name of the wrapped function
+    pub fn_name: &'static str,
+    /// Duration of the call
+    pub elapsed: Duration,
+}
+
+/// Configuration of [`BatchingContext`]
+///
+/// ```
+/// # use context_manager::contexts::{BatchConfig, BatchRecord};
+/// # use std::time::Duration;
+/// struct Metrics;
+/// impl BatchConfig for Metrics {
+///     const BATCH_SIZE: usize = 100;
+///     const INTERVAL: Duration = Duration::from_secs(10);
+///
+///     fn flush(records: Vec<BatchRecord>) {
+///         println!("sending {} records", records.len());
+///     }
+/// }
+/// ```
+pub trait BatchConfig: 'static {
+    /// Number of buffered records triggering a flush
+    const BATCH_SIZE: usize;
+
+    /// Age of the oldest buffered record triggering a flush, [`Duration::MAX`] (never) by default
+    ///
+    /// The age is checked only when a record is added, as no background task is spawned.
+    const INTERVAL: Duration = Duration::MAX;
+
+    /// Flush the buffered records, in call completion order
+    ///
+    /// The records are removed from the buffer beforehand, so the function is not executed while holding its lock.
+    fn flush(records: Vec<BatchRecord>);
+}
+
+#[derive(Debug, Default)]
+struct Buffer {
+    records: Vec<BatchRecord>,
+    /// Time of the oldest buffered record
+    oldest: Option<Instant>,
+}
+
+impl Buffer {
+    /// Remove the buffered records
+    fn take(&mut self) -> Vec<BatchRecord> {
+        self.oldest = None;
+        mem::take(&mut self.records)
+    }
+}
+
+/// Context buffering a record per call of the wrapped functions, and flushing them in batches
+///
+/// The records are flushed, via [`BatchConfig::flush`], once [`BatchConfig::BATCH_SIZE`] of them are buffered or
+/// once the oldest of them is older than [`BatchConfig::INTERVAL`], reducing the cost of high-throughput reporting.
+/// The buffer is shared by all the functions wrapped with the same configuration.
+/// ```
+/// # use context_manager::contexts::{BatchConfig, BatchRecord, BatchingContext};
+/// # use context_manager::wrap;
+/// struct Metrics;
+/// impl BatchConfig for Metrics {
+///     const BATCH_SIZE: usize = 100;
+///
+///     fn flush(records: Vec<BatchRecord>) {
+///         println!("sending {} records", records.len());
+///     }
+/// }
+///
+/// #[wrap(BatchingContext<Metrics>)]
+/// fn handle_request() {}
+///
+/// handle_request();
+/// // Flush the records left in the buffer, ie. on shutdown
+/// BatchingContext::<Metrics>::flush();
+/// ```
+///
+/// The context implements [`Flush`], so the records left in the buffer can be flushed once the program terminates
+/// by wrapping `main` with `#[wrap(FlushContext<BatchingContext<Metrics>>)]`.
+///
+/// The age of the buffered records is measured via the [`Clock`] `K`, so a fake clock can be injected in tests.
+#[derive(Debug)]
+pub struct BatchingContext<C: BatchConfig, K: Clock = SystemClock> {
+    config: PhantomData<(C, K)>,
+}
+
+impl<C: BatchConfig, K: Clock> BatchingContext<C, K> {
+    fn buffer() -> &'static Mutex<Buffer> {
+        BUFFERS.get_or_register(std::any::type_name::<C>())
+    }
+
+    /// Flush the buffered records, if any
+    pub fn flush() {
+        let records = Self::buffer()
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .take();
+        if !records.is_empty() {
+            C::flush(records);
+        }
+    }
+
+    fn record(caller_context: &CallerContext, elapsed: Duration) {
+        let now = K::now();
+        let records = {
+            let mut buffer = Self::buffer()
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            buffer.records.push(BatchRecord {
+                fn_name: caller_context.fn_name(),
+                elapsed,
+            });
+            let oldest = *buffer.oldest.get_or_insert(now);
+            if buffer.records.len() < C::BATCH_SIZE
+                && now.saturating_duration_since(oldest) < C::INTERVAL
+            {
+                return;
+            }
+            buffer.take()
+        };
+        C::flush(records);
+    }
+}
+
+impl<C: BatchConfig, K: Clock> Flush for BatchingContext<C, K> {
+    fn flush() {
+        Self::flush();
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: BatchConfig, K: Clock, T> SyncWrapContext<T> for BatchingContext<C, K> {
+    const HAS_BEFORE: bool = false;
+
+    fn new() -> Self {
+        Self {
+            config: PhantomData,
+        }
+    }
+
+    fn now() -> Instant {
+        K::now()
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::record(caller_context, elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: BatchConfig, K: Clock, T> AsyncWrapContext<T> for BatchingContext<C, K> {
+    const HAS_BEFORE: bool = false;
+
+    async fn new() -> Self {
+        Self {
+            config: PhantomData,
+        }
+    }
+
+    fn now() -> Instant {
+        K::now()
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::record(caller_context, elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BatchConfig, BatchRecord, BatchingContext};
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    fn run<C: BatchConfig>(fn_name: &'static str) {
+        <BatchingContext<C> as SyncWrapContext<_>>::run_sync(CallerContext::new(fn_name), || ());
+    }
+
+    #[test]
+    fn records_are_flushed_in_batches() {
+        static BATCHES: Mutex<Vec<Vec<&str>>> = Mutex::new(Vec::new());
+
+        struct ThreeRecords;
+        impl BatchConfig for ThreeRecords {
+            const BATCH_SIZE: usize = 3;
+
+            fn flush(records: Vec<BatchRecord>) {
+                BATCHES
+                    .lock()
+                    .unwrap()
+                    .push(records.iter().map(|record| record.fn_name).collect());
+            }
+        }
+
+        for fn_name in ["a", "b", "c", "d", "e", "f", "g"] {
+            run::<ThreeRecords>(fn_name);
+        }
+        assert_eq!(*BATCHES.lock().unwrap(), [["a", "b", "c"], ["d", "e", "f"]]);
+
+        BatchingContext::<ThreeRecords>::flush();
+        BatchingContext::<ThreeRecords>::flush();
+        assert_eq!(
+            *BATCHES.lock().unwrap(),
+            [vec!["a", "b", "c"], vec!["d", "e", "f"], vec!["g"]]
+        );
+    }
+
+    #[tokio::test]
+    async fn old_records_are_flushed() {
+        static BATCHES: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+        struct Immediate;
+        impl BatchConfig for Immediate {
+            const BATCH_SIZE: usize = 100;
+            const INTERVAL: Duration = Duration::ZERO;
+
+            fn flush(records: Vec<BatchRecord>) {
+                BATCHES.lock().unwrap().push(records.len());
+            }
+        }
+
+        run::<Immediate>("sync");
+        <BatchingContext<Immediate> as AsyncWrapContext<_>>::run(
+            CallerContext::new("async"),
+            async {},
+        )
+        .await;
+        assert_eq!(*BATCHES.lock().unwrap(), [1, 1]);
+    }
+}
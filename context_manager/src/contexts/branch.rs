@@ -0,0 +1,361 @@
+use alloc::borrow::Cow;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
+use core::time::Duration;
+
+use crate::{AsyncWrapContext, CallerContext, Reentrancy, SyncWrapContext, WrapContextWithArgs};
+
+/// Branch of a [`Result`] selected by [`BranchContext`]
+pub trait Branch {
+    /// Check whether `result` belongs to the branch
+    fn matches<T, E>(result: &Result<T, E>) -> bool;
+}
+
+/// The [`Ok`] branch of a [`Result`], as selected by `#[wrap(Context, on = "ok")]`
+#[derive(Debug)]
+pub struct OkBranch;
+
+impl Branch for OkBranch {
+    fn matches<T, E>(result: &Result<T, E>) -> bool {
+        result.is_ok()
+    }
+}
+
+/// The [`Err`] branch of a [`Result`], as selected by `#[wrap(Context, on = "err")]`
+#[derive(Debug)]
+pub struct ErrBranch;
+
+impl Branch for ErrBranch {
+    fn matches<T, E>(result: &Result<T, E>) -> bool {
+        result.is_err()
+    }
+}
+
+/// Context executing the `after` hooks of the inner context `C` only if the wrapped function
+/// returned a result belonging to the branch `B`
+///
+/// The context is initialised, and its `before` hook executed, for all the calls, as the
+/// result is not known upfront. If the result does not belong to the branch, the inner context
+/// is dropped without executing its `after` hooks.
+///
+/// The context is usually not named directly, but via the `on` argument of the macros.
+/// ```
+/// # use context_manager::{wrap, CallerContext, SyncWrapContext};
+/// struct ReportError;
+/// impl<T, E: std::fmt::Debug> SyncWrapContext<Result<T, E>> for ReportError {
+///     fn new() -> Self { Self }
+///     fn after(self, caller_context: &CallerContext, result: &Result<T, E>) {
+///         // Invoked only if the function returned `Err`
+///         eprintln!("{} failed: {:?}", caller_context.fn_name(), result.as_ref().err());
+///     }
+/// }
+///
+/// #[wrap(ReportError, on = "err")]
+/// fn parse(value: &str) -> Result<u8, std::num::ParseIntError> {
+///     value.parse()
+/// }
+/// ```
+///
+/// The `on` argument accepts `"ok"`, `"err"` and `"always"` (the default). Functions not
+/// returning a [`Result`] are always considered successful, so `on = "ok"` degrades to `"always"`
+/// while `on = "err"` is rejected at compile time.
+#[derive(Debug)]
+pub struct BranchContext<C, B> {
+    inner: C,
+    branch: PhantomData<B>,
+}
+
+#[diagnostic::do_not_recommend]
+impl<C, B, T, E, I> SyncWrapContext<Result<T, E>, I> for BranchContext<C, B>
+where
+    C: SyncWrapContext<Result<T, E>, I>,
+    B: Branch,
+{
+    const REENTRANCY: Reentrancy = C::REENTRANCY;
+    const HAS_BEFORE: bool = C::HAS_BEFORE;
+    const HAS_AFTER: bool = C::HAS_AFTER;
+
+    fn new() -> Self {
+        Self {
+            inner: C::new(),
+            branch: PhantomData,
+        }
+    }
+
+    fn build_caller_context(default: CallerContext) -> CallerContext {
+        C::build_caller_context(default)
+    }
+
+    fn on_first_call(&self, caller_context: &CallerContext) {
+        self.inner.on_first_call(caller_context);
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        self.inner.before(caller_context);
+    }
+
+    fn before_with_input(&self, caller_context: &mut CallerContext, input: &I) {
+        self.inner.before_with_input(caller_context, input);
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, result: &Result<T, E>, elapsed: Duration) {
+        if B::matches(result) {
+            self.inner.after_timed(caller_context, result, elapsed);
+        }
+    }
+
+    fn after_owned(
+        self,
+        caller_context: &CallerContext,
+        result: Result<T, E>,
+        elapsed: Duration,
+    ) -> Result<T, E> {
+        if B::matches(&result) {
+            self.inner.after_owned(caller_context, result, elapsed)
+        } else {
+            result
+        }
+    }
+
+    async fn after_async(
+        self,
+        caller_context: &CallerContext,
+        result: &Result<T, E>,
+        elapsed: Duration,
+    ) {
+        if B::matches(result) {
+            self.inner
+                .after_async(caller_context, result, elapsed)
+                .await;
+        }
+    }
+
+    fn after_ref(
+        &mut self,
+        caller_context: &CallerContext,
+        result: &Result<T, E>,
+        elapsed: Duration,
+    ) {
+        if B::matches(result) {
+            self.inner.after_ref(caller_context, result, elapsed);
+        }
+    }
+
+    fn on_reentry(&self, caller_context: &CallerContext) {
+        self.inner.on_reentry(caller_context);
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<Result<T, E>> {
+        self.inner.short_circuit(caller_context)
+    }
+
+    #[cfg(feature = "std")]
+    fn now() -> std::time::Instant {
+        C::now()
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        self.inner.map_fn_name(name)
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        self.inner.slow_threshold()
+    }
+
+    fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        self.inner.on_slow(caller_context, elapsed);
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        self.inner.on_poisoned(caller_context);
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        self.inner.finally(caller_context);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C, B, T, E, Args> WrapContextWithArgs<Args, Result<T, E>> for BranchContext<C, B>
+where
+    C: WrapContextWithArgs<Args, Result<T, E>>,
+    B: Branch,
+{
+    fn before_with_args(&self, caller_context: &mut CallerContext, args: &Args) {
+        self.inner.before_with_args(caller_context, args);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C, B, T, E, I> AsyncWrapContext<Result<T, E>, I> for BranchContext<C, B>
+where
+    C: AsyncWrapContext<Result<T, E>, I>,
+    B: Branch,
+{
+    const HAS_BEFORE: bool = C::HAS_BEFORE;
+    const HAS_AFTER: bool = C::HAS_AFTER;
+
+    async fn new() -> Self {
+        Self {
+            inner: C::new().await,
+            branch: PhantomData,
+        }
+    }
+
+    fn build_caller_context(default: CallerContext) -> CallerContext {
+        C::build_caller_context(default)
+    }
+
+    fn on_first_call(&self, caller_context: &CallerContext) {
+        self.inner.on_first_call(caller_context);
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        self.inner.before(caller_context).await;
+    }
+
+    async fn before_with_input(&self, caller_context: &mut CallerContext, input: &I) {
+        self.inner.before_with_input(caller_context, input).await;
+    }
+
+    fn on_cancel(&self, caller_context: &CallerContext) {
+        self.inner.on_cancel(caller_context);
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<Result<T, E>> {
+        self.inner.short_circuit(caller_context)
+    }
+
+    #[cfg(feature = "std")]
+    fn now() -> std::time::Instant {
+        C::now()
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        self.inner.map_fn_name(name)
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        self.inner.slow_threshold()
+    }
+
+    async fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        self.inner.on_slow(caller_context, elapsed).await;
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        self.inner.on_poisoned(caller_context);
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        self.inner.finally(caller_context);
+    }
+
+    async fn after_timed(
+        self,
+        caller_context: &CallerContext,
+        result: &Result<T, E>,
+        elapsed: Duration,
+    ) {
+        if B::matches(result) {
+            self.inner
+                .after_timed(caller_context, result, elapsed)
+                .await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{BranchContext, ErrBranch, OkBranch};
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+    thread_local! {
+        static HOOKS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+    }
+
+    struct Recording;
+    impl SyncWrapContext<Result<u8, u8>> for Recording {
+        fn new() -> Self {
+            Self
+        }
+
+        fn before(&self, _: &mut CallerContext) {
+            HOOKS.with_borrow_mut(|hooks| hooks.push("before"));
+        }
+
+        fn after(self, _: &CallerContext, _: &Result<u8, u8>) {
+            HOOKS.with_borrow_mut(|hooks| hooks.push("after"));
+        }
+    }
+    impl AsyncWrapContext<Result<u8, u8>> for Recording {
+        async fn new() -> Self {
+            Self
+        }
+
+        async fn before(&self, _: &mut CallerContext) {
+            HOOKS.with_borrow_mut(|hooks| hooks.push("before"));
+        }
+
+        async fn after(self, _: &CallerContext, _: &Result<u8, u8>) {
+            HOOKS.with_borrow_mut(|hooks| hooks.push("after"));
+        }
+    }
+
+    fn run_sync<C: SyncWrapContext<Result<u8, u8>>>(result: Result<u8, u8>) -> Vec<&'static str> {
+        assert_eq!(C::run_sync(CallerContext::new("test"), || result), result);
+        HOOKS.with_borrow_mut(std::mem::take)
+    }
+
+    async fn run_async<C: AsyncWrapContext<Result<u8, u8>>>(
+        result: Result<u8, u8>,
+    ) -> Vec<&'static str> {
+        assert_eq!(
+            C::run(CallerContext::new("test"), async { result }).await,
+            result
+        );
+        HOOKS.with_borrow_mut(std::mem::take)
+    }
+
+    #[test]
+    fn sync_after_runs_only_on_the_selected_branch() {
+        assert_eq!(
+            run_sync::<BranchContext<Recording, OkBranch>>(Ok(1)),
+            ["before", "after"]
+        );
+        assert_eq!(
+            run_sync::<BranchContext<Recording, OkBranch>>(Err(1)),
+            ["before"]
+        );
+        assert_eq!(
+            run_sync::<BranchContext<Recording, ErrBranch>>(Ok(1)),
+            ["before"]
+        );
+        assert_eq!(
+            run_sync::<BranchContext<Recording, ErrBranch>>(Err(1)),
+            ["before", "after"]
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_after_runs_only_on_the_selected_branch() {
+        assert_eq!(
+            run_async::<BranchContext<Recording, OkBranch>>(Ok(1)).await,
+            ["before", "after"]
+        );
+        assert_eq!(
+            run_async::<BranchContext<Recording, OkBranch>>(Err(1)).await,
+            ["before"]
+        );
+        assert_eq!(
+            run_async::<BranchContext<Recording, ErrBranch>>(Ok(1)).await,
+            ["before"]
+        );
+        assert_eq!(
+            run_async::<BranchContext<Recording, ErrBranch>>(Err(1)).await,
+            ["before", "after"]
+        );
+    }
+}
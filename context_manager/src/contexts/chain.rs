@@ -0,0 +1,346 @@
+use alloc::borrow::Cow;
+use core::ops::ControlFlow;
+use core::time::Duration;
+
+use crate::{AsyncWrapContext, CallerContext, Reentrancy, SyncWrapContext};
+
+/// Context composing two contexts, so that they can be defined once and reused as a single one
+///
+/// All the hooks of `A` are executed before the corresponding hooks of `B`, including the `after` ones.
+/// This differs from stacking `#[wrap(A)]` and `#[wrap(B)]`, where `B` would be nested within `A`
+/// (and so its `after` hook would be executed first). The caller context is built by `A` first, and then by `B`.
+/// ```
+/// # use context_manager::contexts::ContextChain;
+/// # use context_manager::{wrap, SyncWrapContext};
+/// # struct Timing;
+/// # impl<T> SyncWrapContext<T> for Timing { fn new() -> Self { Self } }
+/// # struct Logging;
+/// # impl<T> SyncWrapContext<T> for Logging { fn new() -> Self { Self } }
+/// type Instrumented = ContextChain<Timing, Logging>;
+///
+/// #[wrap(Instrumented)]
+/// fn foo() {}
+/// ```
+///
+/// NOTE: Only the hooks of `A` and `B` are forwarded, so any custom `run_*` implementation of them is ignored.
+/// Re-entrant calls are detected if any of the contexts requests so, in which case `on_reentry` is executed on both.
+/// Chains can be nested, like `ContextChain<A, ContextChain<B, C>>`, to compose more than two contexts.
+/// The duration of the body is measured once for both contexts, via the clock of `A` (like `contexts::ClockContext`).
+#[derive(Debug)]
+pub struct ContextChain<A, B> {
+    first: A,
+    second: B,
+}
+
+#[diagnostic::do_not_recommend]
+impl<A, B, T, I> SyncWrapContext<T, I> for ContextChain<A, B>
+where
+    A: SyncWrapContext<T, I>,
+    B: SyncWrapContext<T, I>,
+{
+    const REENTRANCY: Reentrancy = if matches!(A::REENTRANCY, Reentrancy::Detect)
+        || matches!(B::REENTRANCY, Reentrancy::Detect)
+    {
+        Reentrancy::Detect
+    } else {
+        Reentrancy::Allow
+    };
+    const HAS_BEFORE: bool = A::HAS_BEFORE || B::HAS_BEFORE;
+    const HAS_AFTER: bool = A::HAS_AFTER || B::HAS_AFTER;
+
+    fn new() -> Self {
+        Self {
+            first: A::new(),
+            second: B::new(),
+        }
+    }
+
+    fn build_caller_context(default: CallerContext) -> CallerContext {
+        B::build_caller_context(A::build_caller_context(default))
+    }
+
+    fn on_first_call(&self, caller_context: &CallerContext) {
+        self.first.on_first_call(caller_context);
+        self.second.on_first_call(caller_context);
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        self.first.before(caller_context);
+        self.second.before(caller_context);
+    }
+
+    fn before_with_input(&self, caller_context: &mut CallerContext, input: &I) {
+        self.first.before_with_input(caller_context, input);
+        self.second.before_with_input(caller_context, input);
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        self.first.after_timed(caller_context, result, elapsed);
+        self.second.after_timed(caller_context, result, elapsed);
+    }
+
+    fn after_owned(self, caller_context: &CallerContext, result: T, elapsed: Duration) -> T {
+        let result = self.first.after_owned(caller_context, result, elapsed);
+        self.second.after_owned(caller_context, result, elapsed)
+    }
+
+    async fn after_async(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        self.first
+            .after_async(caller_context, result, elapsed)
+            .await;
+        self.second
+            .after_async(caller_context, result, elapsed)
+            .await;
+    }
+
+    fn after_ref(&mut self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        self.first.after_ref(caller_context, result, elapsed);
+        self.second.after_ref(caller_context, result, elapsed);
+    }
+
+    fn on_reentry(&self, caller_context: &CallerContext) {
+        self.first.on_reentry(caller_context);
+        self.second.on_reentry(caller_context);
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        self.first.short_circuit(caller_context)?;
+        self.second.short_circuit(caller_context)
+    }
+
+    #[cfg(feature = "std")]
+    fn now() -> std::time::Instant {
+        A::now()
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match self.first.map_fn_name(name) {
+            Cow::Borrowed(name) => self.second.map_fn_name(name),
+            Cow::Owned(name) => Cow::Owned(self.second.map_fn_name(&name).into_owned()),
+        }
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        self.first
+            .slow_threshold()
+            .min(self.second.slow_threshold())
+    }
+
+    fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        if elapsed > self.first.slow_threshold() {
+            self.first.on_slow(caller_context, elapsed);
+        }
+        if elapsed > self.second.slow_threshold() {
+            self.second.on_slow(caller_context, elapsed);
+        }
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        self.first.on_poisoned(caller_context);
+        self.second.on_poisoned(caller_context);
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        self.first.finally(caller_context);
+        self.second.finally(caller_context);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<A, B, T, I> AsyncWrapContext<T, I> for ContextChain<A, B>
+where
+    A: AsyncWrapContext<T, I>,
+    B: AsyncWrapContext<T, I>,
+{
+    const HAS_BEFORE: bool = A::HAS_BEFORE || B::HAS_BEFORE;
+    const HAS_AFTER: bool = A::HAS_AFTER || B::HAS_AFTER;
+
+    async fn new() -> Self {
+        Self {
+            first: A::new().await,
+            second: B::new().await,
+        }
+    }
+
+    fn build_caller_context(default: CallerContext) -> CallerContext {
+        B::build_caller_context(A::build_caller_context(default))
+    }
+
+    fn on_first_call(&self, caller_context: &CallerContext) {
+        self.first.on_first_call(caller_context);
+        self.second.on_first_call(caller_context);
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        self.first.before(caller_context).await;
+        self.second.before(caller_context).await;
+    }
+
+    async fn before_with_input(&self, caller_context: &mut CallerContext, input: &I) {
+        self.first.before_with_input(caller_context, input).await;
+        self.second.before_with_input(caller_context, input).await;
+    }
+
+    fn on_cancel(&self, caller_context: &CallerContext) {
+        self.first.on_cancel(caller_context);
+        self.second.on_cancel(caller_context);
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        self.first.short_circuit(caller_context)?;
+        self.second.short_circuit(caller_context)
+    }
+
+    #[cfg(feature = "std")]
+    fn now() -> std::time::Instant {
+        A::now()
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match self.first.map_fn_name(name) {
+            Cow::Borrowed(name) => self.second.map_fn_name(name),
+            Cow::Owned(name) => Cow::Owned(self.second.map_fn_name(&name).into_owned()),
+        }
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        self.first
+            .slow_threshold()
+            .min(self.second.slow_threshold())
+    }
+
+    async fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        if elapsed > self.first.slow_threshold() {
+            self.first.on_slow(caller_context, elapsed).await;
+        }
+        if elapsed > self.second.slow_threshold() {
+            self.second.on_slow(caller_context, elapsed).await;
+        }
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        self.first.on_poisoned(caller_context);
+        self.second.on_poisoned(caller_context);
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        self.first.finally(caller_context);
+        self.second.finally(caller_context);
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        self.first
+            .after_timed(caller_context, result, elapsed)
+            .await;
+        self.second
+            .after_timed(caller_context, result, elapsed)
+            .await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContextChain;
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::sync::Mutex;
+
+    static EVENTS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+
+    fn record(event: &'static str) {
+        EVENTS.lock().unwrap().push(event);
+    }
+
+    fn take_events() -> Vec<&'static str> {
+        std::mem::take(&mut *EVENTS.lock().unwrap())
+    }
+
+    struct Timing;
+    impl SyncWrapContext<u8> for Timing {
+        fn new() -> Self {
+            record("Timing::new");
+            Self
+        }
+
+        fn before(&self, _: &mut CallerContext) {
+            record("Timing::before");
+        }
+
+        fn after(self, _: &CallerContext, _: &u8) {
+            record("Timing::after");
+        }
+    }
+    impl AsyncWrapContext<u8> for Timing {
+        async fn new() -> Self {
+            record("Timing::new");
+            Self
+        }
+
+        async fn before(&self, _: &mut CallerContext) {
+            record("Timing::before");
+        }
+
+        async fn after(self, _: &CallerContext, _: &u8) {
+            record("Timing::after");
+        }
+    }
+
+    struct Logging;
+    impl SyncWrapContext<u8> for Logging {
+        fn new() -> Self {
+            record("Logging::new");
+            Self
+        }
+
+        fn before(&self, _: &mut CallerContext) {
+            record("Logging::before");
+        }
+
+        fn after(self, _: &CallerContext, _: &u8) {
+            record("Logging::after");
+        }
+    }
+    impl AsyncWrapContext<u8> for Logging {
+        async fn new() -> Self {
+            record("Logging::new");
+            Self
+        }
+
+        async fn before(&self, _: &mut CallerContext) {
+            record("Logging::before");
+        }
+
+        async fn after(self, _: &CallerContext, _: &u8) {
+            record("Logging::after");
+        }
+    }
+
+    type Combined = ContextChain<Timing, Logging>;
+
+    const EXPECTED_EVENTS: [&str; 7] = [
+        "Timing::new",
+        "Logging::new",
+        "Timing::before",
+        "Logging::before",
+        "body",
+        "Timing::after",
+        "Logging::after",
+    ];
+
+    #[tokio::test]
+    async fn hooks_of_both_contexts_run_in_order() {
+        let result = Combined::run_sync(CallerContext::new("test"), || {
+            record("body");
+            1
+        });
+        assert_eq!(result, 1);
+        assert_eq!(take_events(), EXPECTED_EVENTS);
+
+        let result = Combined::run(CallerContext::new("test"), async {
+            record("body");
+            2
+        })
+        .await;
+        assert_eq!(result, 2);
+        assert_eq!(take_events(), EXPECTED_EVENTS);
+    }
+}
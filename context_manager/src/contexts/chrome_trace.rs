@@ -0,0 +1,341 @@
+use std::any::type_name;
+use std::borrow::Cow;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, PoisonError};
+use std::time::Instant;
+
+use crate::contexts::registry::Registry;
+use crate::contexts::Flush;
+use crate::{AsyncWrapContext, CallerContext, Clock, SyncWrapContext, SystemClock};
+
+/// Traces written by [`ChromeTraceContext`], one per configuration (keyed by its type name)
+static TRACES: Registry<Mutex<Trace>> = Registry::new();
+
+/// Identifier assigned to the next thread emitting events
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// Identifier of the current thread in the emitted events, as [`std::thread::ThreadId`] is not numeric
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+}
+
+/// State of a trace, shared by all the threads writing into it
+#[derive(Debug, Default)]
+struct Trace {
+    /// Whether the JSON array has been opened, so the following events have to be separated by a comma
+    started: bool,
+    /// Time of the first event, from which the timestamps of the events are measured
+    epoch: Option<Instant>,
+}
+
+/// Configuration of [`ChromeTraceContext`]
+///
+/// ```
+/// # use context_manager::contexts::ChromeTraceConfig;
+/// struct TraceFile;
+/// impl ChromeTraceConfig for TraceFile {
+///     fn writer() -> impl std::io::Write {
+///         std::fs::OpenOptions::new()
+///             .create(true)
+///             .append(true)
+///             .open("trace.json")
+///             .expect("the trace file can be opened")
+///     }
+/// }
+/// ```
+pub trait ChromeTraceConfig {
+    /// Writer of the emitted events
+    ///
+    /// The writer is created for each event, and it is blocking also when wrapping async functions.
+    fn writer() -> impl Write;
+
+    /// Name of the wrapped function in the emitted events, unchanged by default
+    ///
+    /// Parameters:
+    /// - `name`: Name of the wrapped function
+    #[must_use]
+    fn map_fn_name(name: &str) -> Cow<'_, str> {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Context writing the calls of the wrapped function as Chrome tracing events, for local performance investigations
+///
+/// Each call emits a begin and an end duration event (`"ph":"B"` and `"ph":"E"`), named after the wrapped function,
+/// with the timestamp in microseconds (`ts`) and the identifiers of the process (`pid`) and of the thread (`tid`).
+/// The events are written into [`ChromeTraceConfig::writer`] as a JSON array, which can be loaded by
+/// `chrome://tracing` or by [Perfetto](https://ui.perfetto.dev). The end event is emitted by the `finally` hook,
+/// so the events are paired also if the wrapped function panics or, for async functions, it is cancelled.
+/// ```
+/// # use context_manager::contexts::{ChromeTraceConfig, ChromeTraceContext};
+/// # use context_manager::wrap;
+/// struct Stderr;
+/// impl ChromeTraceConfig for Stderr {
+///     fn writer() -> impl std::io::Write {
+///         std::io::stderr()
+///     }
+/// }
+///
+/// #[wrap(ChromeTraceContext<Stderr>)]
+/// fn handle_request() {}
+///
+/// handle_request();
+/// // Close the JSON array, ie. on shutdown
+/// ChromeTraceContext::<Stderr>::finish();
+/// ```
+///
+/// The trace viewers accept a JSON array without the closing bracket, so [`ChromeTraceContext::finish`] is optional.
+/// The context implements [`Flush`], so the array can be closed once the program terminates by wrapping `main`
+/// with `#[wrap(FlushContext<ChromeTraceContext<Stderr>>)]`. The timestamps are measured via the [`Clock`] `K`,
+/// so a fake clock can be injected in tests.
+///
+/// NOTE: The events of an async function are emitted with the thread starting it, and the viewers expect
+/// the events of a thread to be nested. So, concurrent async functions are better traced on separate threads.
+#[derive(Debug)]
+pub struct ChromeTraceContext<C: ChromeTraceConfig, K: Clock = SystemClock> {
+    thread_id: u64,
+    config: PhantomData<(C, K)>,
+}
+
+impl<C: ChromeTraceConfig, K: Clock> ChromeTraceContext<C, K> {
+    fn trace() -> &'static Mutex<Trace> {
+        TRACES.get_or_register(type_name::<C>())
+    }
+
+    fn create() -> Self {
+        Self {
+            thread_id: THREAD_ID.with(|thread_id| *thread_id),
+            config: PhantomData,
+        }
+    }
+
+    /// Close the JSON array of the events written so far, if any
+    ///
+    /// The following events are written into a new JSON array.
+    pub fn finish() {
+        let mut trace = Self::trace().lock().unwrap_or_else(PoisonError::into_inner);
+        if std::mem::take(&mut trace.started) {
+            let _ = C::writer().write_all(b"\n]\n");
+        }
+    }
+
+    fn emit(&self, caller_context: &CallerContext, phase: char) {
+        // The lock is held while writing, so the events are written in the order of their timestamps
+        let mut trace = Self::trace().lock().unwrap_or_else(PoisonError::into_inner);
+        let now = K::now();
+        let ts = now.saturating_duration_since(*trace.epoch.get_or_insert(now));
+        let mut event = String::from(if std::mem::replace(&mut trace.started, true) {
+            ",\n"
+        } else {
+            "[\n"
+        });
+        event.push_str("{\"name\":\"");
+        escape_into(&mut event, caller_context.emitted_fn_name());
+        let _ = write!(
+            event,
+            "\",\"ph\":\"{phase}\",\"ts\":{},\"pid\":{},\"tid\":{}}}",
+            ts.as_secs_f64() * 1_000_000.0,
+            std::process::id(),
+            self.thread_id,
+        );
+        // Written at once, so that concurrent events are not interleaved
+        let _ = C::writer().write_all(event.as_bytes());
+    }
+}
+
+/// Append `value` to `output`, escaped as the content of a JSON string
+fn escape_into(output: &mut String, value: &str) {
+    for character in value.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            character if character.is_control() => {
+                let _ = write!(output, "\\u{:04x}", u32::from(character));
+            }
+            character => output.push(character),
+        }
+    }
+}
+
+impl<C: ChromeTraceConfig, K: Clock> Flush for ChromeTraceContext<C, K> {
+    fn flush() {
+        Self::finish();
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: ChromeTraceConfig, K: Clock, T> SyncWrapContext<T> for ChromeTraceContext<C, K> {
+    fn new() -> Self {
+        Self::create()
+    }
+
+    fn now() -> Instant {
+        K::now()
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        C::map_fn_name(name)
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        self.emit(caller_context, 'B');
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        self.emit(caller_context, 'E');
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: ChromeTraceConfig, K: Clock, T> AsyncWrapContext<T> for ChromeTraceContext<C, K> {
+    async fn new() -> Self {
+        Self::create()
+    }
+
+    fn now() -> Instant {
+        K::now()
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        C::map_fn_name(name)
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        self.emit(caller_context, 'B');
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        self.emit(caller_context, 'E');
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::{Cell, RefCell};
+    use std::io::Write;
+    use std::time::{Duration, Instant};
+
+    use super::{ChromeTraceConfig, ChromeTraceContext};
+    use crate::{AsyncWrapContext, CallerContext, Clock, SyncWrapContext};
+
+    thread_local! {
+        static WRITTEN: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+        static NOW: Cell<Option<Instant>> = const { Cell::new(None) };
+    }
+
+    /// Writer capturing the events of the current thread, with a configuration per test as the traces are per configuration
+    struct Capture<const TEST: u8>;
+    impl<const TEST: u8> Write for Capture<TEST> {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            WRITTEN.with_borrow_mut(|written| written.extend_from_slice(buf));
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<const TEST: u8> ChromeTraceConfig for Capture<TEST> {
+        fn writer() -> impl Write {
+            Self
+        }
+    }
+
+    /// Clock advancing by 10µs at each read
+    struct Ticking;
+    impl Clock for Ticking {
+        fn now() -> Instant {
+            let now = NOW.get().unwrap_or_else(Instant::now) + Duration::from_micros(10);
+            NOW.set(Some(now));
+            now
+        }
+    }
+
+    type Context<const TEST: u8> = ChromeTraceContext<Capture<TEST>, Ticking>;
+
+    fn run<const TEST: u8, T>(fn_name: &'static str, block: impl FnOnce() -> T) -> T {
+        <Context<TEST> as SyncWrapContext<_>>::run_sync(CallerContext::new(fn_name), block)
+    }
+
+    /// Events written on the current thread, as `(name, ph, ts)`, asserting that they form a JSON array
+    fn written_events<const TEST: u8>() -> Vec<(String, String, f64)> {
+        Context::<TEST>::finish();
+        let written = String::from_utf8(WRITTEN.take()).unwrap();
+        let events: Vec<serde_json::Value> = serde_json::from_str(&written).unwrap();
+        events
+            .iter()
+            .map(|event| {
+                assert_eq!(event["pid"], std::process::id());
+                assert!(event["tid"].as_u64().is_some());
+                (
+                    event["name"].as_str().unwrap().to_string(),
+                    event["ph"].as_str().unwrap().to_string(),
+                    event["ts"].as_f64().unwrap(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nested_calls_emit_paired_events() {
+        run::<0, _>("outer", || {
+            run::<0, _>("inner", || {});
+            run::<0, _>("quoted \"name\"", || {});
+        });
+
+        let events = written_events::<0>();
+        assert_eq!(
+            events
+                .iter()
+                .map(|(name, phase, _)| format!("{phase} {name}"))
+                .collect::<Vec<_>>(),
+            [
+                "B outer",
+                "B inner",
+                "E inner",
+                "B quoted \"name\"",
+                "E quoted \"name\"",
+                "E outer"
+            ]
+        );
+        let timestamps: Vec<_> = events.iter().map(|(_, _, ts)| *ts).collect();
+        assert!(
+            timestamps.windows(2).all(|pair| pair[0] < pair[1]),
+            "{timestamps:?}"
+        );
+    }
+
+    #[test]
+    fn panicking_calls_emit_the_end_event() {
+        let result = std::panic::catch_unwind(|| run::<1, ()>("panicking", || panic!("boom")));
+        assert!(result.is_err());
+        assert_eq!(
+            written_events::<1>()
+                .into_iter()
+                .map(|(name, phase, _)| (name, phase))
+                .collect::<Vec<_>>(),
+            [
+                ("panicking".to_string(), "B".to_string()),
+                ("panicking".to_string(), "E".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_calls_emit_paired_events() {
+        <Context<2> as AsyncWrapContext<_>>::run(CallerContext::new("fetch"), async {
+            tokio::task::yield_now().await;
+        })
+        .await;
+
+        let events = written_events::<2>();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].0.as_str(), events[0].1.as_str()), ("fetch", "B"));
+        assert_eq!((events[1].0.as_str(), events[1].1.as_str()), ("fetch", "E"));
+        assert!(events[0].2 < events[1].2);
+    }
+}
@@ -0,0 +1,57 @@
+use std::marker::PhantomData;
+use std::time::Instant;
+
+use crate::{AsyncWrapContext, Clock, SyncWrapContext};
+
+/// Context measuring the duration of the wrapped body via the [`Clock`] `K`, without any hook
+///
+/// It is meant to be the first context of a [`ContextChain`](crate::contexts::ContextChain), which measures
+/// the duration via the clock of its first context, so the durations provided to the other context are read
+/// via `K`. This allows to inject a fake clock into the contexts consuming durations (like
+/// [`StatsContext`](crate::contexts::StatsContext)), making them deterministic in tests.
+/// ```
+/// # use context_manager::contexts::{ClockContext, ContextChain, StatsContext};
+/// # use context_manager::{wrap, Clock};
+/// # use std::time::Instant;
+/// struct MonotonicClock;
+/// impl Clock for MonotonicClock {
+///     fn now() -> Instant {
+///         Instant::now()
+///     }
+/// }
+///
+/// #[wrap(ContextChain<ClockContext<MonotonicClock>, StatsContext>)]
+/// fn foo() {}
+/// ```
+#[derive(Debug)]
+pub struct ClockContext<K: Clock> {
+    clock: PhantomData<K>,
+}
+
+#[diagnostic::do_not_recommend]
+impl<K: Clock, T> SyncWrapContext<T> for ClockContext<K> {
+    const HAS_BEFORE: bool = false;
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self { clock: PhantomData }
+    }
+
+    fn now() -> Instant {
+        K::now()
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<K: Clock, T> AsyncWrapContext<T> for ClockContext<K> {
+    const HAS_BEFORE: bool = false;
+    const HAS_AFTER: bool = false;
+
+    async fn new() -> Self {
+        Self { clock: PhantomData }
+    }
+
+    fn now() -> Instant {
+        K::now()
+    }
+}
@@ -0,0 +1,94 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::contexts::registry::Registry;
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+static COUNTERS: Registry<AtomicU64> = Registry::new();
+
+/// Context counting the calls of each wrapped function
+///
+/// Counters are kept per function name, and are shared by all the functions with the same name.
+/// ```
+/// # use context_manager::contexts::CounterContext;
+/// # use context_manager::wrap;
+/// #[wrap(CounterContext)]
+/// fn counted() {}
+///
+/// counted();
+/// counted();
+/// assert_eq!(CounterContext::count("counted"), 2);
+/// ```
+#[derive(Debug)]
+pub struct CounterContext;
+
+impl CounterContext {
+    /// Number of calls of the wrapped functions named `fn_name`
+    #[must_use]
+    pub fn count(fn_name: &str) -> u64 {
+        COUNTERS
+            .get(fn_name)
+            .map_or(0, |counter| counter.load(Ordering::Relaxed))
+    }
+
+    fn increment(caller_context: &CallerContext) {
+        COUNTERS
+            .get_or_register(caller_context.emitted_fn_name())
+            .fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for CounterContext {
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        Self::increment(caller_context);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for CounterContext {
+    const HAS_AFTER: bool = false;
+
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        Self::increment(caller_context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CounterContext;
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+    #[test]
+    fn sync_calls_are_counted() {
+        assert_eq!(CounterContext::count("counter_sync"), 0);
+        for _ in 0..10 {
+            <CounterContext as SyncWrapContext<_>>::run_sync(
+                CallerContext::new("counter_sync"),
+                || (),
+            );
+        }
+        assert_eq!(CounterContext::count("counter_sync"), 10);
+    }
+
+    #[tokio::test]
+    async fn async_calls_are_counted() {
+        for _ in 0..5 {
+            <CounterContext as AsyncWrapContext<_>>::run(
+                CallerContext::new("counter_async"),
+                async {},
+            )
+            .await;
+        }
+        assert_eq!(CounterContext::count("counter_async"), 5);
+    }
+}
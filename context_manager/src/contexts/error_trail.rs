@@ -0,0 +1,207 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::call_stack::{in_call_stack_async, in_call_stack_sync, Frame};
+use crate::{AsyncBlock, CallerContext, SyncBlock, SyncWrapContext};
+
+/// Error recording the wrapped functions it is propagated through, as done by [`ErrorTrailContext`]
+pub trait Trailable {
+    /// Record that the error is returned by the wrapped function named `fn_name`
+    fn push_trail(&mut self, fn_name: &'static str);
+}
+
+/// Error carrying the trail of the wrapped functions it is propagated through
+///
+/// The trail lists the functions from the innermost, where the error is created, to the outermost.
+/// Any error can be converted into a `Trail` (with an empty trail), so `?` can be used on its results.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trail<E> {
+    error: E,
+    trail: Vec<&'static str>,
+}
+
+impl<E> Trail<E> {
+    /// Create a new `Trail` of `error`, with an empty trail
+    #[must_use]
+    pub const fn new(error: E) -> Self {
+        Self {
+            error,
+            trail: Vec::new(),
+        }
+    }
+
+    /// The wrapped error
+    #[must_use]
+    pub const fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// The wrapped error, discarding the trail
+    #[must_use]
+    pub fn into_error(self) -> E {
+        self.error
+    }
+
+    /// Names of the wrapped functions the error is propagated through, from the innermost to the outermost
+    #[must_use]
+    pub fn trail(&self) -> &[&'static str] {
+        &self.trail
+    }
+}
+
+impl<E> From<E> for Trail<E> {
+    fn from(error: E) -> Self {
+        Self::new(error)
+    }
+}
+
+impl<E> Trailable for Trail<E> {
+    fn push_trail(&mut self, fn_name: &'static str) {
+        self.trail.push(fn_name);
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for Trail<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.error)?;
+        if !self.trail.is_empty() {
+            write!(f, " (trail: {})", self.trail.join(" -> "))?;
+        }
+        Ok(())
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for Trail<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Context recording the name of the wrapped function into the errors it returns
+///
+/// The context can wrap only functions returning `Result<T, E>`, with `E` implementing [`Trailable`] (like [`Trail`]).
+/// Wrapping all the functions an error is propagated through leads to a breadcrumb trail of them.
+/// ```
+/// # use context_manager::wrap;
+/// # use context_manager::contexts::{ErrorTrailContext, Trail};
+/// #[wrap(ErrorTrailContext)]
+/// fn parse(value: &str) -> Result<u8, Trail<std::num::ParseIntError>> {
+///     Ok(value.parse()?)
+/// }
+///
+/// #[wrap(ErrorTrailContext)]
+/// fn load_config() -> Result<u8, Trail<std::num::ParseIntError>> {
+///     parse("a")
+/// }
+///
+/// let error = load_config().unwrap_err();
+/// assert_eq!(error.trail(), ["parse", "load_config"]);
+/// assert_eq!(error.to_string(), "invalid digit found in string (trail: parse -> load_config)");
+/// ```
+#[derive(Debug)]
+pub struct ErrorTrailContext;
+
+impl ErrorTrailContext {
+    fn push_trail<T, E: Trailable>(
+        caller_context: &CallerContext,
+        mut result: Result<T, E>,
+    ) -> Result<T, E> {
+        if let Err(error) = &mut result {
+            error.push_trail(caller_context.fn_name());
+        }
+        result
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T, E: Trailable> SyncWrapContext<Result<T, E>> for ErrorTrailContext {
+    fn new() -> Self {
+        Self
+    }
+
+    fn run_sync(
+        caller_context: CallerContext,
+        block: impl SyncBlock<Result<T, E>>,
+    ) -> Result<T, E> {
+        let result = in_call_stack_sync(Frame::new::<Self>(&caller_context), || {
+            block.call(&caller_context)
+        });
+        Self::push_trail(&caller_context, result)
+    }
+
+    async fn run_async(
+        caller_context: CallerContext,
+        block: impl AsyncBlock<Result<T, E>>,
+    ) -> Result<T, E> {
+        let result = in_call_stack_async(
+            Frame::new::<Self>(&caller_context),
+            block.call(&caller_context),
+        )
+        .await;
+        Self::push_trail(&caller_context, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorTrailContext, Trail, Trailable};
+    use crate::{CallerContext, SyncWrapContext};
+
+    type Result<T> = core::result::Result<T, Trail<&'static str>>;
+
+    fn run<T>(fn_name: &'static str, block: impl FnOnce() -> Result<T>) -> Result<T> {
+        ErrorTrailContext::run_sync(CallerContext::new(fn_name), block)
+    }
+
+    #[test]
+    fn nested_calls_are_recorded_in_order() {
+        let result: Result<()> = run("outer", || {
+            run("middle", || {
+                run("inner", || Err("boom".into()))?;
+                Ok(())
+            })
+        });
+
+        let error = result.unwrap_err();
+        assert_eq!(error.error(), &"boom");
+        assert_eq!(error.trail(), ["inner", "middle", "outer"]);
+        assert_eq!(error.to_string(), "boom (trail: inner -> middle -> outer)");
+    }
+
+    #[test]
+    fn successful_calls_are_not_affected() {
+        assert_eq!(run("outer", || run("inner", || Ok(42))), Ok(42));
+    }
+
+    #[test]
+    fn custom_trailable_errors() {
+        #[derive(Debug, Default)]
+        struct Breadcrumbs(String);
+        impl Trailable for Breadcrumbs {
+            fn push_trail(&mut self, fn_name: &'static str) {
+                self.0.push_str(fn_name);
+                self.0.push('/');
+            }
+        }
+
+        let result = ErrorTrailContext::run_sync(CallerContext::new("outer"), || {
+            ErrorTrailContext::run_sync(CallerContext::new("inner"), || {
+                Err::<(), _>(Breadcrumbs::default())
+            })
+        });
+        assert_eq!(result.unwrap_err().0, "inner/outer/");
+    }
+
+    #[tokio::test]
+    async fn async_calls_are_recorded() {
+        let result: Result<()> = ErrorTrailContext::run_async(CallerContext::new("outer"), async {
+            ErrorTrailContext::run_async(CallerContext::new("inner"), async {
+                tokio::task::yield_now().await;
+                Err("boom".into())
+            })
+            .await
+        })
+        .await;
+        assert_eq!(result.unwrap_err().trail(), ["inner", "outer"]);
+    }
+}
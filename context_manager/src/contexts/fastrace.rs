@@ -0,0 +1,87 @@
+use std::borrow::Cow;
+
+use fastrace::future::FutureExt;
+use fastrace::local::LocalSpan;
+use fastrace::Span;
+
+use crate::call_stack::{in_call_stack_async, in_call_stack_sync, Frame};
+use crate::{AsyncBlock, AsyncWrapContext, CallerContext, SyncBlock, SyncWrapContext};
+
+/// Context executing the wrapped function within a [`fastrace`] span (requires the `fastrace` feature)
+///
+/// The span is named after the wrapped function (refer to [`CallerContext::emitted_fn_name`]) and it is a child
+/// of the local parent of the calling thread, as done by `#[fastrace::trace]`:
+/// * synchronous bodies are executed within a [`LocalSpan`], which is pushed onto the thread-local span stack
+///   of `fastrace`, so the spans created by the body are its children
+/// * asynchronous bodies are executed within a [`Span`], which is set as local parent on every poll of the body,
+///   so the span is correctly reported even if the runtime moves the future across threads between polls
+///
+/// ```
+/// # use context_manager::contexts::FastraceContext;
+/// # use context_manager::{async_wrap, wrap};
+/// #[wrap(FastraceContext)]
+/// fn parse(value: &str) -> Option<u64> {
+///     value.parse().ok()
+/// }
+///
+/// #[async_wrap(FastraceContext)]
+/// async fn load(id: u64) -> Option<u64> {
+///     Some(id)
+/// }
+/// ```
+///
+/// NOTE: The spans are recorded only if the `enable` feature of `fastrace` is enabled by the application,
+/// and they are reported only if they are within a root span (refer to [`Span::root`]).
+#[derive(Debug)]
+pub struct FastraceContext;
+
+impl FastraceContext {
+    /// Name of the span, borrowing the name of the wrapped function if not mapped by the contexts
+    fn span_name(caller_context: &CallerContext) -> Cow<'static, str> {
+        let fn_name = caller_context.fn_name();
+        if caller_context.emitted_fn_name() == fn_name {
+            Cow::Borrowed(fn_name)
+        } else {
+            Cow::Owned(caller_context.emitted_fn_name().to_owned())
+        }
+    }
+
+    async fn run_in_span<T>(caller_context: &CallerContext, block: impl AsyncBlock<T>) -> T {
+        let span = Span::enter_with_local_parent(Self::span_name(caller_context));
+        in_call_stack_async(
+            Frame::new::<Self>(caller_context),
+            block.call(caller_context),
+        )
+        .in_span(span)
+        .await
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for FastraceContext {
+    fn new() -> Self {
+        Self
+    }
+
+    fn run_sync(caller_context: CallerContext, block: impl SyncBlock<T>) -> T {
+        let _span = LocalSpan::enter_with_local_parent(Self::span_name(&caller_context));
+        in_call_stack_sync(Frame::new::<Self>(&caller_context), || {
+            block.call(&caller_context)
+        })
+    }
+
+    async fn run_async(caller_context: CallerContext, block: impl AsyncBlock<T>) -> T {
+        Self::run_in_span(&caller_context, block).await
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for FastraceContext {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn run(caller_context: CallerContext, block: impl AsyncBlock<T>) -> T {
+        Self::run_in_span(&caller_context, block).await
+    }
+}
@@ -0,0 +1,143 @@
+use std::future::Future;
+use std::marker::PhantomData;
+use std::pin::{pin, Pin};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::call_stack::{in_call_stack_async, Frame};
+use crate::{AsyncBlock, AsyncWrapContext, CallerContext, Clock, SystemClock};
+
+/// Configuration of [`FirstPollContext`]
+///
+/// ```
+/// # use std::time::Duration;
+/// # use context_manager::contexts::FirstPollConfig;
+/// struct PrintFirstPoll;
+/// impl FirstPollConfig for PrintFirstPoll {
+///     fn on_first_poll(fn_name: &'static str, first_poll: Duration, total: Duration) {
+///         eprintln!("{fn_name} blocked for {first_poll:?} before its first await (out of {total:?})");
+///     }
+/// }
+/// ```
+pub trait FirstPollConfig {
+    /// Execute the code once the wrapped future completes
+    ///
+    /// Parameters:
+    /// - `fn_name`: Name of the wrapped function
+    /// - `first_poll`: The time spent in the first poll of the wrapped future, ie. until its first yield
+    /// - `total`: The time spent executing the wrapped future, from its first poll to its completion
+    fn on_first_poll(fn_name: &'static str, first_poll: Duration, total: Duration);
+}
+
+/// Context measuring the synchronous prefix of async functions, ie. the time spent until their first yield
+///
+/// Async functions doing expensive synchronous work before their first `.await` block the executor thread,
+/// starving the other tasks. The context reports the duration of the first poll of the wrapped future
+/// (the whole execution, if the future never yields) together with the total duration, via [`FirstPollConfig::on_first_poll`].
+/// The durations are measured via the [`Clock`] `K`, so a fake clock can be injected in tests.
+/// ```
+/// # use std::time::Duration;
+/// # use context_manager::contexts::{FirstPollConfig, FirstPollContext};
+/// # use context_manager::async_wrap;
+/// struct PrintFirstPoll;
+/// impl FirstPollConfig for PrintFirstPoll {
+///     fn on_first_poll(fn_name: &'static str, first_poll: Duration, total: Duration) {
+///         eprintln!("{fn_name} blocked for {first_poll:?} before its first await (out of {total:?})");
+///     }
+/// }
+///
+/// #[async_wrap(FirstPollContext<PrintFirstPoll>)]
+/// async fn handle_request() {}
+/// ```
+#[derive(Debug)]
+pub struct FirstPollContext<C: FirstPollConfig, K: Clock = SystemClock> {
+    config: PhantomData<(C, K)>,
+}
+
+/// Future measuring the duration of the first poll of the wrapped future
+struct FirstPoll<'a, F, K> {
+    inner: Pin<&'a mut F>,
+    elapsed: Option<Duration>,
+    clock: PhantomData<fn() -> K>,
+}
+
+impl<F: Future, K: Clock> Future for FirstPoll<'_, F, K> {
+    type Output = F::Output;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.elapsed.is_some() {
+            return self.inner.as_mut().poll(cx);
+        }
+        let start = K::now();
+        let poll = self.inner.as_mut().poll(cx);
+        self.elapsed = Some(K::now().saturating_duration_since(start));
+        poll
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: FirstPollConfig, K: Clock, T> AsyncWrapContext<T> for FirstPollContext<C, K> {
+    async fn new() -> Self {
+        Self {
+            config: PhantomData,
+        }
+    }
+
+    fn now() -> Instant {
+        K::now()
+    }
+
+    async fn run(caller_context: CallerContext, block: impl AsyncBlock<T>) -> T {
+        let mut first_poll = FirstPoll::<_, K> {
+            inner: pin!(block.call(&caller_context)),
+            elapsed: None,
+            clock: PhantomData,
+        };
+        let start = K::now();
+        let result =
+            in_call_stack_async(Frame::new::<Self>(&caller_context), &mut first_poll).await;
+        C::on_first_poll(
+            caller_context.fn_name(),
+            first_poll.elapsed.unwrap_or_default(),
+            K::now().saturating_duration_since(start),
+        );
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::{FirstPollConfig, FirstPollContext};
+    use crate::{AsyncWrapContext, CallerContext};
+
+    static REPORTS: Mutex<Vec<(&str, Duration, Duration)>> = Mutex::new(Vec::new());
+
+    struct Record;
+    impl FirstPollConfig for Record {
+        fn on_first_poll(fn_name: &'static str, first_poll: Duration, total: Duration) {
+            REPORTS.lock().unwrap().push((fn_name, first_poll, total));
+        }
+    }
+
+    #[tokio::test]
+    async fn first_poll_is_measured_separately() {
+        let result = <FirstPollContext<Record> as AsyncWrapContext<_>>::run(
+            CallerContext::new("first_poll"),
+            async {
+                std::thread::sleep(Duration::from_millis(20));
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                42
+            },
+        )
+        .await;
+        assert_eq!(result, 42);
+
+        let (fn_name, first_poll, total) = REPORTS.lock().unwrap()[0];
+        assert_eq!(fn_name, "first_poll");
+        assert!(first_poll >= Duration::from_millis(20), "{first_poll:?}");
+        assert!(total >= first_poll + Duration::from_millis(50), "{total:?}");
+    }
+}
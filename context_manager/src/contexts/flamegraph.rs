@@ -0,0 +1,183 @@
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::ops::Bound;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+
+use crate::call_stack;
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Total duration, in nanoseconds, of the calls of each folded stack (ie. `outer;inner`)
+static STACKS: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// Context accumulating the duration of the wrapped functions per call stack, to render flamegraphs
+///
+/// Each call is recorded with the stack of the wrapped functions executing it, as tracked by
+/// [`current_fn_name`](crate::current_fn_name), so only wrapped functions are part of the stacks.
+/// The collected stacks are written by [`FlamegraphContext::write_folded`] in the folded format
+/// expected by [`inferno`](https://crates.io/crates/inferno) (ie. `inferno-flamegraph`), as one
+/// `outer;inner <nanoseconds>` line per stack.
+/// ```
+/// # use context_manager::contexts::FlamegraphContext;
+/// # use context_manager::wrap;
+/// #[wrap(FlamegraphContext)]
+/// fn outer() {
+///     inner();
+/// }
+///
+/// #[wrap(FlamegraphContext)]
+/// fn inner() {}
+///
+/// outer();
+/// let mut folded = Vec::new();
+/// FlamegraphContext::write_folded(&mut folded).unwrap();
+/// assert!(String::from_utf8(folded).unwrap().contains("outer;inner "));
+/// ```
+///
+/// The reported duration of each stack excludes the one of the stacks nested in it, as the flamegraph
+/// sums them up. Consecutive frames of the same function, as for stacked decorators, are reported once
+/// (so the calls of directly recursive functions are accumulated into the same stack).
+#[derive(Debug)]
+pub struct FlamegraphContext;
+
+impl FlamegraphContext {
+    /// Write the collected stacks, in the folded format, into `writer`
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `writer`
+    pub fn write_folded(mut writer: impl Write) -> io::Result<()> {
+        let stacks = STACKS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        for (stack, nanos) in &stacks {
+            let nested: u64 = stacks
+                .range::<str, _>((Bound::Excluded(stack.as_str()), Bound::Unbounded))
+                .take_while(|(nested, _)| nested.starts_with(stack.as_str()))
+                .filter(|(nested, _)| {
+                    nested[stack.len()..]
+                        .strip_prefix(';')
+                        .is_some_and(|name| !name.contains(';'))
+                })
+                .map(|(_, nanos)| nanos)
+                .sum();
+            writeln!(writer, "{stack} {}", nanos.saturating_sub(nested))?;
+        }
+        Ok(())
+    }
+
+    /// Discard the collected stacks
+    pub fn clear() {
+        STACKS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clear();
+    }
+
+    fn record(caller_context: &CallerContext, elapsed: Duration) {
+        let stack = call_stack::emitted_fn_names();
+        let mut names: Vec<&str> = stack.iter().map(AsRef::as_ref).collect();
+        names.push(caller_context.emitted_fn_name());
+        names.dedup();
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+
+        let mut stacks = STACKS.lock().unwrap_or_else(PoisonError::into_inner);
+        let total = stacks.entry(names.join(";")).or_default();
+        *total = total.saturating_add(nanos);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for FlamegraphContext {
+    const HAS_BEFORE: bool = false;
+
+    fn new() -> Self {
+        Self
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::record(caller_context, elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for FlamegraphContext {
+    const HAS_BEFORE: bool = false;
+
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::record(caller_context, elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FlamegraphContext;
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::collections::BTreeMap;
+    use std::time::Duration;
+
+    fn run<T>(fn_name: &'static str, block: impl FnOnce() -> T) -> T {
+        <FlamegraphContext as SyncWrapContext<_>>::run_sync(CallerContext::new(fn_name), block)
+    }
+
+    fn folded() -> BTreeMap<String, u64> {
+        let mut folded = Vec::new();
+        FlamegraphContext::write_folded(&mut folded).unwrap();
+        String::from_utf8(folded)
+            .unwrap()
+            .lines()
+            .map(|line| {
+                let (stack, nanos) = line.rsplit_once(' ').unwrap();
+                (stack.to_string(), nanos.parse().unwrap())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn nested_calls_are_folded() {
+        run("flame_outer", || {
+            run("flame_inner", || {
+                std::thread::sleep(Duration::from_millis(2));
+            });
+            run("flame_inner", || {
+                std::thread::sleep(Duration::from_millis(2));
+            });
+            run("flame_other", || {});
+        });
+
+        let folded = folded();
+        let inner = folded["flame_outer;flame_inner"];
+        assert!(inner >= 4_000_000, "{inner}");
+        assert!(folded.contains_key("flame_outer"));
+        assert!(folded.contains_key("flame_outer;flame_other"));
+        // The duration of the nested calls is not counted twice
+        assert!(folded["flame_outer"] < inner);
+    }
+
+    #[test]
+    fn stacked_contexts_are_reported_once() {
+        run("flame_stacked", || run("flame_stacked", || {}));
+        assert!(folded().contains_key("flame_stacked"));
+        assert!(!folded()
+            .keys()
+            .any(|stack| stack.contains("flame_stacked;")));
+    }
+
+    #[tokio::test]
+    async fn async_nested_calls_are_folded() {
+        <FlamegraphContext as AsyncWrapContext<_>>::run(CallerContext::new("flame_async"), async {
+            <FlamegraphContext as AsyncWrapContext<_>>::run(
+                CallerContext::new("flame_async_inner"),
+                tokio::task::yield_now(),
+            )
+            .await;
+        })
+        .await;
+        assert!(folded().contains_key("flame_async;flame_async_inner"));
+    }
+}
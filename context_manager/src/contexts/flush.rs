@@ -0,0 +1,143 @@
+use core::marker::PhantomData;
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Buffer flushed by [`FlushContext`]
+///
+/// ```
+/// # use context_manager::contexts::Flush;
+/// # use std::io::Write;
+/// struct Stdout;
+/// impl Flush for Stdout {
+///     fn flush() {
+///         let _ = std::io::stdout().flush();
+///     }
+/// }
+/// ```
+pub trait Flush {
+    /// Flush the buffered output
+    ///
+    /// The function is synchronous, as it has to be executed also while unwinding or while dropping
+    /// a cancelled future, where futures cannot be awaited.
+    fn flush();
+}
+
+/// Context flushing the buffer `F` once the wrapped body terminates
+///
+/// The flush relies on the `finally` hook, so it happens exactly once per call, whether the body
+/// completes (including returning an error), panics or, for async functions, it is cancelled.
+/// ```
+/// # use context_manager::contexts::{Flush, FlushContext};
+/// # use context_manager::wrap;
+/// # struct Metrics;
+/// # impl Flush for Metrics { fn flush() {} }
+/// #[wrap(FlushContext<Metrics>)]
+/// fn handle_request() {}
+/// ```
+///
+/// NOTE: When stacked with contexts writing into the buffer, `FlushContext` should be the outermost one,
+/// so that their `after` hooks are executed before the flush.
+#[derive(Debug)]
+pub struct FlushContext<F: Flush> {
+    buffer: PhantomData<F>,
+}
+
+#[diagnostic::do_not_recommend]
+impl<F: Flush, T> SyncWrapContext<T> for FlushContext<F> {
+    const HAS_BEFORE: bool = false;
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self {
+            buffer: PhantomData,
+        }
+    }
+
+    fn finally(&self, _: &CallerContext) {
+        F::flush();
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<F: Flush, T> AsyncWrapContext<T> for FlushContext<F> {
+    const HAS_BEFORE: bool = false;
+    const HAS_AFTER: bool = false;
+
+    async fn new() -> Self {
+        Self {
+            buffer: PhantomData,
+        }
+    }
+
+    fn finally(&self, _: &CallerContext) {
+        F::flush();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Flush, FlushContext};
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    static FLUSHES: AtomicUsize = AtomicUsize::new(0);
+
+    struct MockBuffer;
+    impl Flush for MockBuffer {
+        fn flush() {
+            FLUSHES.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    type Flushing = FlushContext<MockBuffer>;
+
+    fn take_flushes() -> usize {
+        FLUSHES.swap(0, Ordering::Relaxed)
+    }
+
+    #[tokio::test]
+    async fn flush_runs_once_per_call() {
+        assert_eq!(Flushing::run_sync(CallerContext::new("test"), || 1), 1);
+        assert_eq!(take_flushes(), 1);
+
+        let result: Result<(), &str> =
+            Flushing::run_sync(CallerContext::new("test"), || Err("error"));
+        assert_eq!(result, Err("error"));
+        assert_eq!(take_flushes(), 1);
+
+        let result = catch_unwind(AssertUnwindSafe(|| {
+            Flushing::run_sync(CallerContext::new("test"), || panic!("panic"));
+        }));
+        assert!(result.is_err());
+        assert_eq!(take_flushes(), 1);
+
+        assert_eq!(
+            Flushing::run(CallerContext::new("test"), async { 1 }).await,
+            1
+        );
+        assert_eq!(take_flushes(), 1);
+
+        let result: Result<(), &str> =
+            Flushing::run(CallerContext::new("test"), async { Err("error") }).await;
+        assert_eq!(result, Err("error"));
+        assert_eq!(take_flushes(), 1);
+
+        tokio::select! {
+            () = Flushing::run(CallerContext::new("test"), tokio::time::sleep(Duration::from_secs(10))) => unreachable!(),
+            () = tokio::time::sleep(Duration::from_millis(1)) => {},
+        }
+        assert_eq!(take_flushes(), 1);
+
+        let result = tokio::spawn(<Flushing as AsyncWrapContext<()>>::run(
+            CallerContext::new("test"),
+            async {
+                panic!("panic");
+            },
+        ))
+        .await;
+        assert!(result.is_err());
+        assert_eq!(take_flushes(), 1);
+    }
+}
@@ -0,0 +1,135 @@
+use std::sync::atomic::{AtomicI64, Ordering};
+
+use crate::contexts::registry::Registry;
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+static GAUGES: Registry<AtomicI64> = Registry::new();
+
+/// Context tracking the number of in-flight calls of each wrapped function
+///
+/// The gauge is incremented in `before` and decremented in `finally`, so it is accurate even if the
+/// wrapped body panics or it is cancelled. Gauges are kept per function name, and are shared by all the
+/// functions with the same name.
+/// ```
+/// # use context_manager::contexts::InflightContext;
+/// # use context_manager::wrap;
+/// #[wrap(InflightContext)]
+/// fn tracked() -> i64 {
+///     InflightContext::current("tracked")
+/// }
+///
+/// assert_eq!(tracked(), 1);
+/// assert_eq!(InflightContext::current("tracked"), 0);
+/// ```
+#[derive(Debug)]
+pub struct InflightContext;
+
+impl InflightContext {
+    /// Number of in-flight calls of the wrapped functions named `fn_name`
+    #[must_use]
+    pub fn current(fn_name: &str) -> i64 {
+        GAUGES
+            .get(fn_name)
+            .map_or(0, |gauge| gauge.load(Ordering::Relaxed))
+    }
+
+    fn add(caller_context: &CallerContext, delta: i64) {
+        GAUGES
+            .get_or_register(caller_context.fn_name())
+            .fetch_add(delta, Ordering::Relaxed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for InflightContext {
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        Self::add(caller_context, 1);
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        Self::add(caller_context, -1);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for InflightContext {
+    const HAS_AFTER: bool = false;
+
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        Self::add(caller_context, 1);
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        Self::add(caller_context, -1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InflightContext;
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::sync::atomic::{AtomicI64, Ordering};
+    use std::sync::Barrier;
+
+    #[test]
+    fn concurrent_sync_calls_are_tracked() {
+        const CONCURRENCY: usize = 4;
+        static PEAK: AtomicI64 = AtomicI64::new(0);
+        let barrier = Barrier::new(CONCURRENCY);
+
+        std::thread::scope(|scope| {
+            for _ in 0..CONCURRENCY {
+                scope.spawn(|| {
+                    <InflightContext as SyncWrapContext<_>>::run_sync(
+                        CallerContext::new("inflight_sync"),
+                        || {
+                            // All the calls are in-flight between the two barriers
+                            barrier.wait();
+                            PEAK.fetch_max(
+                                InflightContext::current("inflight_sync"),
+                                Ordering::Relaxed,
+                            );
+                            barrier.wait();
+                        },
+                    );
+                });
+            }
+        });
+
+        assert_eq!(PEAK.load(Ordering::Relaxed), 4);
+        assert_eq!(InflightContext::current("inflight_sync"), 0);
+    }
+
+    #[test]
+    fn panicking_calls_are_not_leaked() {
+        let result = std::panic::catch_unwind(|| {
+            <InflightContext as SyncWrapContext<()>>::run_sync(
+                CallerContext::new("inflight_panic"),
+                || panic!("boom"),
+            );
+        });
+        assert!(result.is_err());
+        assert_eq!(InflightContext::current("inflight_panic"), 0);
+    }
+
+    #[tokio::test]
+    async fn cancelled_async_calls_are_not_leaked() {
+        let pending = <InflightContext as AsyncWrapContext<()>>::run(
+            CallerContext::new("inflight_async"),
+            std::future::pending(),
+        );
+        let result = tokio::time::timeout(std::time::Duration::from_millis(10), pending).await;
+        assert!(result.is_err());
+        assert_eq!(InflightContext::current("inflight_async"), 0);
+    }
+}
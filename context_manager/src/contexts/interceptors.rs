@@ -0,0 +1,76 @@
+use crate::interceptors::{snapshot, Interceptors};
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Context invoking the interceptors registered in [`interceptors`](crate::interceptors) around the wrapped function
+///
+/// Each call keeps the interceptors registered when it starts, so registering new ones does not affect the calls
+/// in progress. Refer to [`interceptors::register`](crate::interceptors::register) for the invocation order.
+/// ```
+/// # use context_manager::contexts::RegistryContext;
+/// # use context_manager::wrap;
+/// #[wrap(RegistryContext)]
+/// fn handle_request() {}
+/// ```
+#[derive(Debug)]
+pub struct RegistryContext {
+    interceptors: Option<Interceptors>,
+}
+
+impl RegistryContext {
+    fn new() -> Self {
+        Self {
+            interceptors: snapshot(),
+        }
+    }
+
+    fn before(&self, caller_context: &CallerContext) {
+        for (_, interceptor) in self
+            .interceptors
+            .iter()
+            .flat_map(|interceptors| interceptors.iter())
+        {
+            interceptor.before(caller_context);
+        }
+    }
+
+    fn after(&self, caller_context: &CallerContext) {
+        for (_, interceptor) in self
+            .interceptors
+            .iter()
+            .flat_map(|interceptors| interceptors.iter())
+            .rev()
+        {
+            interceptor.after(caller_context);
+        }
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for RegistryContext {
+    fn new() -> Self {
+        Self::new()
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        Self::before(self, caller_context);
+    }
+
+    fn after(self, caller_context: &CallerContext, _: &T) {
+        Self::after(&self, caller_context);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for RegistryContext {
+    async fn new() -> Self {
+        Self::new()
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        Self::before(self, caller_context);
+    }
+
+    async fn after(self, caller_context: &CallerContext, _: &T) {
+        Self::after(&self, caller_context);
+    }
+}
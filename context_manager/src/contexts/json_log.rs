@@ -0,0 +1,238 @@
+use std::borrow::Cow;
+use std::io::Write;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Configuration of [`JsonLogContext`]
+///
+/// ```
+/// # use context_manager::contexts::JsonLogConfig;
+/// struct Stdout;
+/// impl JsonLogConfig for Stdout {
+///     fn writer() -> impl std::io::Write {
+///         std::io::stdout()
+///     }
+/// }
+/// ```
+pub trait JsonLogConfig {
+    /// Writer of the emitted lines, the standard error by default
+    ///
+    /// The writer is created for each line, and it is blocking also when wrapping async functions
+    /// (as for the records emitted via the `log` crate).
+    #[must_use]
+    fn writer() -> impl Write {
+        std::io::stderr()
+    }
+
+    /// Name of the wrapped function in the emitted lines, unchanged by default
+    ///
+    /// Parameters:
+    /// - `name`: Name of the wrapped function
+    #[must_use]
+    fn map_fn_name(name: &str) -> Cow<'_, str> {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Default configuration of [`JsonLogContext`], writing the lines to the standard error
+#[derive(Debug)]
+pub struct DefaultJsonLogConfig;
+impl JsonLogConfig for DefaultJsonLogConfig {}
+
+/// Outcome of the wrapped function, as reported by [`JsonLogContext`]
+///
+/// It is implemented for [`Result`], reporting `"ok"` or `"err"`, and for `()`, reporting `"ok"`.
+/// Implementing it allows to use [`JsonLogContext`] on functions returning other types.
+pub trait Outcome {
+    /// Outcome represented by the returned value
+    fn outcome(&self) -> &'static str;
+}
+
+impl<T, E> Outcome for Result<T, E> {
+    fn outcome(&self) -> &'static str {
+        if self.is_ok() {
+            "ok"
+        } else {
+            "err"
+        }
+    }
+}
+
+impl Outcome for () {
+    fn outcome(&self) -> &'static str {
+        "ok"
+    }
+}
+
+/// Context emitting a JSON line per call of the wrapped function (requires the `json` feature)
+///
+/// The line is a JSON object, like `{"fn":"parse","module":"my_crate","elapsed_ms":0.12,"outcome":"err"}`,
+/// written once the wrapped function returns, which makes it suitable for log aggregation.
+/// The `module` is `null` if unknown, and the `outcome` is provided by the [`Outcome`] of the returned value.
+/// ```
+/// # use context_manager::contexts::JsonLogContext;
+/// # use context_manager::wrap;
+/// #[wrap(JsonLogContext)]
+/// fn parse(value: &str) -> Result<u64, std::num::ParseIntError> {
+///     value.parse()
+/// }
+/// ```
+///
+/// The writer of the lines, and the name of the function they report, can be customised via [`JsonLogConfig`]. Write errors are ignored,
+/// as they should not affect the wrapped function.
+#[derive(Debug)]
+pub struct JsonLogContext<C: JsonLogConfig = DefaultJsonLogConfig>(PhantomData<C>);
+
+impl<C: JsonLogConfig> JsonLogContext<C> {
+    fn emit(caller_context: &CallerContext, outcome: &str, elapsed: Duration) {
+        let value = serde_json::json!({
+            "fn": caller_context.emitted_fn_name(),
+            "module": caller_context.module_path(),
+            "elapsed_ms": elapsed.as_secs_f64() * 1_000.0,
+            "outcome": outcome,
+        });
+        let mut line = value.to_string();
+        line.push('\n');
+        // Written at once, so that concurrent lines are not interleaved
+        let _ = C::writer().write_all(line.as_bytes());
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: JsonLogConfig, T: Outcome> SyncWrapContext<T> for JsonLogContext<C> {
+    const HAS_BEFORE: bool = false;
+
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        C::map_fn_name(name)
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        Self::emit(caller_context, result.outcome(), elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: JsonLogConfig, T: Outcome> AsyncWrapContext<T> for JsonLogContext<C> {
+    const HAS_BEFORE: bool = false;
+
+    async fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        C::map_fn_name(name)
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        Self::emit(caller_context, result.outcome(), elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{JsonLogConfig, JsonLogContext};
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::borrow::Cow;
+    use std::cell::RefCell;
+    use std::io::Write;
+
+    thread_local! {
+        static WRITTEN: RefCell<Vec<u8>> = const { RefCell::new(Vec::new()) };
+    }
+
+    struct Capture;
+    impl Write for Capture {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            WRITTEN.with_borrow_mut(|written| written.extend_from_slice(buf));
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl JsonLogConfig for Capture {
+        fn writer() -> impl Write {
+            Self
+        }
+    }
+
+    fn written_lines() -> Vec<serde_json::Value> {
+        let written = WRITTEN.take();
+        String::from_utf8(written)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn sync_calls_emit_a_line_each() {
+        let caller_context = || CallerContext::new("parse").with_location("my_crate", "file.rs", 1);
+        let _ = <JsonLogContext<Capture> as SyncWrapContext<_>>::run_sync(caller_context(), || {
+            "1".parse::<u8>()
+        });
+        let _ = <JsonLogContext<Capture> as SyncWrapContext<_>>::run_sync(caller_context(), || {
+            "a".parse::<u8>()
+        });
+
+        let lines = written_lines();
+        assert_eq!(lines.len(), 2);
+        for (line, outcome) in lines.iter().zip(["ok", "err"]) {
+            let object = line.as_object().unwrap();
+            assert_eq!(
+                object.keys().collect::<Vec<_>>(),
+                ["elapsed_ms", "fn", "module", "outcome"]
+            );
+            assert_eq!(object["fn"], "parse");
+            assert_eq!(object["module"], "my_crate");
+            assert!(object["elapsed_ms"].as_f64().unwrap() >= 0.0);
+            assert_eq!(object["outcome"], outcome);
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_calls_emit_a_line_each() {
+        <JsonLogContext<Capture> as AsyncWrapContext<_>>::run(CallerContext::new("foo"), async {})
+            .await;
+
+        let mut lines = written_lines();
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0]
+            .as_object_mut()
+            .unwrap()
+            .remove("elapsed_ms")
+            .is_some());
+        assert_eq!(
+            lines[0],
+            serde_json::json!({"fn": "foo", "module": null, "outcome": "ok"})
+        );
+    }
+
+    #[test]
+    fn emitted_names_are_mapped() {
+        struct Namespaced;
+        impl JsonLogConfig for Namespaced {
+            fn writer() -> impl Write {
+                Capture
+            }
+
+            fn map_fn_name(name: &str) -> Cow<'_, str> {
+                format!("service.{name}").into()
+            }
+        }
+
+        <JsonLogContext<Namespaced> as SyncWrapContext<_>>::run_sync(
+            CallerContext::new("parse"),
+            || (),
+        );
+        assert_eq!(written_lines()[0]["fn"], "service.parse");
+    }
+}
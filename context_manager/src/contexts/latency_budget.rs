@@ -0,0 +1,182 @@
+use core::cell::Cell;
+use core::marker::PhantomData;
+use core::time::Duration;
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Configuration of [`LatencyBudgetContext`]
+///
+/// ```
+/// # use std::time::Duration;
+/// # use context_manager::contexts::LatencyBudgetConfig;
+/// struct Budgets;
+/// impl LatencyBudgetConfig for Budgets {
+///     const BUDGETS: &'static [(&'static str, Duration)] = &[
+///         ("get_user", Duration::from_millis(50)),
+///         ("search", Duration::from_millis(500)),
+///     ];
+///
+///     fn on_over_budget(fn_name: &'static str, elapsed: Duration, budget: Duration) {
+///         eprintln!("{fn_name} took {elapsed:?}, over its budget of {budget:?}");
+///     }
+/// }
+/// ```
+pub trait LatencyBudgetConfig {
+    /// Budgets of the wrapped functions, by function name
+    const BUDGETS: &'static [(&'static str, Duration)];
+
+    /// Budget of the wrapped functions not listed in [`LatencyBudgetConfig::BUDGETS`]
+    ///
+    /// By default it is [`Duration::MAX`], so such functions are never over budget.
+    const DEFAULT_BUDGET: Duration = Duration::MAX;
+
+    /// Execute the code when the wrapped function took longer than its budget
+    ///
+    /// Parameters:
+    /// - `fn_name`: Name of the wrapped function
+    /// - `elapsed`: The time spent executing the wrapped function
+    /// - `budget`: The budget of the wrapped function
+    fn on_over_budget(fn_name: &'static str, elapsed: Duration, budget: Duration);
+
+    /// Budget of the wrapped function named `fn_name`
+    #[must_use]
+    fn budget(fn_name: &str) -> Duration {
+        Self::BUDGETS
+            .iter()
+            .find_map(|(name, budget)| (*name == fn_name).then_some(*budget))
+            .unwrap_or(Self::DEFAULT_BUDGET)
+    }
+}
+
+/// Context enforcing a latency budget per wrapped function
+///
+/// A single context, configured via [`LatencyBudgetConfig`], enforces different budgets on different functions.
+/// The budget is the [`SyncWrapContext::slow_threshold`] of the context, so [`LatencyBudgetConfig::on_over_budget`]
+/// is executed from [`SyncWrapContext::on_slow`].
+/// ```
+/// # use std::time::Duration;
+/// # use context_manager::contexts::{LatencyBudgetConfig, LatencyBudgetContext};
+/// # use context_manager::wrap;
+/// struct Budgets;
+/// impl LatencyBudgetConfig for Budgets {
+///     const BUDGETS: &'static [(&'static str, Duration)] = &[("get_user", Duration::from_millis(50))];
+///
+///     fn on_over_budget(fn_name: &'static str, elapsed: Duration, budget: Duration) {
+///         eprintln!("{fn_name} took {elapsed:?}, over its budget of {budget:?}");
+///     }
+/// }
+///
+/// #[wrap(LatencyBudgetContext<Budgets>)]
+/// fn get_user() {}
+/// ```
+#[derive(Debug)]
+pub struct LatencyBudgetContext<C: LatencyBudgetConfig> {
+    budget: Cell<Duration>,
+    config: PhantomData<C>,
+}
+
+impl<C: LatencyBudgetConfig> LatencyBudgetContext<C> {
+    const fn create() -> Self {
+        Self {
+            budget: Cell::new(Duration::MAX),
+            config: PhantomData,
+        }
+    }
+
+    fn on_over_budget(&self, caller_context: &CallerContext, elapsed: Duration) {
+        C::on_over_budget(caller_context.fn_name(), elapsed, self.budget.get());
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: LatencyBudgetConfig, T> SyncWrapContext<T> for LatencyBudgetContext<C> {
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self::create()
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        self.budget.set(C::budget(caller_context.fn_name()));
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        self.budget.get()
+    }
+
+    fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        self.on_over_budget(caller_context, elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: LatencyBudgetConfig, T> AsyncWrapContext<T> for LatencyBudgetContext<C> {
+    const HAS_AFTER: bool = false;
+
+    async fn new() -> Self {
+        Self::create()
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        self.budget.set(C::budget(caller_context.fn_name()));
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        self.budget.get()
+    }
+
+    async fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        self.on_over_budget(caller_context, elapsed);
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::{LatencyBudgetConfig, LatencyBudgetContext};
+    use crate::contexts::test::FakeClock;
+    use crate::contexts::{ClockContext, ContextChain};
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    static OVER_BUDGET: Mutex<Vec<(&str, Duration)>> = Mutex::new(Vec::new());
+
+    struct Budgets;
+    impl LatencyBudgetConfig for Budgets {
+        const BUDGETS: &'static [(&'static str, Duration)] = &[
+            ("fast", Duration::from_millis(1)),
+            ("slow", Duration::from_secs(30)),
+        ];
+
+        fn on_over_budget(fn_name: &'static str, elapsed: Duration, budget: Duration) {
+            assert_eq!(elapsed, Duration::from_millis(10));
+            OVER_BUDGET.lock().unwrap().push((fn_name, budget));
+        }
+    }
+
+    type Context = ContextChain<ClockContext<FakeClock>, LatencyBudgetContext<Budgets>>;
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn only_functions_over_their_budget_are_reported() {
+        for fn_name in ["fast", "slow", "unlisted"] {
+            <Context as SyncWrapContext<_>>::run_sync(CallerContext::new(fn_name), || {
+                FakeClock::advance(Duration::from_millis(10));
+            });
+        }
+        assert_eq!(
+            std::mem::take(&mut *OVER_BUDGET.lock().unwrap()),
+            [("fast", Duration::from_millis(1))]
+        );
+
+        for fn_name in ["fast", "slow"] {
+            <Context as AsyncWrapContext<_>>::run(CallerContext::new(fn_name), async {
+                FakeClock::advance(Duration::from_millis(10));
+            })
+            .await;
+        }
+        assert_eq!(
+            std::mem::take(&mut *OVER_BUDGET.lock().unwrap()),
+            [("fast", Duration::from_millis(1))]
+        );
+    }
+}
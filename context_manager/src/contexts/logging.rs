@@ -0,0 +1,205 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Configuration of [`LogContext`]
+///
+/// ```
+/// # use context_manager::contexts::{LogConfig, LogContext};
+/// # use context_manager::wrap;
+/// struct Info;
+/// impl LogConfig for Info {
+///     const LEVEL: log::Level = log::Level::Info;
+/// }
+///
+/// #[wrap(LogContext<Info>)]
+/// fn foo() {}
+/// ```
+pub trait LogConfig {
+    /// Level of the emitted records
+    const LEVEL: log::Level = log::Level::Debug;
+
+    /// Name of the wrapped function in the emitted records, unchanged by default
+    ///
+    /// Parameters:
+    /// - `name`: Name of the wrapped function
+    #[must_use]
+    fn map_fn_name(name: &str) -> Cow<'_, str> {
+        Cow::Borrowed(name)
+    }
+}
+
+/// Default configuration of [`LogContext`], emitting records at [`log::Level::Debug`] level
+#[derive(Debug)]
+pub struct DefaultLogConfig;
+impl LogConfig for DefaultLogConfig {}
+
+/// Context logging, via the [`log`] crate, the entering and exiting of the wrapped function (requires the `log` feature)
+///
+/// The records are emitted with the module path of the wrapped function as target.
+/// ```
+/// # use context_manager::contexts::LogContext;
+/// # use context_manager::wrap;
+/// #[wrap(LogContext)]
+/// fn foo() {}
+///
+/// foo();
+/// // DEBUG my_crate::my_module: entering foo
+/// // DEBUG my_crate::my_module: exiting foo (10.2µs)
+/// ```
+///
+/// The level of the records, and the name of the function they report, can be customised via [`LogConfig`].
+#[derive(Debug)]
+pub struct LogContext<C: LogConfig = DefaultLogConfig>(PhantomData<C>);
+
+impl<C: LogConfig> LogContext<C> {
+    fn target(caller_context: &CallerContext) -> &'static str {
+        caller_context.module_path().unwrap_or(module_path!())
+    }
+
+    fn log_entering(caller_context: &CallerContext) {
+        log::log!(
+            target: Self::target(caller_context),
+            C::LEVEL,
+            "entering {}",
+            caller_context.emitted_fn_name()
+        );
+    }
+
+    fn log_exiting(caller_context: &CallerContext, elapsed: Duration) {
+        log::log!(
+            target: Self::target(caller_context),
+            C::LEVEL,
+            "exiting {} ({elapsed:?})",
+            caller_context.emitted_fn_name()
+        );
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: LogConfig, T> SyncWrapContext<T> for LogContext<C> {
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        C::map_fn_name(name)
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        Self::log_entering(caller_context);
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::log_exiting(caller_context, elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: LogConfig, T> AsyncWrapContext<T> for LogContext<C> {
+    async fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        C::map_fn_name(name)
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        Self::log_entering(caller_context);
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::log_exiting(caller_context, elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LogConfig, LogContext};
+    use crate::contexts::ContextChain;
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::borrow::Cow;
+
+    struct Info;
+    impl LogConfig for Info {
+        const LEVEL: log::Level = log::Level::Info;
+    }
+
+    fn assert_captured_logs(level: log::Level) {
+        testing_logger::validate(|captured_logs| {
+            assert_eq!(captured_logs.len(), 2);
+            assert_eq!(captured_logs[0].body, "entering foo");
+            assert_eq!(captured_logs[0].target, "my_crate::my_module");
+            assert_eq!(captured_logs[0].level, level);
+            assert!(captured_logs[1].body.starts_with("exiting foo ("));
+            assert_eq!(captured_logs[1].target, "my_crate::my_module");
+            assert_eq!(captured_logs[1].level, level);
+        });
+    }
+
+    #[test]
+    fn sync_records() {
+        testing_logger::setup();
+        let caller_context =
+            CallerContext::new("foo").with_location("my_crate::my_module", "file.rs", 1);
+        <LogContext as SyncWrapContext<_>>::run_sync(caller_context, || ());
+        assert_captured_logs(log::Level::Debug);
+    }
+
+    #[test]
+    fn sync_records_with_custom_level() {
+        testing_logger::setup();
+        let caller_context =
+            CallerContext::new("foo").with_location("my_crate::my_module", "file.rs", 1);
+        <LogContext<Info> as SyncWrapContext<_>>::run_sync(caller_context, || ());
+        assert_captured_logs(log::Level::Info);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_records() {
+        testing_logger::setup();
+        let caller_context =
+            CallerContext::new("foo").with_location("my_crate::my_module", "file.rs", 1);
+        <LogContext as AsyncWrapContext<_>>::run(caller_context, async {}).await;
+        assert_captured_logs(log::Level::Debug);
+    }
+
+    #[test]
+    fn records_report_the_mapped_name() {
+        struct Namespaced;
+        impl LogConfig for Namespaced {
+            fn map_fn_name(name: &str) -> Cow<'_, str> {
+                format!("service.{name}").into()
+            }
+        }
+
+        struct FnName;
+        impl SyncWrapContext<()> for FnName {
+            fn new() -> Self {
+                Self
+            }
+
+            fn after(self, caller_context: &CallerContext, (): &()) {
+                assert_eq!(caller_context.fn_name(), "foo");
+            }
+        }
+
+        // The mapping is forwarded by the chain, while the caller context provided to the other context is unchanged
+        type Chain = ContextChain<LogContext<Namespaced>, FnName>;
+        let context = <Chain as SyncWrapContext<()>>::new();
+        assert_eq!(
+            SyncWrapContext::<()>::map_fn_name(&context, "foo"),
+            "service.foo"
+        );
+
+        testing_logger::setup();
+        <Chain as SyncWrapContext<_>>::run_sync(CallerContext::new("foo"), || ());
+        testing_logger::validate(|captured_logs| {
+            assert_eq!(captured_logs[0].body, "entering service.foo");
+            assert!(captured_logs[1].body.starts_with("exiting service.foo ("));
+        });
+    }
+}
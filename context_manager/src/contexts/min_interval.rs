@@ -0,0 +1,246 @@
+use std::any::TypeId;
+use std::marker::PhantomData;
+use std::ops::ControlFlow;
+use std::sync::{Mutex, PoisonError};
+use std::time::{Duration, Instant};
+
+use crate::contexts::registry::Registry;
+use crate::{
+    skip_with_default, AsyncWrapContext, CallerContext, Clock, Sleep, SyncWrapContext, SystemClock,
+};
+
+static LAST_CALLS: Registry<Mutex<Option<Instant>>> = Registry::new();
+
+/// Configuration of [`MinIntervalContext`]
+///
+/// ```
+/// # use context_manager::contexts::MinIntervalConfig;
+/// # use std::time::Duration;
+/// struct OncePerSecond;
+/// impl MinIntervalConfig for OncePerSecond {
+///     const INTERVAL: Duration = Duration::from_secs(1);
+/// }
+/// ```
+pub trait MinIntervalConfig {
+    /// Minimum interval between the start of two calls of the wrapped function
+    const INTERVAL: Duration;
+
+    /// Current time, [`Instant::now`] by default
+    ///
+    /// It is used only if [`MinIntervalContext`] reads the time via [`SystemClock`], which is its default clock.
+    #[deprecated(
+        note = "inject the clock via the `K` type parameter of `MinIntervalContext` instead"
+    )]
+    #[must_use]
+    fn now() -> Instant {
+        Instant::now()
+    }
+}
+
+/// Context enforcing a minimum interval between the calls of the wrapped function (throttling)
+///
+/// The time of the last call is kept per function name, and a call is throttled if it happens within
+/// [`MinIntervalConfig::INTERVAL`] since the last call that was not throttled. How the throttled calls behave
+/// depends on the trait in use:
+/// * via [`SyncWrapContext`] the body is skipped, returning `T::default()` (refer to [`skip_with_default`])
+/// * via [`AsyncWrapContext`] the call sleeps, via the [`Sleep`] implementation `S`, until the interval elapses
+/// ```
+/// # use context_manager::contexts::{MinIntervalConfig, MinIntervalContext};
+/// # use context_manager::wrap;
+/// # use std::time::Duration;
+/// struct OncePerMinute;
+/// impl MinIntervalConfig for OncePerMinute {
+///     const INTERVAL: Duration = Duration::from_secs(60);
+/// }
+///
+/// #[wrap(MinIntervalContext<OncePerMinute>)]
+/// fn refresh_cache() -> bool {
+///     true
+/// }
+///
+/// assert!(refresh_cache());
+/// assert!(!refresh_cache()); // Throttled, so `bool::default()` is returned
+/// ```
+///
+/// For async functions, `S` should be set to the [`Sleep`] implementation of the runtime in use,
+/// like `#[async_wrap(MinIntervalContext<OncePerMinute, TokioSleep>)]` (requires the `tokio` feature).
+/// The time of the calls is read via the [`Clock`] `K`, so a fake clock can be injected in tests.
+#[derive(Debug)]
+pub struct MinIntervalContext<C: MinIntervalConfig, S = (), K: Clock = SystemClock> {
+    config: PhantomData<(C, S, K)>,
+}
+
+impl<C: MinIntervalConfig, S, K: Clock + 'static> MinIntervalContext<C, S, K> {
+    /// Current time, read via `K` or via the deprecated [`MinIntervalConfig::now`] if `K` is [`SystemClock`]
+    fn current_time() -> Instant {
+        if TypeId::of::<K>() == TypeId::of::<SystemClock>() {
+            #[allow(deprecated)]
+            return C::now();
+        }
+        K::now()
+    }
+
+    /// Register the call of `fn_name`, unless it is throttled, in which case the remaining interval is returned
+    fn try_call(fn_name: &'static str) -> Result<(), Duration> {
+        let mut last_call = LAST_CALLS
+            .get_or_register(fn_name)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let now = Self::current_time();
+        if let Some(last_call) = *last_call {
+            let remaining = C::INTERVAL.saturating_sub(now.saturating_duration_since(last_call));
+            if !remaining.is_zero() {
+                return Err(remaining);
+            }
+        }
+        *last_call = Some(now);
+        Ok(())
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: MinIntervalConfig, K: Clock + 'static, T: Default> SyncWrapContext<T>
+    for MinIntervalContext<C, (), K>
+{
+    const HAS_BEFORE: bool = false;
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self {
+            config: PhantomData,
+        }
+    }
+
+    fn now() -> Instant {
+        Self::current_time()
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        match Self::try_call(caller_context.fn_name()) {
+            Ok(()) => ControlFlow::Continue(()),
+            Err(_) => skip_with_default(),
+        }
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: MinIntervalConfig, S: Sleep, K: Clock + 'static, T> AsyncWrapContext<T>
+    for MinIntervalContext<C, S, K>
+{
+    const HAS_AFTER: bool = false;
+
+    async fn new() -> Self {
+        Self {
+            config: PhantomData,
+        }
+    }
+
+    fn now() -> Instant {
+        Self::current_time()
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        while let Err(remaining) = Self::try_call(caller_context.fn_name()) {
+            S::sleep(remaining).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MinIntervalConfig, MinIntervalContext};
+    use crate::{AsyncWrapContext, CallerContext, Clock, Sleep, SyncWrapContext};
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::time::{Duration, Instant};
+
+    thread_local! {
+        static NOW: Cell<Instant> = Cell::new(Instant::now());
+    }
+
+    fn advance(duration: Duration) {
+        NOW.set(NOW.get() + duration);
+    }
+
+    struct FakeClock;
+    impl MinIntervalConfig for FakeClock {
+        const INTERVAL: Duration = Duration::from_secs(10);
+    }
+
+    impl Clock for FakeClock {
+        fn now() -> Instant {
+            NOW.get()
+        }
+    }
+
+    impl Sleep for FakeClock {
+        fn sleep(duration: Duration) -> impl Future<Output = ()> {
+            advance(duration);
+            std::future::ready(())
+        }
+    }
+
+    fn call_sync() -> bool {
+        <MinIntervalContext<FakeClock, (), FakeClock> as SyncWrapContext<_>>::run_sync(
+            CallerContext::new("min_interval_sync"),
+            || true,
+        )
+    }
+
+    #[test]
+    fn rapid_sync_calls_are_throttled() {
+        assert!(call_sync());
+        advance(Duration::from_secs(3));
+        assert!(!call_sync());
+        advance(Duration::from_secs(3));
+        assert!(!call_sync());
+
+        // The interval is measured since the last call that was not throttled
+        advance(Duration::from_secs(4));
+        assert!(call_sync());
+        advance(Duration::from_secs(15));
+        assert!(call_sync());
+    }
+
+    #[test]
+    fn deprecated_config_clock_is_used_with_the_default_clock() {
+        struct LegacyClock;
+        impl MinIntervalConfig for LegacyClock {
+            const INTERVAL: Duration = Duration::from_secs(10);
+
+            fn now() -> Instant {
+                NOW.get()
+            }
+        }
+
+        let call = || {
+            <MinIntervalContext<LegacyClock> as SyncWrapContext<_>>::run_sync(
+                CallerContext::new("min_interval_legacy"),
+                || true,
+            )
+        };
+        assert!(call());
+        assert!(!call());
+        advance(Duration::from_secs(10));
+        assert!(call());
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn rapid_async_calls_wait_for_the_interval() {
+        let call = || async {
+            <MinIntervalContext<FakeClock, FakeClock, FakeClock> as AsyncWrapContext<_>>::run(
+                CallerContext::new("min_interval_async"),
+                async { NOW.get() },
+            )
+            .await
+        };
+
+        let first = call().await;
+        advance(Duration::from_secs(4));
+        assert_eq!(call().await - first, Duration::from_secs(10));
+
+        advance(Duration::from_secs(30));
+        let start = NOW.get();
+        assert_eq!(call().await, start);
+    }
+}
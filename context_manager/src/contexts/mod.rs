@@ -0,0 +1,133 @@
+//! Ready to use contexts, covering common use cases.
+
+// NOTE: The implementations of `SyncWrapContext` and `AsyncWrapContext` in this module are marked
+// with `#[diagnostic::do_not_recommend]` to avoid them being listed in the compile errors
+// of unrelated contexts, which would be confusing for the users.
+
+#[cfg(feature = "std")]
+mod allocations;
+mod as_async;
+#[cfg(feature = "std")]
+mod batching;
+mod branch;
+mod chain;
+#[cfg(feature = "std")]
+mod chrome_trace;
+#[cfg(feature = "std")]
+mod clock;
+#[cfg(feature = "std")]
+mod counter;
+mod error_trail;
+#[cfg(feature = "fastrace")]
+mod fastrace;
+#[cfg(feature = "std")]
+mod first_poll;
+#[cfg(feature = "std")]
+mod flamegraph;
+mod flush;
+#[cfg(feature = "std")]
+mod inflight;
+#[cfg(feature = "std")]
+mod interceptors;
+#[cfg(feature = "json")]
+mod json_log;
+mod latency_budget;
+#[cfg(feature = "log")]
+mod logging;
+#[cfg(feature = "std")]
+mod min_interval;
+#[cfg(feature = "std")]
+mod mutex;
+#[cfg(feature = "std")]
+mod panic_to_result;
+#[cfg(feature = "std")]
+mod poisonable;
+#[cfg(feature = "prometheus")]
+mod prometheus;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+mod sampling;
+#[cfg(feature = "std")]
+mod scoped_value;
+#[cfg(feature = "sentry")]
+mod sentry;
+#[cfg(feature = "std")]
+mod single_flight;
+#[cfg(feature = "tracing")]
+mod span_fields;
+#[cfg(feature = "tracing")]
+mod span_record;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(any(feature = "testing", all(test, feature = "std")))]
+pub mod test;
+mod toggle;
+#[cfg(feature = "trace")]
+mod trace;
+mod transaction;
+
+#[cfg(feature = "std")]
+pub use crate::contexts::allocations::{AllocConfig, AllocContext, CountingAllocator};
+pub use crate::contexts::as_async::AsAsync;
+#[cfg(feature = "tokio")]
+pub use crate::contexts::as_async::AsAsyncBlocking;
+#[cfg(feature = "std")]
+pub use crate::contexts::batching::{BatchConfig, BatchRecord, BatchingContext};
+pub use crate::contexts::branch::{Branch, BranchContext, ErrBranch, OkBranch};
+pub use crate::contexts::chain::ContextChain;
+#[cfg(feature = "std")]
+pub use crate::contexts::chrome_trace::{ChromeTraceConfig, ChromeTraceContext};
+#[cfg(feature = "std")]
+pub use crate::contexts::clock::ClockContext;
+#[cfg(feature = "std")]
+pub use crate::contexts::counter::CounterContext;
+pub use crate::contexts::error_trail::{ErrorTrailContext, Trail, Trailable};
+#[cfg(feature = "fastrace")]
+pub use crate::contexts::fastrace::FastraceContext;
+#[cfg(feature = "std")]
+pub use crate::contexts::first_poll::{FirstPollConfig, FirstPollContext};
+#[cfg(feature = "std")]
+pub use crate::contexts::flamegraph::FlamegraphContext;
+pub use crate::contexts::flush::{Flush, FlushContext};
+#[cfg(feature = "std")]
+pub use crate::contexts::inflight::InflightContext;
+#[cfg(feature = "std")]
+pub use crate::contexts::interceptors::RegistryContext;
+#[cfg(feature = "json")]
+pub use crate::contexts::json_log::{DefaultJsonLogConfig, JsonLogConfig, JsonLogContext, Outcome};
+pub use crate::contexts::latency_budget::{LatencyBudgetConfig, LatencyBudgetContext};
+#[cfg(feature = "log")]
+pub use crate::contexts::logging::{DefaultLogConfig, LogConfig, LogContext};
+#[cfg(feature = "std")]
+pub use crate::contexts::min_interval::{MinIntervalConfig, MinIntervalContext};
+#[cfg(feature = "tokio")]
+pub use crate::contexts::mutex::{AsyncMutexConfig, AsyncMutexGuardContext};
+#[cfg(feature = "std")]
+pub use crate::contexts::mutex::{MutexConfig, MutexGuardContext};
+#[cfg(feature = "std")]
+pub use crate::contexts::panic_to_result::{PanicError, PanicToResultContext};
+#[cfg(feature = "std")]
+pub use crate::contexts::poisonable::{PoisonableContext, Poisoned};
+#[cfg(feature = "prometheus")]
+pub use crate::contexts::prometheus::PrometheusContext;
+#[cfg(feature = "std")]
+pub use crate::contexts::sampling::{
+    ConstSampleContext, EveryNth, SamplingConfig, SamplingContext,
+};
+#[cfg(feature = "std")]
+pub use crate::contexts::scoped_value::{ScopedKey, ScopedValueContext};
+#[cfg(feature = "sentry")]
+pub use crate::contexts::sentry::{DefaultSentryConfig, SentryConfig, SentryContext};
+#[cfg(feature = "std")]
+pub use crate::contexts::single_flight::SingleFlightContext;
+#[cfg(feature = "tracing")]
+pub use crate::contexts::span_fields::SpanFieldsContext;
+#[cfg(feature = "tracing")]
+pub use crate::contexts::span_record::{SpanRecordConfig, SpanRecordContext};
+#[cfg(feature = "std")]
+pub use crate::contexts::stats::StatsContext;
+pub use crate::contexts::toggle::{ToggleConfig, ToggleContext};
+#[cfg(feature = "trace")]
+pub use crate::contexts::trace::DefaultTraceContext;
+pub use crate::contexts::transaction::{AsyncTransaction, Transaction, TransactionContext};
@@ -0,0 +1,195 @@
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+use crate::{CallerContext, SyncWrapContext};
+
+/// Configuration of [`MutexGuardContext`]
+///
+/// ```
+/// # use context_manager::contexts::MutexConfig;
+/// # use std::sync::Mutex;
+/// struct Exclusive;
+/// impl MutexConfig for Exclusive {
+///     fn mutex() -> &'static Mutex<()> {
+///         static MUTEX: Mutex<()> = Mutex::new(());
+///         &MUTEX
+///     }
+/// }
+/// ```
+pub trait MutexConfig {
+    /// Mutex to hold while executing the wrapped body
+    fn mutex() -> &'static Mutex<()>;
+}
+
+/// Context holding the mutex provided by `C` while executing the wrapped function
+///
+/// The mutex is acquired when the context is initialised, and released once the `after` hook completed.
+/// Functions wrapped with the same configuration are then mutually exclusive.
+/// ```
+/// # use context_manager::contexts::{MutexConfig, MutexGuardContext};
+/// # use context_manager::wrap;
+/// # use std::sync::Mutex;
+/// struct Exclusive;
+/// impl MutexConfig for Exclusive {
+///     fn mutex() -> &'static Mutex<()> {
+///         static MUTEX: Mutex<()> = Mutex::new(());
+///         &MUTEX
+///     }
+/// }
+///
+/// #[wrap(MutexGuardContext<Exclusive>)]
+/// fn update_shared_file() {}
+/// ```
+///
+/// A poisoned mutex is acquired anyway, as it does not protect any data.
+///
+/// **IMPORTANT**: [`Mutex`] is not reentrant, so the wrapped function deadlocks if it tries to acquire
+/// the same mutex again (ie. by recursively calling itself, or calling another function wrapped with
+/// the same configuration). The mutex is blocking, so it should not be held across `.await` points:
+/// please consider `AsyncMutexGuardContext` (requires the `tokio` feature)
+/// for asynchronous functions.
+#[derive(Debug)]
+pub struct MutexGuardContext<C: MutexConfig> {
+    _guard: MutexGuard<'static, ()>,
+    config: std::marker::PhantomData<C>,
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: MutexConfig, T> SyncWrapContext<T> for MutexGuardContext<C> {
+    fn new() -> Self {
+        Self {
+            _guard: C::mutex().lock().unwrap_or_else(PoisonError::into_inner),
+            config: std::marker::PhantomData,
+        }
+    }
+
+    fn after(self, _: &CallerContext, _: &T) {
+        // Dropping self releases the mutex
+    }
+}
+
+/// Configuration of [`AsyncMutexGuardContext`] (requires the `tokio` feature)
+///
+/// ```
+/// # use context_manager::contexts::AsyncMutexConfig;
+/// # use tokio::sync::Mutex;
+/// struct Exclusive;
+/// impl AsyncMutexConfig for Exclusive {
+///     fn mutex() -> &'static Mutex<()> {
+///         static MUTEX: Mutex<()> = Mutex::const_new(());
+///         &MUTEX
+///     }
+/// }
+/// ```
+#[cfg(feature = "tokio")]
+pub trait AsyncMutexConfig {
+    /// Mutex to hold while executing the wrapped body
+    fn mutex() -> &'static tokio::sync::Mutex<()>;
+}
+
+/// Context holding the asynchronous mutex provided by `C` while executing the wrapped function (requires the `tokio` feature)
+///
+/// This is the asynchronous counterpart of [`MutexGuardContext`]: the mutex is acquired, without blocking the
+/// runtime, when the context is initialised and released once the `after` hook completed.
+/// ```
+/// # use context_manager::contexts::{AsyncMutexConfig, AsyncMutexGuardContext};
+/// # use context_manager::async_wrap;
+/// # use tokio::sync::Mutex;
+/// struct Exclusive;
+/// impl AsyncMutexConfig for Exclusive {
+///     fn mutex() -> &'static Mutex<()> {
+///         static MUTEX: Mutex<()> = Mutex::const_new(());
+///         &MUTEX
+///     }
+/// }
+///
+/// #[async_wrap(AsyncMutexGuardContext<Exclusive>)]
+/// async fn update_shared_file() {}
+/// ```
+///
+/// **IMPORTANT**: [`tokio::sync::Mutex`] is not reentrant, so the wrapped function deadlocks if it tries
+/// to acquire the same mutex again.
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct AsyncMutexGuardContext<C: AsyncMutexConfig> {
+    _guard: tokio::sync::MutexGuard<'static, ()>,
+    config: std::marker::PhantomData<C>,
+}
+
+#[cfg(feature = "tokio")]
+#[diagnostic::do_not_recommend]
+impl<C: AsyncMutexConfig, T> crate::AsyncWrapContext<T> for AsyncMutexGuardContext<C> {
+    async fn new() -> Self {
+        Self {
+            _guard: C::mutex().lock().await,
+            config: std::marker::PhantomData,
+        }
+    }
+
+    async fn after(self, _: &CallerContext, _: &T) {
+        // Dropping self releases the mutex
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    use super::{MutexConfig, MutexGuardContext};
+    use crate::{CallerContext, SyncWrapContext};
+
+    struct Exclusive;
+    impl MutexConfig for Exclusive {
+        fn mutex() -> &'static Mutex<()> {
+            static MUTEX: Mutex<()> = Mutex::new(());
+            &MUTEX
+        }
+    }
+
+    #[test]
+    fn sync_calls_are_mutually_exclusive() {
+        static RUNNING: AtomicBool = AtomicBool::new(false);
+
+        let run = || {
+            MutexGuardContext::<Exclusive>::run_sync(CallerContext::new("exclusive"), || {
+                assert!(!RUNNING.swap(true, Ordering::SeqCst));
+                std::thread::sleep(Duration::from_millis(20));
+                RUNNING.store(false, Ordering::SeqCst);
+            });
+        };
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(run);
+            }
+        });
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn async_calls_are_mutually_exclusive() {
+        use super::{AsyncMutexConfig, AsyncMutexGuardContext};
+        use crate::AsyncWrapContext;
+
+        static RUNNING: AtomicBool = AtomicBool::new(false);
+
+        struct AsyncExclusive;
+        impl AsyncMutexConfig for AsyncExclusive {
+            fn mutex() -> &'static tokio::sync::Mutex<()> {
+                static MUTEX: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+                &MUTEX
+            }
+        }
+
+        let run = || {
+            AsyncMutexGuardContext::<AsyncExclusive>::run(CallerContext::new("exclusive"), async {
+                assert!(!RUNNING.swap(true, Ordering::SeqCst));
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                RUNNING.store(false, Ordering::SeqCst);
+            })
+        };
+        let (first, second) = tokio::join!(tokio::spawn(run()), tokio::spawn(run()));
+        first.unwrap();
+        second.unwrap();
+    }
+}
@@ -0,0 +1,194 @@
+use std::any::Any;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::pin::pin;
+use std::task::Poll;
+
+use crate::call_stack::{in_call_stack_async, in_call_stack_sync, Frame};
+use crate::{AsyncBlock, CallerContext, SyncBlock, SyncWrapContext};
+
+/// Error reporting a panic caught by [`PanicToResultContext`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicError {
+    /// Panic message, if the panic payload was a string
+    message: Option<String>,
+    /// Name of the wrapped function that panicked
+    fn_name: &'static str,
+    /// File and line of the wrapped function that panicked, if known
+    location: Option<(&'static str, u32)>,
+}
+
+impl PanicError {
+    fn new(caller_context: &CallerContext, payload: &(dyn Any + Send)) -> Self {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            Some((*message).to_string())
+        } else {
+            payload.downcast_ref::<String>().cloned()
+        };
+        Self {
+            message,
+            fn_name: caller_context.fn_name(),
+            location: caller_context.file().zip(caller_context.line()),
+        }
+    }
+
+    /// Panic message, if the panic payload was a `&str` or a `String` (as for [`panic!`])
+    #[must_use]
+    pub fn message(&self) -> Option<&str> {
+        self.message.as_deref()
+    }
+
+    /// Name of the wrapped function that panicked
+    #[must_use]
+    pub const fn fn_name(&self) -> &'static str {
+        self.fn_name
+    }
+
+    /// File and line of the wrapped function that panicked, if known
+    ///
+    /// NOTE: This is the location of the wrapped function, and not of the panic itself.
+    #[must_use]
+    pub const fn location(&self) -> Option<(&'static str, u32)> {
+        self.location
+    }
+}
+
+impl fmt::Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} panicked", self.fn_name)?;
+        if let Some((file, line)) = self.location {
+            write!(f, " ({file}:{line})")?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for PanicError {}
+
+/// Context converting panics of the wrapped body into [`PanicError`] values
+///
+/// The context can wrap only functions returning `Result<T, PanicError>`.
+/// ```
+/// # use context_manager::wrap;
+/// # use context_manager::contexts::{PanicError, PanicToResultContext};
+/// #[wrap(PanicToResultContext)]
+/// fn divide(dividend: u32, divisor: u32) -> Result<u32, PanicError> {
+///     Ok(dividend / divisor)
+/// }
+///
+/// assert_eq!(divide(10, 2), Ok(5));
+/// assert_eq!(divide(10, 0).unwrap_err().message(), Some("attempt to divide by zero"));
+/// ```
+///
+/// The panic is caught via [`catch_unwind`], so the same caveats apply:
+/// * panics are not caught if the binary is compiled with `panic = "abort"`
+/// * the panic hook is still invoked, so the panic message is still printed on standard error by default
+/// * the body is assumed to be unwind safe (via [`AssertUnwindSafe`]). If the body mutates shared
+///   state (ie. via `&mut` references or interior mutability) that state might be left inconsistent
+///   and callers are responsible to not observe it after an [`Err`] is returned
+#[derive(Debug)]
+pub struct PanicToResultContext;
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<Result<T, PanicError>> for PanicToResultContext {
+    fn new() -> Self {
+        Self
+    }
+
+    fn run_sync(
+        caller_context: CallerContext,
+        block: impl SyncBlock<Result<T, PanicError>>,
+    ) -> Result<T, PanicError> {
+        let frame = Frame::new::<Self>(&caller_context);
+        catch_unwind(AssertUnwindSafe(|| {
+            in_call_stack_sync(frame, || block.call(&caller_context))
+        }))
+        .unwrap_or_else(|payload| Err(PanicError::new(&caller_context, payload.as_ref())))
+    }
+
+    async fn run_async(
+        caller_context: CallerContext,
+        block: impl AsyncBlock<Result<T, PanicError>>,
+    ) -> Result<T, PanicError> {
+        let mut block = pin!(in_call_stack_async(
+            Frame::new::<Self>(&caller_context),
+            block.call(&caller_context)
+        ));
+        poll_fn(|cx| {
+            catch_unwind(AssertUnwindSafe(|| block.as_mut().poll(cx))).unwrap_or_else(|payload| {
+                Poll::Ready(Err(PanicError::new(&caller_context, payload.as_ref())))
+            })
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PanicError, PanicToResultContext};
+    use crate::{CallerContext, SyncWrapContext};
+
+    #[test]
+    fn sync_body_not_panicking() {
+        assert_eq!(
+            PanicToResultContext::run_sync(CallerContext::new("test"), || Ok(42)),
+            Ok(42)
+        );
+    }
+
+    #[test]
+    fn sync_body_panicking_with_str() {
+        let caller_context = CallerContext::new("test").with_location("module", "file.rs", 10);
+        let error = PanicToResultContext::run_sync(caller_context, || -> Result<(), PanicError> {
+            panic!("boom")
+        })
+        .unwrap_err();
+
+        assert_eq!(error.message(), Some("boom"));
+        assert_eq!(error.fn_name(), "test");
+        assert_eq!(error.location(), Some(("file.rs", 10)));
+        assert_eq!(error.to_string(), "test panicked (file.rs:10): boom");
+    }
+
+    #[test]
+    fn sync_body_panicking_with_string() {
+        let value = 42;
+        let error = PanicToResultContext::run_sync(
+            CallerContext::new("test"),
+            || -> Result<(), PanicError> { panic!("formatted {value}") },
+        )
+        .unwrap_err();
+
+        assert_eq!(error.message(), Some("formatted 42"));
+        assert_eq!(error.to_string(), "test panicked: formatted 42");
+    }
+
+    #[test]
+    fn sync_body_panicking_with_custom_payload() {
+        let error = PanicToResultContext::run_sync(
+            CallerContext::new("test"),
+            || -> Result<(), PanicError> { std::panic::panic_any(42_usize) },
+        )
+        .unwrap_err();
+
+        assert_eq!(error.message(), None);
+    }
+
+    #[tokio::test]
+    async fn async_body_panicking_after_await() {
+        async fn panicking() -> Result<(), PanicError> {
+            tokio::task::yield_now().await;
+            panic!("boom")
+        }
+
+        let error = PanicToResultContext::run_async(CallerContext::new("test"), panicking())
+            .await
+            .unwrap_err();
+
+        assert_eq!(error.message(), Some("boom"));
+    }
+}
@@ -0,0 +1,167 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::contexts::registry::Registry;
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+static POISONED: Registry<AtomicBool> = Registry::new();
+
+/// Extension inserted into the [`CallerContext`] by [`PoisonableContext`] if the wrapped function is poisoned
+#[derive(Debug)]
+pub struct Poisoned;
+
+/// Context poisoning the wrapped function when its body panics, as [`std::sync::Mutex`] does
+///
+/// This is the reference implementation of [`SyncWrapContext::on_poisoned`]: the poisoned state is kept
+/// per function name, and the following calls observe it in `before`, which inserts the [`Poisoned`] extension
+/// into the caller context. The state stays poisoned until [`PoisonableContext::clear_poison`] is invoked.
+/// ```
+/// # use context_manager::contexts::PoisonableContext;
+/// # use context_manager::wrap;
+/// #[wrap(PoisonableContext)]
+/// fn update(value: u8) {
+///     assert!(value > 0, "invalid value");
+/// }
+///
+/// assert!(std::panic::catch_unwind(|| update(0)).is_err());
+/// assert!(PoisonableContext::is_poisoned("update"));
+///
+/// PoisonableContext::clear_poison("update");
+/// update(1);
+/// assert!(!PoisonableContext::is_poisoned("update"));
+/// ```
+///
+/// When combined with contexts observing the poisoned state (ie. via [`ContextChain`](crate::contexts::ContextChain)),
+/// `PoisonableContext` should come first, so that the extension is inserted before their `before` hooks.
+#[derive(Debug)]
+pub struct PoisonableContext;
+
+impl PoisonableContext {
+    /// Whether a call of the wrapped functions named `fn_name` panicked, since the poison was last cleared
+    #[must_use]
+    pub fn is_poisoned(fn_name: &str) -> bool {
+        POISONED
+            .get(fn_name)
+            .is_some_and(|poisoned| poisoned.load(Ordering::Acquire))
+    }
+
+    /// Clear the poisoned state of the wrapped functions named `fn_name`, ie. once their state has been recovered
+    pub fn clear_poison(fn_name: &str) {
+        if let Some(poisoned) = POISONED.get(fn_name) {
+            poisoned.store(false, Ordering::Release);
+        }
+    }
+
+    fn observe(caller_context: &mut CallerContext) {
+        if Self::is_poisoned(caller_context.fn_name()) {
+            caller_context.insert_extension(Poisoned);
+        }
+    }
+
+    fn poison(caller_context: &CallerContext) {
+        POISONED
+            .get_or_register(caller_context.fn_name())
+            .store(true, Ordering::Release);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for PoisonableContext {
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        Self::observe(caller_context);
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        Self::poison(caller_context);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for PoisonableContext {
+    const HAS_AFTER: bool = false;
+
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        Self::observe(caller_context);
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        Self::poison(caller_context);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PoisonableContext, Poisoned};
+    use crate::contexts::ContextChain;
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::cell::Cell;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    thread_local! {
+        static OBSERVED_POISON: Cell<Option<bool>> = const { Cell::new(None) };
+    }
+
+    struct ObservePoison;
+    impl<T> SyncWrapContext<T> for ObservePoison {
+        fn new() -> Self {
+            Self
+        }
+
+        fn before(&self, caller_context: &mut CallerContext) {
+            OBSERVED_POISON.set(Some(caller_context.extension::<Poisoned>().is_some()));
+        }
+    }
+    impl<T> AsyncWrapContext<T> for ObservePoison {
+        async fn new() -> Self {
+            Self
+        }
+
+        async fn before(&self, caller_context: &mut CallerContext) {
+            OBSERVED_POISON.set(Some(caller_context.extension::<Poisoned>().is_some()));
+        }
+    }
+
+    type Observed = ContextChain<PoisonableContext, ObservePoison>;
+
+    #[test]
+    fn sync_panic_poisons_the_next_calls() {
+        let call = |panics: bool| {
+            <Observed as SyncWrapContext<()>>::run_sync(CallerContext::new("poison_sync"), || {
+                assert!(!panics, "panic in the body");
+            });
+            OBSERVED_POISON.take()
+        };
+
+        assert_eq!(call(false), Some(false));
+        assert!(!PoisonableContext::is_poisoned("poison_sync"));
+
+        assert!(catch_unwind(|| call(true)).is_err());
+        assert!(PoisonableContext::is_poisoned("poison_sync"));
+        assert_eq!(call(false), Some(true));
+        assert_eq!(call(false), Some(true));
+
+        PoisonableContext::clear_poison("poison_sync");
+        assert_eq!(call(false), Some(false));
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_panic_poisons_the_next_calls() {
+        let result = AssertUnwindSafe(<Observed as AsyncWrapContext<()>>::run(
+            CallerContext::new("poison_async"),
+            async { panic!("panic in the body") },
+        ));
+        assert!(futures::FutureExt::catch_unwind(result).await.is_err());
+
+        <Observed as AsyncWrapContext<_>>::run(CallerContext::new("poison_async"), async {}).await;
+        assert_eq!(OBSERVED_POISON.take(), Some(true));
+    }
+}
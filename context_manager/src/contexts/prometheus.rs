@@ -0,0 +1,173 @@
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use prometheus::{HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder};
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+static METRICS: OnceLock<Metrics> = OnceLock::new();
+
+/// Metrics recorded by [`PrometheusContext`], labelled by the name of the wrapped function
+struct Metrics {
+    registry: Registry,
+    calls: IntCounterVec,
+    durations: HistogramVec,
+}
+
+impl Metrics {
+    fn get() -> &'static Self {
+        METRICS.get_or_init(|| {
+            let calls = IntCounterVec::new(
+                Opts::new(
+                    "wrapped_calls_total",
+                    "Number of calls of the wrapped function",
+                ),
+                &["fn"],
+            )
+            .expect("the options of the counter are valid");
+            let durations = HistogramVec::new(
+                HistogramOpts::new(
+                    "wrapped_call_duration_seconds",
+                    "Duration of the calls of the wrapped function",
+                ),
+                &["fn"],
+            )
+            .expect("the options of the histogram are valid");
+
+            let registry = Registry::new();
+            registry
+                .register(Box::new(calls.clone()))
+                .expect("the counter is registered once");
+            registry
+                .register(Box::new(durations.clone()))
+                .expect("the histogram is registered once");
+            Self {
+                registry,
+                calls,
+                durations,
+            }
+        })
+    }
+
+    fn render(&self) -> String {
+        TextEncoder::new()
+            .encode_to_string(&self.registry.gather())
+            .expect("the gathered metrics are valid")
+    }
+}
+
+/// Context recording Prometheus metrics of each wrapped function (requires the `prometheus` feature)
+///
+/// Each call increments the `wrapped_calls_total` counter and observes its duration, in seconds, in the
+/// `wrapped_call_duration_seconds` histogram. Both the metrics are labelled by `fn`, the name of the wrapped
+/// function, so the series are shared by all the functions with the same name.
+///
+/// The metrics are kept in a dedicated [`prometheus::Registry`], whose text exposition is provided by
+/// [`PrometheusContext::render`], ready to be served by a `/metrics` endpoint.
+/// ```
+/// # use context_manager::contexts::PrometheusContext;
+/// # use context_manager::wrap;
+/// #[wrap(PrometheusContext)]
+/// fn handle() {}
+///
+/// handle();
+/// assert!(PrometheusContext::render().contains("wrapped_calls_total{fn=\"handle\"} 1"));
+/// ```
+#[derive(Debug)]
+pub struct PrometheusContext;
+
+impl PrometheusContext {
+    /// Registry holding the metrics recorded by the context
+    ///
+    /// This allows to gather the metrics alongside the ones of the application.
+    #[must_use]
+    pub fn registry() -> &'static Registry {
+        &Metrics::get().registry
+    }
+
+    /// Metrics recorded by the context, in the Prometheus text exposition format
+    #[must_use]
+    pub fn render() -> String {
+        Metrics::get().render()
+    }
+
+    fn record(caller_context: &CallerContext, elapsed: Duration) {
+        let metrics = Metrics::get();
+        let labels = [caller_context.emitted_fn_name()];
+        metrics.calls.with_label_values(&labels).inc();
+        metrics
+            .durations
+            .with_label_values(&labels)
+            .observe(elapsed.as_secs_f64());
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for PrometheusContext {
+    const HAS_BEFORE: bool = false;
+
+    fn new() -> Self {
+        Self
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::record(caller_context, elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for PrometheusContext {
+    const HAS_BEFORE: bool = false;
+
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::record(caller_context, elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PrometheusContext;
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+    #[test]
+    fn sync_calls_are_rendered() {
+        for _ in 0..3 {
+            <PrometheusContext as SyncWrapContext<_>>::run_sync(
+                CallerContext::new("prometheus_sync"),
+                || (),
+            );
+        }
+
+        let rendered = PrometheusContext::render();
+        assert!(rendered.contains("# TYPE wrapped_calls_total counter\n"));
+        assert!(rendered.contains("# TYPE wrapped_call_duration_seconds histogram\n"));
+        assert!(rendered.contains("wrapped_calls_total{fn=\"prometheus_sync\"} 3\n"));
+        assert!(
+            rendered.contains("wrapped_call_duration_seconds_count{fn=\"prometheus_sync\"} 3\n")
+        );
+        assert!(rendered.contains(
+            "wrapped_call_duration_seconds_bucket{fn=\"prometheus_sync\",le=\"+Inf\"} 3\n"
+        ));
+    }
+
+    #[tokio::test]
+    async fn async_calls_are_rendered() {
+        for _ in 0..2 {
+            <PrometheusContext as AsyncWrapContext<_>>::run(
+                CallerContext::new("prometheus_async"),
+                async {},
+            )
+            .await;
+        }
+
+        let rendered = PrometheusContext::render();
+        assert!(rendered.contains("wrapped_calls_total{fn=\"prometheus_async\"} 2\n"));
+        assert!(
+            rendered.contains("wrapped_call_duration_seconds_count{fn=\"prometheus_async\"} 2\n")
+        );
+    }
+}
@@ -0,0 +1,98 @@
+use std::borrow::Borrow;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use crate::CallerContext;
+
+/// Registry of values, one per key (by default the wrapped function name), living for the whole program
+///
+/// Values are allocated (and leaked) once per key, so the lookup of an already
+/// registered key requires only a shared lock and no allocations.
+#[derive(Debug)]
+pub(crate) struct Registry<V: 'static, K = Box<str>> {
+    entries: RwLock<BTreeMap<K, &'static V>>,
+}
+
+impl<V: Default + Sync, K: Ord> Registry<V, K> {
+    pub(crate) const fn new() -> Self {
+        Self {
+            entries: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// Value registered for `key`, if any
+    pub(crate) fn get<Q>(&self, key: &Q) -> Option<&'static V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.entries
+            .read()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .get(key)
+            .copied()
+    }
+
+    /// Value registered for `key`, registering a default value if missing
+    pub(crate) fn get_or_register<Q>(&self, key: &Q) -> &'static V
+    where
+        K: Borrow<Q>,
+        Q: Ord + ToOwned + ?Sized,
+        Q::Owned: Into<K>,
+    {
+        if let Some(value) = self.get(key) {
+            return value;
+        }
+        self.entries
+            .write()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .entry(key.to_owned().into())
+            .or_insert_with(|| Box::leak(Box::default()))
+    }
+}
+
+/// Key of a wrapped function, distinguishing the functions with the same name defined in different locations
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub(crate) struct FnKey {
+    module_path: Option<&'static str>,
+    fn_name: &'static str,
+    file: Option<&'static str>,
+    line: Option<u32>,
+}
+
+impl FnKey {
+    /// Key of the function described by `caller_context`
+    pub(crate) const fn new(caller_context: &CallerContext) -> Self {
+        Self {
+            module_path: caller_context.module_path(),
+            fn_name: caller_context.fn_name(),
+            file: caller_context.file(),
+            line: caller_context.line(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Registry;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn values_are_registered_once() {
+        static REGISTRY: Registry<AtomicU64> = Registry::new();
+
+        assert!(REGISTRY.get("foo").is_none());
+        REGISTRY
+            .get_or_register("foo")
+            .fetch_add(1, Ordering::Relaxed);
+        REGISTRY
+            .get_or_register("foo")
+            .fetch_add(1, Ordering::Relaxed);
+        REGISTRY
+            .get_or_register("bar")
+            .fetch_add(1, Ordering::Relaxed);
+
+        assert_eq!(REGISTRY.get("foo").unwrap().load(Ordering::Relaxed), 2);
+        assert_eq!(REGISTRY.get("bar").unwrap().load(Ordering::Relaxed), 1);
+    }
+}
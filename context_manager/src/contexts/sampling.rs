@@ -0,0 +1,590 @@
+use std::any::type_name;
+use std::cell::Cell;
+use std::collections::hash_map::RandomState;
+use std::hash::BuildHasher;
+use std::marker::PhantomData;
+use std::ops::ControlFlow;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+use crate::contexts::registry::{FnKey, Registry};
+use crate::{AsyncWrapContext, CallerContext, Reentrancy, SyncWrapContext};
+
+/// Calls counted by [`EveryNth`], per sampling context (by type name) and per wrapped function
+static CALLS: Registry<AtomicU32, (&'static str, FnKey)> = Registry::new();
+
+/// Configuration of [`SamplingContext`]
+///
+/// ```
+/// # use context_manager::contexts::SamplingConfig;
+/// struct OnePercent;
+/// impl SamplingConfig for OnePercent {
+///     const RATE: f64 = 0.01;
+/// }
+/// ```
+pub trait SamplingConfig {
+    /// Fraction of the calls for which the hooks of the inner context are executed, in `[0, 1]`
+    const RATE: f64;
+
+    /// Generate a random number in `[0, 1)`, used to decide whether a call is sampled
+    ///
+    /// By default a thread-local pseudo random generator, which is not cryptographically secure, is used.
+    /// Overriding it allows to inject a different generator (ie. a deterministic one for tests).
+    #[must_use]
+    fn random() -> f64 {
+        thread_local! {
+            static STATE: Cell<u64> = Cell::new(RandomState::new().hash_one(0_u64) | 1);
+        }
+
+        // xorshift64* generator, more than enough to decide about sampling
+        let value = STATE.with(|state| {
+            let mut x = state.get();
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            state.set(x);
+            x.wrapping_mul(0x2545_f491_4f6c_dd1d)
+        });
+        // Use the 53 most significant bits, as they fit exactly the f64 mantissa
+        #[allow(clippy::cast_precision_loss)]
+        let random = (value >> 11) as f64 / (1_u64 << 53) as f64;
+        random
+    }
+
+    /// Decide whether the current call of the wrapped function described by `caller_context` is sampled
+    ///
+    /// `layer` is the type name of the sampling context taking the decision, so that the decisions of the sampling
+    /// contexts wrapping the same function (ie. stacked via the macros) can be kept apart.
+    ///
+    /// By default a call is sampled with probability [`SamplingConfig::RATE`], via [`SamplingConfig::random`].
+    #[must_use]
+    fn sample(caller_context: &CallerContext, layer: &'static str) -> bool {
+        let _ = (caller_context, layer);
+        Self::random() < Self::RATE
+    }
+}
+
+/// Sampling configuration selecting every `N`th call of each wrapped function, as used by [`ConstSampleContext`]
+///
+/// The calls are counted per sampling context and per wrapped function (by location, so functions with the same name
+/// in different modules are counted separately): the first call is sampled, then the `N + 1`th and so on.
+#[derive(Debug)]
+pub struct EveryNth<const N: u32>;
+
+impl<const N: u32> SamplingConfig for EveryNth<N> {
+    #[allow(clippy::cast_lossless)] // `f64::from` is not const
+    const RATE: f64 = 1.0 / N as f64;
+
+    fn sample(caller_context: &CallerContext, layer: &'static str) -> bool {
+        const { assert!(N > 0, "the sampling rate of `EveryNth` has to be positive") };
+        N == 1
+            || CALLS
+                .get_or_register(&(layer, FnKey::new(caller_context)))
+                .fetch_add(1, Ordering::Relaxed)
+                .is_multiple_of(N)
+    }
+}
+
+/// Context executing the hooks of the inner context `C` every `N`th call of each wrapped function
+///
+/// This is a [`SamplingContext`] deterministically sampling the calls, via [`EveryNth`]. As `N` is a constant,
+/// the sampling is specialised at compile time, ie. `ConstSampleContext<C, 1>` samples all the calls without counting them.
+/// ```
+/// # use context_manager::contexts::ConstSampleContext;
+/// # use context_manager::{wrap, SyncWrapContext};
+/// # struct PrintDuration;
+/// # impl<T> SyncWrapContext<T> for PrintDuration { fn new() -> Self { Self } }
+/// #[wrap(ConstSampleContext<PrintDuration, 100>)]
+/// fn hot_function() {}
+/// ```
+pub type ConstSampleContext<C, const N: u32> = SamplingContext<C, EveryNth<N>>;
+
+/// Context executing the hooks of the inner context `C` only for a fraction of the calls
+///
+/// The wrapped body is always executed, the sampling decides only whether the hooks of `C` are executed.
+/// The decision is taken once per call, via [`SamplingConfig::sample`], in the `before` hook, where the inner context
+/// is initialised if the call is sampled. So, the decision of each sampling context depends only on its own configuration,
+/// also when composed with other sampling contexts (ie. via `ContextChain` or by nesting them).
+/// ```
+/// # use context_manager::contexts::{SamplingConfig, SamplingContext};
+/// # use context_manager::{wrap, SyncWrapContext};
+/// # struct PrintDuration;
+/// # impl<T> SyncWrapContext<T> for PrintDuration { fn new() -> Self { Self } }
+/// struct OnePercent;
+/// impl SamplingConfig for OnePercent {
+///     const RATE: f64 = 0.01;
+/// }
+///
+/// #[wrap(SamplingContext<PrintDuration, OnePercent>)]
+/// fn hot_function() {}
+/// ```
+///
+/// NOTE: Only the hooks of `C` are forwarded, so any custom `run_*` implementation of `C` is ignored.
+/// `map_fn_name` is not forwarded either, as it is invoked before the sampling decision is taken (so the emitted name
+/// is the name of the wrapped function).
+/// The caller context is built via `C::build_caller_context` for all the calls, as the sampling decision is not taken yet.
+/// As the inner context is initialised in the `before` hook, its `on_first_call` hook is executed right before its `before` one.
+#[derive(Debug)]
+pub struct SamplingContext<C, S: SamplingConfig> {
+    inner: OnceLock<C>,
+    first_call: AtomicBool,
+    config: PhantomData<S>,
+}
+
+impl<C, S: SamplingConfig> SamplingContext<C, S> {
+    /// Whether the current call is sampled, and so the hooks of the inner context are executed
+    ///
+    /// The decision is taken in the `before` hook, so until then the call is reported as not sampled.
+    #[must_use]
+    pub fn is_sampled(&self) -> bool {
+        self.inner.get().is_some()
+    }
+
+    const fn create() -> Self {
+        Self {
+            inner: OnceLock::new(),
+            first_call: AtomicBool::new(false),
+            config: PhantomData,
+        }
+    }
+
+    fn inner(&self) -> Option<&C> {
+        self.inner.get()
+    }
+
+    /// Store the inner context of a sampled call, returning it if `on_first_call` has to be forwarded to it
+    fn sampled(&self, inner: C) -> (&C, bool) {
+        (
+            self.inner.get_or_init(|| inner),
+            self.first_call.load(Ordering::Relaxed),
+        )
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: SyncWrapContext<T>, S: SamplingConfig, T> SyncWrapContext<T> for SamplingContext<C, S> {
+    const REENTRANCY: Reentrancy = C::REENTRANCY;
+    const HAS_AFTER: bool = C::HAS_AFTER;
+
+    fn new() -> Self {
+        Self::create()
+    }
+
+    fn build_caller_context(default: CallerContext) -> CallerContext {
+        C::build_caller_context(default)
+    }
+
+    fn on_first_call(&self, _: &CallerContext) {
+        self.first_call.store(true, Ordering::Relaxed);
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        if S::sample(caller_context, type_name::<Self>()) {
+            let (inner, first_call) = self.sampled(C::new());
+            if first_call {
+                inner.on_first_call(caller_context);
+            }
+            inner.before(caller_context);
+        }
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        if let Some(inner) = self.inner.into_inner() {
+            inner.after_timed(caller_context, result, elapsed);
+        }
+    }
+
+    fn after_owned(self, caller_context: &CallerContext, result: T, elapsed: Duration) -> T {
+        match self.inner.into_inner() {
+            Some(inner) => inner.after_owned(caller_context, result, elapsed),
+            None => result,
+        }
+    }
+
+    async fn after_async(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        if let Some(inner) = self.inner.into_inner() {
+            inner.after_async(caller_context, result, elapsed).await;
+        }
+    }
+
+    fn after_ref(&mut self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        if let Some(inner) = self.inner.get_mut() {
+            inner.after_ref(caller_context, result, elapsed);
+        }
+    }
+
+    fn on_reentry(&self, caller_context: &CallerContext) {
+        // Re-entrant calls skip `before`, so the decision is taken here
+        if S::sample(caller_context, type_name::<Self>()) {
+            self.sampled(C::new()).0.on_reentry(caller_context);
+        }
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        match self.inner() {
+            Some(inner) => inner.short_circuit(caller_context),
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    fn now() -> std::time::Instant {
+        C::now()
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        match self.inner() {
+            Some(inner) => inner.slow_threshold(),
+            None => Duration::MAX,
+        }
+    }
+
+    fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        if let Some(inner) = self.inner() {
+            inner.on_slow(caller_context, elapsed);
+        }
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        if let Some(inner) = self.inner() {
+            inner.on_poisoned(caller_context);
+        }
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        if let Some(inner) = self.inner() {
+            inner.finally(caller_context);
+        }
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: AsyncWrapContext<T>, S: SamplingConfig, T> AsyncWrapContext<T> for SamplingContext<C, S> {
+    const HAS_AFTER: bool = C::HAS_AFTER;
+
+    async fn new() -> Self {
+        Self::create()
+    }
+
+    fn build_caller_context(default: CallerContext) -> CallerContext {
+        C::build_caller_context(default)
+    }
+
+    fn on_first_call(&self, _: &CallerContext) {
+        self.first_call.store(true, Ordering::Relaxed);
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        if S::sample(caller_context, type_name::<Self>()) {
+            let (inner, first_call) = self.sampled(C::new().await);
+            if first_call {
+                inner.on_first_call(caller_context);
+            }
+            inner.before(caller_context).await;
+        }
+    }
+
+    fn on_cancel(&self, caller_context: &CallerContext) {
+        if let Some(inner) = self.inner() {
+            inner.on_cancel(caller_context);
+        }
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        match self.inner() {
+            Some(inner) => inner.short_circuit(caller_context),
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    fn now() -> std::time::Instant {
+        C::now()
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        match self.inner() {
+            Some(inner) => inner.slow_threshold(),
+            None => Duration::MAX,
+        }
+    }
+
+    async fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        if let Some(inner) = self.inner() {
+            inner.on_slow(caller_context, elapsed).await;
+        }
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        if let Some(inner) = self.inner() {
+            inner.on_poisoned(caller_context);
+        }
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        if let Some(inner) = self.inner() {
+            inner.finally(caller_context);
+        }
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        if let Some(inner) = self.inner.into_inner() {
+            inner.after_timed(caller_context, result, elapsed).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ConstSampleContext, SamplingConfig, SamplingContext};
+    use crate::contexts::ContextChain;
+    use crate::{AsyncWrapContext, CallerContext, FirstCall, SyncWrapContext};
+    use std::cell::{Cell, RefCell};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    thread_local! {
+        static NEXT: Cell<u32> = const { Cell::new(0) };
+    }
+
+    /// Deterministic generator, cycling over `0.00, 0.01, ..., 0.99`
+    struct QuarterDeterministic;
+    impl SamplingConfig for QuarterDeterministic {
+        const RATE: f64 = 0.25;
+
+        fn random() -> f64 {
+            let next = NEXT.with(|next| next.replace((next.get() + 1) % 100));
+            f64::from(next) / 100.0
+        }
+    }
+
+    struct Quarter;
+    impl SamplingConfig for Quarter {
+        const RATE: f64 = 0.25;
+    }
+
+    #[test]
+    fn default_random_is_in_range() {
+        for _ in 0..1_000 {
+            let random = Quarter::random();
+            assert!((0.0..1.0).contains(&random));
+        }
+    }
+
+    #[test]
+    fn sync_hooks_run_for_the_configured_fraction() {
+        static BEFORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static AFTER_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static BODY_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Inner;
+        impl SyncWrapContext<()> for Inner {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
+                BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn after(self, _: &CallerContext, (): &()) {
+                AFTER_CALLS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        for _ in 0..1_000 {
+            SamplingContext::<Inner, QuarterDeterministic>::run_sync(
+                CallerContext::new("test"),
+                || {
+                    BODY_CALLS.fetch_add(1, Ordering::Relaxed);
+                },
+            );
+        }
+
+        assert_eq!(BODY_CALLS.load(Ordering::Relaxed), 1_000);
+        assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 250);
+        assert_eq!(AFTER_CALLS.load(Ordering::Relaxed), 250);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_hooks_run_for_the_configured_fraction() {
+        static BEFORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Inner;
+        impl AsyncWrapContext<()> for Inner {
+            async fn new() -> Self {
+                Self
+            }
+
+            async fn before(&self, _: &mut CallerContext) {
+                BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        for _ in 0..1_000 {
+            SamplingContext::<Inner, QuarterDeterministic>::run(
+                CallerContext::new("test"),
+                async {},
+            )
+            .await;
+        }
+
+        assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 250);
+    }
+
+    #[test]
+    fn const_sampling_runs_every_nth_call() {
+        static BEFORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Inner;
+        impl SyncWrapContext<()> for Inner {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
+                BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        fn run_many<const N: u32>(fn_name: &'static str) -> usize {
+            BEFORE_CALLS.store(0, Ordering::Relaxed);
+            for _ in 0..300 {
+                ConstSampleContext::<Inner, N>::run_sync(CallerContext::new(fn_name), || ());
+            }
+            BEFORE_CALLS.load(Ordering::Relaxed)
+        }
+
+        assert_eq!(run_many::<1>("const_sampling_always"), 300);
+        assert_eq!(run_many::<3>("const_sampling_third"), 100);
+
+        // The calls are counted per function
+        BEFORE_CALLS.store(0, Ordering::Relaxed);
+        for fn_name in ["const_sampling_a", "const_sampling_b", "const_sampling_a"] {
+            ConstSampleContext::<Inner, 2>::run_sync(CallerContext::new(fn_name), || ());
+        }
+        assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 2);
+
+        // Functions with the same name in different modules are counted separately
+        BEFORE_CALLS.store(0, Ordering::Relaxed);
+        for module_path in ["v1", "v2", "v1"] {
+            ConstSampleContext::<Inner, 2>::run_sync(
+                CallerContext::new("const_sampling_fetch").with_location(module_path, "lib.rs", 1),
+                || (),
+            );
+        }
+        assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn stacked_const_sampling_contexts_count_their_own_calls() {
+        thread_local! {
+            static EVENTS: RefCell<Vec<char>> = const { RefCell::new(Vec::new()) };
+        }
+
+        struct Named<const NAME: char>;
+        impl<const NAME: char> SyncWrapContext<()> for Named<NAME> {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
+                EVENTS.with_borrow_mut(|events| events.push(NAME));
+            }
+        }
+
+        // As via `#[wrap(ConstSampleContext<Named<'a'>, 2>)] #[wrap(ConstSampleContext<Named<'b'>, 2>)]`
+        for _ in 0..4 {
+            ConstSampleContext::<Named<'a'>, 2>::run_sync(
+                CallerContext::new("const_sampling_stacked"),
+                || {
+                    ConstSampleContext::<Named<'b'>, 2>::run_sync(
+                        CallerContext::new("const_sampling_stacked"),
+                        || (),
+                    );
+                },
+            );
+        }
+        assert_eq!(EVENTS.take(), ['a', 'b', 'a', 'b']);
+    }
+
+    #[test]
+    fn composed_sampling_contexts_take_their_own_decision() {
+        thread_local! {
+            static EVENTS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+        }
+
+        struct Always;
+        impl SamplingConfig for Always {
+            const RATE: f64 = 1.0;
+        }
+
+        struct Never;
+        impl SamplingConfig for Never {
+            const RATE: f64 = 0.0;
+        }
+
+        struct Named<const NAME: char>;
+        impl<const NAME: char> SyncWrapContext<()> for Named<NAME> {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
+                EVENTS.with_borrow_mut(|events| {
+                    events.push(if NAME == 'a' { "before a" } else { "before b" });
+                });
+            }
+        }
+
+        fn before_calls<C: SyncWrapContext<()>>() -> Vec<&'static str> {
+            C::run_sync(CallerContext::new("sampling_composed"), || ());
+            EVENTS.take()
+        }
+
+        type A<S> = SamplingContext<Named<'a'>, S>;
+        type B<S> = SamplingContext<Named<'b'>, S>;
+        assert_eq!(
+            before_calls::<ContextChain<A<Always>, B<Never>>>(),
+            ["before a"]
+        );
+        assert_eq!(
+            before_calls::<ContextChain<A<Never>, B<Always>>>(),
+            ["before b"]
+        );
+        assert!(before_calls::<SamplingContext<A<Always>, Never>>().is_empty());
+        assert!(before_calls::<SamplingContext<A<Never>, Always>>().is_empty());
+        assert_eq!(
+            before_calls::<SamplingContext<B<Always>, Always>>(),
+            ["before b"]
+        );
+    }
+
+    #[test]
+    fn sampled_calls_forward_the_first_call() {
+        static FIRST_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Inner;
+        impl SyncWrapContext<()> for Inner {
+            fn new() -> Self {
+                Self
+            }
+
+            fn on_first_call(&self, _: &CallerContext) {
+                FIRST_CALLS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        static FIRST_CALL: FirstCall = FirstCall::new();
+
+        // The decision is taken in `before`
+        let context = <ConstSampleContext<Inner, 1> as SyncWrapContext<()>>::new();
+        assert!(!context.is_sampled());
+
+        for _ in 0..3 {
+            ConstSampleContext::<Inner, 1>::run_sync(
+                CallerContext::new("sampling_first_call").with_first_call(&FIRST_CALL),
+                || (),
+            );
+        }
+        assert_eq!(FIRST_CALLS.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,234 @@
+use std::any::{Any, TypeId};
+use std::cell::{Cell, RefCell};
+use std::marker::PhantomData;
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Entry of the scoped values of the current thread
+struct Entry {
+    /// Type of the key, as multiple keys share the same stack
+    key: TypeId,
+    /// Identifier of the entry, used to remove it once the wrapped body terminates
+    id: u64,
+    value: Box<dyn Any>,
+}
+
+thread_local! {
+    static NEXT_ID: Cell<u64> = const { Cell::new(0) };
+    static SCOPED_VALUES: RefCell<Vec<Entry>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Key of the values set by [`ScopedValueContext`], providing the value to set for each call
+///
+/// ```
+/// # use context_manager::contexts::ScopedKey;
+/// # use context_manager::CallerContext;
+/// struct Operation;
+/// impl ScopedKey<&'static str> for Operation {
+///     fn value(caller_context: &CallerContext) -> &'static str {
+///         caller_context.fn_name()
+///     }
+/// }
+/// ```
+pub trait ScopedKey<V> {
+    /// Value to set while executing the wrapped body
+    fn value(caller_context: &CallerContext) -> V;
+}
+
+/// Context setting a value, like a logging diagnostic context (MDC), while executing the wrapped function
+///
+/// The value is provided by the key `K` in `before`, and it is removed in `finally`, so that the previous value
+/// (if any) is restored once the wrapped function terminates, even if it panics. Nested calls shadow the values of
+/// the outer ones, and values of different keys are independent.
+/// ```
+/// # use context_manager::contexts::{ScopedKey, ScopedValueContext};
+/// # use context_manager::{wrap, CallerContext};
+/// struct Operation;
+/// impl ScopedKey<&'static str> for Operation {
+///     fn value(caller_context: &CallerContext) -> &'static str {
+///         caller_context.fn_name()
+///     }
+/// }
+///
+/// type OperationContext = ScopedValueContext<Operation, &'static str>;
+///
+/// #[wrap(OperationContext)]
+/// fn handle_request() -> Option<&'static str> {
+///     OperationContext::current()
+/// }
+///
+/// assert_eq!(handle_request(), Some("handle_request"));
+/// assert_eq!(OperationContext::current(), None);
+/// ```
+///
+/// NOTE: The values are kept per thread, so for async functions they are visible to the wrapped body only
+/// if it is not interleaved with other tasks setting the same key on the same thread.
+#[derive(Debug)]
+pub struct ScopedValueContext<K: ScopedKey<V>, V> {
+    id: Cell<Option<u64>>,
+    types: PhantomData<(K, V)>,
+}
+
+impl<K: ScopedKey<V> + 'static, V: 'static> ScopedValueContext<K, V> {
+    /// Execute `f` with the innermost value of the key `K` set on the current thread, if any
+    pub fn with_current<R>(f: impl FnOnce(Option<&V>) -> R) -> R {
+        SCOPED_VALUES.with_borrow(|entries| {
+            f(entries
+                .iter()
+                .rev()
+                .find(|entry| entry.key == TypeId::of::<K>())
+                .and_then(|entry| entry.value.downcast_ref()))
+        })
+    }
+
+    /// Innermost value of the key `K` set on the current thread, if any
+    #[must_use]
+    #[allow(clippy::redundant_closure_for_method_calls)] // `Option::cloned` is not general over the lifetime
+    pub fn current() -> Option<V>
+    where
+        V: Clone,
+    {
+        Self::with_current(|value| value.cloned())
+    }
+
+    const fn create() -> Self {
+        Self {
+            id: Cell::new(None),
+            types: PhantomData,
+        }
+    }
+
+    fn push(&self, caller_context: &CallerContext) {
+        let id = NEXT_ID.replace(NEXT_ID.get().wrapping_add(1));
+        let value = Box::new(K::value(caller_context));
+        SCOPED_VALUES.with_borrow_mut(|entries| {
+            entries.push(Entry {
+                key: TypeId::of::<K>(),
+                id,
+                value,
+            });
+        });
+        self.id.set(Some(id));
+    }
+
+    fn remove(&self) {
+        if let Some(id) = self.id.take() {
+            SCOPED_VALUES.with_borrow_mut(|entries| entries.retain(|entry| entry.id != id));
+        }
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<K: ScopedKey<V> + 'static, V: 'static, T> SyncWrapContext<T> for ScopedValueContext<K, V> {
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self::create()
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        self.push(caller_context);
+    }
+
+    fn finally(&self, _: &CallerContext) {
+        self.remove();
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<K: ScopedKey<V> + 'static, V: 'static, T> AsyncWrapContext<T> for ScopedValueContext<K, V> {
+    const HAS_AFTER: bool = false;
+
+    async fn new() -> Self {
+        Self::create()
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        self.push(caller_context);
+    }
+
+    fn finally(&self, _: &CallerContext) {
+        self.remove();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ScopedKey, ScopedValueContext};
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use core::future::Future;
+
+    struct FnName;
+    impl ScopedKey<&'static str> for FnName {
+        fn value(caller_context: &CallerContext) -> &'static str {
+            caller_context.fn_name()
+        }
+    }
+
+    struct ArgCount;
+    impl ScopedKey<usize> for ArgCount {
+        fn value(caller_context: &CallerContext) -> usize {
+            caller_context.arg_names().len()
+        }
+    }
+
+    type FnNameContext = ScopedValueContext<FnName, &'static str>;
+    type ArgCountContext = ScopedValueContext<ArgCount, usize>;
+
+    fn run<T>(fn_name: &'static str, block: impl FnOnce() -> T) -> T {
+        <FnNameContext as SyncWrapContext<_>>::run_sync(CallerContext::new(fn_name), block)
+    }
+
+    #[test]
+    fn nested_values_are_restored() {
+        assert_eq!(FnNameContext::current(), None);
+        run("outer", || {
+            assert_eq!(FnNameContext::current(), Some("outer"));
+            run("inner", || {
+                assert_eq!(FnNameContext::current(), Some("inner"));
+            });
+            assert_eq!(FnNameContext::current(), Some("outer"));
+        });
+        assert_eq!(FnNameContext::current(), None);
+    }
+
+    #[test]
+    fn values_are_restored_on_panic() {
+        run("outer", || {
+            let result = std::panic::catch_unwind(|| run("inner", || panic!("panic in the body")));
+            assert!(result.is_err());
+            assert_eq!(FnNameContext::current(), Some("outer"));
+        });
+    }
+
+    #[test]
+    fn keys_are_independent() {
+        let caller_context = CallerContext::new("foo").with_arg_names(&["a", "b"]);
+        <ArgCountContext as SyncWrapContext<_>>::run_sync(caller_context, || {
+            assert_eq!(ArgCountContext::current(), Some(2));
+            assert_eq!(FnNameContext::current(), None);
+            run("outer", || {
+                assert_eq!(ArgCountContext::current(), Some(2));
+                assert!(FnNameContext::with_current(|value| value == Some(&"outer")));
+            });
+        });
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_nested_values_are_restored() {
+        async fn run(fn_name: &'static str, block: impl Future<Output = ()>) {
+            <FnNameContext as AsyncWrapContext<_>>::run(CallerContext::new(fn_name), block).await;
+        }
+
+        run("outer", async {
+            assert_eq!(FnNameContext::current(), Some("outer"));
+            run("inner", async {
+                assert_eq!(FnNameContext::current(), Some("inner"));
+            })
+            .await;
+            assert_eq!(FnNameContext::current(), Some("outer"));
+        })
+        .await;
+        assert_eq!(FnNameContext::current(), None);
+    }
+}
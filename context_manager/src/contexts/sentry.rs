@@ -0,0 +1,103 @@
+use std::error::Error;
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use sentry_core::protocol::{Event, Level};
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Configuration of [`SentryContext`]
+///
+/// ```
+/// # use context_manager::contexts::SentryConfig;
+/// struct Warning;
+/// impl SentryConfig for Warning {
+///     const LEVEL: sentry_core::Level = sentry_core::Level::Warning;
+///     const CAPTURE_PANICS: bool = true;
+/// }
+/// ```
+pub trait SentryConfig {
+    /// Level of the captured events
+    const LEVEL: Level = Level::Error;
+
+    /// Whether panics of the wrapped body are captured as well
+    const CAPTURE_PANICS: bool = false;
+}
+
+/// Default configuration of [`SentryContext`], capturing errors at [`Level::Error`] level and ignoring panics
+#[derive(Debug)]
+pub struct DefaultSentryConfig;
+impl SentryConfig for DefaultSentryConfig {}
+
+/// Context reporting to Sentry, via the [`sentry_core`] crate, the errors returned by the wrapped function (requires the `sentry` feature)
+///
+/// The context operates on functions returning a `Result` whose error implements [`Error`]. Each `Err` result
+/// is captured as an event of the current hub, with the name of the wrapped function as transaction.
+/// ```
+/// # use context_manager::contexts::SentryContext;
+/// # use context_manager::wrap;
+/// #[wrap(SentryContext)]
+/// fn parse(value: &str) -> Result<u64, std::num::ParseIntError> {
+///     value.parse()
+/// }
+/// ```
+///
+/// The level of the events, and whether panics are captured as well, can be customised via [`SentryConfig`].
+/// Panics are captured while unwinding, so their message is not part of the event: please consider
+/// the panic integration of the `sentry` crate if the message is needed.
+#[derive(Debug)]
+pub struct SentryContext<C: SentryConfig = DefaultSentryConfig>(PhantomData<C>);
+
+impl<C: SentryConfig> SentryContext<C> {
+    fn capture<E: Error + ?Sized>(caller_context: &CallerContext, error: &E) {
+        let mut event = sentry_core::event_from_error(error);
+        event.level = C::LEVEL;
+        event.transaction = Some(caller_context.emitted_fn_name().to_owned());
+        sentry_core::capture_event(event);
+    }
+
+    fn capture_panic(caller_context: &CallerContext) {
+        if C::CAPTURE_PANICS && std::thread::panicking() {
+            sentry_core::capture_event(Event {
+                message: Some(format!("{} panicked", caller_context.emitted_fn_name())),
+                level: C::LEVEL,
+                transaction: Some(caller_context.emitted_fn_name().to_owned()),
+                ..Event::default()
+            });
+        }
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: SentryConfig, T, E: Error> SyncWrapContext<Result<T, E>> for SentryContext<C> {
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        Self::capture_panic(caller_context);
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, result: &Result<T, E>, _: Duration) {
+        if let Err(error) = result {
+            Self::capture(caller_context, error);
+        }
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: SentryConfig, T, E: Error> AsyncWrapContext<Result<T, E>> for SentryContext<C> {
+    async fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        Self::capture_panic(caller_context);
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, result: &Result<T, E>, _: Duration) {
+        if let Err(error) = result {
+            Self::capture(caller_context, error);
+        }
+    }
+}
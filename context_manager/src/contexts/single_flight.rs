@@ -0,0 +1,274 @@
+use std::any::Any;
+use std::future::poll_fn;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::task::{Poll, Waker};
+
+use crate::call_stack::{in_call_stack_async, Frame};
+use crate::contexts::registry::{FnKey, Registry};
+use crate::{CallerContext, SyncWrapContext, WrapContextWithArgs};
+
+/// Calls in progress, per function, as `Flight<Args, T>`
+static FLIGHTS: Registry<Mutex<Vec<Arc<dyn Any + Send + Sync>>>, FnKey> = Registry::new();
+
+/// Call in progress, shared by all the concurrent callers with the same arguments
+struct Flight<Args, T> {
+    args: Args,
+    state: Mutex<FlightState<T>>,
+}
+
+enum FlightState<T> {
+    /// The body is being executed, the wakers are the ones of the callers waiting for its result
+    Running(Vec<Waker>),
+    /// The body completed with the result
+    Done(T),
+    /// The body did not complete, as the caller executing it was cancelled or it panicked
+    Abandoned,
+}
+
+impl<Args, T> Flight<Args, T> {
+    fn set_state(&self, state: FlightState<T>) {
+        let previous = std::mem::replace(
+            &mut *self.state.lock().unwrap_or_else(PoisonError::into_inner),
+            state,
+        );
+        if let FlightState::Running(wakers) = previous {
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
+}
+
+/// Guard of the caller executing the body, publishing the outcome to the waiting callers
+struct Leader<'a, Args: 'static, T: 'static> {
+    flights: &'static Mutex<Vec<Arc<dyn Any + Send + Sync>>>,
+    flight: &'a Arc<Flight<Args, T>>,
+}
+
+impl<Args, T> Leader<'_, Args, T> {
+    /// Stop sharing the flight, so the following calls execute the body again
+    fn land(&self) {
+        let flight: *const Flight<Args, T> = Arc::as_ptr(self.flight);
+        self.flights
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .retain(|other| !std::ptr::addr_eq(Arc::as_ptr(other), flight));
+    }
+
+    fn complete(self, result: T) {
+        self.land();
+        self.flight.set_state(FlightState::Done(result));
+        std::mem::forget(self);
+    }
+}
+
+impl<Args, T> Drop for Leader<'_, Args, T> {
+    fn drop(&mut self) {
+        self.land();
+        self.flight.set_state(FlightState::Abandoned);
+    }
+}
+
+/// Context deduplicating the concurrent calls of async functions with the same arguments (single-flight)
+///
+/// While a call is in progress, the calls with equal arguments do not execute the body: they wait for the
+/// call in progress and receive a clone of its result. Once the call completes, the following calls execute
+/// the body again, so the results are not cached. If the call in progress is cancelled (or it panics),
+/// the waiting calls execute the body themselves.
+///
+/// Calls are deduplicated per function, identified by its name and location, so functions with the same name
+/// in different modules never share their calls.
+///
+/// The context requires the `args` macro argument, which provides the arguments to compare, so they have to
+/// implement [`Clone`] and [`PartialEq`] and the result has to implement [`Clone`].
+/// ```
+/// # use context_manager::contexts::SingleFlightContext;
+/// # use context_manager::wrap;
+/// #[wrap(SingleFlightContext, args)]
+/// async fn load_user(id: u64) -> String {
+///     // Expensive query, executed once for concurrent calls with the same `id`
+///     format!("user {id}")
+/// }
+/// ```
+///
+/// NOTE: Only async functions are deduplicated, synchronous ones are executed as usual.
+#[derive(Debug)]
+pub struct SingleFlightContext;
+
+impl SingleFlightContext {
+    /// Join the flight of the call in progress with the same arguments, or start a new one,
+    /// returning whether the caller has to execute the body
+    fn join<Args, T>(fn_key: &FnKey, args: &Args) -> (Arc<Flight<Args, T>>, bool)
+    where
+        Args: Clone + PartialEq + Send + Sync + 'static,
+        T: Send + 'static,
+    {
+        let mut flights = FLIGHTS
+            .get_or_register(fn_key)
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        let existing = flights.iter().find_map(|flight| {
+            let flight = Arc::clone(flight).downcast::<Flight<Args, T>>().ok()?;
+            (flight.args == *args).then_some(flight)
+        });
+        if let Some(flight) = existing {
+            return (flight, false);
+        }
+        let flight = Arc::new(Flight {
+            args: args.clone(),
+            state: Mutex::new(FlightState::Running(Vec::new())),
+        });
+        flights.push(Arc::clone(&flight) as Arc<dyn Any + Send + Sync>);
+        (flight, true)
+    }
+
+    /// Wait for the result of the flight, or `None` if it was abandoned
+    async fn wait<Args, T: Clone>(flight: &Flight<Args, T>) -> Option<T> {
+        poll_fn(
+            |cx| match &mut *flight.state.lock().unwrap_or_else(PoisonError::into_inner) {
+                FlightState::Running(wakers) => {
+                    if !wakers.iter().any(|waker| waker.will_wake(cx.waker())) {
+                        wakers.push(cx.waker().clone());
+                    }
+                    Poll::Pending
+                }
+                FlightState::Done(result) => Poll::Ready(Some(result.clone())),
+                FlightState::Abandoned => Poll::Ready(None),
+            },
+        )
+        .await
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for SingleFlightContext {
+    const HAS_BEFORE: bool = false;
+    const HAS_AFTER: bool = false;
+
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<Args, T> WrapContextWithArgs<Args, T> for SingleFlightContext
+where
+    Args: Clone + PartialEq + Send + Sync + 'static,
+    T: Clone + Send + 'static,
+{
+    async fn run_async_with_args(
+        caller_context: CallerContext,
+        args: Args,
+        block: impl AsyncFnOnce(Args, &CallerContext) -> T,
+    ) -> T {
+        let frame = Frame::new::<Self>(&caller_context);
+        let fn_key = FnKey::new(&caller_context);
+        let (flight, leader) = Self::join::<Args, T>(&fn_key, &args);
+        if !leader {
+            if let Some(result) = Self::wait(&flight).await {
+                return result;
+            }
+            return in_call_stack_async(frame, block(args, &caller_context)).await;
+        }
+
+        let leader = Leader {
+            flights: FLIGHTS.get_or_register(&fn_key),
+            flight: &flight,
+        };
+        let result = in_call_stack_async(frame, block(args, &caller_context)).await;
+        leader.complete(result.clone());
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::pending;
+    use std::pin::pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::time::Duration;
+
+    use futures::future::join_all;
+    use futures::poll;
+
+    use super::SingleFlightContext;
+    use crate::{CallerContext, WrapContextWithArgs};
+
+    async fn load(fn_name: &'static str, executions: &'static AtomicUsize, id: u64) -> u64 {
+        load_in(CallerContext::new(fn_name), executions, id).await
+    }
+
+    async fn load_in(
+        caller_context: CallerContext,
+        executions: &'static AtomicUsize,
+        id: u64,
+    ) -> u64 {
+        SingleFlightContext::run_async_with_args(
+            caller_context,
+            (id,),
+            async move |(id,), _: &CallerContext| {
+                executions.fetch_add(1, Ordering::Relaxed);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                id * 2
+            },
+        )
+        .await
+    }
+
+    #[tokio::test]
+    async fn different_arguments_are_not_deduplicated() {
+        static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+        let load = |id| load("single_flight_args", &EXECUTIONS, id);
+        let results = join_all([1, 2, 1, 2].map(load)).await;
+        assert_eq!(results, [2, 4, 2, 4]);
+        assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 2);
+
+        // Completed calls are not cached
+        assert_eq!(load(1).await, 2);
+        assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn functions_with_the_same_name_in_different_modules_are_not_deduplicated() {
+        static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+        let fetch = |module_path, file| {
+            let caller_context = CallerContext::new("fetch").with_location(module_path, file, 7);
+            load_in(caller_context, &EXECUTIONS, 1)
+        };
+        let results = join_all([
+            fetch("crate::v1", "src/v1.rs"),
+            fetch("crate::v2", "src/v2.rs"),
+            fetch("crate::v1", "src/v1.rs"),
+            fetch("crate::v2", "src/v2.rs"),
+        ])
+        .await;
+        assert_eq!(results, [2, 2, 2, 2]);
+        assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 2);
+    }
+
+    #[tokio::test]
+    async fn waiting_calls_execute_the_body_if_the_call_in_progress_is_cancelled() {
+        static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+        let load = || {
+            SingleFlightContext::run_async_with_args(
+                CallerContext::new("single_flight_cancel"),
+                (3,),
+                async |(id,): (u64,), _: &CallerContext| {
+                    // The first execution never completes, so it is in progress until cancelled
+                    if EXECUTIONS.fetch_add(1, Ordering::Relaxed) == 0 {
+                        pending::<()>().await;
+                    }
+                    id * 2
+                },
+            )
+        };
+
+        let mut leader = Box::pin(load());
+        assert!(poll!(&mut leader).is_pending());
+        let mut waiting = pin!(load());
+        assert!(poll!(&mut waiting).is_pending());
+        assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 1);
+
+        drop(leader);
+        assert_eq!(waiting.await, 6);
+        assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 2);
+    }
+}
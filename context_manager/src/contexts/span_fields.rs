@@ -0,0 +1,87 @@
+use tracing::Instrument;
+
+use crate::{
+    AsyncBlock, AsyncWrapContext, CallerContext, SyncBlock, SyncWrapContext, WithCallerContext,
+};
+
+/// Context executing the wrapped function within a [`tracing`] span (requires the `tracing` feature)
+///
+/// The span is created by the macros, and the arguments listed in `fields(...)` are recorded as fields of the span,
+/// via their [`Debug`](std::fmt::Debug) implementation.
+/// ```
+/// # use context_manager::contexts::SpanFieldsContext;
+/// # use context_manager::{async_wrap, wrap};
+/// #[async_wrap(SpanFieldsContext, fields(user_id, order_id))]
+/// async fn ship(user_id: u64, order_id: u64, _address: String) {}
+///
+/// #[wrap(SpanFieldsContext, fields(user_id))]
+/// fn bill(user_id: u64, _amount: u64) {}
+/// ```
+///
+/// The span is named after the wrapped function, created at `INFO` level and with the module of the wrapped function
+/// as target, as done by `#[tracing::instrument]`, so the wrapped functions are reported consistently by the tools
+/// consuming the spans (like `tokio-console`). The metadata can be configured via the `span(...)` argument,
+/// which also creates the span without `fields(...)`:
+/// ```
+/// # use context_manager::contexts::SpanFieldsContext;
+/// # use context_manager::async_wrap;
+/// #[async_wrap(SpanFieldsContext, span(name = "db.load", target = "db", level = "debug"))]
+/// async fn load() {}
+/// ```
+///
+/// As the field names have to be known at compile time, the context is usable only via the macros
+/// with the `fields(...)` or `span(...)` arguments, or by explicitly providing the span via [`SyncWrapContext::run_sync_with_input`],
+/// [`SyncWrapContext::run_async_with_input`] or [`AsyncWrapContext::run_with_input`].
+#[derive(Debug)]
+pub struct SpanFieldsContext;
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T, tracing::Span> for SpanFieldsContext {
+    fn new() -> Self {
+        Self
+    }
+
+    fn run_sync_with_input(
+        caller_context: CallerContext,
+        span: tracing::Span,
+        block: impl SyncBlock<T>,
+    ) -> T {
+        let _entered = span.enter();
+        <Self as SyncWrapContext<T, tracing::Span>>::run_sync(caller_context, block)
+    }
+
+    async fn run_async_with_input(
+        caller_context: CallerContext,
+        span: tracing::Span,
+        block: impl AsyncBlock<T>,
+    ) -> T {
+        <Self as SyncWrapContext<T, tracing::Span>>::run_async(
+            caller_context,
+            WithCallerContext(async |caller_context: &CallerContext| {
+                block.call(caller_context).instrument(span).await
+            }),
+        )
+        .await
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T, tracing::Span> for SpanFieldsContext {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn run_with_input(
+        caller_context: CallerContext,
+        span: tracing::Span,
+        block: impl AsyncBlock<T>,
+    ) -> T {
+        <Self as AsyncWrapContext<T, tracing::Span>>::run(
+            caller_context,
+            WithCallerContext(async |caller_context: &CallerContext| {
+                block.call(caller_context).instrument(span).await
+            }),
+        )
+        .await
+    }
+}
@@ -0,0 +1,99 @@
+use core::marker::PhantomData;
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Configuration of [`SpanRecordContext`], extracting the value to record from the result of the wrapped function
+///
+/// ```
+/// # use context_manager::contexts::SpanRecordConfig;
+/// struct RowsReturned;
+/// impl<E> SpanRecordConfig<Result<Vec<String>, E>> for RowsReturned {
+///     const FIELD: &'static str = "rows_returned";
+///
+///     fn value(result: &Result<Vec<String>, E>) -> impl tracing::Value {
+///         result.as_ref().map_or(0, Vec::len)
+///     }
+/// }
+/// ```
+pub trait SpanRecordConfig<T> {
+    /// Name of the field of the span to record
+    const FIELD: &'static str;
+
+    /// Value of the field, computed from the result of the wrapped function
+    ///
+    /// Parameters:
+    /// - `result`: The result of the wrapped function
+    fn value(result: &T) -> impl tracing::Value;
+}
+
+/// Context recording a field, computed from the result of the wrapped function, onto the current [`tracing`] span
+/// (requires the `tracing` feature)
+///
+/// The field is recorded via [`tracing::Span::record`] once the wrapped function returns, which is useful
+/// for values known only at the end of the call (ie. the number of rows returned by a query).
+/// As `tracing` records only the fields declared when the span is created, the span has to declare the field
+/// as [`tracing::field::Empty`], and the context has to be applied within the span: with `#[tracing::instrument]`
+/// the macro of the context has to be listed first, so that the span is entered around its hooks.
+/// ```
+/// # use context_manager::contexts::{SpanRecordConfig, SpanRecordContext};
+/// # use context_manager::wrap;
+/// struct RowsReturned;
+/// impl SpanRecordConfig<Vec<String>> for RowsReturned {
+///     const FIELD: &'static str = "rows_returned";
+///
+///     fn value(rows: &Vec<String>) -> impl tracing::Value {
+///         rows.len()
+///     }
+/// }
+///
+/// #[wrap(SpanRecordContext<RowsReturned>)]
+/// #[tracing::instrument(fields(rows_returned = tracing::field::Empty))]
+/// fn query() -> Vec<String> {
+///     vec!["row".to_string()]
+/// }
+/// ```
+#[derive(Debug)]
+pub struct SpanRecordContext<C> {
+    config: PhantomData<C>,
+}
+
+impl<C> SpanRecordContext<C> {
+    const fn create() -> Self {
+        Self {
+            config: PhantomData,
+        }
+    }
+
+    fn record<T>(result: &T)
+    where
+        C: SpanRecordConfig<T>,
+    {
+        tracing::Span::current().record(C::FIELD, C::value(result));
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: SpanRecordConfig<T>, T> SyncWrapContext<T> for SpanRecordContext<C> {
+    const HAS_BEFORE: bool = false;
+
+    fn new() -> Self {
+        Self::create()
+    }
+
+    fn after(self, _: &CallerContext, result: &T) {
+        Self::record(result);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: SpanRecordConfig<T>, T> AsyncWrapContext<T> for SpanRecordContext<C> {
+    const HAS_BEFORE: bool = false;
+
+    async fn new() -> Self {
+        Self::create()
+    }
+
+    async fn after(self, _: &CallerContext, result: &T) {
+        Self::record(result);
+    }
+}
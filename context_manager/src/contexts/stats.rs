@@ -0,0 +1,245 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::contexts::registry::Registry;
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+static HISTOGRAMS: Registry<Histogram> = Registry::new();
+
+/// Bits of the value used to select the bucket within a power of two, so the relative error is below `1 / 2^SUB_BITS`
+const SUB_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BITS;
+/// Values below `SUB_BUCKETS` have a bucket each, while the others have `SUB_BUCKETS` buckets per power of two
+const BUCKETS: usize = SUB_BUCKETS + (u64::BITS - SUB_BITS) as usize * SUB_BUCKETS;
+
+/// Log-linear histogram of durations, in nanoseconds
+#[derive(Debug)]
+struct Histogram {
+    buckets: [AtomicU64; BUCKETS],
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: std::array::from_fn(|_| AtomicU64::new(0)),
+        }
+    }
+}
+
+impl Histogram {
+    /// Index of the bucket containing `value`
+    fn index(value: u64) -> usize {
+        if value < SUB_BUCKETS as u64 {
+            #[allow(clippy::cast_possible_truncation)] // The value is below `SUB_BUCKETS`
+            return value as usize;
+        }
+        let exponent = u64::BITS - 1 - value.leading_zeros();
+        let shift = exponent - SUB_BITS;
+        #[allow(clippy::cast_possible_truncation)] // The value is shifted below `2 * SUB_BUCKETS`
+        let sub_bucket = (value >> shift) as usize - SUB_BUCKETS;
+        SUB_BUCKETS + shift as usize * SUB_BUCKETS + sub_bucket
+    }
+
+    /// Value representing the bucket at `index`, as the midpoint of its range
+    fn value(index: usize) -> u64 {
+        if index < SUB_BUCKETS {
+            return index as u64;
+        }
+        let shift = (index - SUB_BUCKETS) / SUB_BUCKETS;
+        let sub_bucket = ((index - SUB_BUCKETS) % SUB_BUCKETS + SUB_BUCKETS) as u64;
+        (sub_bucket << shift) + ((1 << shift) >> 1)
+    }
+
+    fn record(&self, elapsed: Duration) {
+        let nanos = u64::try_from(elapsed.as_nanos()).unwrap_or(u64::MAX);
+        self.buckets[Self::index(nanos)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn count(&self) -> u64 {
+        self.buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    fn percentile(&self, quantile: f64) -> Option<Duration> {
+        let counts: Vec<u64> = self
+            .buckets
+            .iter()
+            .map(|bucket| bucket.load(Ordering::Relaxed))
+            .collect();
+        let total: u64 = counts.iter().sum();
+        if total == 0 {
+            return None;
+        }
+        // Nearest-rank method: the smallest value such that at least `quantile` of the values are not greater
+        #[allow(
+            clippy::cast_precision_loss,
+            clippy::cast_possible_truncation,
+            clippy::cast_sign_loss
+        )]
+        let rank = ((quantile.clamp(0.0, 1.0) * total as f64).ceil() as u64).clamp(1, total);
+        let mut seen = 0;
+        counts.iter().enumerate().find_map(|(index, count)| {
+            seen += count;
+            (seen >= rank).then(|| Duration::from_nanos(Self::value(index)))
+        })
+    }
+}
+
+/// Context keeping statistics about the duration of each wrapped function, queryable at runtime
+///
+/// Durations are recorded, per function name, into histograms with buckets growing exponentially,
+/// so percentiles are reported with a relative error below 1/16 (6.25%) and with a fixed memory usage.
+/// ```
+/// # use context_manager::contexts::StatsContext;
+/// # use context_manager::wrap;
+/// #[wrap(StatsContext)]
+/// fn handle_request() {}
+///
+/// for _ in 0..100 {
+///     handle_request();
+/// }
+/// assert_eq!(StatsContext::count("handle_request"), 100);
+/// let p99 = StatsContext::percentile("handle_request", 0.99).unwrap();
+/// println!("p99 of handle_request: {p99:?}");
+/// ```
+///
+/// Statistics are kept per function name, and are shared by all the functions with the same name.
+#[derive(Debug)]
+pub struct StatsContext;
+
+impl StatsContext {
+    /// Number of recorded calls of the wrapped functions named `fn_name`
+    #[must_use]
+    pub fn count(fn_name: &str) -> u64 {
+        HISTOGRAMS.get(fn_name).map_or(0, Histogram::count)
+    }
+
+    /// Duration of the calls of the wrapped functions named `fn_name` at `quantile` (ie. `0.99` for the p99)
+    ///
+    /// The quantile is clamped between `0.0` (the fastest call) and `1.0` (the slowest call),
+    /// and `None` is returned if no call was recorded.
+    #[must_use]
+    pub fn percentile(fn_name: &str, quantile: f64) -> Option<Duration> {
+        HISTOGRAMS.get(fn_name)?.percentile(quantile)
+    }
+
+    fn record(fn_name: &str, elapsed: Duration) {
+        HISTOGRAMS.get_or_register(fn_name).record(elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for StatsContext {
+    const HAS_BEFORE: bool = false;
+
+    fn new() -> Self {
+        Self
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::record(caller_context.emitted_fn_name(), elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for StatsContext {
+    const HAS_BEFORE: bool = false;
+
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::record(caller_context.emitted_fn_name(), elapsed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Histogram, StatsContext, BUCKETS};
+    use crate::contexts::test::FakeClock;
+    use crate::contexts::{ClockContext, ContextChain};
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::time::Duration;
+
+    fn assert_close(actual: Option<Duration>, expected: Duration) {
+        let actual = actual.unwrap();
+        let error = actual.abs_diff(expected).as_secs_f64() / expected.as_secs_f64();
+        assert!(
+            error < 1.0 / 16.0,
+            "{actual:?} is not close to {expected:?}"
+        );
+    }
+
+    #[test]
+    fn buckets_cover_all_the_values() {
+        assert_eq!(Histogram::index(0), 0);
+        assert_eq!(Histogram::index(15), 15);
+        assert_eq!(Histogram::index(16), 16);
+        assert_eq!(Histogram::index(u64::MAX), BUCKETS - 1);
+        for value in [0, 1, 15, 16, 17, 100, 1_000, 123_456_789, u64::MAX / 3] {
+            let index = Histogram::index(value);
+            assert_eq!(Histogram::index(Histogram::value(index)), index);
+        }
+    }
+
+    #[test]
+    fn percentiles_of_a_known_distribution() {
+        for millis in (1..=1_000).rev() {
+            StatsContext::record("stats_uniform", Duration::from_millis(millis));
+        }
+
+        assert_eq!(StatsContext::count("stats_uniform"), 1_000);
+        assert_close(
+            StatsContext::percentile("stats_uniform", 0.5),
+            Duration::from_millis(500),
+        );
+        assert_close(
+            StatsContext::percentile("stats_uniform", 0.9),
+            Duration::from_millis(900),
+        );
+        assert_close(
+            StatsContext::percentile("stats_uniform", 0.99),
+            Duration::from_millis(990),
+        );
+        assert_close(
+            StatsContext::percentile("stats_uniform", 0.0),
+            Duration::from_millis(1),
+        );
+        assert_close(
+            StatsContext::percentile("stats_uniform", 2.0),
+            Duration::from_secs(1),
+        );
+    }
+
+    #[test]
+    fn unknown_functions_have_no_statistics() {
+        assert_eq!(StatsContext::count("stats_unknown"), 0);
+        assert_eq!(StatsContext::percentile("stats_unknown", 0.5), None);
+    }
+
+    #[test]
+    fn sync_calls_are_recorded() {
+        for _ in 0..3 {
+            <ContextChain<ClockContext<FakeClock>, StatsContext> as SyncWrapContext<_>>::run_sync(
+                CallerContext::new("stats_sync"),
+                || FakeClock::advance(Duration::from_millis(10)),
+            );
+        }
+        assert_eq!(StatsContext::count("stats_sync"), 3);
+        assert_close(
+            StatsContext::percentile("stats_sync", 0.5),
+            Duration::from_millis(10),
+        );
+    }
+
+    #[tokio::test]
+    async fn async_calls_are_recorded() {
+        <StatsContext as AsyncWrapContext<_>>::run(CallerContext::new("stats_async"), async {})
+            .await;
+        assert_eq!(StatsContext::count("stats_async"), 1);
+        assert!(StatsContext::percentile("stats_async", 1.0).is_some());
+    }
+}
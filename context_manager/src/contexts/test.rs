@@ -0,0 +1,612 @@
+//! Helpers to test contexts and wrapped functions (requires the `testing` feature)
+
+use std::cell::{Cell, RefCell};
+use std::fmt::Debug;
+use std::future::Future;
+use std::ops::ControlFlow;
+use std::pin::pin;
+use std::sync::{Mutex, PoisonError};
+use std::task::{Context, Poll, Waker};
+use std::time::{Duration, Instant};
+
+use crate::{AsyncWrapContext, CallerContext, Clock, Sleep, SyncWrapContext};
+
+thread_local! {
+    static RECORDED: RefCell<Vec<Hook>> = const { RefCell::new(Vec::new()) };
+    static FAKE_NOW: Cell<Option<Instant>> = const { Cell::new(None) };
+}
+
+/// Calls recorded by [`RecorderContext`], shared by all the threads
+static CALLS: Mutex<Vec<Call>> = Mutex::new(Vec::new());
+
+/// Hook of a context, as recorded by [`HookRecorder`]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Hook {
+    /// [`SyncWrapContext::new`] or [`AsyncWrapContext::new`]
+    New,
+    /// [`SyncWrapContext::on_first_call`] or [`AsyncWrapContext::on_first_call`]
+    OnFirstCall,
+    /// [`SyncWrapContext::before`] or [`AsyncWrapContext::before`]
+    Before,
+    /// [`SyncWrapContext::short_circuit`] or [`AsyncWrapContext::short_circuit`]
+    ShortCircuit,
+    /// [`SyncWrapContext::on_poisoned`] or [`AsyncWrapContext::on_poisoned`]
+    OnPoisoned,
+    /// [`AsyncWrapContext::on_cancel`]
+    OnCancel,
+    /// [`SyncWrapContext::finally`] or [`AsyncWrapContext::finally`]
+    Finally,
+    /// [`SyncWrapContext::after`] or [`AsyncWrapContext::after`]
+    After,
+}
+
+/// Context recording the hooks executed on the current thread, in execution order
+///
+/// It implements both [`SyncWrapContext`] and [`AsyncWrapContext`], relying on the default implementation
+/// of the hooks not being recorded (ie. [`SyncWrapContext::after_timed`] delegating to [`SyncWrapContext::after`]).
+/// This allows to assert the order of the hooks (refer to the "Hooks order" section of [`SyncWrapContext`]),
+/// or to assert whether a wrapped function has been executed.
+/// ```
+/// # use context_manager::contexts::test::{Hook, HookRecorder};
+/// # use context_manager::wrap;
+/// #[wrap(HookRecorder)]
+/// fn foo() {}
+///
+/// foo();
+/// assert_eq!(
+///     HookRecorder::take(),
+///     [Hook::New, Hook::OnFirstCall, Hook::Before, Hook::ShortCircuit, Hook::Finally, Hook::After],
+/// );
+/// ```
+///
+/// NOTE: The hooks are recorded per thread, so async functions should be executed on a single threaded runtime.
+#[derive(Debug)]
+pub struct HookRecorder;
+
+impl HookRecorder {
+    /// Hooks recorded on the current thread, clearing them
+    #[must_use]
+    pub fn take() -> Vec<Hook> {
+        RECORDED.take()
+    }
+
+    fn record(hook: Hook) {
+        RECORDED.with_borrow_mut(|recorded| recorded.push(hook));
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for HookRecorder {
+    fn new() -> Self {
+        Self::record(Hook::New);
+        Self
+    }
+
+    fn on_first_call(&self, _: &CallerContext) {
+        Self::record(Hook::OnFirstCall);
+    }
+
+    fn before(&self, _: &mut CallerContext) {
+        Self::record(Hook::Before);
+    }
+
+    fn short_circuit(&self, _: &CallerContext) -> ControlFlow<T> {
+        Self::record(Hook::ShortCircuit);
+        ControlFlow::Continue(())
+    }
+
+    fn on_poisoned(&self, _: &CallerContext) {
+        Self::record(Hook::OnPoisoned);
+    }
+
+    fn finally(&self, _: &CallerContext) {
+        Self::record(Hook::Finally);
+    }
+
+    fn after(self, _: &CallerContext, _: &T) {
+        Self::record(Hook::After);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for HookRecorder {
+    async fn new() -> Self {
+        Self::record(Hook::New);
+        Self
+    }
+
+    fn on_first_call(&self, _: &CallerContext) {
+        Self::record(Hook::OnFirstCall);
+    }
+
+    async fn before(&self, _: &mut CallerContext) {
+        Self::record(Hook::Before);
+    }
+
+    fn short_circuit(&self, _: &CallerContext) -> ControlFlow<T> {
+        Self::record(Hook::ShortCircuit);
+        ControlFlow::Continue(())
+    }
+
+    fn on_cancel(&self, _: &CallerContext) {
+        Self::record(Hook::OnCancel);
+    }
+
+    fn on_poisoned(&self, _: &CallerContext) {
+        Self::record(Hook::OnPoisoned);
+    }
+
+    fn finally(&self, _: &CallerContext) {
+        Self::record(Hook::Finally);
+    }
+
+    async fn after(self, _: &CallerContext, _: &T) {
+        Self::record(Hook::After);
+    }
+}
+
+/// Call of a wrapped function, as recorded by [`RecorderContext`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Call {
+    /// Name of the wrapped function, as reported by [`CallerContext::fn_name`]
+    pub fn_name: &'static str,
+    /// Duration of the body of the wrapped function
+    pub elapsed: Duration,
+    /// Result of the wrapped function, formatted via [`Debug`]
+    pub outcome: String,
+}
+
+/// Context recording the calls of the wrapped functions, with their results, in completion order
+///
+/// The calls are shared by all the threads, so the functions executed on a multi threaded runtime are recorded
+/// as well. [`RecorderContext::take`] returns the calls of the requested functions only, so concurrent tests
+/// wrapping different functions do not affect each other.
+/// ```
+/// # use context_manager::contexts::test::RecorderContext;
+/// # use context_manager::wrap;
+/// #[wrap(RecorderContext)]
+/// fn parse(value: &str) -> Result<u8, std::num::ParseIntError> {
+///     value.parse()
+/// }
+///
+/// let _ = parse("1");
+/// let _ = parse("a");
+/// let calls = RecorderContext::take(&["parse"]);
+/// assert_eq!(
+///     calls.iter().map(|call| call.outcome.as_str()).collect::<Vec<_>>(),
+///     ["Ok(1)", "Err(ParseIntError { kind: InvalidDigit })"],
+/// );
+/// ```
+///
+/// The calls are recorded once the wrapped functions return, so nested calls precede the calls wrapping them.
+#[derive(Debug)]
+pub struct RecorderContext;
+
+impl RecorderContext {
+    /// Calls of the wrapped functions named as any of `fn_names`, in completion order, removing them
+    #[must_use]
+    pub fn take(fn_names: &[&str]) -> Vec<Call> {
+        let mut calls = CALLS.lock().unwrap_or_else(PoisonError::into_inner);
+        let (taken, kept) = calls
+            .drain(..)
+            .partition(|call| fn_names.contains(&call.fn_name));
+        *calls = kept;
+        taken
+    }
+
+    fn record(caller_context: &CallerContext, result: &impl Debug, elapsed: Duration) {
+        let call = Call {
+            fn_name: caller_context.fn_name(),
+            elapsed,
+            outcome: format!("{result:?}"),
+        };
+        CALLS
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .push(call);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T: Debug> SyncWrapContext<T> for RecorderContext {
+    const HAS_BEFORE: bool = false;
+
+    fn new() -> Self {
+        Self
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        Self::record(caller_context, result, elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T: Debug> AsyncWrapContext<T> for RecorderContext {
+    const HAS_BEFORE: bool = false;
+
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        Self::record(caller_context, result, elapsed);
+    }
+}
+
+/// Assert that the context executes the body of the wrapped functions once, returning its output unchanged
+///
+/// A context overriding [`SyncWrapContext::run_sync`] or [`SyncWrapContext::run_async`] without executing the
+/// block would silently skip the body of the wrapped functions, so the helper runs a body returning `output` via
+/// both of them (polling the async one on the current thread). Contexts short-circuiting the body are expected to fail.
+/// ```
+/// # use context_manager::contexts::test::assert_runs_body;
+/// # use context_manager::contexts::CounterContext;
+/// assert_runs_body::<CounterContext, _>(42);
+/// ```
+///
+/// # Panics
+/// If the body is not executed exactly once, or if its output is not returned.
+pub fn assert_runs_body<C, T>(output: T)
+where
+    C: SyncWrapContext<T>,
+    T: Clone + PartialEq + Debug,
+{
+    let calls = Cell::new(0);
+    let expected = output.clone();
+
+    let result = C::run_sync(CallerContext::new("assert_runs_body"), || {
+        calls.set(calls.get() + 1);
+        expected.clone()
+    });
+    assert_body_run(&calls, &result, &expected, "run_sync");
+
+    let result = block_on(C::run_async(
+        CallerContext::new("assert_runs_body"),
+        async {
+            calls.set(calls.get() + 1);
+            output
+        },
+    ));
+    assert_body_run(&calls, &result, &expected, "run_async");
+}
+
+/// Assert that the async context executes the body of the wrapped functions once, returning its output unchanged
+///
+/// This is equivalent to [`assert_runs_body`], for [`AsyncWrapContext::run`].
+///
+/// # Panics
+/// If the body is not executed exactly once, or if its output is not returned.
+pub async fn assert_async_runs_body<C, T>(output: T)
+where
+    C: AsyncWrapContext<T>,
+    T: Clone + PartialEq + Debug,
+{
+    let calls = Cell::new(0);
+    let expected = output.clone();
+    let result = C::run(CallerContext::new("assert_runs_body"), async {
+        calls.set(calls.get() + 1);
+        output
+    })
+    .await;
+    assert_body_run(&calls, &result, &expected, "run");
+}
+
+/// Assert at compile time that `C` is a synchronous context wrapping functions returning `T`
+///
+/// Libraries exposing contexts can use it in their tests to catch signature drifts (ie. a context
+/// no longer implementing [`SyncWrapContext`] for an output type), which would otherwise surface only
+/// in the downstream code decorating its functions. The function does nothing at runtime.
+/// ```
+/// # use context_manager::contexts::test::assert_sync_context;
+/// # use context_manager::contexts::{CounterContext, ErrorTrailContext, Trail};
+/// assert_sync_context::<CounterContext, String>();
+/// assert_sync_context::<ErrorTrailContext, Result<u8, Trail<String>>>();
+/// ```
+pub const fn assert_sync_context<C, T>()
+where
+    C: SyncWrapContext<T>,
+{
+}
+
+/// Assert at compile time that `C` is an asynchronous context wrapping functions returning `T`
+///
+/// This is equivalent to [`assert_sync_context`], for [`AsyncWrapContext`].
+pub const fn assert_async_context<C, T>()
+where
+    C: AsyncWrapContext<T>,
+{
+}
+
+/// [`Clock`] advancing only when requested, to make the durations measured by the timing contexts deterministic
+///
+/// The time is tracked per thread, starting from the time of its first read. It implements [`Sleep`] as well,
+/// by advancing the time instead of waiting, so it can be injected in contexts sleeping (like [`MinIntervalContext`](crate::contexts::MinIntervalContext)).
+/// ```
+/// # use std::time::Duration;
+/// # use context_manager::contexts::test::FakeClock;
+/// # use context_manager::Clock;
+/// let start = FakeClock::now();
+/// FakeClock::advance(Duration::from_secs(5));
+/// assert_eq!(FakeClock::now() - start, Duration::from_secs(5));
+/// ```
+///
+/// NOTE: The time is advanced per thread, so async functions should be executed on a single threaded runtime.
+#[derive(Debug)]
+pub struct FakeClock;
+
+impl FakeClock {
+    /// Advance the time of the current thread by `duration`
+    pub fn advance(duration: Duration) {
+        FAKE_NOW.set(Some(Self::now() + duration));
+    }
+}
+
+impl Clock for FakeClock {
+    fn now() -> Instant {
+        FAKE_NOW.get().unwrap_or_else(|| {
+            let now = Instant::now();
+            FAKE_NOW.set(Some(now));
+            now
+        })
+    }
+}
+
+impl Sleep for FakeClock {
+    fn sleep(duration: Duration) -> impl Future<Output = ()> {
+        Self::advance(duration);
+        std::future::ready(())
+    }
+}
+
+fn assert_body_run<T: PartialEq + Debug>(calls: &Cell<usize>, result: &T, output: &T, run: &str) {
+    assert_eq!(
+        calls.replace(0),
+        1,
+        "the body is expected to be executed once by `{run}`"
+    );
+    assert_eq!(
+        result, output,
+        "the output of the body is expected to be returned by `{run}`"
+    );
+}
+
+/// Poll `future` on the current thread until it completes
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    loop {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return output;
+        }
+        std::thread::yield_now();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        assert_async_context, assert_async_runs_body, assert_runs_body, assert_sync_context,
+        FakeClock, Hook, HookRecorder, RecorderContext,
+    };
+    use crate::contexts::{
+        ContextChain, CounterContext, ErrorTrailContext, FirstPollConfig, FirstPollContext,
+        FlamegraphContext, InflightContext, PanicError, PanicToResultContext, PoisonableContext,
+        RegistryContext, StatsContext, Trail,
+    };
+    use crate::{AsyncWrapContext, CallerContext, FirstCall, SyncWrapContext};
+    use std::cell::Cell;
+    use std::future::pending;
+    use std::time::Duration;
+
+    const COMPLETED: [Hook; 5] = [
+        Hook::New,
+        Hook::Before,
+        Hook::ShortCircuit,
+        Hook::Finally,
+        Hook::After,
+    ];
+
+    #[test]
+    fn sync_order() {
+        static FIRST_CALL: FirstCall = FirstCall::new();
+        let caller_context = || CallerContext::new("foo").with_first_call(&FIRST_CALL);
+
+        <HookRecorder as SyncWrapContext<_>>::run_sync(caller_context(), || ());
+        assert_eq!(
+            HookRecorder::take(),
+            [
+                Hook::New,
+                Hook::OnFirstCall,
+                Hook::Before,
+                Hook::ShortCircuit,
+                Hook::Finally,
+                Hook::After,
+            ]
+        );
+
+        <HookRecorder as SyncWrapContext<_>>::run_sync(caller_context(), || ());
+        assert_eq!(HookRecorder::take(), COMPLETED);
+    }
+
+    #[test]
+    fn sync_error_order() {
+        let result =
+            <HookRecorder as SyncWrapContext<_>>::run_sync(CallerContext::new("foo"), || {
+                "a".parse::<u8>()
+            });
+        assert!(result.is_err());
+        assert_eq!(HookRecorder::take(), COMPLETED);
+    }
+
+    #[test]
+    fn sync_panic_order() {
+        let result = std::panic::catch_unwind(|| {
+            <HookRecorder as SyncWrapContext<()>>::run_sync(CallerContext::new("foo"), || {
+                panic!("panic in the body")
+            });
+        });
+        assert!(result.is_err());
+        assert_eq!(
+            HookRecorder::take(),
+            [
+                Hook::New,
+                Hook::Before,
+                Hook::ShortCircuit,
+                Hook::OnPoisoned,
+                Hook::Finally,
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn sync_hooks_async_order() {
+        <HookRecorder as SyncWrapContext<_>>::run_async(CallerContext::new("foo"), async {}).await;
+        assert_eq!(HookRecorder::take(), COMPLETED);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_order() {
+        <HookRecorder as AsyncWrapContext<_>>::run(CallerContext::new("foo"), async {}).await;
+        assert_eq!(HookRecorder::take(), COMPLETED);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn async_cancel_order() {
+        tokio::select! {
+            () = <HookRecorder as AsyncWrapContext<_>>::run(CallerContext::new("foo"), pending()) => unreachable!(),
+            () = tokio::task::yield_now() => {},
+        }
+        assert_eq!(
+            HookRecorder::take(),
+            [
+                Hook::New,
+                Hook::Before,
+                Hook::ShortCircuit,
+                Hook::OnCancel,
+                Hook::Finally,
+            ]
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn calls_are_recorded_in_order() {
+        fn run<T: std::fmt::Debug>(fn_name: &'static str, block: impl FnOnce() -> T) -> T {
+            <RecorderContext as SyncWrapContext<_>>::run_sync(CallerContext::new(fn_name), block)
+        }
+
+        run("recorder_outer", || run("recorder_inner", || 1) + 1);
+        let _ = run("recorder_parse", || "a".parse::<u8>());
+        run("recorder_ignored", || ());
+        tokio::spawn(<RecorderContext as AsyncWrapContext<_>>::run(
+            CallerContext::new("recorder_async"),
+            async { "spawned" },
+        ))
+        .await
+        .unwrap();
+
+        let calls = RecorderContext::take(&[
+            "recorder_outer",
+            "recorder_inner",
+            "recorder_parse",
+            "recorder_async",
+        ]);
+        assert_eq!(
+            calls
+                .iter()
+                .map(|call| (call.fn_name, call.outcome.as_str()))
+                .collect::<Vec<_>>(),
+            [
+                ("recorder_inner", "1"),
+                ("recorder_outer", "2"),
+                (
+                    "recorder_parse",
+                    "Err(ParseIntError { kind: InvalidDigit })"
+                ),
+                ("recorder_async", "\"spawned\""),
+            ]
+        );
+        assert!(calls[1].elapsed >= calls[0].elapsed);
+
+        // The calls are removed once taken, while the other ones are kept
+        assert!(RecorderContext::take(&["recorder_outer"]).is_empty());
+        assert_eq!(RecorderContext::take(&["recorder_ignored"]).len(), 1);
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn built_in_contexts_run_the_body() {
+        assert_runs_body::<HookRecorder, _>(1);
+        assert_runs_body::<RecorderContext, _>("recorded");
+        assert_runs_body::<CounterContext, _>(1);
+        assert_runs_body::<StatsContext, _>(1);
+        assert_runs_body::<FlamegraphContext, _>(1);
+        assert_runs_body::<InflightContext, _>(1);
+        assert_runs_body::<PoisonableContext, _>(1);
+        assert_runs_body::<RegistryContext, _>(1);
+        assert_runs_body::<ContextChain<CounterContext, StatsContext>, _>(1);
+        assert_runs_body::<ErrorTrailContext, _>(Ok::<_, Trail<&str>>(1));
+        assert_runs_body::<PanicToResultContext, _>(Ok::<_, PanicError>(1));
+
+        assert_async_runs_body::<HookRecorder, _>(1).await;
+        assert_async_runs_body::<RecorderContext, _>("recorded").await;
+        assert_async_runs_body::<CounterContext, _>(1).await;
+        assert_async_runs_body::<StatsContext, _>(1).await;
+        assert_async_runs_body::<ContextChain<CounterContext, StatsContext>, _>(1).await;
+
+        assert_eq!(HookRecorder::take(), COMPLETED.repeat(3));
+        assert_eq!(RecorderContext::take(&["assert_runs_body"]).len(), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "the body is expected to be executed once by `run_sync`")]
+    fn contexts_skipping_the_body_are_detected() {
+        struct Skip;
+        impl SyncWrapContext<u8> for Skip {
+            fn new() -> Self {
+                Self
+            }
+
+            fn run_sync(_: CallerContext, _: impl crate::SyncBlock<u8>) -> u8 {
+                0
+            }
+        }
+
+        assert_runs_body::<Skip, _>(0);
+    }
+
+    #[test]
+    fn built_in_contexts_match_their_outputs() {
+        const _: () = assert_sync_context::<CounterContext, u8>();
+        const _: () = assert_async_context::<StatsContext, ()>();
+        assert_sync_context::<ErrorTrailContext, Result<(), Trail<PanicError>>>();
+        assert_async_context::<ContextChain<CounterContext, StatsContext>, String>();
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn fake_clock_drives_timing_contexts() {
+        thread_local! {
+            static REPORTED: Cell<Option<(Duration, Duration)>> = const { Cell::new(None) };
+        }
+        struct Report;
+        impl FirstPollConfig for Report {
+            fn on_first_poll(_: &'static str, first_poll: Duration, total: Duration) {
+                REPORTED.set(Some((first_poll, total)));
+            }
+        }
+
+        <FirstPollContext<Report, FakeClock> as AsyncWrapContext<_>>::run(
+            CallerContext::new("fake_clock"),
+            async {
+                FakeClock::advance(Duration::from_secs(3));
+                tokio::task::yield_now().await;
+                FakeClock::advance(Duration::from_secs(4));
+            },
+        )
+        .await;
+        assert_eq!(
+            REPORTED.get(),
+            Some((Duration::from_secs(3), Duration::from_secs(7)))
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use alloc::borrow::Cow;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+
+use crate::{AsyncWrapContext, CallerContext, Reentrancy, SyncWrapContext};
+
+/// Configuration of [`ToggleContext`]
+///
+/// ```
+/// # use context_manager::contexts::ToggleConfig;
+/// # use std::sync::atomic::AtomicBool;
+/// struct Tracing;
+/// impl ToggleConfig for Tracing {
+///     fn flag() -> &'static AtomicBool {
+///         static FLAG: AtomicBool = AtomicBool::new(true);
+///         &FLAG
+///     }
+/// }
+/// ```
+pub trait ToggleConfig {
+    /// Flag controlling whether the hooks of the inner context are executed
+    fn flag() -> &'static AtomicBool;
+}
+
+/// Context executing the hooks of the inner context `C` only while the flag provided by `F` is set
+///
+/// This allows to enable or disable instrumentation at runtime (ie. as a kill-switch), without recompiling.
+/// The wrapped body is always executed, the flag decides only whether the hooks of `C` are executed.
+/// The flag is checked once per call, when the context is initialised, via a single relaxed atomic load.
+/// ```
+/// # use context_manager::contexts::{ToggleConfig, ToggleContext};
+/// # use context_manager::{wrap, SyncWrapContext};
+/// # use std::sync::atomic::AtomicBool;
+/// # struct PrintDuration;
+/// # impl<T> SyncWrapContext<T> for PrintDuration { fn new() -> Self { Self } }
+/// struct Tracing;
+/// impl ToggleConfig for Tracing {
+///     fn flag() -> &'static AtomicBool {
+///         static FLAG: AtomicBool = AtomicBool::new(true);
+///         &FLAG
+///     }
+/// }
+///
+/// #[wrap(ToggleContext<PrintDuration, Tracing>)]
+/// fn hot_function() {}
+///
+/// ToggleContext::<PrintDuration, Tracing>::disable();
+/// hot_function(); // PrintDuration hooks are not executed
+/// ```
+///
+/// NOTE: Only the hooks of `C` are forwarded, so any custom `run_*` implementation of `C` is ignored.
+/// The caller context is built via `C::build_caller_context` for all the calls, as the flag is not yet checked.
+#[derive(Debug)]
+pub struct ToggleContext<C, F: ToggleConfig> {
+    inner: Option<C>,
+    config: PhantomData<F>,
+}
+
+impl<C, F: ToggleConfig> ToggleContext<C, F> {
+    /// Enable the hooks of the inner context, for all the following calls
+    pub fn enable() {
+        F::flag().store(true, Ordering::Relaxed);
+    }
+
+    /// Disable the hooks of the inner context, for all the following calls
+    pub fn disable() {
+        F::flag().store(false, Ordering::Relaxed);
+    }
+
+    /// Whether the hooks of the inner context are currently enabled
+    #[must_use]
+    pub fn is_enabled() -> bool {
+        F::flag().load(Ordering::Relaxed)
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: SyncWrapContext<T>, F: ToggleConfig, T> SyncWrapContext<T> for ToggleContext<C, F> {
+    const REENTRANCY: Reentrancy = C::REENTRANCY;
+    const HAS_BEFORE: bool = C::HAS_BEFORE;
+    const HAS_AFTER: bool = C::HAS_AFTER;
+
+    fn new() -> Self {
+        Self {
+            inner: Self::is_enabled().then(C::new),
+            config: PhantomData,
+        }
+    }
+
+    fn build_caller_context(default: CallerContext) -> CallerContext {
+        C::build_caller_context(default)
+    }
+
+    fn on_first_call(&self, caller_context: &CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.on_first_call(caller_context);
+        }
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.before(caller_context);
+        }
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        if let Some(inner) = self.inner {
+            inner.after_timed(caller_context, result, elapsed);
+        }
+    }
+
+    fn after_owned(self, caller_context: &CallerContext, result: T, elapsed: Duration) -> T {
+        match self.inner {
+            Some(inner) => inner.after_owned(caller_context, result, elapsed),
+            None => result,
+        }
+    }
+
+    async fn after_async(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        if let Some(inner) = self.inner {
+            inner.after_async(caller_context, result, elapsed).await;
+        }
+    }
+
+    fn after_ref(&mut self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        if let Some(inner) = &mut self.inner {
+            inner.after_ref(caller_context, result, elapsed);
+        }
+    }
+
+    fn on_reentry(&self, caller_context: &CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.on_reentry(caller_context);
+        }
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        match &self.inner {
+            Some(inner) => inner.short_circuit(caller_context),
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn now() -> std::time::Instant {
+        C::now()
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match &self.inner {
+            Some(inner) => inner.map_fn_name(name),
+            None => Cow::Borrowed(name),
+        }
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        match &self.inner {
+            Some(inner) => inner.slow_threshold(),
+            None => Duration::MAX,
+        }
+    }
+
+    fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        if let Some(inner) = &self.inner {
+            inner.on_slow(caller_context, elapsed);
+        }
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.on_poisoned(caller_context);
+        }
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.finally(caller_context);
+        }
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<C: AsyncWrapContext<T>, F: ToggleConfig, T> AsyncWrapContext<T> for ToggleContext<C, F> {
+    const HAS_BEFORE: bool = C::HAS_BEFORE;
+    const HAS_AFTER: bool = C::HAS_AFTER;
+
+    async fn new() -> Self {
+        Self {
+            inner: if Self::is_enabled() {
+                Some(C::new().await)
+            } else {
+                None
+            },
+            config: PhantomData,
+        }
+    }
+
+    fn build_caller_context(default: CallerContext) -> CallerContext {
+        C::build_caller_context(default)
+    }
+
+    fn on_first_call(&self, caller_context: &CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.on_first_call(caller_context);
+        }
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.before(caller_context).await;
+        }
+    }
+
+    fn on_cancel(&self, caller_context: &CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.on_cancel(caller_context);
+        }
+    }
+
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        match &self.inner {
+            Some(inner) => inner.short_circuit(caller_context),
+            None => ControlFlow::Continue(()),
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn now() -> std::time::Instant {
+        C::now()
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match &self.inner {
+            Some(inner) => inner.map_fn_name(name),
+            None => Cow::Borrowed(name),
+        }
+    }
+
+    fn slow_threshold(&self) -> Duration {
+        match &self.inner {
+            Some(inner) => inner.slow_threshold(),
+            None => Duration::MAX,
+        }
+    }
+
+    async fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {
+        if let Some(inner) = &self.inner {
+            inner.on_slow(caller_context, elapsed).await;
+        }
+    }
+
+    fn on_poisoned(&self, caller_context: &CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.on_poisoned(caller_context);
+        }
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        if let Some(inner) = &self.inner {
+            inner.finally(caller_context);
+        }
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration) {
+        if let Some(inner) = self.inner {
+            inner.after_timed(caller_context, result, elapsed).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+    use super::{ToggleConfig, ToggleContext};
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+    static HOOKS: AtomicUsize = AtomicUsize::new(0);
+
+    struct Inner;
+    impl SyncWrapContext<()> for Inner {
+        fn new() -> Self {
+            Self
+        }
+
+        fn before(&self, _: &mut CallerContext) {
+            HOOKS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn after(self, _: &CallerContext, (): &()) {
+            HOOKS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+    impl AsyncWrapContext<()> for Inner {
+        async fn new() -> Self {
+            Self
+        }
+
+        async fn before(&self, _: &mut CallerContext) {
+            HOOKS.fetch_add(1, Ordering::Relaxed);
+        }
+
+        async fn after(self, _: &CallerContext, (): &()) {
+            HOOKS.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    struct Flag;
+    impl ToggleConfig for Flag {
+        fn flag() -> &'static AtomicBool {
+            static FLAG: AtomicBool = AtomicBool::new(true);
+            &FLAG
+        }
+    }
+
+    type Toggle = ToggleContext<Inner, Flag>;
+
+    #[tokio::test]
+    async fn hooks_run_only_while_enabled() {
+        let mut body_calls = 0;
+
+        assert!(Toggle::is_enabled());
+        Toggle::run_sync(CallerContext::new("test"), || body_calls += 1);
+        Toggle::run(CallerContext::new("test"), async {}).await;
+        assert_eq!(HOOKS.swap(0, Ordering::Relaxed), 4);
+
+        Toggle::disable();
+        assert!(!Toggle::is_enabled());
+        Toggle::run_sync(CallerContext::new("test"), || body_calls += 1);
+        Toggle::run(CallerContext::new("test"), async {}).await;
+        assert_eq!(HOOKS.swap(0, Ordering::Relaxed), 0);
+
+        Toggle::enable();
+        Toggle::run_sync(CallerContext::new("test"), || body_calls += 1);
+        assert_eq!(HOOKS.swap(0, Ordering::Relaxed), 2);
+
+        assert_eq!(body_calls, 3);
+    }
+}
@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Context printing to the standard error when the wrapped function is entered and exited (requires the `trace` feature)
+///
+/// It is meant for quick debugging, and it is the context used by [`wrap`](crate::wrap) and
+/// [`async_wrap`](crate::async_wrap) when no context is provided.
+/// ```
+/// # use context_manager::wrap;
+/// #[wrap]
+/// fn add(a: u8, b: u8) -> u8 {
+///     a + b
+/// }
+///
+/// // Prints `enter add` and `exit add (elapsed: ...)`
+/// assert_eq!(add(1, 2), 3);
+/// ```
+#[derive(Debug)]
+pub struct DefaultTraceContext;
+
+impl DefaultTraceContext {
+    fn enter(caller_context: &CallerContext) {
+        eprintln!("enter {}", caller_context.fn_name());
+    }
+
+    fn exit(caller_context: &CallerContext, elapsed: Duration) {
+        eprintln!("exit {} (elapsed: {elapsed:?})", caller_context.fn_name());
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> SyncWrapContext<T> for DefaultTraceContext {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        Self::enter(caller_context);
+    }
+
+    fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::exit(caller_context, elapsed);
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<T> AsyncWrapContext<T> for DefaultTraceContext {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        Self::enter(caller_context);
+    }
+
+    async fn after_timed(self, caller_context: &CallerContext, _: &T, elapsed: Duration) {
+        Self::exit(caller_context, elapsed);
+    }
+}
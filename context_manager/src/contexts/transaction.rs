@@ -0,0 +1,184 @@
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Transaction handled by [`TransactionContext`], in synchronous contexts
+pub trait Transaction {
+    /// Begin a new transaction
+    fn begin() -> Self;
+
+    /// Commit the transaction, invoked if the wrapped function returned [`Ok`]
+    fn commit(self);
+
+    /// Rollback the transaction, invoked if the wrapped function returned [`Err`]
+    fn rollback(self);
+}
+
+/// Transaction handled by [`TransactionContext`], in asynchronous contexts
+#[allow(async_fn_in_trait)]
+pub trait AsyncTransaction {
+    /// Begin a new transaction
+    async fn begin() -> Self;
+
+    /// Commit the transaction, invoked if the wrapped function returned [`Ok`]
+    async fn commit(self);
+
+    /// Rollback the transaction, invoked if the wrapped function returned [`Err`]
+    async fn rollback(self);
+}
+
+/// Context executing the wrapped function within a transaction
+///
+/// The transaction begins when the context is initialised, before the execution of the wrapped body,
+/// and then it is committed if the body returns [`Ok`] or rolled back if the body returns [`Err`].
+/// ```
+/// # use context_manager::contexts::{Transaction, TransactionContext};
+/// # use context_manager::wrap;
+/// struct DbTransaction;
+/// impl Transaction for DbTransaction {
+///     fn begin() -> Self { /* BEGIN */ Self }
+///     fn commit(self) { /* COMMIT */ }
+///     fn rollback(self) { /* ROLLBACK */ }
+/// }
+///
+/// #[wrap(TransactionContext<DbTransaction>)]
+/// fn transfer(amount: u64) -> Result<(), String> {
+///     Ok(())
+/// }
+/// ```
+///
+/// The context can wrap only functions returning [`Result`]. If `X` implements [`AsyncTransaction`],
+/// then the context is an asynchronous context (to be used via [`async_wrap`](crate::async_wrap)).
+///
+/// NOTE: If the wrapped body panics, or the wrapped future is dropped before completion,
+/// the transaction is dropped without being committed nor rolled back.
+#[derive(Debug)]
+pub struct TransactionContext<X>(X);
+
+#[diagnostic::do_not_recommend]
+impl<X: Transaction, T, E> SyncWrapContext<Result<T, E>> for TransactionContext<X> {
+    fn new() -> Self {
+        Self(X::begin())
+    }
+
+    fn after(self, _: &CallerContext, result: &Result<T, E>) {
+        if result.is_ok() {
+            self.0.commit();
+        } else {
+            self.0.rollback();
+        }
+    }
+}
+
+#[diagnostic::do_not_recommend]
+impl<X: AsyncTransaction, T, E> AsyncWrapContext<Result<T, E>> for TransactionContext<X> {
+    async fn new() -> Self {
+        Self(X::begin().await)
+    }
+
+    async fn after(self, _: &CallerContext, result: &Result<T, E>) {
+        if result.is_ok() {
+            self.0.commit().await;
+        } else {
+            self.0.rollback().await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AsyncTransaction, Transaction, TransactionContext};
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+    use std::sync::Mutex;
+
+    #[derive(Debug, PartialEq, Eq)]
+    enum Event {
+        Begin,
+        Body,
+        Commit,
+        Rollback,
+    }
+
+    macro_rules! mock_transaction {
+        ($name:ident, $events:ident) => {
+            static $events: Mutex<Vec<Event>> = Mutex::new(Vec::new());
+
+            struct $name;
+            impl Transaction for $name {
+                fn begin() -> Self {
+                    $events.lock().unwrap().push(Event::Begin);
+                    Self
+                }
+
+                fn commit(self) {
+                    $events.lock().unwrap().push(Event::Commit);
+                }
+
+                fn rollback(self) {
+                    $events.lock().unwrap().push(Event::Rollback);
+                }
+            }
+
+            impl AsyncTransaction for $name {
+                async fn begin() -> Self {
+                    <Self as Transaction>::begin()
+                }
+
+                async fn commit(self) {
+                    <Self as Transaction>::commit(self);
+                }
+
+                async fn rollback(self) {
+                    <Self as Transaction>::rollback(self);
+                }
+            }
+        };
+    }
+
+    #[test]
+    fn sync_commit_on_ok() {
+        mock_transaction!(Mock, EVENTS);
+        let result: Result<u8, ()> =
+            TransactionContext::<Mock>::run_sync(CallerContext::new("test"), || {
+                EVENTS.lock().unwrap().push(Event::Body);
+                Ok(1)
+            });
+        assert_eq!(result, Ok(1));
+        assert_eq!(
+            *EVENTS.lock().unwrap(),
+            [Event::Begin, Event::Body, Event::Commit]
+        );
+    }
+
+    #[test]
+    fn sync_rollback_on_err() {
+        mock_transaction!(Mock, EVENTS);
+        let result: Result<(), &str> =
+            TransactionContext::<Mock>::run_sync(CallerContext::new("test"), || {
+                EVENTS.lock().unwrap().push(Event::Body);
+                Err("failure")
+            });
+        assert_eq!(result, Err("failure"));
+        assert_eq!(
+            *EVENTS.lock().unwrap(),
+            [Event::Begin, Event::Body, Event::Rollback]
+        );
+    }
+
+    #[tokio::test]
+    async fn async_commit_on_ok() {
+        mock_transaction!(Mock, EVENTS);
+        let result: Result<u8, ()> =
+            TransactionContext::<Mock>::run(CallerContext::new("test"), async { Ok(1) }).await;
+        assert_eq!(result, Ok(1));
+        assert_eq!(*EVENTS.lock().unwrap(), [Event::Begin, Event::Commit]);
+    }
+
+    #[tokio::test]
+    async fn async_rollback_on_err() {
+        mock_transaction!(Mock, EVENTS);
+        let result: Result<(), &str> =
+            TransactionContext::<Mock>::run(CallerContext::new("test"), async { Err("failure") })
+                .await;
+        assert_eq!(result, Err("failure"));
+        assert_eq!(*EVENTS.lock().unwrap(), [Event::Begin, Event::Rollback]);
+    }
+}
@@ -0,0 +1,49 @@
+/// Guard ensuring that the `on_first_call` hooks are executed once per wrapped function
+///
+/// The [`wrap`](crate::wrap) and [`async_wrap`](crate::async_wrap) macros generate a `static` guard per
+/// wrapped function and attach it via [`CallerContext::with_first_call`](crate::CallerContext::with_first_call).
+/// ```
+/// # use context_manager::{CallerContext, FirstCall};
+/// static FIRST_CALL: FirstCall = FirstCall::new();
+///
+/// let caller_context = CallerContext::new("manual").with_first_call(&FIRST_CALL);
+/// ```
+///
+/// With the `std` feature the guard is backed by [`std::sync::Once`], so concurrent first calls wait for
+/// the hook to complete. Without it only one caller executes the hook, but the others do not wait for it.
+#[derive(Debug)]
+pub struct FirstCall {
+    #[cfg(feature = "std")]
+    once: std::sync::Once,
+    #[cfg(not(feature = "std"))]
+    called: core::sync::atomic::AtomicBool,
+}
+
+impl FirstCall {
+    /// Create a new guard, whose first call is still pending
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            once: std::sync::Once::new(),
+            #[cfg(not(feature = "std"))]
+            called: core::sync::atomic::AtomicBool::new(false),
+        }
+    }
+
+    /// Execute `hook` if this is the first call guarded by `self`
+    pub(crate) fn call_once(&self, hook: impl FnOnce()) {
+        #[cfg(feature = "std")]
+        self.once.call_once(hook);
+        #[cfg(not(feature = "std"))]
+        if !self.called.swap(true, core::sync::atomic::Ordering::AcqRel) {
+            hook();
+        }
+    }
+}
+
+impl Default for FirstCall {
+    fn default() -> Self {
+        Self::new()
+    }
+}
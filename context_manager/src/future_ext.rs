@@ -0,0 +1,71 @@
+use core::future::Future;
+
+use crate::{AsyncWrapContext, CallerContext};
+
+/// Extension trait wrapping already built futures with a context, in a fluent style
+///
+/// It is implemented for all the futures, so it is enough to import it.
+/// ```
+/// # use context_manager::{AsyncWrapContext, CallerContext, FutureContextExt};
+/// struct AsyncPrintDuration;
+/// impl<T> AsyncWrapContext<T> for AsyncPrintDuration {
+///   async fn new() -> Self { Self }
+/// }
+///
+/// # async fn foo() {
+/// let future = async { "created elsewhere" };
+/// let output = future
+///     .instrument::<AsyncPrintDuration>(CallerContext::new("manual"))
+///     .await;
+/// # }
+/// ```
+pub trait FutureContextExt: Future + Sized {
+    /// Wrap the future with the context `C`, equivalent to [`AsyncWrapContext::run`]
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    fn instrument<C>(self, caller_context: CallerContext) -> impl Future<Output = Self::Output>
+    where
+        C: AsyncWrapContext<Self::Output>,
+    {
+        C::run(caller_context, self)
+    }
+}
+
+impl<F: Future> FutureContextExt for F {}
+
+#[cfg(test)]
+mod tests {
+    use super::FutureContextExt;
+    use crate::{AsyncWrapContext, CallerContext};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[tokio::test]
+    async fn instrument_runs_the_hooks() {
+        static BEFORE: AtomicUsize = AtomicUsize::new(0);
+        static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+        struct Async;
+        impl AsyncWrapContext<u8> for Async {
+            async fn new() -> Self {
+                Self
+            }
+
+            async fn before(&self, caller_context: &mut CallerContext) {
+                assert_eq!(caller_context.fn_name(), "fluent");
+                BEFORE.fetch_add(1, Ordering::Relaxed);
+            }
+
+            async fn after(self, _: &CallerContext, result: &u8) {
+                assert_eq!(*result, 42);
+                AFTER.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let future = async { 42 }.instrument::<Async>(CallerContext::new("fluent"));
+        assert_eq!(BEFORE.load(Ordering::Relaxed), 0);
+        assert_eq!(future.await, 42);
+        assert_eq!(BEFORE.load(Ordering::Relaxed), 1);
+        assert_eq!(AFTER.load(Ordering::Relaxed), 1);
+    }
+}
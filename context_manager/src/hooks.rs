@@ -0,0 +1,157 @@
+use core::fmt;
+use core::future::Future;
+
+use crate::call_stack::{in_call_stack_async, in_call_stack_sync, Frame};
+use crate::CallerContext;
+#[cfg(doc)] // Imports needed only for doc purposes
+use crate::{wrap, SyncWrapContext};
+
+/// Closure executed before the wrapped body, as returned by [`before_only`]
+pub struct BeforeOnly<F>(F);
+
+/// Closure executed after the wrapped body, as returned by [`after_only`]
+pub struct AfterOnly<F>(F);
+
+/// Create a context executing `f` before the wrapped body, and nothing after it.
+///
+/// This reduces the ceremony of quick instrumentation, as no type has to be defined.
+/// ```
+/// # use context_manager::{before_only, CallerContext};
+/// let log_call = before_only(|caller_context: &CallerContext| println!("calling {}", caller_context.fn_name()));
+///
+/// assert_eq!(log_call.run_sync(CallerContext::new("manual"), || 42), 42);
+/// ```
+///
+/// NOTE: The returned value carries the closure, so it cannot be used via the [`wrap`] macro
+/// (which initialises the contexts via [`SyncWrapContext::new`]). Please consider implementing
+/// [`SyncWrapContext::before`] on a dedicated type for that.
+pub const fn before_only<F: Fn(&CallerContext)>(f: F) -> BeforeOnly<F> {
+    BeforeOnly(f)
+}
+
+/// Create a context executing `f` after the wrapped body, and nothing before it.
+///
+/// This reduces the ceremony of quick instrumentation, as no type has to be defined.
+/// ```
+/// # use context_manager::{after_only, CallerContext};
+/// let log_return = after_only(|caller_context: &CallerContext| println!("{} returned", caller_context.fn_name()));
+///
+/// assert_eq!(log_return.run_sync(CallerContext::new("manual"), || 42), 42);
+/// ```
+///
+/// NOTE: The returned value carries the closure, so it cannot be used via the [`wrap`] macro
+/// (which initialises the contexts via [`SyncWrapContext::new`]). Please consider implementing
+/// [`SyncWrapContext::after`] on a dedicated type for that.
+pub const fn after_only<F: Fn(&CallerContext)>(f: F) -> AfterOnly<F> {
+    AfterOnly(f)
+}
+
+impl<F: Fn(&CallerContext)> BeforeOnly<F> {
+    /// Execute a synchronous block of code, after having executed the closure
+    #[allow(clippy::needless_pass_by_value)] // Consistent with SyncWrapContext::run_sync
+    pub fn run_sync<T>(&self, caller_context: CallerContext, block: impl FnOnce() -> T) -> T {
+        (self.0)(&caller_context);
+        in_call_stack_sync(Frame::new::<Self>(&caller_context), block)
+    }
+
+    /// Execute an asynchronous block of code, after having executed the closure
+    pub async fn run_async<T>(
+        &self,
+        caller_context: CallerContext,
+        block: impl Future<Output = T>,
+    ) -> T {
+        (self.0)(&caller_context);
+        in_call_stack_async(Frame::new::<Self>(&caller_context), block).await
+    }
+}
+
+impl<F: Fn(&CallerContext)> AfterOnly<F> {
+    /// Execute a synchronous block of code, and then the closure
+    #[allow(clippy::needless_pass_by_value)] // Consistent with SyncWrapContext::run_sync
+    pub fn run_sync<T>(&self, caller_context: CallerContext, block: impl FnOnce() -> T) -> T {
+        let result = in_call_stack_sync(Frame::new::<Self>(&caller_context), block);
+        (self.0)(&caller_context);
+        result
+    }
+
+    /// Execute an asynchronous block of code, and then the closure
+    pub async fn run_async<T>(
+        &self,
+        caller_context: CallerContext,
+        block: impl Future<Output = T>,
+    ) -> T {
+        let result = in_call_stack_async(Frame::new::<Self>(&caller_context), block).await;
+        (self.0)(&caller_context);
+        result
+    }
+}
+
+impl<F> fmt::Debug for BeforeOnly<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BeforeOnly").finish_non_exhaustive()
+    }
+}
+
+impl<F> fmt::Debug for AfterOnly<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AfterOnly").finish_non_exhaustive()
+    }
+}
+
+// The tests rely on the call stack and on the timing, which require the `std` feature
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::{after_only, before_only};
+    use crate::{current_fn_name, CallerContext};
+
+    #[test]
+    fn before_only_runs_the_closure_before_the_body() {
+        let events = RefCell::new(Vec::new());
+        let context = before_only(|caller_context: &CallerContext| {
+            events.borrow_mut().push(caller_context.fn_name());
+        });
+
+        let result = context.run_sync(CallerContext::new("before"), || {
+            events.borrow_mut().push("body");
+            current_fn_name()
+        });
+        assert_eq!(result, Some("before"));
+        assert_eq!(*events.borrow(), ["before", "body"]);
+    }
+
+    #[test]
+    fn after_only_runs_the_closure_after_the_body() {
+        let events = RefCell::new(Vec::new());
+        let context = after_only(|caller_context: &CallerContext| {
+            events.borrow_mut().push(caller_context.fn_name());
+        });
+
+        let result = context.run_sync(CallerContext::new("after"), || {
+            events.borrow_mut().push("body");
+            current_fn_name()
+        });
+        assert_eq!(result, Some("after"));
+        assert_eq!(*events.borrow(), ["body", "after"]);
+    }
+
+    #[tokio::test]
+    async fn async_bodies() {
+        let events = RefCell::new(Vec::new());
+        let before = before_only(|_: &CallerContext| events.borrow_mut().push("before"));
+        let after = after_only(|_: &CallerContext| events.borrow_mut().push("after"));
+
+        before
+            .run_async(CallerContext::new("test"), async {
+                events.borrow_mut().push("body");
+            })
+            .await;
+        after
+            .run_async(CallerContext::new("test"), async {
+                events.borrow_mut().push("body");
+            })
+            .await;
+        assert_eq!(*events.borrow(), ["before", "body", "body", "after"]);
+    }
+}
@@ -0,0 +1,147 @@
+//! Global interceptors, invoked around the functions wrapped by [`RegistryContext`](crate::contexts::RegistryContext).
+//!
+//! Interceptors allow plugin-style architectures, where independent components register their own hooks at startup
+//! without the wrapped functions having to know about them.
+//! ```
+//! # use context_manager::contexts::RegistryContext;
+//! # use context_manager::interceptors::{self, Interceptor};
+//! # use context_manager::{wrap, CallerContext};
+//! struct Audit;
+//! impl Interceptor for Audit {
+//!     fn before(&self, caller_context: &CallerContext) {
+//!         println!("calling {}", caller_context.fn_name());
+//!     }
+//! }
+//!
+//! interceptors::register(10, Box::new(Audit));
+//!
+//! #[wrap(RegistryContext)]
+//! fn handle_request() {}
+//!
+//! // Prints `calling handle_request`
+//! handle_request();
+//! ```
+
+use std::fmt;
+use std::sync::{Arc, PoisonError, RwLock};
+
+use crate::CallerContext;
+
+static INTERCEPTORS: RwLock<Option<Interceptors>> = RwLock::new(None);
+
+/// Hooks invoked around every function wrapped by [`RegistryContext`](crate::contexts::RegistryContext)
+///
+/// As they are shared by all the wrapped functions, the hooks do not observe the result of the function.
+pub trait Interceptor: Send + Sync {
+    /// Hook executed before the wrapped function
+    fn before(&self, caller_context: &CallerContext) {
+        let _ = caller_context;
+    }
+
+    /// Hook executed after the wrapped function
+    fn after(&self, caller_context: &CallerContext) {
+        let _ = caller_context;
+    }
+}
+
+/// Registered interceptors, sorted by decreasing priority
+pub(crate) type Interceptors = Arc<[(i32, Arc<dyn Interceptor>)]>;
+
+/// Register `interceptor`, invoked around all the calls of the functions wrapped by
+/// [`RegistryContext`](crate::contexts::RegistryContext) starting after the registration
+///
+/// Interceptors with higher `priority` are outermost: their `before` hook runs first and their `after` hook runs last.
+/// Interceptors with the same priority are invoked in registration order (and in reverse order for `after`).
+pub fn register(priority: i32, interceptor: Box<dyn Interceptor>) {
+    let mut interceptors = INTERCEPTORS.write().unwrap_or_else(PoisonError::into_inner);
+    // Calls in progress keep a snapshot of the interceptors, so the registered ones are copied rather than mutated
+    let mut updated = interceptors.as_deref().unwrap_or_default().to_vec();
+    let index = updated.partition_point(|(existing, _)| *existing >= priority);
+    updated.insert(index, (priority, Arc::from(interceptor)));
+    *interceptors = Some(updated.into());
+}
+
+/// Snapshot of the interceptors registered so far
+pub(crate) fn snapshot() -> Option<Interceptors> {
+    INTERCEPTORS
+        .read()
+        .unwrap_or_else(PoisonError::into_inner)
+        .clone()
+}
+
+impl fmt::Debug for dyn Interceptor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Interceptor")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use super::{register, Interceptor};
+    use crate::contexts::RegistryContext;
+    use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+    static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    struct Named(&'static str);
+
+    impl Named {
+        fn record(&self, hook: &str, caller_context: &CallerContext) {
+            let fn_name = caller_context.fn_name();
+            if fn_name.starts_with("interceptors_") {
+                EVENTS
+                    .lock()
+                    .unwrap()
+                    .push(format!("{} {hook} {fn_name}", self.0));
+            }
+        }
+    }
+
+    impl Interceptor for Named {
+        fn before(&self, caller_context: &CallerContext) {
+            self.record("before", caller_context);
+        }
+
+        fn after(&self, caller_context: &CallerContext) {
+            self.record("after", caller_context);
+        }
+    }
+
+    #[tokio::test]
+    async fn interceptors_run_in_priority_order() {
+        register(1, Box::new(Named("low")));
+        register(5, Box::new(Named("high")));
+        register(1, Box::new(Named("low-late")));
+
+        <RegistryContext as SyncWrapContext<_>>::run_sync(
+            CallerContext::new("interceptors_sync"),
+            || EVENTS.lock().unwrap().push("body".to_string()),
+        );
+        <RegistryContext as AsyncWrapContext<_>>::run(
+            CallerContext::new("interceptors_async"),
+            async {},
+        )
+        .await;
+
+        assert_eq!(
+            *EVENTS.lock().unwrap(),
+            [
+                "high before interceptors_sync",
+                "low before interceptors_sync",
+                "low-late before interceptors_sync",
+                "body",
+                "low-late after interceptors_sync",
+                "low after interceptors_sync",
+                "high after interceptors_sync",
+                "high before interceptors_async",
+                "low before interceptors_async",
+                "low-late before interceptors_async",
+                "low-late after interceptors_async",
+                "low after interceptors_async",
+                "high after interceptors_async",
+            ]
+        );
+    }
+}
@@ -15,31 +15,79 @@
 #![doc = include_str!("../README.md")]
 //!
 #![doc = include_str!("../CHANGELOG.md")]
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
+extern crate alloc;
+
+mod async_guard;
+mod block;
+mod call_stack;
+mod caller_context;
+mod context_future;
+pub mod contexts;
+mod first_call;
+mod future_ext;
+mod hooks;
+#[cfg(feature = "std")]
+pub mod interceptors;
+mod platform;
+#[cfg(feature = "std")]
+mod scope_timer;
 mod t_async;
 mod t_sync;
+mod time;
+mod with_args;
+mod with_context;
+pub use crate::async_guard::AsyncContextGuard;
+pub use crate::block::{AsyncBlock, SyncBlock, WithCallerContext};
+#[cfg(feature = "std")]
+pub use crate::call_stack::current_fn_name;
+pub use crate::caller_context::CallerContext;
+pub use crate::context_future::ContextFuture;
+pub use crate::first_call::FirstCall;
+pub use crate::future_ext::FutureContextExt;
+pub use crate::hooks::{after_only, before_only, AfterOnly, BeforeOnly};
+#[cfg(feature = "std")]
+pub use crate::scope_timer::ScopeTimer;
 pub use crate::t_async::AsyncWrapContext;
-pub use crate::t_sync::SyncWrapContext;
-
-/// Context about the caller propagated into the context.
-#[derive(Debug)]
-#[non_exhaustive]
-pub struct CallerContext {
-    /// Name of the wrapped function
-    fn_name: &'static str,
-}
+pub use crate::t_sync::{skip_with_default, Reentrancy, SyncWrapContext};
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub use crate::time::timeout;
+#[cfg(feature = "async-std")]
+pub use crate::time::AsyncStdSleep;
+#[cfg(feature = "tokio")]
+pub use crate::time::TokioSleep;
+pub use crate::time::{timeout_with, Sleep, TimeoutError};
+#[cfg(feature = "std")]
+pub use crate::time::{Clock, SystemClock};
+pub use crate::with_args::WrapContextWithArgs;
+pub use crate::with_context::{with_async_context, with_context, with_context_async};
+
+/// Items used by the code generated by the macros, not part of the public API
+#[doc(hidden)]
+pub mod __private {
+    #[cfg(feature = "tracing")]
+    pub use tracing;
+
+    use crate::{CallerContext, WithCallerContext};
+
+    /// Synchronous body of a wrapped function, whose output is provided explicitly to drive the inference of the closure
+    pub const fn sync_block<T, F: FnOnce() -> T>(block: F) -> F {
+        block
+    }
 
-impl CallerContext {
-    /// Create a new instance of the `CallerContext`
-    #[must_use]
-    pub const fn new(fn_name: &'static str) -> Self {
-        Self { fn_name }
+    /// Synchronous body of a wrapped function binding the caller context (refer to [`sync_block`])
+    pub const fn sync_block_with_caller_context<T, F: FnOnce(&CallerContext) -> T>(
+        block: F,
+    ) -> WithCallerContext<F> {
+        WithCallerContext(block)
     }
 
-    /// Name of the wrapped function
-    #[must_use]
-    pub const fn fn_name(&self) -> &'static str {
-        self.fn_name
+    /// Asynchronous body of a wrapped function binding the caller context
+    pub const fn async_block_with_caller_context<T, F: AsyncFnOnce(&CallerContext) -> T>(
+        block: F,
+    ) -> WithCallerContext<F> {
+        WithCallerContext(block)
     }
 }
 
@@ -64,7 +112,27 @@ impl CallerContext {
 /// ```
 ///
 /// The decorator does not induce limits on the shape of the incoming function, in terms
-/// of generics, sync/async, lifetime, etc.
+/// of generics (including `impl Trait` arguments), sync/async, lifetime, etc.
+///
+/// Async trait methods providing a default body can be decorated as well, in which case all the
+/// implementors not overriding the method will inherit the wrapped behaviour. The returned future is
+/// `Send` whenever the body of the implementor allows it, as for undecorated methods.
+/// ```
+/// # use context_manager_macro::async_wrap;
+/// # struct AsyncPrintDuration;
+/// # impl<T> context_manager::AsyncWrapContext<T> for AsyncPrintDuration {
+/// #   async fn new() -> Self { Self }
+/// # }
+/// #[allow(async_fn_in_trait)]
+/// trait Repository {
+///     async fn load(&self, id: u32) -> Option<String>;
+///
+///     #[async_wrap(AsyncPrintDuration)]
+///     async fn load_or_default(&self, id: u32) -> String {
+///         self.load(id).await.unwrap_or_default()
+///     }
+/// }
+/// ```
 ///
 /// The decorator will expand the incoming function by adding the context handling
 /// rendering something similar to
@@ -75,7 +143,8 @@ impl CallerContext {
 /// #   async fn new() -> Self { Self }
 /// # }
 /// async fn foo<'a, T>(int_value: usize, str_ref: &'a str, generic: T) -> usize {
-///     AsyncPrintDuration::run(CallerContext { fn_name: "foo" }, async {
+///     AsyncPrintDuration::run(CallerContext::new("foo"), async move {
+///         let _ = (&int_value, &str_ref, &generic);
 ///         let type_name = std::any::type_name::<T>();
 ///         println!("Async call with int_value={int_value}, str_ref={str_ref}, type_of(T)={type_name}");
 ///         10
@@ -86,6 +155,9 @@ impl CallerContext {
 /// The structuring of the generated code is though to avoid any clone/copy of data,
 /// as well as reducing the number of jumps needed to execute the original code.
 ///
+/// The body takes ownership of all the arguments, as the future of an undecorated async function does, so the
+/// captures of the body are not affected and the arguments are dropped once the body completes (before `after`).
+///
 /// # Possible compile errors
 /// ## Passing a type that does not implement `AsyncWrapContext` trait will lead to compile errors.
 /// ```compile_fail
@@ -100,13 +172,13 @@ impl CallerContext {
 /// ```
 /// would lead to the following compile error
 /// ```text
-/// error[E0277]: the trait bound `PrintDuration: AsyncWrapContext<_>` is not satisfied
-///   --> src/lib.rs:206:1
+/// error[E0277]: `PrintDuration` is not an asynchronous context, as it does not implement `AsyncWrapContext<_>`
+///   --> src/lib.rs:11:14
 ///    |
 /// 11 | #[async_wrap(PrintDuration)]
-///    |                  ^^^^^^^^^^^^^ the trait `AsyncWrapContext<_>` is not implemented for `PrintDuration`
+///    |              ^^^^^^^^^^^^^ not an asynchronous context
 ///    |
-///    = help: the trait `AsyncWrapContext<T>` is implemented for `AsyncTraceDuration`
+///    = note: if `PrintDuration` implements `SyncWrapContext`, please consider using `#[wrap(PrintDuration)]` instead of `#[async_wrap(PrintDuration)]`
 /// ```
 ///
 /// ## Decorating a synchronous function
@@ -161,7 +233,142 @@ pub use context_manager_macro::async_wrap;
 /// ```
 ///
 /// The decorator does not induce limits on the shape of the incoming function, in terms
-/// of generics, sync/async, lifetime, etc.
+/// of generics (including `impl Trait` arguments), sync/async, lifetime, etc.
+///
+/// Functions returning references, with elided or explicit lifetimes, are supported as well:
+/// the references captured by the body are moved into it, so the returned references remain
+/// bound to the lifetimes of the function's arguments. Returning a reference to a local variable
+/// is rejected by the compiler exactly as for the non-decorated function.
+///
+/// Functions returning opaque types, like `-> impl Display`, `-> impl Iterator<Item = u8> + '_` or
+/// `-> Result<impl Display, E>`, are supported for both sync and async functions. The body is the defining
+/// scope of the opaque type, so the context observes the concrete type being returned (ie. `T = u8` for
+/// `fn foo() -> impl Display { 42_u8 }`), and contexts bounded on the trait (ie. `impl<T: Display>`) apply as well.
+///
+/// The `return` keyword preserves its semantic: a `return` in the body returns from the wrapped function,
+/// after the execution of the `after` hooks, while a `return` in a closure (or in an async block) nested in
+/// the body returns from the closure only.
+///
+/// Diverging functions, like an event loop `fn run() -> !`, are supported as well. The `before` hook
+/// is executed as usual, while the `after` hooks are unreachable as the body never completes
+/// (unless it unwinds, in which case the hooks are skipped as for any panicking body).
+///
+/// Arguments can use any irrefutable pattern, like `(a, b): (u8, u8)` or `Point { x, y }: Point`.
+/// The identifiers bound by the arguments are reported, in declaration order, by [`CallerContext::arg_names`].
+/// Methods taking `self` by value are supported as well, so builder methods like `fn with_x(mut self, x: u32) -> Self`
+/// can be decorated and chained as usual.
+///
+/// The context can be any type, including generic ones and fully qualified paths,
+/// like `#[wrap(crate::contexts::Logging<Json>)]` or `#[wrap(Logging::<Json>)]`. The generic parameters of the
+/// function (and of the enclosing `impl`) are in scope, so `#[wrap(Logging<T>)]` on `fn load<T: Format>()` wraps each
+/// instantiation of the function with the matching context.
+///
+/// The `after` hooks can be restricted to a branch of the returned [`Result`] via the `on` argument,
+/// like `#[wrap(ReportError, on = "err")]`. Accepted values are `"ok"`, `"err"` and `"always"` (the default),
+/// refer to [`contexts::BranchContext`] for more details. The argument is accepted by [`async_wrap`] as well.
+///
+/// The `alias` argument, like `#[wrap(Metrics, alias = "foo_instrumented")]` on `fn foo`, leaves the function bare
+/// and generates a wrapped copy of it named `foo_instrumented`, which is reported as [`CallerContext::fn_name`].
+/// This allows adopting a context gradually, or comparing the overhead of the two versions. With stacked macros
+/// the alias applies to all of them, so it can be provided only once. The argument is accepted by [`async_wrap`] as well.
+///
+/// The `bind` argument, like `#[wrap(Logging, bind = "ctx")]`, binds a `&CallerContext` describing the call to `ctx`
+/// in the body, so the body can read its own [`CallerContext::fn_name`] or [`CallerContext::meta`]. The binding behaves
+/// as a `let` written at the start of the body, so it can be shadowed by the body and it does not clash with the
+/// code generated by the macro. The bound caller context is the one provided to the hooks (refer to [`WithCallerContext`]),
+/// so the extensions inserted by them are visible. With stacked macros it is the caller context of the layer providing
+/// the argument. Combined with `alias`, the bare function binds a caller context describing itself. The argument is
+/// accepted by [`async_wrap`] as well.
+///
+/// With the `trace` feature the context can be omitted, like `#[wrap]`, for quick debugging: the function is then
+/// wrapped by `contexts::DefaultTraceContext`, printing to the standard error when it is entered and exited.
+/// The same applies to [`async_wrap`].
+///
+/// Async functions are wrapped via [`SyncWrapContext::run_async`], so the hooks of the context are synchronous. The `hooks`
+/// argument, like `#[wrap(Tracing, hooks = "async")]`, wraps them via [`AsyncWrapContext::run`] instead, for contexts implementing
+/// both the traits. Accepted values are `"sync"` (the default) and `"async"`, which requires an async function.
+/// The argument is accepted by [`async_wrap`] as well, where the default is `"async"`.
+///
+/// Only `async fn` are awaited: functions returning a future, like `fn fetch() -> Pin<Box<dyn Future<Output = u8>>>`,
+/// are wrapped via [`SyncWrapContext::run_sync`] with the future as output. Hence, the hooks wrap the creation
+/// of the future rather than its execution, and `after` observes the future before it is polled, not its eventual output.
+///
+/// The output type of the context is inferred from the body, as in `<Context as SyncWrapContext<_>>::run_sync(...)`.
+/// If the inference fails, like for a body returning `"42".parse().unwrap()` from a function returning `impl Display`,
+/// the type can be pinned via the trait of the context, like `#[wrap(Logging as SyncWrapContext<u32>)]`. The trait has
+/// to match the hooks of the context: `AsyncWrapContext<T>` for [`async_wrap`] or with `hooks = "async"`.
+///
+/// The `fields(...)` argument, like `#[wrap(SpanFieldsContext, fields(user_id))]`, lists arguments of the function
+/// to record as fields of a `tracing` span provided as input to the context (requires the `tracing` feature).
+/// Refer to `contexts::SpanFieldsContext` for more details. The argument is accepted by [`async_wrap`] as well.
+///
+/// The `span(...)` argument, like `#[wrap(SpanFieldsContext, span(name = "load", target = "db", level = "debug"))]`,
+/// configures the metadata of that span, and provides it to the context even without `fields(...)`. The defaults match
+/// the ones of `#[tracing::instrument]`: the name of the function, its module as target and `"info"` level.
+/// The argument is accepted by [`async_wrap`] as well.
+///
+/// The `meta(...)` argument, like `#[wrap(Metrics, meta(service = "auth", tier = "edge"))]`, attaches static key-value
+/// metadata to the caller context, readable by the hooks via [`CallerContext::meta`]. The argument is accepted by [`async_wrap`] as well.
+///
+/// The `args` argument, like `#[wrap(LogArgs, args)]`, provides the arguments of the function (`self` excluded) to
+/// [`WrapContextWithArgs::before_with_args`] as a tuple, so the context has to implement [`WrapContextWithArgs`].
+/// The argument is not accepted by [`async_wrap`], nor together with `fields(...)` or `span(...)`.
+///
+/// The `skip` argument, like `#[wrap(Metrics, skip = "after")]`, omits one hook of the context, avoiding its cost entirely
+/// on hot paths: `"before"` skips [`SyncWrapContext::before`], while `"after"` skips [`SyncWrapContext::after_owned`] (and so
+/// [`SyncWrapContext::after_timed`] and [`SyncWrapContext::after`]). The function is wrapped via the `run*_before_only` or
+/// `run*_after_only` variants of the `run*` functions, like [`SyncWrapContext::run_sync_before_only`]. The argument is accepted
+/// by [`async_wrap`] as well, but not together with `fields(...)`, `span(...)` or `args`.
+///
+/// The `timeout` argument, like `#[wrap(PrintDuration, timeout = "1m 30s")]`, limits the duration of the body of async functions
+/// (requires either the `tokio` or the `async-std` feature). The duration is a sequence of integers with units (`ns`, `us`, `ms`, `s`, `min` or `m`, `h`).
+/// The body is cancelled once the timeout elapses, and the function returns `Err(TimeoutError.into())`, so it has to return
+/// a [`Result`] whose error implements `From<TimeoutError>`. The timeout applies to the body only, so the context observes the
+/// timeout error as the result. The argument is accepted by [`async_wrap`] as well.
+///
+/// The decorator can be applied also on trait methods providing a default body, including `async fn` ones,
+/// in which case all the implementors not overriding the method will inherit the wrapped behaviour.
+/// ```
+/// # use context_manager_macro::wrap;
+/// # struct PrintDuration;
+/// # impl<T> context_manager::SyncWrapContext<T> for PrintDuration {
+/// #   fn new() -> Self { Self }
+/// # }
+/// trait Answer {
+///     #[wrap(PrintDuration)]
+///     fn answer(&self) -> usize {
+///         42
+///     }
+/// }
+/// ```
+///
+/// Multiple decorators can be stacked on the same function, in which case the contexts are
+/// nested according to the source order: the top decorator is the outermost context, so its
+/// `before` hook runs first and its `after` hook runs last. All the contexts receive the same
+/// function name via [`CallerContext::fn_name`].
+/// ```
+/// # use context_manager_macro::wrap;
+/// # struct PrintDuration;
+/// # impl<T> context_manager::SyncWrapContext<T> for PrintDuration { fn new() -> Self { Self } }
+/// # struct CountCalls;
+/// # impl<T> context_manager::SyncWrapContext<T> for CountCalls { fn new() -> Self { Self } }
+/// #[wrap(PrintDuration)] // Outermost context: the measured duration includes CountCalls hooks
+/// #[wrap(CountCalls)]
+/// fn foo() {}
+/// ```
+///
+/// The other attributes of the function are preserved, so conditionally compiled variants of the same function
+/// can be wrapped independently, whether `#[cfg(...)]` is listed before or after the decorator. Decorators can be
+/// enabled conditionally too, via `#[cfg_attr(..., wrap(...))]`. Similarly `#[must_use]` is preserved, and the
+/// returned value is the one of the body, so ignoring the result of a `#[must_use]` function (or of a function
+/// returning a `#[must_use]` type, like [`Result`]) is still reported by the `unused_must_use` lint.
+///
+/// Recursive functions are supported: the decorator wraps the body of the function, not its call sites,
+/// so each recursive call goes through the wrapped function exactly once. As a result the hooks are executed
+/// for every call, nested according to the recursion (the `before` hook of the inner call runs after
+/// the one of the outer call, and its `after` hook runs first). Contexts interested only in the outermost
+/// call can opt into [`Reentrancy::Detect`], in which case [`SyncWrapContext::on_reentry`] is executed
+/// for the recursive calls instead of the hooks.
 ///
 /// The decorator will expand the incoming function by adding the context handling
 /// rendering something similar to
@@ -172,7 +379,7 @@ pub use context_manager_macro::async_wrap;
 /// #   fn new() -> Self { Self }
 /// # }
 /// fn sync_foo<'a, T>(int_value: usize, str_ref: &'a str, generic: T) -> usize {
-///     PrintDuration::run_sync(CallerContext { fn_name: "sync_foo" }, move || {
+///     PrintDuration::run_sync(CallerContext::new("sync_foo"), move || {
 ///         let type_name = std::any::type_name::<T>();
 ///         println!("Sync call with int_value={int_value}, str_ref={str_ref}, type_of(T)={type_name}");
 ///         10
@@ -180,7 +387,7 @@ pub use context_manager_macro::async_wrap;
 /// }
 ///
 /// async fn async_foo<'a, T>(int_value: usize, str_ref: &'a str, generic: T) -> usize {
-///     PrintDuration::run_async(CallerContext { fn_name: "async_foo" }, async {
+///     PrintDuration::run_async(CallerContext::new("async_foo"), async move {
 ///         let type_name = std::any::type_name::<T>();
 ///         println!("Async call with int_value={int_value}, str_ref={str_ref}, type_of(T)={type_name}");
 ///         10
@@ -206,13 +413,37 @@ pub use context_manager_macro::async_wrap;
 /// would lead to the following compile error
 /// ```text
 /// ---- src/lib.rs - decorate (line 98) stdout ----
-/// error[E0277]: the trait bound `AsyncPrintDuration: SyncWrapContext<_>` is not satisfied
-///   --> src/lib.rs:106:12
+/// error[E0277]: `AsyncPrintDuration` is not a synchronous context, as it does not implement `SyncWrapContext<_>`
+///   --> src/lib.rs:11:8
 ///    |
 /// 11 | #[wrap(AsyncPrintDuration)]
-///    |            ^^^^^^^^^^^^^^^^^^ the trait `SyncWrapContext<_>` is not implemented for `AsyncPrintDuration`
+///    |        ^^^^^^^^^^^^^^^^^^ not a synchronous context
+///    |
+///    = note: if `AsyncPrintDuration` implements `AsyncWrapContext`, please consider using `#[async_wrap(AsyncPrintDuration)]` instead of `#[wrap(AsyncPrintDuration)]`
+/// ```
+///
+/// ## Decorating a trait method without a default body
+/// There is no body to wrap, so please consider decorating the implementations instead.
+/// ```compile_fail
+/// # use context_manager_macro::wrap;
+/// struct PrintDuration;
+/// impl<T> context_manager::SyncWrapContext<T> for PrintDuration {
+///   fn new() -> Self { Self }
+/// }
+///
+/// trait Answer {
+///     #[wrap(PrintDuration)]
+///     fn answer(&self) -> usize;
+/// }
+/// ```
+/// would lead to the following error
+/// ```text
+/// error: #[wrap] cannot operate on trait methods without a default body. Please consider decorating the implementations instead.
+///   --> src/lib.rs:11:5
+///    |
+/// 11 |     fn answer(&self) -> usize;
+///    |     ^^^^^^^^^^^^^^^^^^^^^^^^^
 ///    |
-///    = help: the trait `SyncWrapContext<T>` is implemented for `TraceDuration`
 /// ```
 ///
 /// ## Decorating a constant function
@@ -238,8 +469,147 @@ pub use context_manager_macro::async_wrap;
 ///    | ^^^^^^^^^^^^^^^^^^^^^^
 ///    |
 /// ```
+///
+/// ## Decorating a function with a non-Rust ABI
+/// Functions like `extern "C" fn` are not supported for decoration, as the wrapped body would be
+/// executed within a Rust closure. Please consider decorating a Rust function and calling it
+/// from an `extern` shim instead.
+///
+/// ```compile_fail
+/// # use context_manager_macro::wrap;
+/// struct PrintDuration;
+/// impl<T> context_manager::SyncWrapContext<T> for PrintDuration {
+///   fn new() -> Self { Self }
+/// }
+///
+/// #[wrap(PrintDuration)]
+/// extern "C" fn foo() {}
+/// ```
+/// would lead to the following error
+/// ```text
+/// error: #[wrap] cannot operate on functions with a non-Rust ABI. Please consider decorating a Rust function and calling it from an `extern` shim instead.
+///   --> src/lib.rs:11:1
+///    |
+/// 11 | extern "C" fn foo() {}
+///    | ^^^^^^^^^^
+///    |
+/// ```
 pub use context_manager_macro::wrap;
 
+/// Procedural macro that will implement `new` of a context via its [`Default`] implementation.
+///
+/// The macro decorates an implementation of [`SyncWrapContext`] or [`AsyncWrapContext`], removing the
+/// need of writing `fn new() -> Self { Self::default() }` (or its async counterpart) for each of them.
+///
+/// Usage example:
+/// ```
+/// # use context_manager::{default_new, CallerContext, SyncWrapContext};
+/// # use std::time::Duration;
+/// #[derive(Default)]
+/// struct Histogram {
+///     buckets: Vec<Duration>,
+/// }
+///
+/// #[default_new]
+/// impl<T> SyncWrapContext<T> for Histogram {
+///     fn before(&self, _: &mut CallerContext) {
+///         assert!(self.buckets.is_empty());
+///     }
+/// }
+/// ```
+///
+/// # Possible compile errors
+/// ## Decorating an implementation that already provides `new`
+/// ```compile_fail
+/// # use context_manager::{default_new, SyncWrapContext};
+/// #[derive(Default)]
+/// struct PrintDuration;
+///
+/// #[default_new]
+/// impl<T> SyncWrapContext<T> for PrintDuration {
+///     fn new() -> Self { Self }
+/// }
+/// ```
+/// would lead to the following error
+/// ```text
+/// error: #[default_new] generates `new`, please remove the existing implementation
+///   --> src/lib.rs:8:5
+///    |
+/// 8  |     fn new() -> Self { Self }
+///    |     ^^^^^^^^^^^^^^^^
+///    |
+/// ```
+pub use context_manager_macro::default_new;
+
+/// Procedural macro that will decorate all the functions of the incoming module with the provided context.
+///
+/// `#[wrap_module(Context)]` on an inline module (`mod name { ... }`) decorates each function defined directly
+/// within it with `#[wrap(Context)]`, so async functions are wrapped via [`SyncWrapContext::run_async`] as usual.
+/// The arguments are the ones accepted by [`wrap`], applied to all the functions, except `alias` as the functions
+/// would share it. Functions can opt out via the `#[wrap_skip]` attribute, which is accepted only within the module.
+/// Methods, functions of nested modules, functions generated by macros, const functions and functions with a
+/// non-Rust ABI are not decorated.
+///
+/// As the functions are decorated within the module, the context is resolved from there,
+/// so it has to be in scope within the module or referenced via a path like `super::Context`.
+///
+/// Usage example:
+/// ```
+/// # use context_manager::{wrap_module, SyncWrapContext};
+/// struct PrintDuration;
+/// impl<T> SyncWrapContext<T> for PrintDuration {
+///   fn new() -> Self { Self }
+/// }
+///
+/// #[wrap_module(super::PrintDuration)]
+/// mod handlers {
+///     pub fn get_user() -> u64 {
+///         42
+///     }
+///
+///     pub async fn list_users() -> Vec<u64> {
+///         vec![get_user()]
+///     }
+///
+///     #[wrap_skip]
+///     pub fn health_check() {}
+/// }
+///
+/// # fn main() {
+/// assert_eq!(handlers::get_user(), 42);
+/// # }
+/// ```
+pub use context_manager_macro::wrap_module;
+
+/// Function-like macro that will wrap the incoming closure with the provided context.
+///
+/// Attribute macros cannot decorate closures, so `wrap_fn!(Context, closure)` evaluates to the closure
+/// with its body wrapped by the context, as [`wrap`] does for functions. The context is expected to be
+/// a type that implements the [`SyncWrapContext`] trait.
+///
+/// Usage example:
+/// ```
+/// # use context_manager::{wrap_fn, SyncWrapContext};
+/// struct PrintDuration;
+/// impl<T> SyncWrapContext<T> for PrintDuration {
+///   fn new() -> Self { Self }
+/// }
+///
+/// let doubled: Vec<u8> = [1, 2, 3].into_iter().map(wrap_fn!(PrintDuration, |value| value * 2)).collect();
+/// assert_eq!(doubled, [2, 4, 6]);
+///
+/// # async fn foo() {
+/// let fetch = wrap_fn!(PrintDuration, |id: u64| async move { id });
+/// assert_eq!(fetch(42).await, 42);
+/// # }
+/// ```
+///
+/// Whether the closure is async is inferred from its shape: async closures (`async |..| ..`) and closures
+/// whose body is an async block (`|..| async { .. }`) are wrapped via [`SyncWrapContext::run_async`],
+/// any other closure via [`SyncWrapContext::run_sync`]. The hooks are executed for each call of the closure,
+/// and they receive `{closure}` as [`CallerContext::fn_name`].
+pub use context_manager_macro::wrap_fn;
+
 #[cfg(test)]
 mod tests {
     use trybuild::TestCases;
@@ -250,4 +620,32 @@ mod tests {
         t.pass("tests/ui/pass/*.rs");
         t.compile_fail("tests/ui/fail/*.rs");
     }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn procedural_macros_tokio_ui_tests() {
+        let t = TestCases::new();
+        t.pass("tests/ui/pass_tokio/*.rs");
+    }
+
+    #[cfg(feature = "testing")]
+    #[test]
+    fn procedural_macros_testing_ui_tests() {
+        let t = TestCases::new();
+        t.compile_fail("tests/ui/fail_testing/*.rs");
+    }
+
+    #[cfg(feature = "trace")]
+    #[test]
+    fn procedural_macros_trace_ui_tests() {
+        let t = TestCases::new();
+        t.pass("tests/ui/pass_trace/*.rs");
+    }
+
+    #[cfg(not(feature = "trace"))]
+    #[test]
+    fn procedural_macros_no_trace_ui_tests() {
+        let t = TestCases::new();
+        t.compile_fail("tests/ui/fail_no_trace/*.rs");
+    }
 }
@@ -0,0 +1,68 @@
+//! Facilities provided by `std`, degrading gracefully when the `std` feature is disabled
+
+use core::time::Duration;
+
+use crate::{AsyncWrapContext, SyncWrapContext};
+
+/// Point in time from which the duration of the wrapped body is measured
+///
+/// The time is read via the clock of the context in use ([`SyncWrapContext::now`] or [`AsyncWrapContext::now`]).
+/// Without the `std` feature there is no clock available, so the measured duration is always zero.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Stopwatch {
+    #[cfg(feature = "std")]
+    start: std::time::Instant,
+    #[cfg(feature = "std")]
+    now: fn() -> std::time::Instant,
+}
+
+impl Stopwatch {
+    /// Start measuring via the [`Clock`](crate::Clock) `K`
+    #[cfg(feature = "std")]
+    pub(crate) fn start_with<K: crate::Clock>() -> Self {
+        Self {
+            start: K::now(),
+            now: K::now,
+        }
+    }
+
+    /// Start measuring via the clock of the synchronous context `C`
+    #[cfg_attr(not(feature = "std"), allow(clippy::extra_unused_type_parameters))]
+    pub(crate) fn start_sync<C: SyncWrapContext<T, I>, T, I>() -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            start: C::now(),
+            #[cfg(feature = "std")]
+            now: C::now,
+        }
+    }
+
+    /// Start measuring via the clock of the asynchronous context `C`
+    #[cfg_attr(not(feature = "std"), allow(clippy::extra_unused_type_parameters))]
+    pub(crate) fn start_async<C: AsyncWrapContext<T, I>, T, I>() -> Self {
+        Self {
+            #[cfg(feature = "std")]
+            start: C::now(),
+            #[cfg(feature = "std")]
+            now: C::now,
+        }
+    }
+
+    #[cfg_attr(not(feature = "std"), allow(clippy::unused_self))]
+    pub(crate) fn elapsed(self) -> Duration {
+        #[cfg(feature = "std")]
+        return (self.now)().saturating_duration_since(self.start);
+        #[cfg(not(feature = "std"))]
+        return Duration::ZERO;
+    }
+}
+
+/// Check whether the current thread is unwinding because of a panic
+///
+/// Without the `std` feature panics cannot be observed while unwinding, so it is always `false`.
+pub(crate) fn panicking() -> bool {
+    #[cfg(feature = "std")]
+    return std::thread::panicking();
+    #[cfg(not(feature = "std"))]
+    return false;
+}
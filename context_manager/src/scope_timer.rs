@@ -0,0 +1,104 @@
+use core::time::Duration;
+
+use crate::platform::Stopwatch;
+use crate::{CallerContext, Clock, SystemClock};
+
+/// Timer reporting the time elapsed since its creation when dropped, as a lightweight alternative to a context
+///
+/// This allows timing ad-hoc blocks, without defining a context nor wrapping a function.
+/// The time is measured as for the wrapped bodies, and by default it is printed to the standard error.
+/// ```
+/// # use context_manager::{CallerContext, ScopeTimer};
+/// {
+///     let _timer = ScopeTimer::new(CallerContext::new("load_configuration"));
+///     // Prints `load_configuration took ...` at the end of the block
+/// }
+/// ```
+///
+/// The report can be customised via [`ScopeTimer::with_report`], and the clock via [`ScopeTimer::with_clock`].
+#[derive(Debug)]
+#[must_use = "the timer reports when dropped, so it should be bound to a variable (ie. `let _timer = ...`)"]
+pub struct ScopeTimer {
+    caller_context: CallerContext,
+    start: Stopwatch,
+    report: fn(&CallerContext, Duration),
+}
+
+impl ScopeTimer {
+    /// Start a timer printing the elapsed time to the standard error when dropped
+    pub fn new(caller_context: CallerContext) -> Self {
+        Self::with_report(caller_context, |caller_context, elapsed| {
+            eprintln!("{} took {elapsed:?}", caller_context.fn_name());
+        })
+    }
+
+    /// Start a timer executing `report` with the elapsed time when dropped
+    /// ```
+    /// # use context_manager::{CallerContext, ScopeTimer};
+    /// let _timer = ScopeTimer::with_report(CallerContext::new("load_configuration"), |caller_context, elapsed| {
+    ///     println!("{} took {}ms", caller_context.fn_name(), elapsed.as_millis());
+    /// });
+    /// ```
+    pub fn with_report(
+        caller_context: CallerContext,
+        report: fn(&CallerContext, Duration),
+    ) -> Self {
+        Self::with_clock::<SystemClock>(caller_context, report)
+    }
+
+    /// Start a timer executing `report` with the elapsed time when dropped, measured via the [`Clock`] `K`
+    ///
+    /// This allows to inject a different clock (ie. a fake one, making the reported time deterministic in tests).
+    pub fn with_clock<K: Clock>(
+        caller_context: CallerContext,
+        report: fn(&CallerContext, Duration),
+    ) -> Self {
+        Self {
+            caller_context,
+            start: Stopwatch::start_with::<K>(),
+            report,
+        }
+    }
+
+    /// Context of the caller, as provided when the timer was started
+    #[must_use]
+    pub const fn caller_context(&self) -> &CallerContext {
+        &self.caller_context
+    }
+}
+
+impl Drop for ScopeTimer {
+    fn drop(&mut self) {
+        (self.report)(&self.caller_context, self.start.elapsed());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ScopeTimer;
+    use crate::contexts::test::FakeClock;
+    use crate::CallerContext;
+    use std::cell::Cell;
+    use std::time::Duration;
+
+    thread_local! {
+        static REPORTED: Cell<Option<(&'static str, Duration)>> = const { Cell::new(None) };
+    }
+
+    #[test]
+    fn elapsed_time_is_reported_when_dropped() {
+        let timer = ScopeTimer::with_clock::<FakeClock>(
+            CallerContext::new("block"),
+            |caller_context, elapsed| {
+                REPORTED.set(Some((caller_context.fn_name(), elapsed)));
+            },
+        );
+        FakeClock::advance(Duration::from_millis(10));
+        assert_eq!(REPORTED.get(), None);
+
+        drop(timer);
+        let (fn_name, elapsed) = REPORTED.get().unwrap();
+        assert_eq!(fn_name, "block");
+        assert_eq!(elapsed, Duration::from_millis(10));
+    }
+}
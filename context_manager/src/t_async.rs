@@ -1,8 +1,14 @@
-use std::future::Future;
+use alloc::borrow::Cow;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
+use core::time::Duration;
 
-use crate::CallerContext;
+use crate::call_stack::{in_call_stack_async, Frame};
+use crate::platform::{self, Stopwatch};
 #[cfg(doc)] // Imports needed only for doc purposes
 use crate::{wrap, SyncWrapContext};
+use crate::{AsyncBlock, AsyncContextGuard, CallerContext};
 
 /// Context Manager definition (async hooks)
 ///
@@ -13,6 +19,17 @@ use crate::{wrap, SyncWrapContext};
 /// This is intentional in order to avoid possibly stalling the async-runtime in use.
 /// Please consider wrapping yourself the synchronous code in an async block, or using [`SyncWrapContext`] whether possible.
 ///
+/// # Cancellation
+/// Futures can be dropped before completion (ie. when losing a `select!`), in which case the wrapped body
+/// stops at its current `.await` point and [`AsyncWrapContext::after`] is never executed.
+/// Contexts acquiring resources in [`AsyncWrapContext::new`] or [`AsyncWrapContext::before`] should release
+/// them in [`AsyncWrapContext::on_cancel`] too, or rely on [`Drop`] of the context itself.
+///
+/// # Hooks order
+/// The hooks are executed in the same order as for [`SyncWrapContext`] (refer to its documentation), with
+/// [`AsyncWrapContext::on_cancel`] executed right before [`AsyncWrapContext::finally`] if the body is cancelled
+/// (as [`AsyncWrapContext::on_poisoned`] if it panics).
+///
 /// Implementers are then expected to be used via the [`wrap`] macro
 /// ```
 /// # use context_manager::{async_wrap, AsyncWrapContext};
@@ -42,19 +59,176 @@ use crate::{wrap, SyncWrapContext};
 /// }).await;
 /// # }
 /// ```
-pub trait AsyncWrapContext<T> {
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not an asynchronous context, as it does not implement `AsyncWrapContext<{T}>`",
+    label = "not an asynchronous context",
+    note = "if `{Self}` implements `SyncWrapContext`, please consider using `#[wrap({Self})]` instead of `#[async_wrap({Self})]`"
+)]
+pub trait AsyncWrapContext<T, I = ()> {
+    /// Whether the context implements the before hooks, so that they have to be executed
+    ///
+    /// Defaults to `true`. Contexts not overriding [`AsyncWrapContext::before`] (nor [`AsyncWrapContext::before_with_input`])
+    /// can set it to `false`, so that the `run*` functions skip the call of the no-op hooks altogether.
+    /// Refer to [`SyncWrapContext::HAS_BEFORE`] for more details.
+    const HAS_BEFORE: bool = true;
+
+    /// Whether the context implements the after hooks, so that they have to be executed
+    ///
+    /// Defaults to `true`. Contexts not overriding [`AsyncWrapContext::after`] (nor [`AsyncWrapContext::after_timed`])
+    /// can set it to `false`, as for [`AsyncWrapContext::HAS_BEFORE`].
+    const HAS_AFTER: bool = true;
+
     /// Initialize the context
     #[allow(async_fn_in_trait)]
     async fn new() -> Self
     where
         Self: Sized;
 
+    /// Build the caller context provided to the hooks, starting from the one generated for the wrapped function
+    ///
+    /// The function is invoked by all the `run*` functions before initialising the context, so it is the single
+    /// place where a context can uniformly enrich the caller context (ie. by inserting extensions) for all
+    /// the functions it wraps. By default the caller context is returned unchanged.
+    ///
+    /// Parameters:
+    /// - `default`: Context of the caller, as generated for the wrapped function
+    #[must_use]
+    fn build_caller_context(default: CallerContext) -> CallerContext
+    where
+        Self: Sized,
+    {
+        default
+    }
+
+    /// Execute the code the first time the wrapped function is called, before [`AsyncWrapContext::before`]
+    ///
+    /// The hook is executed exactly once across the program for each wrapped function, making it the place
+    /// for lazy one-time setups (ie. registering a metric). The once-per-function guard is provided via
+    /// [`CallerContext::with_first_call`], so the hook is executed only if the caller context carries it.
+    /// As concurrent first calls wait for its completion, the hook is synchronous.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn on_first_call(&self, caller_context: &CallerContext) {}
+
     /// Execute the code before the execution of the wrapped body
     ///
+    /// The hook can enrich the caller context (ie. via [`CallerContext::insert_extension`]),
+    /// and the modifications will be visible to [`AsyncWrapContext::after`].
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(async_fn_in_trait, unused_variables, clippy::unused_async)]
+    async fn before(&self, caller_context: &mut CallerContext) {}
+
+    /// Decide whether to skip the wrapped body, returning the provided value instead of executing it
+    ///
+    /// The hook is executed right after [`AsyncWrapContext::before`], and by default it continues with the body.
+    /// If it breaks with a value, the body is skipped, and [`AsyncWrapContext::finally`] and [`AsyncWrapContext::after_timed`]
+    /// (with a zero duration) are executed with the value as the result. Refer to [`skip_with_default`](crate::skip_with_default)
+    /// for functions whose result implements [`Default`].
+    ///
+    /// NOTE: The hook is not executed by [`AsyncWrapContext::guard`], as there is no body to skip.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        ControlFlow::Continue(())
+    }
+
+    /// Execute the code before the execution of the wrapped body, it provides also the input supplied by the caller
+    ///
+    /// This is invoked only by [`AsyncWrapContext::run_with_input`], and by default it delegates to [`AsyncWrapContext::before`].
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `input`: The input explicitly supplied by the caller
+    #[allow(async_fn_in_trait, unused_variables)]
+    async fn before_with_input(&self, caller_context: &mut CallerContext, input: &I) {
+        self.before(caller_context).await;
+    }
+
+    /// Execute the code if the wrapped body is cancelled, ie. its future is dropped before completion
+    ///
+    /// Cancellation happens, for example, when the wrapped future loses a `select!` or exceeds a timeout.
+    /// In such case [`AsyncWrapContext::after`] is not executed, so this hook is the place for the cleanup
+    /// that would have happened there. As futures cannot be awaited while being dropped, the hook is synchronous.
+    ///
+    /// The hook is not executed if the body panics, nor if the future returned by `after` is cancelled.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn on_cancel(&self, caller_context: &CallerContext) {}
+
+    /// Execute the code while unwinding, when the wrapped body panics, right before [`AsyncWrapContext::finally`]
+    ///
+    /// Contexts holding state should mark it as poisoned here, so that the following calls can observe it.
+    /// Refer to [`SyncWrapContext::on_poisoned`] for more details.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn on_poisoned(&self, caller_context: &CallerContext) {}
+
+    /// Execute the code once the wrapped body terminates, whether it completes, panics or it is cancelled
+    ///
+    /// The hook is executed right after the body (and after [`AsyncWrapContext::on_cancel`] if cancelled),
+    /// so before [`AsyncWrapContext::after_timed`] if the body completes. It is the place for cleanups that
+    /// must always happen (ie. flushing buffers), and as futures cannot be awaited while being dropped the hook is synchronous.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn finally(&self, caller_context: &CallerContext) {}
+
+    /// Name of the wrapped function, as emitted by the context (ie. in logs or in metrics)
+    ///
+    /// It allows to transform the emitted names (ie. namespacing them like `service.foo`) without changing the name
+    /// stored in the caller context, which is still provided by [`CallerContext::fn_name`]. By default the name is
+    /// returned unchanged. The name is mapped once per call, right after `new`, and it is provided to the hooks by
+    /// [`CallerContext::emitted_fn_name`], which the built-in contexts emitting names (like `contexts::LogContext` or
+    /// `contexts::StatsContext`) use. Some of them provide the mapping via their configuration, and the ones wrapping
+    /// other contexts (like `contexts::ContextChain`) forward it.
+    ///
+    /// Parameters:
+    /// - `name`: Name of the wrapped function
+    #[must_use]
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(name)
+    }
+
+    /// Current time, from which the duration of the wrapped body is measured (requires the `std` feature)
+    ///
+    /// The duration is the one provided to [`AsyncWrapContext::after_timed`] and [`AsyncWrapContext::on_slow`]. By default the time
+    /// is read via [`SystemClock`](crate::SystemClock), overriding it allows to inject a different
+    /// [`Clock`](crate::Clock) (ie. a fake one, making the durations deterministic in tests). The built-in contexts
+    /// accepting a clock use it, and the ones wrapping other contexts (like `contexts::ContextChain`) forward it.
+    #[cfg(feature = "std")]
+    #[must_use]
+    fn now() -> std::time::Instant {
+        <crate::SystemClock as crate::Clock>::now()
+    }
+
+    /// Duration above which the wrapped body is considered slow, triggering [`AsyncWrapContext::on_slow`]
+    ///
+    /// By default it is [`Duration::MAX`], so [`AsyncWrapContext::on_slow`] is never executed.
+    #[must_use]
+    fn slow_threshold(&self) -> Duration {
+        Duration::MAX
+    }
+
+    /// Execute the code when the wrapped body took longer than [`AsyncWrapContext::slow_threshold`]
+    ///
+    /// The hook is executed right before [`AsyncWrapContext::after_timed`], and it is meant for latency alerting.
+    ///
     /// Parameters:
     /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `elapsed`: The time spent executing the wrapped body
     #[allow(async_fn_in_trait, unused_variables, clippy::unused_async)]
-    async fn before(&self, caller_context: &CallerContext) {}
+    async fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {}
 
     /// Execute the code after the execution of the wrapped body, it provides also the result of the wrapped body
     ///
@@ -68,10 +242,32 @@ pub trait AsyncWrapContext<T> {
     {
     }
 
+    /// Execute the code after the execution of the wrapped body, it provides also the result of the wrapped body and its duration
+    ///
+    /// By default it delegates to [`AsyncWrapContext::after`], so implementers interested in the duration
+    /// of the body should override this hook instead of [`AsyncWrapContext::after`].
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `result`: The result of the wrapped body
+    /// - `elapsed`: The time spent executing the wrapped body
+    #[allow(async_fn_in_trait, unused_variables)]
+    async fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration)
+    where
+        Self: Sized,
+    {
+        self.after(caller_context, result).await;
+    }
+
     /// Execute a asynchronous block of code wrapped by the context
     ///
     /// This will lead to context initialisation and execution of before/after hooks
     ///
+    /// The duration of the body is measured and provided to [`AsyncWrapContext::after_timed`].
+    ///
+    /// The block is pinned in place, so it is not required to be [`Unpin`] and it is not boxed
+    /// (the wrapping does not allocate, other than the hooks of the context).
+    ///
     /// Parameters:
     /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
     /// - `block`: the future to wrap and execute
@@ -91,15 +287,267 @@ pub trait AsyncWrapContext<T> {
     /// # }
     /// ```
     #[allow(async_fn_in_trait)]
-    async fn run(caller_context: CallerContext, block: impl Future<Output = T>) -> T
+    async fn run(caller_context: CallerContext, block: impl AsyncBlock<T>) -> T
+    where
+        Self: Sized,
+    {
+        run_in_context::<Self, T, I>(
+            &mut Self::build_caller_context(caller_context),
+            Hooks::All(None),
+            block,
+        )
+        .await
+    }
+
+    /// Execute a asynchronous block of code wrapped by the context, skipping the after hooks
+    ///
+    /// This is equivalent to [`AsyncWrapContext::run`], but [`AsyncWrapContext::after_timed`] (and so
+    /// [`AsyncWrapContext::after`]) is not executed, as for `#[async_wrap(Ctx, skip = "after")]`.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `block`: the future to wrap and execute
+    #[allow(async_fn_in_trait)]
+    async fn run_before_only(caller_context: CallerContext, block: impl AsyncBlock<T>) -> T
     where
         Self: Sized,
     {
-        let context = Self::new().await;
-        context.before(&caller_context).await;
-        let result = block.await;
-        context.after(&caller_context, &result).await;
-        result
+        run_in_context::<Self, T, I>(
+            &mut Self::build_caller_context(caller_context),
+            Hooks::BeforeOnly,
+            block,
+        )
+        .await
+    }
+
+    /// Execute a asynchronous block of code wrapped by the context, skipping the before hook
+    ///
+    /// This is equivalent to [`AsyncWrapContext::run`], but [`AsyncWrapContext::before`] is not executed,
+    /// as for `#[async_wrap(Ctx, skip = "before")]`.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `block`: the future to wrap and execute
+    #[allow(async_fn_in_trait)]
+    async fn run_after_only(caller_context: CallerContext, block: impl AsyncBlock<T>) -> T
+    where
+        Self: Sized,
+    {
+        run_in_context::<Self, T, I>(
+            &mut Self::build_caller_context(caller_context),
+            Hooks::AfterOnly,
+            block,
+        )
+        .await
+    }
+
+    /// Execute a asynchronous block of code wrapped by the context, providing an input to the context
+    ///
+    /// This is equivalent to [`AsyncWrapContext::run`], but [`AsyncWrapContext::before_with_input`]
+    /// is invoked instead of [`AsyncWrapContext::before`].
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `input`: the input provided to [`AsyncWrapContext::before_with_input`]
+    /// - `block`: the future to wrap and execute
+    #[allow(async_fn_in_trait)]
+    async fn run_with_input(caller_context: CallerContext, input: I, block: impl AsyncBlock<T>) -> T
+    where
+        Self: Sized,
+    {
+        run_in_context::<Self, T, I>(
+            &mut Self::build_caller_context(caller_context),
+            Hooks::All(Some(&input)),
+            block,
+        )
+        .await
+    }
+
+    /// Execute an asynchronous block of code wrapped by the context, retrying it while requested
+    ///
+    /// The future created by `block` is executed, each time wrapped by a new context, until `should_retry`
+    /// returns `false` or `max_attempts` executions are reached (at least one execution always happens).
+    /// The result of the last execution is returned.
+    ///
+    /// The hooks can access the current attempt via [`CallerContext::attempt`]. The caller context is
+    /// shared by all the attempts, so the extensions inserted during an attempt are visible to the following ones.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `max_attempts`: maximum number of executions of the future
+    /// - `should_retry`: check whether the result of an execution requires a new attempt
+    /// - `block`: the factory of the future to wrap and execute
+    ///
+    /// Usage example:
+    /// ```
+    /// # use context_manager::{AsyncWrapContext, CallerContext};
+    /// struct LogAttempt;
+    /// impl<T> AsyncWrapContext<T> for LogAttempt {
+    ///   async fn new() -> Self { Self }
+    ///   async fn before(&self, caller_context: &mut CallerContext) {
+    ///     println!("{}: attempt {} of 3", caller_context.fn_name(), caller_context.attempt());
+    ///   }
+    /// }
+    ///
+    /// # async fn foo(fetch: impl Fn() -> std::future::Ready<Result<u8, String>>) {
+    /// let result = LogAttempt::run_retrying(CallerContext::new("manual"), 3, Result::is_err, || fetch()).await;
+    /// # }
+    /// ```
+    #[allow(async_fn_in_trait)]
+    async fn run_retrying<F>(
+        caller_context: CallerContext,
+        max_attempts: u32,
+        mut should_retry: impl FnMut(&T) -> bool,
+        mut block: impl FnMut() -> F,
+    ) -> T
+    where
+        Self: Sized,
+        F: Future<Output = T>,
+    {
+        let mut caller_context = Self::build_caller_context(caller_context);
+        let mut attempt = 1;
+        loop {
+            caller_context.set_attempt(attempt);
+            let result =
+                run_in_context::<Self, T, I>(&mut caller_context, Hooks::All(None), block()).await;
+            if attempt >= max_attempts || !should_retry(&result) {
+                return result;
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Initialise the context and execute [`AsyncWrapContext::before`], returning a guard
+    /// that executes [`AsyncWrapContext::after_timed`] once [`AsyncContextGuard::finish`] is awaited
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    ///
+    /// Usage example:
+    /// ```
+    /// # use context_manager::{AsyncWrapContext, CallerContext};
+    /// struct PrintDuration;
+    /// impl<T> AsyncWrapContext<T> for PrintDuration {
+    ///   async fn new() -> Self { Self }
+    /// }
+    ///
+    /// # async fn foo() {
+    /// let guard = PrintDuration::guard(CallerContext::new("manual")).await;
+    /// guard.finish(&"async").await;
+    /// # }
+    /// ```
+    #[allow(async_fn_in_trait)]
+    #[must_use = "dropping the guard without awaiting `finish` skips the `after` hook"]
+    async fn guard(caller_context: CallerContext) -> AsyncContextGuard<Self, T, I>
+    where
+        Self: Sized,
+    {
+        AsyncContextGuard::enter(caller_context).await
+    }
+}
+
+/// Hooks executed by [`run_in_context`]
+enum Hooks<'a, I> {
+    /// All the hooks, invoking [`AsyncWrapContext::before_with_input`] if the input is provided
+    All(Option<&'a I>),
+    /// All the hooks but the after ones
+    BeforeOnly,
+    /// All the hooks but the before one
+    AfterOnly,
+}
+
+/// Implementation of [`AsyncWrapContext::run`], preserving the caller context
+///
+/// [`AsyncWrapContext::before_with_input`] is invoked if the input is provided, [`AsyncWrapContext::before`] otherwise.
+async fn run_in_context<C, T, I>(
+    caller_context: &mut CallerContext,
+    hooks: Hooks<'_, I>,
+    block: impl AsyncBlock<T>,
+) -> T
+where
+    C: AsyncWrapContext<T, I>,
+{
+    let context = C::new().await;
+    caller_context.map_fn_name(|fn_name| context.map_fn_name(fn_name));
+    caller_context.on_first_call(|caller_context| context.on_first_call(caller_context));
+    if C::HAS_BEFORE {
+        match hooks {
+            Hooks::All(Some(input)) => context.before_with_input(caller_context, input).await,
+            Hooks::All(None) | Hooks::BeforeOnly => context.before(caller_context).await,
+            Hooks::AfterOnly => {}
+        }
+    }
+    let after = !matches!(hooks, Hooks::BeforeOnly);
+    if let ControlFlow::Break(result) = context.short_circuit(caller_context) {
+        context.finally(caller_context);
+        if C::HAS_AFTER && after {
+            context
+                .after_timed(caller_context, &result, Duration::ZERO)
+                .await;
+        }
+        return result;
+    }
+    let start = Stopwatch::start_async::<C, T, I>();
+    let cancel_guard = CancelGuard::<C, T, I>::new(context, caller_context);
+    let result =
+        in_call_stack_async(Frame::new::<C>(caller_context), block.call(caller_context)).await;
+    let context = cancel_guard.disarm();
+    let elapsed = start.elapsed();
+    if elapsed > context.slow_threshold() {
+        context.on_slow(caller_context, elapsed).await;
+    }
+    if C::HAS_AFTER && after {
+        context.after_timed(caller_context, &result, elapsed).await;
+    }
+    result
+}
+
+/// Guard executing [`AsyncWrapContext::on_cancel`] if dropped while armed, and [`AsyncWrapContext::finally`] in any case
+struct CancelGuard<'a, C, T, I>
+where
+    C: AsyncWrapContext<T, I>,
+{
+    context: Option<C>,
+    caller_context: &'a CallerContext,
+    hook: PhantomData<fn(&T, &I)>,
+}
+
+impl<'a, C, T, I> CancelGuard<'a, C, T, I>
+where
+    C: AsyncWrapContext<T, I>,
+{
+    const fn new(context: C, caller_context: &'a CallerContext) -> Self {
+        Self {
+            context: Some(context),
+            caller_context,
+            hook: PhantomData,
+        }
+    }
+
+    /// Disarm the guard, as the body completed, executing [`AsyncWrapContext::finally`] and returning the context
+    fn disarm(mut self) -> C {
+        let context = self
+            .context
+            .take()
+            .expect("the context is taken only once, while disarming the guard");
+        context.finally(self.caller_context);
+        context
+    }
+}
+
+impl<C, T, I> Drop for CancelGuard<'_, C, T, I>
+where
+    C: AsyncWrapContext<T, I>,
+{
+    fn drop(&mut self) {
+        if let Some(context) = self.context.take() {
+            if platform::panicking() {
+                context.on_poisoned(self.caller_context);
+            } else {
+                context.on_cancel(self.caller_context);
+            }
+            context.finally(self.caller_context);
+        }
     }
 }
 
@@ -121,7 +569,7 @@ mod tests {
                 Self
             }
 
-            async fn before(&self, _: &CallerContext) {
+            async fn before(&self, _: &mut CallerContext) {
                 // Reset the value to 0
                 VALUE.store(0, Ordering::Relaxed);
                 // Which will be verified in the function execution
@@ -144,4 +592,191 @@ mod tests {
         // The return value is doubled in the after hook
         assert_eq!(VALUE.load(Ordering::Relaxed), 84);
     }
+
+    #[tokio::test]
+    async fn attempts_are_visible_to_the_hooks() {
+        static ATTEMPTS: std::sync::Mutex<Vec<u32>> = std::sync::Mutex::new(Vec::new());
+
+        struct Async;
+        impl AsyncWrapContext<Result<u32, u32>> for Async {
+            async fn new() -> Self {
+                Self
+            }
+
+            async fn before(&self, caller_context: &mut CallerContext) {
+                ATTEMPTS.lock().unwrap().push(caller_context.attempt());
+            }
+        }
+
+        let calls = AtomicUsize::new(0);
+        let result = Async::run_retrying(CallerContext::new("test"), 5, Result::is_err, || async {
+            match calls.fetch_add(1, Ordering::Relaxed) {
+                0 | 1 => Err(1),
+                _ => Ok(2),
+            }
+        })
+        .await;
+
+        assert_eq!(result, Ok(2));
+        assert_eq!(*ATTEMPTS.lock().unwrap(), [1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn build_caller_context_enriches_all_the_hooks() {
+        static OBSERVED: AtomicUsize = AtomicUsize::new(0);
+
+        struct RequestId(usize);
+
+        struct Async;
+        impl AsyncWrapContext<()> for Async {
+            async fn new() -> Self {
+                Self
+            }
+
+            fn build_caller_context(mut default: CallerContext) -> CallerContext {
+                default.insert_extension(RequestId(42));
+                default
+            }
+
+            async fn after(self, caller_context: &CallerContext, (): &()) {
+                let request_id = caller_context.extension::<RequestId>().unwrap();
+                OBSERVED.store(request_id.0, Ordering::Relaxed);
+            }
+        }
+
+        Async::run(CallerContext::new("test"), async {}).await;
+        assert_eq!(OBSERVED.load(Ordering::Relaxed), 42);
+    }
+
+    #[tokio::test]
+    async fn before_with_input_observes_the_input() {
+        static OBSERVED: AtomicUsize = AtomicUsize::new(0);
+
+        struct Async;
+        impl AsyncWrapContext<(), usize> for Async {
+            async fn new() -> Self {
+                Self
+            }
+
+            async fn before_with_input(&self, _: &mut CallerContext, input: &usize) {
+                OBSERVED.store(*input, Ordering::Relaxed);
+            }
+        }
+
+        Async::run_with_input(CallerContext::new("test"), 42, async {}).await;
+        assert_eq!(OBSERVED.load(Ordering::Relaxed), 42);
+    }
+
+    #[cfg(feature = "std")]
+    #[tokio::test]
+    async fn on_slow_runs_only_for_slow_bodies() {
+        use crate::contexts::test::FakeClock;
+        use crate::Clock;
+        use std::time::Duration;
+
+        static ON_SLOW: AtomicUsize = AtomicUsize::new(0);
+
+        struct Async;
+        impl AsyncWrapContext<()> for Async {
+            async fn new() -> Self {
+                Self
+            }
+
+            fn now() -> std::time::Instant {
+                FakeClock::now()
+            }
+
+            fn slow_threshold(&self) -> Duration {
+                Duration::from_millis(10)
+            }
+
+            async fn on_slow(&self, _: &CallerContext, elapsed: Duration) {
+                assert_eq!(elapsed, Duration::from_millis(20));
+                ON_SLOW.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        Async::run(CallerContext::new("test"), async {
+            FakeClock::advance(Duration::from_millis(10));
+        })
+        .await;
+        assert_eq!(ON_SLOW.load(Ordering::Relaxed), 0);
+
+        Async::run(CallerContext::new("test"), async {
+            FakeClock::advance(Duration::from_millis(20));
+        })
+        .await;
+        assert_eq!(ON_SLOW.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn cancelled_body_runs_on_cancel_instead_of_after() {
+        static ON_CANCEL: AtomicUsize = AtomicUsize::new(0);
+        static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+        struct Async;
+        impl AsyncWrapContext<()> for Async {
+            async fn new() -> Self {
+                Self
+            }
+
+            fn on_cancel(&self, caller_context: &CallerContext) {
+                assert_eq!(caller_context.fn_name(), "test");
+                ON_CANCEL.fetch_add(1, Ordering::Relaxed);
+            }
+
+            async fn after(self, _: &CallerContext, (): &()) {
+                AFTER.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        tokio::select! {
+            () = Async::run(CallerContext::new("test"), std::future::pending()) => unreachable!(),
+            () = tokio::task::yield_now() => {},
+        }
+        assert_eq!(ON_CANCEL.load(Ordering::Relaxed), 1);
+        assert_eq!(AFTER.load(Ordering::Relaxed), 0);
+
+        // Completed bodies are not cancelled
+        Async::run(CallerContext::new("test"), async {}).await;
+        assert_eq!(ON_CANCEL.load(Ordering::Relaxed), 1);
+        assert_eq!(AFTER.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn run_accepts_futures_not_implementing_unpin() {
+        use core::future::Future;
+        use core::marker::PhantomPinned;
+        use core::pin::Pin;
+        use core::task::{Context, Poll};
+
+        /// Future that cannot be moved once pinned, so it cannot be awaited without pinning it
+        struct NotUnpin(PhantomPinned);
+        impl Future for NotUnpin {
+            type Output = usize;
+
+            fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<usize> {
+                Poll::Ready(42)
+            }
+        }
+
+        struct Async;
+        impl AsyncWrapContext<usize> for Async {
+            async fn new() -> Self {
+                Self
+            }
+        }
+
+        // The future is pinned by the context, without requiring `Box::pin`
+        let result = Async::run(CallerContext::new("test"), NotUnpin(PhantomPinned)).await;
+        assert_eq!(result, 42);
+        let result = Async::run(CallerContext::new("test"), async {
+            let marker = PhantomPinned;
+            tokio::task::yield_now().await;
+            let _ = &marker;
+            42
+        })
+        .await;
+        assert_eq!(result, 42);
+    }
 }
@@ -1,8 +1,68 @@
-use std::future::Future;
+use alloc::borrow::Cow;
+use core::future::Future;
+use core::marker::PhantomData;
+use core::ops::ControlFlow;
+use core::time::Duration;
 
-use crate::CallerContext;
+use crate::call_stack::{in_call_stack_async, in_call_stack_sync, is_active, Frame};
+use crate::platform::{self, Stopwatch};
 #[cfg(doc)] // Imports needed only for doc purposes
 use crate::{wrap, AsyncWrapContext};
+use crate::{AsyncBlock, CallerContext, ContextFuture, SyncBlock};
+
+/// Behaviour of a context when the wrapped function re-enters itself, while its body is still executing
+///
+/// Re-entrancy is detected on the current thread, or on the current task for async functions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reentrancy {
+    /// Re-entrant calls are wrapped as any other call
+    Allow,
+    /// Re-entrant calls skip the before/after hooks, and invoke [`SyncWrapContext::on_reentry`] instead
+    ///
+    /// ```
+    /// # use context_manager::{wrap, CallerContext, Reentrancy, SyncWrapContext};
+    /// struct Lock;
+    /// impl<T> SyncWrapContext<T> for Lock {
+    ///     const REENTRANCY: Reentrancy = Reentrancy::Detect;
+    ///
+    ///     fn new() -> Self { Self }
+    ///     fn before(&self, _: &mut CallerContext) { /* acquire the lock */ }
+    ///     fn after(self, _: &CallerContext, _: &T) { /* release the lock */ }
+    /// }
+    ///
+    /// #[wrap(Lock)]
+    /// fn factorial(n: u64) -> u64 {
+    ///     // The lock is acquired only by the outermost call
+    ///     if n == 0 { 1 } else { n * factorial(n - 1) }
+    /// }
+    /// # assert_eq!(factorial(5), 120);
+    /// ```
+    Detect,
+}
+
+/// Skip the wrapped body, returning `T::default()` instead, as the outcome of [`SyncWrapContext::short_circuit`]
+///
+/// It is convenient for the "skip if disabled" contexts wrapping functions whose result has a meaningful default.
+/// ```
+/// # use std::ops::ControlFlow;
+/// # use context_manager::{skip_with_default, wrap, CallerContext, SyncWrapContext};
+/// struct Disabled;
+/// impl<T: Default> SyncWrapContext<T> for Disabled {
+///     fn new() -> Self { Self }
+///     fn short_circuit(&self, _: &CallerContext) -> ControlFlow<T> {
+///         skip_with_default()
+///     }
+/// }
+///
+/// #[wrap(Disabled)]
+/// fn expensive() -> Vec<u8> {
+///     unreachable!("the body is skipped")
+/// }
+/// assert!(expensive().is_empty());
+/// ```
+pub fn skip_with_default<T: Default>() -> ControlFlow<T> {
+    ControlFlow::Break(T::default())
+}
 
 /// Context Manager definition (sync hooks)
 ///
@@ -48,18 +108,125 @@ use crate::{wrap, AsyncWrapContext};
 /// # }
 /// ```
 ///
-pub trait SyncWrapContext<T> {
+/// # Hooks order
+/// The `run*` functions execute the hooks in the following order, which is guaranteed:
+/// 1. [`SyncWrapContext::build_caller_context`] and [`SyncWrapContext::new`]
+/// 2. [`SyncWrapContext::on_reentry`], if the wrapped function is re-entered, after which only the body is executed
+/// 3. [`SyncWrapContext::on_first_call`], if this is the first call of the wrapped function
+/// 4. [`SyncWrapContext::before`] (or its `_with_input` and `_with_args` variants)
+/// 5. [`SyncWrapContext::short_circuit`], if it breaks the body is skipped and the following steps run without the body
+/// 6. the wrapped body
+/// 7. [`SyncWrapContext::finally`], executed also if the body panics (right after [`SyncWrapContext::on_poisoned`])
+///    or, for async functions, it is cancelled
+/// 8. [`SyncWrapContext::on_slow`], if the body took longer than [`SyncWrapContext::slow_threshold`]
+/// 9. [`SyncWrapContext::after_owned`] (or [`SyncWrapContext::after_async`] for async functions), which delegates to
+///    [`SyncWrapContext::after_timed`] and then to [`SyncWrapContext::after`] by default. It is executed for errors too, but not if the body panics or it is cancelled.
+///    [`SyncWrapContext::run_sync_with_context`] executes [`SyncWrapContext::after_ref`] instead.
+///
+/// The `contexts::test::HookRecorder` context (requires the `testing` feature) records the executed hooks,
+/// allowing to assert their order in tests.
+#[diagnostic::on_unimplemented(
+    message = "`{Self}` is not a synchronous context, as it does not implement `SyncWrapContext<{T}>`",
+    label = "not a synchronous context",
+    note = "if `{Self}` implements `AsyncWrapContext`, please consider using `#[async_wrap({Self})]` instead of `#[wrap({Self})]`"
+)]
+pub trait SyncWrapContext<T, I = ()> {
+    /// Behaviour of the context when a wrapped function re-enters itself (ie. recursion)
+    ///
+    /// Defaults to [`Reentrancy::Allow`], so every call is wrapped independently.
+    const REENTRANCY: Reentrancy = Reentrancy::Allow;
+
+    /// Whether the context implements the before hooks, so that they have to be executed
+    ///
+    /// Defaults to `true`. Contexts not overriding [`SyncWrapContext::before`] (nor [`SyncWrapContext::before_with_input`])
+    /// can set it to `false`, so that the `run*` functions skip the call of the no-op hooks altogether.
+    /// This is a micro-optimisation for very hot paths, as the empty call has a measurable cost in debug builds.
+    /// ```
+    /// # use context_manager::{CallerContext, SyncWrapContext};
+    /// struct CountCalls;
+    /// impl<T> SyncWrapContext<T> for CountCalls {
+    ///     const HAS_AFTER: bool = false;
+    ///
+    ///     fn new() -> Self { Self }
+    ///     fn before(&self, caller_context: &mut CallerContext) { /* increment the counter */ }
+    /// }
+    /// ```
+    const HAS_BEFORE: bool = true;
+
+    /// Whether the context implements the after hooks, so that they have to be executed
+    ///
+    /// Defaults to `true`. Contexts not overriding [`SyncWrapContext::after`], [`SyncWrapContext::after_timed`]
+    /// (nor [`SyncWrapContext::after_owned`], [`SyncWrapContext::after_async`] and [`SyncWrapContext::after_ref`]) can set it to `false`, as for [`SyncWrapContext::HAS_BEFORE`].
+    const HAS_AFTER: bool = true;
+
     /// Initialize the context
     fn new() -> Self
     where
         Self: Sized;
 
+    /// Build the caller context provided to the hooks, starting from the one generated for the wrapped function
+    ///
+    /// The function is invoked by all the `run*` functions before initialising the context, so it is the single
+    /// place where a context can uniformly enrich the caller context (ie. by inserting extensions) for all
+    /// the functions it wraps. By default the caller context is returned unchanged.
+    ///
+    /// Parameters:
+    /// - `default`: Context of the caller, as generated for the wrapped function
+    #[must_use]
+    fn build_caller_context(default: CallerContext) -> CallerContext
+    where
+        Self: Sized,
+    {
+        default
+    }
+
+    /// Execute the code the first time the wrapped function is called, before [`SyncWrapContext::before`]
+    ///
+    /// The hook is executed exactly once across the program for each wrapped function, making it the place
+    /// for lazy one-time setups (ie. registering a metric). The once-per-function guard is provided via
+    /// [`CallerContext::with_first_call`], so the hook is executed only if the caller context carries it.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn on_first_call(&self, caller_context: &CallerContext) {}
+
     /// Execute the code before the execution of the wrapped body
     ///
+    /// The hook can enrich the caller context (ie. via [`CallerContext::insert_extension`]),
+    /// and the modifications will be visible to [`SyncWrapContext::after`].
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn before(&self, caller_context: &mut CallerContext) {}
+
+    /// Decide whether to skip the wrapped body, returning the provided value instead of executing it
+    ///
+    /// The hook is executed right after [`SyncWrapContext::before`], and by default it continues with the body.
+    /// If it breaks with a value, the body is skipped, and [`SyncWrapContext::finally`] and [`SyncWrapContext::after_timed`]
+    /// (with a zero duration) are executed with the value as the result. Refer to [`skip_with_default`] for
+    /// functions whose result implements [`Default`].
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn short_circuit(&self, caller_context: &CallerContext) -> ControlFlow<T> {
+        ControlFlow::Continue(())
+    }
+
+    /// Execute the code before the execution of the wrapped body, it provides also the input supplied by the caller
+    ///
+    /// This is invoked only by [`SyncWrapContext::run_sync_with_input`] and [`SyncWrapContext::run_async_with_input`],
+    /// and by default it delegates to [`SyncWrapContext::before`].
+    ///
     /// Parameters:
     /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `input`: The input explicitly supplied by the caller
     #[allow(unused_variables)]
-    fn before(&self, caller_context: &CallerContext) {}
+    fn before_with_input(&self, caller_context: &mut CallerContext, input: &I) {
+        self.before(caller_context);
+    }
 
     /// Execute the code after the execution of the wrapped body, it provides also the result of the wrapped body
     ///
@@ -73,10 +240,159 @@ pub trait SyncWrapContext<T> {
     {
     }
 
+    /// Execute the code after the execution of the wrapped body, it provides also the result of the wrapped body and its duration
+    ///
+    /// By default it delegates to [`SyncWrapContext::after`], so implementers interested in the duration
+    /// of the body should override this hook instead of [`SyncWrapContext::after`].
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `result`: The result of the wrapped body
+    /// - `elapsed`: The time spent executing the wrapped body
+    #[allow(unused_variables)]
+    fn after_timed(self, caller_context: &CallerContext, result: &T, elapsed: Duration)
+    where
+        Self: Sized,
+    {
+        self.after(caller_context, result);
+    }
+
+    /// Execute the code after the execution of the wrapped body, taking ownership of its result
+    ///
+    /// This is invoked by the sync `run*` functions (ie. [`SyncWrapContext::run_sync`]) instead of
+    /// [`SyncWrapContext::after_timed`], to which it delegates by default. The returned value is the output
+    /// of the wrapped function, so the hook can move the result temporarily (ie. into a channel and back)
+    /// without requiring it to implement [`Clone`]. The async `run*` functions invoke [`SyncWrapContext::after_async`] instead.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `result`: The result of the wrapped body
+    /// - `elapsed`: The time spent executing the wrapped body
+    fn after_owned(self, caller_context: &CallerContext, result: T, elapsed: Duration) -> T
+    where
+        Self: Sized,
+    {
+        self.after_timed(caller_context, &result, elapsed);
+        result
+    }
+
+    /// Execute the code after the execution of the wrapped future, allowing the teardown to be asynchronous
+    ///
+    /// This is invoked by the async `run*` functions (ie. [`SyncWrapContext::run_async`]) instead of
+    /// [`SyncWrapContext::after_timed`], to which it delegates by default. It allows awaiting during the teardown
+    /// (ie. sending to a channel) while keeping [`SyncWrapContext::new`] and [`SyncWrapContext::before`] synchronous.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `result`: The result of the wrapped future
+    /// - `elapsed`: The time spent executing the wrapped future
+    #[allow(async_fn_in_trait, clippy::unused_async)]
+    async fn after_async(self, caller_context: &CallerContext, result: &T, elapsed: Duration)
+    where
+        Self: Sized,
+    {
+        self.after_timed(caller_context, result, elapsed);
+    }
+
+    /// Execute the code after the execution of the wrapped body, without consuming the context
+    ///
+    /// This is invoked by [`SyncWrapContext::run_sync_with_context`] instead of [`SyncWrapContext::after_timed`],
+    /// as the context is returned to the caller afterwards. By default it does nothing, so contexts supporting
+    /// the inspection of their state should implement it (ie. sharing the implementation with `after_timed`).
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `result`: The result of the wrapped body
+    /// - `elapsed`: The time spent executing the wrapped body
+    #[allow(unused_variables)]
+    fn after_ref(&mut self, caller_context: &CallerContext, result: &T, elapsed: Duration) {}
+
+    /// Name of the wrapped function, as emitted by the context (ie. in logs or in metrics)
+    ///
+    /// It allows to transform the emitted names (ie. namespacing them like `service.foo`) without changing the name
+    /// stored in the caller context, which is still provided by [`CallerContext::fn_name`]. By default the name is
+    /// returned unchanged. The name is mapped once per call, right after `new`, and it is provided to the hooks by
+    /// [`CallerContext::emitted_fn_name`], which the built-in contexts emitting names (like `contexts::LogContext` or
+    /// `contexts::StatsContext`) use. Some of them provide the mapping via their configuration, and the ones wrapping
+    /// other contexts (like `contexts::ContextChain`) forward it.
+    ///
+    /// Parameters:
+    /// - `name`: Name of the wrapped function
+    #[must_use]
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        Cow::Borrowed(name)
+    }
+
+    /// Current time, from which the duration of the wrapped body is measured (requires the `std` feature)
+    ///
+    /// The duration is the one provided to [`SyncWrapContext::after_timed`] and [`SyncWrapContext::on_slow`]. By default the time
+    /// is read via [`SystemClock`](crate::SystemClock), overriding it allows to inject a different
+    /// [`Clock`](crate::Clock) (ie. a fake one, making the durations deterministic in tests). The built-in contexts
+    /// accepting a clock use it, and the ones wrapping other contexts (like `contexts::ContextChain`) forward it.
+    #[cfg(feature = "std")]
+    #[must_use]
+    fn now() -> std::time::Instant {
+        <crate::SystemClock as crate::Clock>::now()
+    }
+
+    /// Duration above which the wrapped body is considered slow, triggering [`SyncWrapContext::on_slow`]
+    ///
+    /// By default it is [`Duration::MAX`], so [`SyncWrapContext::on_slow`] is never executed.
+    #[must_use]
+    fn slow_threshold(&self) -> Duration {
+        Duration::MAX
+    }
+
+    /// Execute the code when the wrapped body took longer than [`SyncWrapContext::slow_threshold`]
+    ///
+    /// The hook is executed right before [`SyncWrapContext::after_timed`], and it is meant for latency alerting.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `elapsed`: The time spent executing the wrapped body
+    #[allow(unused_variables)]
+    fn on_slow(&self, caller_context: &CallerContext, elapsed: Duration) {}
+
+    /// Execute the code while unwinding, when the wrapped body panics, right before [`SyncWrapContext::finally`]
+    ///
+    /// The panic may leave the state held by the context (ie. shared counters or the data behind a lock)
+    /// half-updated, so contexts holding state should mark it as poisoned here. The following calls can
+    /// then observe the poisoned state, ie. in [`SyncWrapContext::before`], as [`std::sync::Mutex`] does.
+    /// Refer to `contexts::PoisonableContext` for a reference implementation.
+    ///
+    /// Panics can be observed only with the `std` feature, so without it the hook is never executed.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn on_poisoned(&self, caller_context: &CallerContext) {}
+
+    /// Execute the code once the wrapped body terminates, whether it completes or panics
+    ///
+    /// The hook is executed right after the body, so before [`SyncWrapContext::after_timed`] if the body completes.
+    /// It is executed also while unwinding, or if the future of [`SyncWrapContext::run_async`] is dropped
+    /// before completion, making it the place for cleanups that must always happen (ie. flushing buffers).
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn finally(&self, caller_context: &CallerContext) {}
+
+    /// Execute the code instead of the before/after hooks when the wrapped function is re-entered
+    ///
+    /// This is invoked only if [`SyncWrapContext::REENTRANCY`] is [`Reentrancy::Detect`].
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    #[allow(unused_variables)]
+    fn on_reentry(&self, caller_context: &CallerContext) {}
+
     /// Execute a synchronous block of code wrapped by the context
     ///
     /// This will lead to context initialisation and execution of before/after hooks
     ///
+    /// The duration of the body is measured and provided to [`SyncWrapContext::after_owned`].
+    ///
     /// Parameters:
     /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
     /// - `block`: the callable to wrap and execute
@@ -95,21 +411,151 @@ pub trait SyncWrapContext<T> {
     /// });
     /// # }
     /// ```
-    fn run_sync(caller_context: CallerContext, block: impl FnOnce() -> T) -> T
+    fn run_sync(caller_context: CallerContext, block: impl SyncBlock<T>) -> T
+    where
+        Self: Sized,
+    {
+        run_sync_with_hook(
+            &mut Self::build_caller_context(caller_context),
+            Self::before,
+            block,
+        )
+    }
+
+    /// Execute a synchronous block of code wrapped by the context, skipping the after hooks
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_sync`], but [`SyncWrapContext::after_owned`] (and so
+    /// [`SyncWrapContext::after_timed`] and [`SyncWrapContext::after`]) is not executed, as for `#[wrap(Ctx, skip = "after")]`.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `block`: the callable to wrap and execute
+    fn run_sync_before_only(caller_context: CallerContext, block: impl SyncBlock<T>) -> T
     where
         Self: Sized,
     {
-        let context = Self::new();
-        context.before(&caller_context);
-        let result = block();
-        context.after(&caller_context, &result);
+        let (result, ()) = run_sync_with_hooks(
+            &mut Self::build_caller_context(caller_context),
+            Self::before,
+            block,
+            |_: Self, _, result, _| (result, ()),
+        );
         result
     }
 
+    /// Execute a synchronous block of code wrapped by the context, skipping the before hook
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_sync`], but [`SyncWrapContext::before`] is not executed,
+    /// as for `#[wrap(Ctx, skip = "before")]`.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `block`: the callable to wrap and execute
+    fn run_sync_after_only(caller_context: CallerContext, block: impl SyncBlock<T>) -> T
+    where
+        Self: Sized,
+    {
+        run_sync_with_hook(
+            &mut Self::build_caller_context(caller_context),
+            |_: &Self, _| {},
+            block,
+        )
+    }
+
+    /// Execute a synchronous block of code wrapped by the context, providing an input to the context
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_sync`], but [`SyncWrapContext::before_with_input`]
+    /// is invoked instead of [`SyncWrapContext::before`]. This allows contexts to depend on values
+    /// explicitly supplied by the caller (ie. some of the arguments of the wrapped function).
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `input`: the input provided to [`SyncWrapContext::before_with_input`]
+    /// - `block`: the callable to wrap and execute
+    ///
+    /// Usage example:
+    /// ```
+    /// # use context_manager::{CallerContext, SyncWrapContext};
+    /// struct User { id: u64 }
+    ///
+    /// struct PrintUser;
+    /// impl<T> SyncWrapContext<T, User> for PrintUser {
+    ///   fn new() -> Self { Self }
+    ///   fn before_with_input(&self, caller_context: &mut CallerContext, user: &User) {
+    ///     println!("{} invoked by user {}", caller_context.fn_name(), user.id);
+    ///   }
+    /// }
+    ///
+    /// let output: &'static str = PrintUser::run_sync_with_input(CallerContext::new("manual"), User { id: 1 }, || {
+    ///     "sync"
+    /// });
+    /// ```
+    fn run_sync_with_input(caller_context: CallerContext, input: I, block: impl SyncBlock<T>) -> T
+    where
+        Self: Sized,
+    {
+        run_sync_with_hook(
+            &mut Self::build_caller_context(caller_context),
+            |context: &Self, caller_context| context.before_with_input(caller_context, &input),
+            block,
+        )
+    }
+
+    /// Execute a synchronous block of code wrapped by the context, returning the context as well
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_sync`], but [`SyncWrapContext::after_ref`] is invoked
+    /// instead of [`SyncWrapContext::after_timed`], so that the context can be returned to the caller
+    /// to inspect the state accumulated during the call (ie. in tests).
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `block`: the callable to wrap and execute
+    ///
+    /// Usage example:
+    /// ```
+    /// # use context_manager::{CallerContext, SyncWrapContext};
+    /// # use std::time::Duration;
+    /// struct RecordResult(Option<String>);
+    /// impl<T: std::fmt::Debug> SyncWrapContext<T> for RecordResult {
+    ///   fn new() -> Self { Self(None) }
+    ///   fn after_ref(&mut self, _: &CallerContext, result: &T, _: Duration) {
+    ///     self.0 = Some(format!("{result:?}"));
+    ///   }
+    /// }
+    ///
+    /// let (output, context) = RecordResult::run_sync_with_context(CallerContext::new("manual"), || 42);
+    /// assert_eq!(output, 42);
+    /// assert_eq!(context.0.as_deref(), Some("42"));
+    /// ```
+    fn run_sync_with_context(caller_context: CallerContext, block: impl SyncBlock<T>) -> (T, Self)
+    where
+        Self: Sized,
+    {
+        run_sync_with_hooks(
+            &mut Self::build_caller_context(caller_context),
+            Self::before,
+            block,
+            |mut context: Self, caller_context, result, elapsed| {
+                if let (true, Some(elapsed)) = (Self::HAS_AFTER, elapsed) {
+                    context.after_ref(caller_context, &result, elapsed);
+                }
+                (result, context)
+            },
+        )
+    }
+
     /// Execute a asynchronous block of code wrapped by the context
     ///
     /// This will lead to context initialisation and execution of before/after hooks
     ///
+    /// The duration of the body is measured and provided to [`SyncWrapContext::after_async`].
+    ///
+    /// The block is pinned in place, so it is not required to be [`Unpin`] and it is not boxed
+    /// (the wrapping does not allocate, other than the hooks of the context).
+    ///
+    /// As for any async function nothing happens until the returned future is polled, so the context
+    /// is not initialised if the future is never awaited. Refer to [`SyncWrapContext::run_async_eager`]
+    /// to initialise the context immediately.
     ///
     /// Parameters:
     /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
@@ -130,70 +576,501 @@ pub trait SyncWrapContext<T> {
     /// # }
     /// ```
     #[allow(async_fn_in_trait)]
-    async fn run_async(caller_context: CallerContext, block: impl Future<Output = T>) -> T
+    async fn run_async(caller_context: CallerContext, block: impl AsyncBlock<T>) -> T
     where
         Self: Sized,
     {
-        let context = Self::new();
-        context.before(&caller_context);
-        let result = block.await;
-        context.after(&caller_context, &result);
-        result
+        run_async_with_hook(
+            &mut Self::build_caller_context(caller_context),
+            Self::before,
+            block,
+        )
+        .await
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use crate::CallerContext;
-
-    use super::SyncWrapContext;
-    use std::sync::atomic::AtomicUsize;
-    use std::sync::atomic::Ordering;
-
-    #[test]
-    fn wrapper_usage_on_sync_function() {
-        static VALUE: AtomicUsize = AtomicUsize::new(100);
-
-        struct Sync;
-        impl SyncWrapContext<usize> for Sync {
-            fn new() -> Self {
-                Self
-            }
-
-            fn before(&self, _: &CallerContext) {
-                // Reset the value to 0
-                VALUE.store(0, Ordering::Relaxed);
-                // Which will be verified in the function execution
-            }
-
-            fn after(self, _: &CallerContext, result: &usize) {
-                VALUE.store(2 * (*result), Ordering::Relaxed);
-            }
-        }
-
-        assert_eq!(
-            Sync::run_sync(CallerContext::new("test"), || {
-                assert_eq!(VALUE.load(Ordering::Relaxed), 0);
-                42
-            },),
-            42,
-        );
 
-        // The return value is doubled in the after hook
-        assert_eq!(VALUE.load(Ordering::Relaxed), 84);
+    /// Execute an asynchronous block of code wrapped by the context, skipping the after hooks
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_async`], but [`SyncWrapContext::after_async`] (and so
+    /// [`SyncWrapContext::after_timed`] and [`SyncWrapContext::after`]) is not executed, as for `#[wrap(Ctx, skip = "after")]`.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `block`: the future to wrap and execute
+    #[allow(async_fn_in_trait)]
+    async fn run_async_before_only(caller_context: CallerContext, block: impl AsyncBlock<T>) -> T
+    where
+        Self: Sized,
+    {
+        let caller_context = &mut Self::build_caller_context(caller_context);
+        let (frame, context) = enter_async::<Self, T, I>(caller_context, Self::before);
+        complete_async(caller_context, frame, context, block, false).await
     }
 
-    #[tokio::test]
-    async fn wrapper_usage_on_async_function() {
-        static VALUE: AtomicUsize = AtomicUsize::new(100);
+    /// Execute an asynchronous block of code wrapped by the context, skipping the before hook
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_async`], but [`SyncWrapContext::before`] is not executed,
+    /// as for `#[wrap(Ctx, skip = "before")]`.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `block`: the future to wrap and execute
+    #[allow(async_fn_in_trait)]
+    async fn run_async_after_only(caller_context: CallerContext, block: impl AsyncBlock<T>) -> T
+    where
+        Self: Sized,
+    {
+        run_async_with_hook(
+            &mut Self::build_caller_context(caller_context),
+            |_: &Self, _| {},
+            block,
+        )
+        .await
+    }
 
-        struct Sync;
-        impl SyncWrapContext<usize> for Sync {
+    /// Execute a asynchronous block of code wrapped by the context, initialising it eagerly
+    ///
+    /// Differently from [`SyncWrapContext::run_async`], where nothing happens until the returned future is polled,
+    /// the context initialisation and [`SyncWrapContext::before`] are executed immediately, while the body
+    /// and the after hooks are deferred to the await of the returned future. This is opt-in, as it changes
+    /// when the side effects of the context occur (ie. a lock is acquired when the future is created).
+    ///
+    /// If the returned future is dropped before completing, even if never polled, [`SyncWrapContext::finally`] is executed.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `block`: the future to wrap and execute
+    ///
+    /// Usage example:
+    /// ```
+    /// # use context_manager::{CallerContext, SyncWrapContext};
+    /// struct PrintDuration;
+    /// impl<T> SyncWrapContext<T> for PrintDuration {
+    ///   fn new() -> Self { Self }
+    /// }
+    ///
+    /// # async fn foo() {
+    /// // `PrintDuration::before` is already executed here
+    /// let future = PrintDuration::run_async_eager(CallerContext::new("manual"), async { "async" });
+    /// assert_eq!(future.await, "async");
+    /// # }
+    /// ```
+    fn run_async_eager(
+        caller_context: CallerContext,
+        block: impl AsyncBlock<T>,
+    ) -> impl Future<Output = T>
+    where
+        Self: Sized,
+    {
+        let mut caller_context = Self::build_caller_context(caller_context);
+        let (frame, context) = enter_async::<Self, T, I>(&mut caller_context, Self::before);
+        let mut eager_context = EagerContext::<Self, T, I> {
+            context,
+            caller_context,
+            hook: PhantomData,
+        };
+        async move {
+            let context = eager_context.context.take();
+            complete_async(&eager_context.caller_context, frame, context, block, true).await
+        }
+    }
+
+    /// Execute a asynchronous block of code wrapped by the context, returning a future of a nameable type
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_async`], but the returned future is a [`ContextFuture`],
+    /// which can be stored (ie. in a struct) and, for bodies implementing [`Unpin`], polled without being pinned first.
+    /// The completion of the body is handled via [`SyncWrapContext::after_timed`] (refer to [`ContextFuture`]).
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `block`: the future to wrap and execute
+    fn run_future<F>(caller_context: CallerContext, block: F) -> ContextFuture<Self, F, I>
+    where
+        Self: Sized,
+        F: Future<Output = T>,
+    {
+        ContextFuture::new(Self::build_caller_context(caller_context), block)
+    }
+
+    /// Execute a asynchronous block of code wrapped by the context, providing an input to the context
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_async`], but [`SyncWrapContext::before_with_input`]
+    /// is invoked instead of [`SyncWrapContext::before`].
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `input`: the input provided to [`SyncWrapContext::before_with_input`]
+    /// - `block`: the future to wrap and execute
+    #[allow(async_fn_in_trait)]
+    async fn run_async_with_input(
+        caller_context: CallerContext,
+        input: I,
+        block: impl AsyncBlock<T>,
+    ) -> T
+    where
+        Self: Sized,
+    {
+        run_async_with_hook(
+            &mut Self::build_caller_context(caller_context),
+            |context: &Self, caller_context| context.before_with_input(caller_context, &input),
+            block,
+        )
+        .await
+    }
+
+    /// Execute a synchronous block of code wrapped by the context, retrying it while requested
+    ///
+    /// The block is executed, each time wrapped by a new context, until `should_retry` returns `false`
+    /// or `max_attempts` executions are reached (at least one execution always happens).
+    /// The result of the last execution is returned.
+    ///
+    /// The hooks can access the current attempt via [`CallerContext::attempt`]. The caller context is
+    /// shared by all the attempts, so the extensions inserted during an attempt are visible to the following ones.
+    ///
+    /// NOTE: Each attempt executes the hooks as the default implementation of [`SyncWrapContext::run_sync`],
+    /// so customisations of [`SyncWrapContext::run_sync`] are not applied.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `max_attempts`: maximum number of executions of the block
+    /// - `should_retry`: check whether the result of an execution requires a new attempt
+    /// - `block`: the callable to wrap and execute
+    ///
+    /// Usage example:
+    /// ```
+    /// # use context_manager::{CallerContext, SyncWrapContext};
+    /// struct LogAttempt;
+    /// impl<T> SyncWrapContext<T> for LogAttempt {
+    ///   fn new() -> Self { Self }
+    ///   fn before(&self, caller_context: &mut CallerContext) {
+    ///     println!("{}: attempt {} of 3", caller_context.fn_name(), caller_context.attempt());
+    ///   }
+    /// }
+    ///
+    /// let mut failures = 2;
+    /// let result = LogAttempt::run_sync_retrying(CallerContext::new("manual"), 3, Result::is_err, || {
+    ///     if failures > 0 {
+    ///         failures -= 1;
+    ///         Err("transient error")
+    ///     } else {
+    ///         Ok("sync")
+    ///     }
+    /// });
+    /// assert_eq!(result, Ok("sync"));
+    /// ```
+    fn run_sync_retrying(
+        caller_context: CallerContext,
+        max_attempts: u32,
+        mut should_retry: impl FnMut(&T) -> bool,
+        mut block: impl FnMut() -> T,
+    ) -> T
+    where
+        Self: Sized,
+    {
+        let mut caller_context = Self::build_caller_context(caller_context);
+        let mut attempt = 1;
+        loop {
+            caller_context.set_attempt(attempt);
+            let result = run_sync_with_hook(&mut caller_context, Self::before, &mut block);
+            if attempt >= max_attempts || !should_retry(&result) {
+                return result;
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Execute an asynchronous block of code wrapped by the context, retrying it while requested
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_sync_retrying`], with `block` creating the future
+    /// to execute for each attempt.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `max_attempts`: maximum number of executions of the future
+    /// - `should_retry`: check whether the result of an execution requires a new attempt
+    /// - `block`: the factory of the future to wrap and execute
+    #[allow(async_fn_in_trait)]
+    async fn run_async_retrying<F>(
+        caller_context: CallerContext,
+        max_attempts: u32,
+        mut should_retry: impl FnMut(&T) -> bool,
+        mut block: impl FnMut() -> F,
+    ) -> T
+    where
+        Self: Sized,
+        F: Future<Output = T>,
+    {
+        let mut caller_context = Self::build_caller_context(caller_context);
+        let mut attempt = 1;
+        loop {
+            caller_context.set_attempt(attempt);
+            let result = run_async_with_hook(&mut caller_context, Self::before, block()).await;
+            if attempt >= max_attempts || !should_retry(&result) {
+                return result;
+            }
+            attempt += 1;
+        }
+    }
+}
+
+/// Implementation of [`SyncWrapContext::run_sync`], with a customisable before hook
+pub(crate) fn run_sync_with_hook<C, T, I>(
+    caller_context: &mut CallerContext,
+    before: impl FnOnce(&C, &mut CallerContext),
+    block: impl SyncBlock<T>,
+) -> T
+where
+    C: SyncWrapContext<T, I>,
+{
+    let (result, ()) = run_sync_with_hooks(
+        caller_context,
+        before,
+        block,
+        |context: C, caller_context, result, elapsed| match (C::HAS_AFTER, elapsed) {
+            (true, Some(elapsed)) => (context.after_owned(caller_context, result, elapsed), ()),
+            _ => (result, ()),
+        },
+    );
+    result
+}
+
+/// Implementation of [`SyncWrapContext::run_sync`], with customisable before and after hooks
+///
+/// The after hook receives the result and the duration of the body, unless the hooks are skipped
+/// because the wrapped function is re-entered, and it returns the result alongside its output.
+fn run_sync_with_hooks<C, T, I, R>(
+    caller_context: &mut CallerContext,
+    before: impl FnOnce(&C, &mut CallerContext),
+    block: impl SyncBlock<T>,
+    after: impl FnOnce(C, &CallerContext, T, Option<Duration>) -> (T, R),
+) -> (T, R)
+where
+    C: SyncWrapContext<T, I>,
+{
+    let context = C::new();
+    caller_context.map_fn_name(|fn_name| context.map_fn_name(fn_name));
+    let frame = Frame::new::<C>(caller_context);
+    if matches!(C::REENTRANCY, Reentrancy::Detect) && is_active(&frame) {
+        context.on_reentry(caller_context);
+        let result = in_call_stack_sync(frame, || block.call(caller_context));
+        return after(context, caller_context, result, None);
+    }
+    caller_context.on_first_call(|caller_context| context.on_first_call(caller_context));
+    if C::HAS_BEFORE {
+        before(&context, caller_context);
+    }
+    if let ControlFlow::Break(result) = context.short_circuit(caller_context) {
+        context.finally(caller_context);
+        return after(context, caller_context, result, Some(Duration::ZERO));
+    }
+    let start = Stopwatch::start_sync::<C, T, I>();
+    let finally_guard = FinallyGuard::<C, T, I>::new(context, caller_context);
+    let result = in_call_stack_sync(frame, || block.call(caller_context));
+    let context = finally_guard.finish();
+    let elapsed = start.elapsed();
+    if elapsed > context.slow_threshold() {
+        context.on_slow(caller_context, elapsed);
+    }
+    after(context, caller_context, result, Some(elapsed))
+}
+
+/// Implementation of [`SyncWrapContext::run_async`], with a customisable before hook
+pub(crate) async fn run_async_with_hook<C, T, I>(
+    caller_context: &mut CallerContext,
+    before: impl FnOnce(&C, &mut CallerContext),
+    block: impl AsyncBlock<T>,
+) -> T
+where
+    C: SyncWrapContext<T, I>,
+{
+    let (frame, context) = enter_async::<C, T, I>(caller_context, before);
+    complete_async(caller_context, frame, context, block, true).await
+}
+
+/// Synchronous part of [`SyncWrapContext::run_async`]: context initialisation and before hook
+///
+/// The returned context is `None` if the wrapped function is re-entered, as the hooks have to be skipped.
+pub(crate) fn enter_async<C, T, I>(
+    caller_context: &mut CallerContext,
+    before: impl FnOnce(&C, &mut CallerContext),
+) -> (Frame, Option<C>)
+where
+    C: SyncWrapContext<T, I>,
+{
+    let context = C::new();
+    caller_context.map_fn_name(|fn_name| context.map_fn_name(fn_name));
+    let frame = Frame::new::<C>(caller_context);
+    if matches!(C::REENTRANCY, Reentrancy::Detect) && is_active(&frame) {
+        context.on_reentry(caller_context);
+        return (frame, None);
+    }
+    caller_context.on_first_call(|caller_context| context.on_first_call(caller_context));
+    if C::HAS_BEFORE {
+        before(&context, caller_context);
+    }
+    (frame, Some(context))
+}
+
+/// Asynchronous part of [`SyncWrapContext::run_async`]: execution of the body and of the after hooks (if `after`)
+async fn complete_async<C, T, I>(
+    caller_context: &CallerContext,
+    frame: Frame,
+    context: Option<C>,
+    block: impl AsyncBlock<T>,
+    after: bool,
+) -> T
+where
+    C: SyncWrapContext<T, I>,
+{
+    let Some(context) = context else {
+        return in_call_stack_async(frame, block.call(caller_context)).await;
+    };
+    if let ControlFlow::Break(result) = context.short_circuit(caller_context) {
+        context.finally(caller_context);
+        if C::HAS_AFTER && after {
+            context
+                .after_async(caller_context, &result, Duration::ZERO)
+                .await;
+        }
+        return result;
+    }
+    let start = Stopwatch::start_sync::<C, T, I>();
+    let finally_guard = FinallyGuard::<C, T, I>::new(context, caller_context);
+    let result = in_call_stack_async(frame, block.call(caller_context)).await;
+    let context = finally_guard.finish();
+    let elapsed = start.elapsed();
+    if elapsed > context.slow_threshold() {
+        context.on_slow(caller_context, elapsed);
+    }
+    if C::HAS_AFTER && after {
+        context.after_async(caller_context, &result, elapsed).await;
+    }
+    result
+}
+
+/// Context initialised by [`SyncWrapContext::run_async_eager`], executing [`SyncWrapContext::finally`]
+/// if dropped before the returned future is polled
+struct EagerContext<C, T, I>
+where
+    C: SyncWrapContext<T, I>,
+{
+    context: Option<C>,
+    caller_context: CallerContext,
+    hook: PhantomData<fn(&T, &I)>,
+}
+
+impl<C, T, I> Drop for EagerContext<C, T, I>
+where
+    C: SyncWrapContext<T, I>,
+{
+    fn drop(&mut self) {
+        if let Some(context) = self.context.take() {
+            context.finally(&self.caller_context);
+        }
+    }
+}
+
+/// Guard executing [`SyncWrapContext::finally`] when the body terminates, even if it panics or it is cancelled
+struct FinallyGuard<'a, C, T, I>
+where
+    C: SyncWrapContext<T, I>,
+{
+    context: Option<C>,
+    caller_context: &'a CallerContext,
+    hook: PhantomData<fn(&T, &I)>,
+}
+
+impl<'a, C, T, I> FinallyGuard<'a, C, T, I>
+where
+    C: SyncWrapContext<T, I>,
+{
+    const fn new(context: C, caller_context: &'a CallerContext) -> Self {
+        Self {
+            context: Some(context),
+            caller_context,
+            hook: PhantomData,
+        }
+    }
+
+    /// Execute [`SyncWrapContext::finally`], as the body completed, returning the context
+    fn finish(mut self) -> C {
+        let context = self
+            .context
+            .take()
+            .expect("the context is taken only once, while finishing the guard");
+        context.finally(self.caller_context);
+        context
+    }
+}
+
+impl<C, T, I> Drop for FinallyGuard<'_, C, T, I>
+where
+    C: SyncWrapContext<T, I>,
+{
+    fn drop(&mut self) {
+        if let Some(context) = self.context.take() {
+            if platform::panicking() {
+                context.on_poisoned(self.caller_context);
+            }
+            context.finally(self.caller_context);
+        }
+    }
+}
+
+// The tests rely on the call stack and on the timing, which require the `std` feature
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::contexts::test::FakeClock;
+    use crate::{current_fn_name, CallerContext, Clock};
+
+    use super::{Reentrancy, SyncWrapContext};
+    use std::sync::atomic::AtomicUsize;
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    #[test]
+    fn wrapper_usage_on_sync_function() {
+        static VALUE: AtomicUsize = AtomicUsize::new(100);
+
+        struct Sync;
+        impl SyncWrapContext<usize> for Sync {
             fn new() -> Self {
                 Self
             }
 
-            fn before(&self, _: &CallerContext) {
+            fn before(&self, _: &mut CallerContext) {
+                // Reset the value to 0
+                VALUE.store(0, Ordering::Relaxed);
+                // Which will be verified in the function execution
+            }
+
+            fn after(self, _: &CallerContext, result: &usize) {
+                VALUE.store(2 * (*result), Ordering::Relaxed);
+            }
+        }
+
+        assert_eq!(
+            Sync::run_sync(CallerContext::new("test"), || {
+                assert_eq!(VALUE.load(Ordering::Relaxed), 0);
+                42
+            },),
+            42,
+        );
+
+        // The return value is doubled in the after hook
+        assert_eq!(VALUE.load(Ordering::Relaxed), 84);
+    }
+
+    #[tokio::test]
+    async fn wrapper_usage_on_async_function() {
+        static VALUE: AtomicUsize = AtomicUsize::new(100);
+
+        struct Sync;
+        impl SyncWrapContext<usize> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
                 // Reset the value to 0
                 VALUE.store(0, Ordering::Relaxed);
                 // Which will be verified in the function execution
@@ -216,4 +1093,559 @@ mod tests {
         // The return value is doubled in the after hook
         assert_eq!(VALUE.load(Ordering::Relaxed), 84);
     }
+
+    #[test]
+    fn current_fn_name_inside_the_wrapped_body() {
+        struct Sync;
+        impl<T> SyncWrapContext<T> for Sync {
+            fn new() -> Self {
+                Self
+            }
+        }
+
+        assert_eq!(
+            Sync::run_sync(CallerContext::new("test"), current_fn_name),
+            Some("test")
+        );
+        assert_eq!(current_fn_name(), None);
+    }
+
+    #[test]
+    fn reentrancy_detection_on_recursive_function() {
+        static BEFORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+        static REENTRY_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Lock;
+        impl<T> SyncWrapContext<T> for Lock {
+            const REENTRANCY: Reentrancy = Reentrancy::Detect;
+
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
+                BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn on_reentry(&self, _: &CallerContext) {
+                REENTRY_CALLS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        fn countdown(n: usize) -> usize {
+            Lock::run_sync(CallerContext::new("countdown"), || {
+                if n == 0 {
+                    0
+                } else {
+                    1 + countdown(n - 1)
+                }
+            })
+        }
+
+        assert_eq!(countdown(3), 3);
+        assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 1);
+        assert_eq!(REENTRY_CALLS.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn before_enriches_the_caller_context() {
+        struct RequestId(u64);
+
+        struct Sync;
+        impl SyncWrapContext<usize> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, caller_context: &mut CallerContext) {
+                caller_context.insert_extension(RequestId(42));
+            }
+
+            fn after(self, caller_context: &CallerContext, result: &usize) {
+                assert_eq!(caller_context.extension::<RequestId>().unwrap().0, 42);
+                assert_eq!(*result, 1);
+            }
+        }
+
+        assert_eq!(Sync::run_sync(CallerContext::new("test"), || 1), 1);
+    }
+
+    #[test]
+    fn after_timed_receives_the_body_duration() {
+        struct Sync;
+        impl SyncWrapContext<()> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn now() -> std::time::Instant {
+                FakeClock::now()
+            }
+
+            fn after_timed(self, _: &CallerContext, (): &(), elapsed: Duration) {
+                assert_eq!(elapsed, Duration::from_millis(10));
+            }
+        }
+
+        Sync::run_sync(CallerContext::new("test"), || {
+            FakeClock::advance(Duration::from_millis(10));
+        });
+    }
+
+    #[test]
+    fn after_owned_moves_the_result() {
+        use std::sync::{mpsc, Mutex};
+
+        static SUMMARIES: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+        /// Result not implementing `Clone`, moved into the channel and back
+        #[derive(Debug, PartialEq, Eq)]
+        struct Report(Vec<u8>);
+
+        struct Forward;
+        impl SyncWrapContext<Report> for Forward {
+            fn new() -> Self {
+                Self
+            }
+
+            fn after_owned(self, _: &CallerContext, result: Report, _: Duration) -> Report {
+                let (sender, receiver) = mpsc::channel();
+                sender.send(result).unwrap();
+                let result = receiver.recv().unwrap();
+                SUMMARIES.lock().unwrap().push(result.0.len());
+                result
+            }
+        }
+
+        let report = Forward::run_sync(CallerContext::new("test"), || Report(vec![1, 2, 3]));
+        assert_eq!(report, Report(vec![1, 2, 3]));
+        assert_eq!(*SUMMARIES.lock().unwrap(), [3]);
+    }
+
+    #[test]
+    fn after_owned_delegates_to_after_timed() {
+        static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+        struct Sync;
+        impl SyncWrapContext<usize> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn after(self, _: &CallerContext, result: &usize) {
+                AFTER.store(*result, Ordering::Relaxed);
+            }
+        }
+
+        let result = Sync::new().after_owned(&CallerContext::new("test"), 42, Duration::ZERO);
+        assert_eq!(result, 42);
+        assert_eq!(AFTER.load(Ordering::Relaxed), 42);
+    }
+
+    #[tokio::test]
+    async fn run_async_eager_runs_before_ahead_of_the_first_poll() {
+        static BEFORE: AtomicUsize = AtomicUsize::new(0);
+        static AFTER: AtomicUsize = AtomicUsize::new(0);
+        static FINALLY: AtomicUsize = AtomicUsize::new(0);
+
+        struct Sync;
+        impl SyncWrapContext<()> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
+                BEFORE.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn finally(&self, _: &CallerContext) {
+                FINALLY.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn after(self, _: &CallerContext, (): &()) {
+                AFTER.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let lazy = Sync::run_async(CallerContext::new("test"), async {});
+        assert_eq!(BEFORE.load(Ordering::Relaxed), 0);
+        lazy.await;
+        assert_eq!(BEFORE.swap(0, Ordering::Relaxed), 1);
+
+        let eager = Sync::run_async_eager(CallerContext::new("test"), async {
+            assert_eq!(current_fn_name(), Some("test"));
+        });
+        assert_eq!(BEFORE.load(Ordering::Relaxed), 1);
+        assert_eq!(AFTER.load(Ordering::Relaxed), 1);
+        eager.await;
+        assert_eq!(AFTER.load(Ordering::Relaxed), 2);
+        assert_eq!(FINALLY.swap(0, Ordering::Relaxed), 2);
+
+        drop(Sync::run_async_eager(CallerContext::new("test"), async {}));
+        assert_eq!(BEFORE.load(Ordering::Relaxed), 2);
+        assert_eq!(AFTER.load(Ordering::Relaxed), 2);
+        assert_eq!(FINALLY.load(Ordering::Relaxed), 1);
+    }
+
+    #[tokio::test]
+    async fn after_async_is_awaited_by_the_async_run() {
+        use futures::channel::mpsc::{unbounded, UnboundedSender};
+        use futures::{SinkExt, StreamExt};
+        use std::sync::OnceLock;
+
+        static SENDER: OnceLock<UnboundedSender<(&'static str, usize)>> = OnceLock::new();
+
+        struct Sync;
+        impl SyncWrapContext<usize> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn after(self, _: &CallerContext, result: &usize) {
+                SENDER
+                    .get()
+                    .unwrap()
+                    .unbounded_send(("after", *result))
+                    .unwrap();
+            }
+
+            async fn after_async(self, _: &CallerContext, result: &usize, _: Duration) {
+                let mut sender = SENDER.get().unwrap().clone();
+                sender.send(("after_async", *result)).await.unwrap();
+            }
+        }
+
+        let (sender, mut receiver) = unbounded();
+        SENDER.set(sender).unwrap();
+
+        assert_eq!(Sync::run_sync(CallerContext::new("test"), || 1), 1);
+        assert_eq!(
+            Sync::run_async(CallerContext::new("test"), async { 2 }).await,
+            2
+        );
+        assert_eq!(receiver.next().await, Some(("after", 1)));
+        assert_eq!(receiver.next().await, Some(("after_async", 2)));
+    }
+
+    #[tokio::test]
+    async fn short_circuit_skips_the_body() {
+        use crate::skip_with_default;
+        use std::ops::ControlFlow;
+        use std::sync::atomic::AtomicBool;
+
+        static ENABLED: AtomicBool = AtomicBool::new(false);
+        static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+        struct FeatureFlag;
+        impl SyncWrapContext<Vec<u8>> for FeatureFlag {
+            fn new() -> Self {
+                Self
+            }
+
+            fn short_circuit(&self, _: &CallerContext) -> ControlFlow<Vec<u8>> {
+                if ENABLED.load(Ordering::Relaxed) {
+                    ControlFlow::Continue(())
+                } else {
+                    skip_with_default()
+                }
+            }
+
+            fn after_timed(self, _: &CallerContext, result: &Vec<u8>, elapsed: Duration) {
+                AFTER.store(result.len(), Ordering::Relaxed);
+                assert!(ENABLED.load(Ordering::Relaxed) || elapsed.is_zero());
+            }
+        }
+
+        let skipped = FeatureFlag::run_sync(CallerContext::new("test"), || unreachable!());
+        assert!(skipped.is_empty());
+        let skipped =
+            FeatureFlag::run_async(CallerContext::new("test"), async { unreachable!() }).await;
+        assert!(skipped.is_empty());
+        assert_eq!(AFTER.load(Ordering::Relaxed), 0);
+
+        ENABLED.store(true, Ordering::Relaxed);
+        assert_eq!(
+            FeatureFlag::run_sync(CallerContext::new("test"), || vec![1, 2]),
+            [1, 2]
+        );
+        assert_eq!(AFTER.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn opted_out_hooks_are_skipped() {
+        static HOOK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        struct OptedOut;
+        impl SyncWrapContext<usize> for OptedOut {
+            const HAS_BEFORE: bool = false;
+            const HAS_AFTER: bool = false;
+
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
+                HOOK_CALLS.fetch_add(1, Ordering::Relaxed);
+            }
+
+            fn finally(&self, _: &CallerContext) {
+                HOOK_CALLS.fetch_add(10, Ordering::Relaxed);
+            }
+
+            fn after(self, _: &CallerContext, _: &usize) {
+                HOOK_CALLS.fetch_add(100, Ordering::Relaxed);
+            }
+        }
+
+        assert_eq!(OptedOut::run_sync(CallerContext::new("test"), || 42), 42);
+        // Only the hooks not covered by the constants are executed
+        assert_eq!(HOOK_CALLS.load(Ordering::Relaxed), 10);
+    }
+
+    #[test]
+    fn on_slow_runs_only_for_slow_bodies() {
+        static ON_SLOW: AtomicUsize = AtomicUsize::new(0);
+
+        struct Sync;
+        impl SyncWrapContext<()> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn now() -> std::time::Instant {
+                FakeClock::now()
+            }
+
+            fn slow_threshold(&self) -> Duration {
+                Duration::from_millis(10)
+            }
+
+            fn on_slow(&self, _: &CallerContext, elapsed: Duration) {
+                assert_eq!(elapsed, Duration::from_millis(20));
+                ON_SLOW.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        Sync::run_sync(CallerContext::new("test"), || {
+            FakeClock::advance(Duration::from_millis(10));
+        });
+        assert_eq!(ON_SLOW.load(Ordering::Relaxed), 0);
+
+        Sync::run_sync(CallerContext::new("test"), || {
+            FakeClock::advance(Duration::from_millis(20));
+        });
+        assert_eq!(ON_SLOW.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn before_with_input_observes_the_input() {
+        struct User {
+            id: u64,
+        }
+
+        struct Sync;
+        impl SyncWrapContext<u64, User> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
+                unreachable!("before_with_input is overridden");
+            }
+
+            fn before_with_input(&self, caller_context: &mut CallerContext, user: &User) {
+                caller_context.insert_extension(user.id);
+            }
+
+            fn after(self, caller_context: &CallerContext, result: &u64) {
+                assert_eq!(caller_context.extension::<u64>(), Some(result));
+            }
+        }
+
+        assert_eq!(
+            Sync::run_sync_with_input(CallerContext::new("test"), User { id: 42 }, || 42),
+            42
+        );
+    }
+
+    #[tokio::test]
+    async fn before_with_input_defaults_to_before() {
+        static BEFORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Sync;
+        impl SyncWrapContext<(), &str> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, _: &mut CallerContext) {
+                BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        Sync::run_async_with_input(CallerContext::new("test"), "input", async {}).await;
+        assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn attempts_are_visible_to_the_hooks() {
+        thread_local! {
+            static ATTEMPTS: std::cell::RefCell<Vec<(&'static str, u32)>> = const { std::cell::RefCell::new(Vec::new()) };
+        }
+
+        fn record(hook: &'static str, caller_context: &CallerContext) {
+            ATTEMPTS.with_borrow_mut(|attempts| attempts.push((hook, caller_context.attempt())));
+        }
+
+        struct Sync;
+        impl SyncWrapContext<Result<(), ()>> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn before(&self, caller_context: &mut CallerContext) {
+                record("before", caller_context);
+            }
+
+            fn after(self, caller_context: &CallerContext, _: &Result<(), ()>) {
+                record("after", caller_context);
+            }
+        }
+
+        // The body is retried up to the maximum number of attempts
+        let result =
+            Sync::run_sync_retrying(CallerContext::new("test"), 3, Result::is_err, || Err(()));
+        assert_eq!(result, Err(()));
+        assert_eq!(
+            ATTEMPTS.with_borrow_mut(std::mem::take),
+            [
+                ("before", 1),
+                ("after", 1),
+                ("before", 2),
+                ("after", 2),
+                ("before", 3),
+                ("after", 3)
+            ]
+        );
+
+        // The body is not retried once successful
+        let mut failures = 1;
+        let result = Sync::run_sync_retrying(CallerContext::new("test"), 3, Result::is_err, || {
+            failures -= 1;
+            if failures < 0 {
+                Ok(())
+            } else {
+                Err(())
+            }
+        });
+        assert_eq!(result, Ok(()));
+        assert_eq!(
+            ATTEMPTS.with_borrow_mut(std::mem::take),
+            [("before", 1), ("after", 1), ("before", 2), ("after", 2)]
+        );
+        assert_eq!(CallerContext::new("test").attempt(), 1);
+    }
+
+    #[test]
+    fn build_caller_context_enriches_all_the_hooks() {
+        static OBSERVED: AtomicUsize = AtomicUsize::new(0);
+
+        struct RequestId(usize);
+
+        struct Sync;
+        impl SyncWrapContext<()> for Sync {
+            fn new() -> Self {
+                Self
+            }
+
+            fn build_caller_context(mut default: CallerContext) -> CallerContext {
+                default.insert_extension(RequestId(42));
+                default
+            }
+
+            fn after(self, caller_context: &CallerContext, (): &()) {
+                let request_id = caller_context.extension::<RequestId>().unwrap();
+                OBSERVED.store(request_id.0, Ordering::Relaxed);
+            }
+        }
+
+        Sync::run_sync(CallerContext::new("test"), || ());
+        assert_eq!(OBSERVED.load(Ordering::Relaxed), 42);
+    }
+
+    #[test]
+    fn run_sync_with_context_returns_the_context() {
+        struct Accumulate {
+            events: Vec<String>,
+        }
+        impl SyncWrapContext<u8> for Accumulate {
+            const REENTRANCY: Reentrancy = Reentrancy::Detect;
+
+            fn new() -> Self {
+                Self { events: Vec::new() }
+            }
+
+            fn after_ref(&mut self, caller_context: &CallerContext, result: &u8, _: Duration) {
+                self.events
+                    .push(format!("{}={result}", caller_context.fn_name()));
+            }
+
+            fn after(self, _: &CallerContext, _: &u8) {
+                unreachable!("`after_ref` is invoked instead");
+            }
+        }
+
+        let (result, context) =
+            Accumulate::run_sync_with_context(CallerContext::new("test"), || 42);
+        assert_eq!(result, 42);
+        assert_eq!(context.events, ["test=42"]);
+
+        // The hooks are skipped for re-entrant calls, but the context is returned anyway
+        let (result, context) =
+            Accumulate::run_sync_with_context(CallerContext::new("test"), || {
+                let (result, context) =
+                    Accumulate::run_sync_with_context(CallerContext::new("test"), || 1);
+                assert!(context.events.is_empty());
+                result + 1
+            });
+        assert_eq!(result, 2);
+        assert_eq!(context.events, ["test=2"]);
+    }
+
+    #[tokio::test]
+    async fn run_async_accepts_futures_not_implementing_unpin() {
+        use core::future::Future;
+        use core::marker::PhantomPinned;
+        use core::pin::Pin;
+        use core::task::{Context, Poll};
+
+        /// Future that cannot be moved once pinned, so it cannot be awaited without pinning it
+        struct NotUnpin(PhantomPinned);
+        impl Future for NotUnpin {
+            type Output = usize;
+
+            fn poll(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<usize> {
+                Poll::Ready(42)
+            }
+        }
+
+        struct Sync;
+        impl SyncWrapContext<usize> for Sync {
+            fn new() -> Self {
+                Self
+            }
+        }
+
+        // The future is pinned by the context, without requiring `Box::pin`
+        let result = Sync::run_async(CallerContext::new("test"), NotUnpin(PhantomPinned)).await;
+        assert_eq!(result, 42);
+        let result = Sync::run_async(CallerContext::new("test"), async {
+            let marker = PhantomPinned;
+            tokio::task::yield_now().await;
+            let _ = &marker;
+            42
+        })
+        .await;
+        assert_eq!(result, 42);
+    }
 }
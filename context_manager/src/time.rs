@@ -0,0 +1,223 @@
+use core::fmt;
+use core::future::{poll_fn, Future};
+use core::pin::pin;
+use core::task::Poll;
+use core::time::Duration;
+
+/// Asynchronous sleep, abstracting over the async runtime in use
+///
+/// Implementations are provided for the supported runtimes, behind their respective features
+/// (`TokioSleep` requires the `tokio` feature, `AsyncStdSleep` requires the `async-std` feature).
+/// Implementing it allows to use a different runtime.
+/// ```
+/// # use context_manager::Sleep;
+/// # use std::future::Future;
+/// # use std::time::Duration;
+/// struct MyRuntimeSleep;
+/// impl Sleep for MyRuntimeSleep {
+///     fn sleep(duration: Duration) -> impl Future<Output = ()> {
+///         # async move { let _ = duration; }
+///         // my_runtime::sleep(duration)
+///     }
+/// }
+/// ```
+pub trait Sleep {
+    /// Create a future completing once `duration` elapsed
+    fn sleep(duration: Duration) -> impl Future<Output = ()>;
+}
+
+/// [`Sleep`] implementation based on [`tokio::time::sleep`] (requires the `tokio` feature)
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct TokioSleep;
+
+#[cfg(feature = "tokio")]
+impl Sleep for TokioSleep {
+    fn sleep(duration: Duration) -> impl Future<Output = ()> {
+        tokio::time::sleep(duration)
+    }
+}
+
+/// [`Sleep`] implementation based on [`async_std::task::sleep`] (requires the `async-std` feature)
+#[cfg(feature = "async-std")]
+#[derive(Debug)]
+pub struct AsyncStdSleep;
+
+#[cfg(feature = "async-std")]
+impl Sleep for AsyncStdSleep {
+    fn sleep(duration: Duration) -> impl Future<Output = ()> {
+        async_std::task::sleep(duration)
+    }
+}
+
+/// Source of the current time, abstracting over the clock in use (requires the `std` feature)
+///
+/// The timing contexts (like [`MinIntervalContext`](crate::contexts::MinIntervalContext)) accept the clock as a type parameter defaulting to
+/// [`SystemClock`], so a fake clock can be injected to make their tests deterministic (like `contexts::test::FakeClock`,
+/// requires the `testing` feature). The duration of the wrapped bodies is measured via [`SyncWrapContext::now`](crate::SyncWrapContext::now)
+/// (or [`AsyncWrapContext::now`](crate::AsyncWrapContext::now)), which the timing contexts forward to their clock, and
+/// [`ClockContext`](crate::contexts::ClockContext) injects a clock into the other contexts.
+/// ```
+/// # use context_manager::Clock;
+/// # use std::time::Instant;
+/// struct MonotonicClock;
+/// impl Clock for MonotonicClock {
+///     fn now() -> Instant {
+///         Instant::now()
+///     }
+/// }
+/// ```
+#[cfg(feature = "std")]
+pub trait Clock {
+    /// Current time
+    fn now() -> std::time::Instant;
+}
+
+/// [`Clock`] implementation based on [`std::time::Instant::now`] (requires the `std` feature)
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    fn now() -> std::time::Instant {
+        std::time::Instant::now()
+    }
+}
+
+/// [`Sleep`] implementation of the runtime enabled via features, giving precedence to `tokio`
+#[cfg(feature = "tokio")]
+type DefaultSleep = TokioSleep;
+#[cfg(all(feature = "async-std", not(feature = "tokio")))]
+type DefaultSleep = AsyncStdSleep;
+
+/// Error reported when a future did not complete within its timeout
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TimeoutError {
+    timeout: Duration,
+}
+
+impl TimeoutError {
+    /// Timeout that elapsed
+    #[must_use]
+    pub const fn timeout(&self) -> Duration {
+        self.timeout
+    }
+}
+
+impl fmt::Display for TimeoutError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "timed out after {:?}", self.timeout)
+    }
+}
+
+impl core::error::Error for TimeoutError {}
+
+/// Await `block`, failing with `E::from(TimeoutError)` if it does not complete within `timeout`
+///
+/// The time is measured via the [`Sleep`] implementation `S`. If `block` does not complete in time
+/// it is dropped, so it is cancelled at its current `.await` point.
+///
+/// # Errors
+/// Returns the error of `block`, or `E::from(TimeoutError)` if `block` did not complete within `timeout`.
+/// ```
+/// # use context_manager::{timeout_with, Sleep, TimeoutError};
+/// # use std::future::Future;
+/// # use std::time::Duration;
+/// # struct MyRuntimeSleep;
+/// # impl Sleep for MyRuntimeSleep {
+/// #     fn sleep(duration: Duration) -> impl Future<Output = ()> { std::future::pending() }
+/// # }
+/// # async fn foo() {
+/// let result: Result<u8, TimeoutError> = timeout_with::<MyRuntimeSleep, _, _>(Duration::from_secs(1), async { Ok(1) }).await;
+/// # }
+/// ```
+pub async fn timeout_with<S, T, E>(
+    timeout: Duration,
+    block: impl Future<Output = Result<T, E>>,
+) -> Result<T, E>
+where
+    S: Sleep,
+    E: From<TimeoutError>,
+{
+    let mut block = pin!(block);
+    let mut sleep = pin!(S::sleep(timeout));
+    poll_fn(|cx| {
+        if let Poll::Ready(result) = block.as_mut().poll(cx) {
+            Poll::Ready(result)
+        } else if sleep.as_mut().poll(cx).is_ready() {
+            Poll::Ready(Err(E::from(TimeoutError { timeout })))
+        } else {
+            Poll::Pending
+        }
+    })
+    .await
+}
+
+/// Await `block`, failing with `E::from(TimeoutError)` if it does not complete within `timeout` (requires a runtime feature)
+///
+/// This is [`timeout_with`] using the [`Sleep`] implementation of the runtime enabled via features, and it is
+/// the combinator used by the `timeout` argument of the macros. Exactly one of the `tokio` and `async-std`
+/// features is expected to be enabled; if both are enabled `tokio` takes precedence.
+///
+/// # Errors
+/// Returns the error of `block`, or `E::from(TimeoutError)` if `block` did not complete within `timeout`.
+#[cfg(any(feature = "tokio", feature = "async-std"))]
+pub async fn timeout<T, E>(
+    timeout: Duration,
+    block: impl Future<Output = Result<T, E>>,
+) -> Result<T, E>
+where
+    E: From<TimeoutError>,
+{
+    timeout_with::<DefaultSleep, T, E>(timeout, block).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::{pending, ready, Future};
+    use std::time::Duration;
+
+    use super::{timeout_with, Sleep, TimeoutError};
+
+    /// Sleep completing immediately, so any pending future times out
+    struct Immediate;
+    impl Sleep for Immediate {
+        fn sleep(_: Duration) -> impl Future<Output = ()> {
+            ready(())
+        }
+    }
+
+    #[tokio::test]
+    async fn completed_block_is_returned() {
+        let result: Result<u8, TimeoutError> =
+            timeout_with::<Immediate, _, _>(Duration::from_secs(1), ready(Ok(1))).await;
+        assert_eq!(result, Ok(1));
+    }
+
+    #[tokio::test]
+    async fn pending_block_times_out() {
+        let result: Result<u8, TimeoutError> =
+            timeout_with::<Immediate, _, _>(Duration::from_secs(1), pending()).await;
+        assert_eq!(result.unwrap_err().to_string(), "timed out after 1s");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test]
+    async fn tokio_sleep_times_out() {
+        let result: Result<u8, TimeoutError> =
+            timeout_with::<super::TokioSleep, _, _>(Duration::from_millis(1), pending()).await;
+        assert!(result.is_err());
+    }
+
+    #[cfg(feature = "async-std")]
+    #[test]
+    fn async_std_sleep_times_out() {
+        let result: Result<u8, TimeoutError> =
+            async_std::task::block_on(timeout_with::<super::AsyncStdSleep, _, _>(
+                Duration::from_millis(1),
+                pending(),
+            ));
+        assert!(result.is_err());
+    }
+}
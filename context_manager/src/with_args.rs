@@ -0,0 +1,122 @@
+use core::cell::Cell;
+
+use crate::t_sync::{run_async_with_hook, run_sync_with_hook};
+use crate::{CallerContext, SyncWrapContext, WithCallerContext};
+
+/// Extension of [`SyncWrapContext`] whose before hook receives the arguments of the wrapped function
+///
+/// The [`wrap`](crate::wrap) macro uses it when the `args` argument is provided, like `#[wrap(LogArgs, args)]`,
+/// collecting the identifiers bound by the arguments of the function (`self` excluded) into the tuple `Args`.
+/// The tuple is provided by reference to [`WrapContextWithArgs::before_with_args`], and moved back into the body afterwards.
+/// Functions without arguments (other than `self`) lead to `Args = ()`.
+/// ```
+/// # use context_manager::{wrap, CallerContext, SyncWrapContext, WrapContextWithArgs};
+/// struct LogArgs;
+/// impl<T> SyncWrapContext<T> for LogArgs {
+///     fn new() -> Self { Self }
+/// }
+/// impl<'a, T> WrapContextWithArgs<(u64, &'a str), T> for LogArgs {
+///     fn before_with_args(&self, caller_context: &mut CallerContext, (id, name): &(u64, &'a str)) {
+///         println!("{}(id={id}, name={name})", caller_context.fn_name());
+///     }
+/// }
+///
+/// #[wrap(LogArgs, args)]
+/// fn greet(id: u64, name: &str) -> String {
+///     format!("Hello {name} ({id})")
+/// }
+/// ```
+pub trait WrapContextWithArgs<Args, T>: SyncWrapContext<T> {
+    /// Execute the code before the execution of the wrapped body, it provides also the arguments of the wrapped function
+    ///
+    /// This is invoked instead of [`SyncWrapContext::before`], to which it delegates by default.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `args`: The arguments of the wrapped function, in declaration order
+    #[allow(unused_variables)]
+    fn before_with_args(&self, caller_context: &mut CallerContext, args: &Args) {
+        self.before(caller_context);
+    }
+
+    /// Execute a synchronous block of code wrapped by the context, providing the arguments to the context
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_sync`], but [`WrapContextWithArgs::before_with_args`]
+    /// is invoked instead of [`SyncWrapContext::before`] and the arguments are then moved into `block`,
+    /// alongside the caller context provided to the hooks.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `args`: the arguments provided to [`WrapContextWithArgs::before_with_args`]
+    /// - `block`: the callable to wrap and execute
+    fn run_sync_with_args(
+        caller_context: CallerContext,
+        args: Args,
+        block: impl FnOnce(Args, &CallerContext) -> T,
+    ) -> T
+    where
+        Self: Sized,
+    {
+        let args = ArgsCell::new(args);
+        run_sync_with_hook(
+            &mut Self::build_caller_context(caller_context),
+            |context: &Self, caller_context| {
+                args.with(|args| context.before_with_args(caller_context, args));
+            },
+            WithCallerContext(|caller_context: &CallerContext| block(args.take(), caller_context)),
+        )
+    }
+
+    /// Execute an asynchronous block of code wrapped by the context, providing the arguments to the context
+    ///
+    /// This is equivalent to [`SyncWrapContext::run_async`], but [`WrapContextWithArgs::before_with_args`]
+    /// is invoked instead of [`SyncWrapContext::before`] and the arguments are then moved into the future created by `block`,
+    /// alongside the caller context provided to the hooks.
+    ///
+    /// Parameters:
+    /// - `caller_context`: Context of the caller (including the name of the function that is being wrapped)
+    /// - `args`: the arguments provided to [`WrapContextWithArgs::before_with_args`]
+    /// - `block`: the async callable to wrap and execute
+    #[allow(async_fn_in_trait)]
+    async fn run_async_with_args(
+        caller_context: CallerContext,
+        args: Args,
+        block: impl AsyncFnOnce(Args, &CallerContext) -> T,
+    ) -> T
+    where
+        Self: Sized,
+    {
+        let args = ArgsCell::new(args);
+        run_async_with_hook(
+            &mut Self::build_caller_context(caller_context),
+            |context: &Self, caller_context| {
+                args.with(|args| context.before_with_args(caller_context, args));
+            },
+            WithCallerContext(async |caller_context: &CallerContext| {
+                block(args.take(), caller_context).await
+            }),
+        )
+        .await
+    }
+}
+
+/// Arguments shared by the before hook, which borrows them, and by the body, which consumes them
+struct ArgsCell<Args>(Cell<Option<Args>>);
+
+impl<Args> ArgsCell<Args> {
+    const fn new(args: Args) -> Self {
+        Self(Cell::new(Some(args)))
+    }
+
+    fn with(&self, f: impl FnOnce(&Args)) {
+        let args = self.take();
+        f(&args);
+        self.0.set(Some(args));
+    }
+
+    fn take(&self) -> Args {
+        self.0
+            .take()
+            .expect("the arguments are consumed only once, by the body")
+    }
+}
@@ -0,0 +1,73 @@
+use core::future::Future;
+
+use crate::{AsyncWrapContext, CallerContext, SyncWrapContext};
+
+/// Execute a synchronous block of code wrapped by the context `C`, without the need of the macros
+///
+/// This is equivalent to [`SyncWrapContext::run_sync`], with a signature friendlier to the turbofish syntax.
+/// It is the macro-free counterpart of decorating a sync function with [`wrap`](crate::wrap).
+/// ```
+/// # use context_manager::{with_context, CallerContext, SyncWrapContext};
+/// struct PrintDuration;
+/// impl<T> SyncWrapContext<T> for PrintDuration {
+///     fn new() -> Self { Self }
+/// }
+///
+/// let output = with_context::<PrintDuration, _, _>(CallerContext::new("block"), || 42);
+/// assert_eq!(output, 42);
+/// ```
+pub fn with_context<C, T, F>(caller_context: CallerContext, block: F) -> T
+where
+    C: SyncWrapContext<T>,
+    F: FnOnce() -> T,
+{
+    C::run_sync(caller_context, block)
+}
+
+/// Execute an asynchronous block of code wrapped by the context `C`, whose hooks are synchronous
+///
+/// This is equivalent to [`SyncWrapContext::run_async`], with a signature friendlier to the turbofish syntax.
+/// It is the macro-free counterpart of decorating an async function with [`wrap`](crate::wrap).
+/// ```
+/// # use context_manager::{with_context_async, CallerContext, SyncWrapContext};
+/// struct PrintDuration;
+/// impl<T> SyncWrapContext<T> for PrintDuration {
+///     fn new() -> Self { Self }
+/// }
+///
+/// # async fn foo() {
+/// let output = with_context_async::<PrintDuration, _, _>(CallerContext::new("block"), async { 42 }).await;
+/// assert_eq!(output, 42);
+/// # }
+/// ```
+pub async fn with_context_async<C, T, F>(caller_context: CallerContext, block: F) -> T
+where
+    C: SyncWrapContext<T>,
+    F: Future<Output = T>,
+{
+    C::run_async(caller_context, block).await
+}
+
+/// Execute an asynchronous block of code wrapped by the context `C`, whose hooks are asynchronous
+///
+/// This is equivalent to [`AsyncWrapContext::run`], with a signature friendlier to the turbofish syntax.
+/// It is the macro-free counterpart of decorating an async function with [`async_wrap`](crate::async_wrap).
+/// ```
+/// # use context_manager::{with_async_context, AsyncWrapContext, CallerContext};
+/// struct AsyncPrintDuration;
+/// impl<T> AsyncWrapContext<T> for AsyncPrintDuration {
+///     async fn new() -> Self { Self }
+/// }
+///
+/// # async fn foo() {
+/// let output = with_async_context::<AsyncPrintDuration, _, _>(CallerContext::new("block"), async { 42 }).await;
+/// assert_eq!(output, 42);
+/// # }
+/// ```
+pub async fn with_async_context<C, T, F>(caller_context: CallerContext, block: F) -> T
+where
+    C: AsyncWrapContext<T>,
+    F: Future<Output = T>,
+{
+    C::run(caller_context, block).await
+}
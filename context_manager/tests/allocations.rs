@@ -0,0 +1,107 @@
+#![cfg(feature = "std")]
+
+use std::alloc::System;
+use std::hint::black_box;
+use std::sync::Mutex;
+
+use context_manager::contexts::{AllocConfig, AllocContext, CountingAllocator};
+use context_manager::{async_wrap, wrap};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator::new(System);
+
+static REPORTS: Mutex<Vec<(&str, u64, u64)>> = Mutex::new(Vec::new());
+static DEALLOC_REPORTS: Mutex<Vec<(&str, u64, u64)>> = Mutex::new(Vec::new());
+
+struct RecordAllocations;
+impl AllocConfig for RecordAllocations {
+    fn on_alloc(fn_name: &'static str, allocs: u64, bytes: u64) {
+        REPORTS.lock().unwrap().push((fn_name, allocs, bytes));
+    }
+
+    fn on_dealloc(fn_name: &'static str, deallocs: u64, bytes: u64) {
+        DEALLOC_REPORTS
+            .lock()
+            .unwrap()
+            .push((fn_name, deallocs, bytes));
+    }
+}
+
+fn filter(reports: &Mutex<Vec<(&str, u64, u64)>>, fn_name: &str) -> Vec<(u64, u64)> {
+    reports
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(name, ..)| *name == fn_name)
+        .map(|(_, count, bytes)| (*count, *bytes))
+        .collect()
+}
+
+fn reports(fn_name: &str) -> Vec<(u64, u64)> {
+    filter(&REPORTS, fn_name)
+}
+
+fn dealloc_reports(fn_name: &str) -> Vec<(u64, u64)> {
+    filter(&DEALLOC_REPORTS, fn_name)
+}
+
+#[wrap(AllocContext<RecordAllocations>)]
+fn allocate_vec(len: usize) -> usize {
+    black_box(vec![0_u8; len]).len()
+}
+
+#[async_wrap(AllocContext<RecordAllocations>)]
+async fn allocate_vec_async(len: usize) -> usize {
+    black_box(vec![0_u8; len]).len()
+}
+
+#[test]
+fn allocations_are_reported() {
+    assert_eq!(allocate_vec(1024), 1024);
+
+    let reports = reports("allocate_vec");
+    assert_eq!(reports.len(), 1);
+    let (allocs, bytes) = reports[0];
+    assert!(allocs >= 1, "{allocs} allocations reported");
+    assert!(bytes >= 1024, "{bytes} bytes reported");
+
+    // The vector is dropped within the wrapped function
+    let dealloc_reports = dealloc_reports("allocate_vec");
+    assert_eq!(dealloc_reports.len(), 1);
+    let (deallocs, bytes) = dealloc_reports[0];
+    assert!(deallocs >= 1, "{deallocs} deallocations reported");
+    assert!(bytes >= 1024, "{bytes} bytes reported");
+}
+
+#[wrap(AllocContext<RecordAllocations>)]
+fn retain_vec(len: usize) -> Vec<u8> {
+    black_box(vec![0_u8; len])
+}
+
+#[test]
+fn retained_allocations_are_not_deallocated() {
+    let retained = retain_vec(2048);
+
+    let (_, allocated) = reports("retain_vec")[0];
+    let (_, deallocated) = dealloc_reports("retain_vec")[0];
+    assert!(
+        allocated - deallocated >= 2048,
+        "{allocated} bytes allocated, {deallocated} bytes deallocated"
+    );
+    drop(retained);
+}
+
+#[tokio::test]
+async fn async_allocations_are_reported() {
+    assert_eq!(allocate_vec_async(4096).await, 4096);
+
+    let reports = reports("allocate_vec_async");
+    assert_eq!(reports.len(), 1);
+    let (allocs, bytes) = reports[0];
+    assert!(allocs >= 1, "{allocs} allocations reported");
+    assert!(bytes >= 4096, "{bytes} bytes reported");
+
+    let (deallocs, bytes) = dealloc_reports("allocate_vec_async")[0];
+    assert!(deallocs >= 1, "{deallocs} deallocations reported");
+    assert!(bytes >= 4096, "{bytes} bytes reported");
+}
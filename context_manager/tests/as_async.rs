@@ -0,0 +1,76 @@
+use std::sync::Mutex;
+
+use context_manager::contexts::AsAsync;
+use context_manager::{async_wrap, CallerContext, SyncWrapContext};
+
+static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn events(fn_name: &str) -> Vec<String> {
+    EVENTS
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|event| event.ends_with(fn_name))
+        .cloned()
+        .collect()
+}
+
+/// Synchronous context, recording its hooks
+struct MySync;
+impl<T> SyncWrapContext<T> for MySync {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        EVENTS
+            .lock()
+            .unwrap()
+            .push(format!("before {}", caller_context.fn_name()));
+    }
+
+    fn after(self, caller_context: &CallerContext, _: &T) {
+        EVENTS
+            .lock()
+            .unwrap()
+            .push(format!("after {}", caller_context.fn_name()));
+    }
+}
+
+#[async_wrap(AsAsync<MySync>)]
+async fn inline_hooks() -> u8 {
+    tokio::task::yield_now().await;
+    42
+}
+
+#[tokio::test]
+async fn sync_context_via_async_wrap() {
+    assert_eq!(inline_hooks().await, 42);
+    assert_eq!(
+        events("inline_hooks"),
+        ["before inline_hooks", "after inline_hooks"]
+    );
+}
+
+#[cfg(feature = "tokio")]
+mod blocking {
+    use context_manager::async_wrap;
+    use context_manager::contexts::AsAsyncBlocking;
+
+    use super::{events, MySync};
+
+    #[async_wrap(AsAsyncBlocking<MySync>)]
+    async fn blocking_hooks() -> u8 {
+        tokio::task::yield_now().await;
+        42
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn sync_context_via_async_wrap_blocking() {
+        assert_eq!(blocking_hooks().await, 42);
+        assert_eq!(
+            events("blocking_hooks"),
+            ["before blocking_hooks", "after blocking_hooks"]
+        );
+    }
+}
@@ -0,0 +1,62 @@
+#![cfg(feature = "std")]
+
+use std::borrow::Cow;
+
+use context_manager::contexts::{ContextChain, CounterContext, FlamegraphContext, StatsContext};
+use context_manager::{wrap, CallerContext, SyncWrapContext};
+
+/// Context namespacing the emitted names of the wrapped functions, without any hook
+struct Namespace;
+impl<T> SyncWrapContext<T> for Namespace {
+    fn new() -> Self {
+        Self
+    }
+
+    fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        Cow::Owned(format!("service.{name}"))
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        assert_eq!(caller_context.fn_name(), "load");
+        assert_eq!(caller_context.emitted_fn_name(), "service.load");
+    }
+}
+
+type Metrics = ContextChain<StatsContext, ContextChain<CounterContext, FlamegraphContext>>;
+
+#[wrap(ContextChain<Namespace, Metrics>)]
+fn load() -> Option<&'static str> {
+    context_manager::current_fn_name()
+}
+
+#[test]
+fn metrics_are_recorded_with_the_emitted_name() {
+    assert_eq!(load(), Some("load"));
+    assert_eq!(load(), Some("load"));
+
+    assert_eq!(StatsContext::count("service.load"), 2);
+    assert_eq!(StatsContext::count("load"), 0);
+    assert_eq!(CounterContext::count("service.load"), 2);
+    assert_eq!(CounterContext::count("load"), 0);
+
+    let mut folded = Vec::new();
+    FlamegraphContext::write_folded(&mut folded).unwrap();
+    let folded = String::from_utf8(folded).unwrap();
+    assert!(folded.starts_with("service.load "), "{folded}");
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn prometheus_labels_use_the_emitted_name() {
+    use context_manager::contexts::PrometheusContext;
+
+    #[wrap(ContextChain<Namespace, PrometheusContext>)]
+    fn load() {}
+
+    load();
+    let rendered = PrometheusContext::render();
+    assert!(
+        rendered.contains("wrapped_calls_total{fn=\"service.load\"} 1"),
+        "{rendered}"
+    );
+}
@@ -0,0 +1,90 @@
+#![cfg(feature = "fastrace")]
+
+use context_manager::contexts::FastraceContext;
+use context_manager::{async_wrap, wrap};
+use fastrace::collector::{Config, SpanRecord, TestReporter};
+use fastrace::future::FutureExt;
+use fastrace::local::LocalSpan;
+use fastrace::prelude::{Span, SpanContext};
+
+#[wrap(FastraceContext)]
+fn sync_parse(value: &str) -> Option<u64> {
+    let _nested = LocalSpan::enter_with_local_parent("nested");
+    value.parse().ok()
+}
+
+#[wrap(FastraceContext)]
+async fn sync_context_async_load(id: u64) -> u64 {
+    tokio::task::yield_now().await;
+    sync_parse("1").map_or(id, |value| id + value)
+}
+
+#[async_wrap(FastraceContext)]
+async fn async_load(id: u64) -> u64 {
+    tokio::task::yield_now().await;
+    id * 2
+}
+
+fn span<'a>(spans: &'a [SpanRecord], name: &str) -> &'a SpanRecord {
+    spans
+        .iter()
+        .find(|span| span.name == name)
+        .unwrap_or_else(|| panic!("span {name} not recorded"))
+}
+
+#[tokio::test]
+async fn spans_are_recorded_within_the_local_parent() {
+    let (reporter, spans) = TestReporter::new();
+    fastrace::set_reporter(reporter, Config::default());
+
+    let root = Span::root("root", SpanContext::random());
+    {
+        let _guard = root.set_local_parent();
+        assert_eq!(sync_parse("42"), Some(42));
+    }
+    assert_eq!(
+        sync_context_async_load(1)
+            .in_span(Span::enter_with_parent("load", &root))
+            .await,
+        2
+    );
+    assert_eq!(
+        async_load(3)
+            .in_span(Span::enter_with_parent("load", &root))
+            .await,
+        6
+    );
+    drop(root);
+    fastrace::flush();
+
+    let spans = spans.lock();
+    let names = |name: &str| spans.iter().filter(|span| span.name == name).count();
+    assert_eq!(names("sync_parse"), 2);
+    assert_eq!(names("nested"), 2);
+    assert_eq!(names("sync_context_async_load"), 1);
+    assert_eq!(names("async_load"), 1);
+
+    let root = span(&spans, "root");
+    let sync_parse = spans
+        .iter()
+        .find(|span| span.name == "sync_parse" && span.parent_id == root.span_id)
+        .expect("sync_parse not recorded within the root span");
+    assert!(spans
+        .iter()
+        .any(|span| span.name == "nested" && span.parent_id == sync_parse.span_id));
+    let sync_context_async_load = span(&spans, "sync_context_async_load");
+    assert!(
+        spans
+            .iter()
+            .any(|span| span.name == "sync_parse"
+                && span.parent_id == sync_context_async_load.span_id)
+    );
+    let loads: Vec<_> = spans.iter().filter(|span| span.name == "load").collect();
+    assert_eq!(loads.len(), 2);
+    assert!(loads
+        .iter()
+        .any(|load| sync_context_async_load.parent_id == load.span_id));
+    assert!(loads
+        .iter()
+        .any(|load| span(&spans, "async_load").parent_id == load.span_id));
+}
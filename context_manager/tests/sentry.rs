@@ -0,0 +1,96 @@
+#![cfg(feature = "sentry")]
+
+use std::num::ParseIntError;
+use std::panic::catch_unwind;
+
+use context_manager::contexts::{SentryConfig, SentryContext};
+use context_manager::{async_wrap, wrap};
+use sentry_core::protocol::Level;
+use sentry_core::test::with_captured_events;
+
+struct WarningWithPanics;
+impl SentryConfig for WarningWithPanics {
+    const LEVEL: Level = Level::Warning;
+    const CAPTURE_PANICS: bool = true;
+}
+
+#[wrap(SentryContext)]
+fn parse(value: &str) -> Result<u64, ParseIntError> {
+    value.parse()
+}
+
+#[async_wrap(SentryContext<WarningWithPanics>)]
+async fn async_parse(value: &str) -> Result<u64, ParseIntError> {
+    value.parse()
+}
+
+#[wrap(SentryContext<WarningWithPanics>)]
+fn panicking() -> Result<(), ParseIntError> {
+    panic!("panicking")
+}
+
+#[test]
+fn ok_results_are_not_captured() {
+    let events = with_captured_events(|| {
+        assert_eq!(parse("42"), Ok(42));
+        assert_eq!(futures::executor::block_on(async_parse("42")), Ok(42));
+    });
+    assert!(events.is_empty());
+}
+
+#[test]
+fn err_results_are_captured() {
+    let events = with_captured_events(|| {
+        assert!(parse("NaN").is_err());
+        assert!(futures::executor::block_on(async_parse("NaN")).is_err());
+    });
+
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].transaction.as_deref(), Some("parse"));
+    assert_eq!(events[0].level, Level::Error);
+    assert_eq!(events[0].exception[0].ty, "ParseIntError");
+    assert_eq!(events[1].transaction.as_deref(), Some("async_parse"));
+    assert_eq!(events[1].level, Level::Warning);
+}
+
+#[test]
+fn panics_are_captured_if_configured() {
+    let events = with_captured_events(|| {
+        assert!(catch_unwind(panicking).is_err());
+    });
+
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].transaction.as_deref(), Some("panicking"));
+    assert_eq!(events[0].message.as_deref(), Some("panicking panicked"));
+}
+
+#[test]
+fn transactions_are_named_after_the_emitted_name() {
+    use context_manager::contexts::ContextChain;
+    use context_manager::SyncWrapContext;
+    use std::borrow::Cow;
+
+    struct Namespace;
+    impl<T> SyncWrapContext<T> for Namespace {
+        fn new() -> Self {
+            Self
+        }
+
+        fn map_fn_name<'a>(&self, name: &'a str) -> Cow<'a, str> {
+            Cow::Owned(format!("service.{name}"))
+        }
+    }
+
+    #[wrap(ContextChain<Namespace, SentryContext>)]
+    fn parse_namespaced(value: &str) -> Result<u64, ParseIntError> {
+        value.parse()
+    }
+
+    let events = with_captured_events(|| {
+        assert!(parse_namespaced("NaN").is_err());
+    });
+    assert_eq!(
+        events[0].transaction.as_deref(),
+        Some("service.parse_namespaced")
+    );
+}
@@ -0,0 +1,47 @@
+#![cfg(feature = "std")]
+
+use std::pin::pin;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use context_manager::contexts::SingleFlightContext;
+use context_manager::wrap;
+use futures::poll;
+use tokio::sync::{Barrier, Semaphore};
+
+const CALLS: usize = 32;
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+/// Released once all the calls are in progress, so the body cannot complete before
+static RELEASE: Semaphore = Semaphore::const_new(0);
+
+#[wrap(SingleFlightContext, args)]
+async fn load_report(id: u64, name: String) -> String {
+    EXECUTIONS.fetch_add(1, Ordering::Relaxed);
+    let _permit = RELEASE.acquire().await.unwrap();
+    format!("report {id} for {name}")
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_identical_calls_execute_the_body_once() {
+    let in_progress = Arc::new(Barrier::new(CALLS + 1));
+    let tasks: Vec<_> = (0..CALLS)
+        .map(|_| {
+            let in_progress = Arc::clone(&in_progress);
+            tokio::spawn(async move {
+                // The first poll joins the call in progress (or starts it), which waits for the release
+                let mut call = pin!(load_report(42, "alice".to_string()));
+                assert!(poll!(&mut call).is_pending());
+                in_progress.wait().await;
+                call.await
+            })
+        })
+        .collect();
+
+    in_progress.wait().await;
+    RELEASE.add_permits(CALLS);
+    for task in tasks {
+        assert_eq!(task.await.unwrap(), "report 42 for alice");
+    }
+    assert_eq!(EXECUTIONS.load(Ordering::Relaxed), 1);
+}
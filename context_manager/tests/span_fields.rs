@@ -0,0 +1,246 @@
+#![cfg(feature = "tracing")]
+
+use std::fmt::Debug;
+use std::sync::{Arc, Mutex};
+
+use context_manager::contexts::{SpanFieldsContext, SpanRecordConfig, SpanRecordContext};
+use context_manager::{async_wrap, wrap};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::{Layer, Registry};
+
+/// Spans created while the layer is installed, as (name, target, fields)
+type RecordedSpans = Arc<Mutex<Vec<(&'static str, &'static str, Vec<(String, String)>)>>>;
+
+#[derive(Default)]
+struct RecordSpans(RecordedSpans);
+
+struct RecordFields<'a>(&'a mut Vec<(String, String)>);
+
+impl Visit for RecordFields<'_> {
+    fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+        self.0
+            .push((field.name().to_string(), format!("{value:?}")));
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RecordSpans {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+        let mut fields = Vec::new();
+        attrs.record(&mut RecordFields(&mut fields));
+        self.0
+            .lock()
+            .unwrap()
+            .push((attrs.metadata().name(), attrs.metadata().target(), fields));
+    }
+}
+
+fn current_span_name() -> Option<&'static str> {
+    tracing::Span::current()
+        .metadata()
+        .map(tracing::Metadata::name)
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Order {
+    id: u64,
+}
+
+#[wrap(SpanFieldsContext, fields(user_id, order))]
+fn sync_ship(user_id: u64, order: Order, address: &str) -> (Option<&'static str>, u64, usize) {
+    (current_span_name(), user_id + order.id, address.len())
+}
+
+#[wrap(SpanFieldsContext, fields(user_id))]
+async fn sync_context_async_ship(user_id: u64) -> Option<&'static str> {
+    tokio::task::yield_now().await;
+    current_span_name()
+}
+
+#[async_wrap(SpanFieldsContext, fields(order_id))]
+async fn async_ship(order_id: u64, _quantity: u8) -> Option<&'static str> {
+    tokio::task::yield_now().await;
+    current_span_name()
+}
+
+fn expected_fields(fields: &[(&str, &str)]) -> Vec<(String, String)> {
+    fields
+        .iter()
+        .map(|(name, value)| ((*name).to_string(), (*value).to_string()))
+        .collect()
+}
+
+#[test]
+fn span_records_the_selected_arguments() {
+    let layer = RecordSpans::default();
+    let spans = Arc::clone(&layer.0);
+    let subscriber = Registry::default().with(layer);
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    assert_eq!(
+        sync_ship(1, Order { id: 2 }, "address"),
+        (Some("sync_ship"), 3, 7)
+    );
+
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    assert_eq!(
+        runtime.block_on(sync_context_async_ship(3)),
+        Some("sync_context_async_ship")
+    );
+    assert_eq!(runtime.block_on(async_ship(4, 5)), Some("async_ship"));
+
+    assert_eq!(
+        *spans.lock().unwrap(),
+        [
+            (
+                "sync_ship",
+                module_path!(),
+                expected_fields(&[("user_id", "1"), ("order", "Order { id: 2 }")])
+            ),
+            (
+                "sync_context_async_ship",
+                module_path!(),
+                expected_fields(&[("user_id", "3")])
+            ),
+            (
+                "async_ship",
+                module_path!(),
+                expected_fields(&[("order_id", "4")])
+            ),
+        ]
+    );
+}
+
+/// Metadata of the spans created while the layer is installed, as (name, target, level)
+type RecordedMetadata = Arc<Mutex<Vec<(&'static str, &'static str, tracing::Level)>>>;
+
+#[derive(Default)]
+struct RecordMetadata(RecordedMetadata);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RecordMetadata {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _: &Id, _: Context<'_, S>) {
+        let metadata = attrs.metadata();
+        self.0
+            .lock()
+            .unwrap()
+            .push((metadata.name(), metadata.target(), *metadata.level()));
+    }
+}
+
+#[tracing::instrument]
+fn instrumented() {}
+
+#[wrap(SpanFieldsContext, span())]
+fn wrapped() {}
+
+#[async_wrap(
+    SpanFieldsContext,
+    span(name = "db.load", target = "db", level = "debug")
+)]
+async fn load() -> Option<&'static str> {
+    tokio::task::yield_now().await;
+    current_span_name()
+}
+
+#[wrap(SpanFieldsContext, fields(user_id), span(level = "WARN"))]
+fn warn_user(user_id: u64) {}
+
+#[test]
+fn span_metadata_matches_instrument() {
+    let layer = RecordMetadata::default();
+    let spans = Arc::clone(&layer.0);
+    let subscriber = Registry::default().with(layer);
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    instrumented();
+    wrapped();
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    assert_eq!(runtime.block_on(load()), Some("db.load"));
+    warn_user(1);
+
+    assert_eq!(
+        *spans.lock().unwrap(),
+        [
+            ("instrumented", module_path!(), tracing::Level::INFO),
+            ("wrapped", module_path!(), tracing::Level::INFO),
+            ("db.load", "db", tracing::Level::DEBUG),
+            ("warn_user", module_path!(), tracing::Level::WARN),
+        ]
+    );
+}
+
+/// Fields recorded after the creation of the spans, as (span name, field, value)
+type RecordedValues = Arc<Mutex<Vec<(&'static str, String, String)>>>;
+
+#[derive(Default)]
+struct RecordValues(RecordedValues);
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for RecordValues {
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let name = ctx.span(id).unwrap().name();
+        let mut fields = Vec::new();
+        values.record(&mut RecordFields(&mut fields));
+        self.0.lock().unwrap().extend(
+            fields
+                .into_iter()
+                .map(|(field, value)| (name, field, value)),
+        );
+    }
+}
+
+struct RowsReturned;
+impl<E> SpanRecordConfig<Result<Vec<u64>, E>> for RowsReturned {
+    const FIELD: &'static str = "rows_returned";
+
+    fn value(result: &Result<Vec<u64>, E>) -> impl tracing::Value {
+        result.as_ref().map_or(0, Vec::len)
+    }
+}
+
+#[wrap(SpanRecordContext<RowsReturned>)]
+#[tracing::instrument(fields(rows_returned = tracing::field::Empty))]
+fn query(limit: u64) -> Result<Vec<u64>, String> {
+    Ok((0..limit).collect())
+}
+
+#[async_wrap(SpanRecordContext<RowsReturned>)]
+#[tracing::instrument(fields(rows_returned = tracing::field::Empty))]
+async fn query_async(fail: bool) -> Result<Vec<u64>, String> {
+    tokio::task::yield_now().await;
+    if fail {
+        Err("unavailable".to_string())
+    } else {
+        Ok(vec![1])
+    }
+}
+
+#[test]
+fn span_records_the_field_derived_from_the_result() {
+    let layer = RecordValues::default();
+    let values = Arc::clone(&layer.0);
+    let subscriber = Registry::default().with(layer);
+    let _default = tracing::subscriber::set_default(subscriber);
+
+    assert_eq!(query(3).unwrap().len(), 3);
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    assert!(runtime.block_on(query_async(true)).is_err());
+    assert!(runtime.block_on(query_async(false)).is_ok());
+
+    assert_eq!(
+        *values.lock().unwrap(),
+        [
+            ("query", "rows_returned".to_string(), "3".to_string()),
+            ("query_async", "rows_returned".to_string(), "0".to_string()),
+            ("query_async", "rows_returned".to_string(), "1".to_string()),
+        ]
+    );
+}
@@ -0,0 +1,38 @@
+#![cfg(feature = "tokio")]
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use context_manager::{async_wrap, AsyncWrapContext, CallerContext, TimeoutError};
+
+static RESULTS: Mutex<Vec<Result<u64, TimeoutError>>> = Mutex::new(Vec::new());
+
+/// Context recording the results, as seen by the `after` hook
+struct RecordResult;
+impl AsyncWrapContext<Result<u64, TimeoutError>> for RecordResult {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn after(self, _: &CallerContext, result: &Result<u64, TimeoutError>) {
+        RESULTS.lock().unwrap().push(*result);
+    }
+}
+
+#[async_wrap(RecordResult, timeout = "50ms")]
+async fn sleep_for(millis: u64) -> Result<u64, TimeoutError> {
+    tokio::time::sleep(Duration::from_millis(millis)).await;
+    Ok(millis)
+}
+
+#[tokio::test]
+async fn slow_body_times_out() {
+    assert_eq!(sleep_for(0).await, Ok(0));
+
+    let error = sleep_for(10_000).await.unwrap_err();
+    assert_eq!(error.timeout(), Duration::from_millis(50));
+    assert_eq!(error.to_string(), "timed out after 50ms");
+
+    // The timeout is applied to the body, so the context observes it as the result
+    assert_eq!(*RESULTS.lock().unwrap(), [Ok(0), Err(error)]);
+}
@@ -0,0 +1,16 @@
+use context_manager::AsyncWrapContext;
+use context_manager_macro::async_wrap;
+
+struct Async;
+impl<T> AsyncWrapContext<T> for Async {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+#[async_wrap(Async, args)]
+async fn login(user: &str) -> usize {
+    user.len()
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::async_wrap;
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[async_wrap(Sync)]
+async fn async_foo() -> usize {
+    10
+}
+
+#[tokio::main]
+async fn main() {
+    assert_eq!(async_foo().await, 10);
+}
@@ -0,0 +1,24 @@
+use context_manager::AsyncWrapContext;
+use context_manager_macro::async_wrap;
+
+struct Async;
+impl<T> AsyncWrapContext<T> for Async {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+#[allow(async_fn_in_trait)]
+trait Value {
+    #[async_wrap(Async)]
+    async fn value(&self) -> usize;
+}
+
+struct Implementor;
+impl Value for Implementor {
+    async fn value(&self) -> usize {
+        42
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use context_manager::default_new;
+
+#[derive(Default)]
+struct PrintDuration;
+
+#[default_new]
+impl PrintDuration {
+    fn print(&self) {}
+}
+
+fn main() {}
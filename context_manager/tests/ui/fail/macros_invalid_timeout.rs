@@ -0,0 +1,36 @@
+use context_manager::{AsyncWrapContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+struct Async;
+impl<T> AsyncWrapContext<T> for Async {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[async_wrap(Async, timeout = "5 minutes")]
+async fn invalid_unit() -> Result<(), ()> {
+    Ok(())
+}
+
+#[async_wrap(Async, timeout = "500")]
+async fn missing_unit() -> Result<(), ()> {
+    Ok(())
+}
+
+#[async_wrap(Async, timeout = "500ms")]
+async fn without_result() {}
+
+#[wrap(Sync, timeout = "500ms")]
+fn sync_function() -> Result<(), ()> {
+    Ok(())
+}
+
+fn main() {}
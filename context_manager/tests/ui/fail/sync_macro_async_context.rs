@@ -0,0 +1,18 @@
+use context_manager::AsyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Async;
+impl<T> AsyncWrapContext<T> for Async {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Async)]
+fn sync_foo() -> usize {
+    10
+}
+
+fn main() {
+    assert_eq!(sync_foo(), 10);
+}
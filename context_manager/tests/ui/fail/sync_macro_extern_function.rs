@@ -0,0 +1,24 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Sync)]
+extern "C" fn sync_foo(value: u8) -> u8 {
+    value
+}
+
+#[wrap(Sync)]
+extern "Rust" fn sync_bar(value: u8) -> u8 {
+    value
+}
+
+fn main() {
+    assert_eq!(sync_foo(10), 10);
+    assert_eq!(sync_bar(10), 10);
+}
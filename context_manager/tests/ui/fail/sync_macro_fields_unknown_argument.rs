@@ -0,0 +1,18 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Sync;
+impl<T, I> SyncWrapContext<T, I> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Sync, fields(user_id, order_id))]
+fn sync_foo(user_id: u64) -> u64 {
+    user_id
+}
+
+fn main() {
+    assert_eq!(sync_foo(10), 10);
+}
@@ -0,0 +1,20 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Context;
+impl<T> SyncWrapContext<T> for Context {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Context, alias = "not an identifier")]
+fn invalid() {}
+
+#[wrap(Context, alias = "first")]
+#[wrap(Context, alias = "second")]
+fn duplicated() {}
+
+fn main() {
+    duplicated();
+}
@@ -0,0 +1,14 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Context;
+impl<T> SyncWrapContext<T> for Context {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Context, bind = "not an identifier")]
+fn invalid() {}
+
+fn main() {}
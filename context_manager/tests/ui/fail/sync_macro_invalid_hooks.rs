@@ -0,0 +1,22 @@
+use context_manager::{AsyncWrapContext, SyncWrapContext};
+use context_manager_macro::wrap;
+
+struct Both;
+impl<T> SyncWrapContext<T> for Both {
+    fn new() -> Self {
+        Self
+    }
+}
+impl<T> AsyncWrapContext<T> for Both {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Both, hooks = "async")]
+fn sync_function() {}
+
+#[wrap(Both, hooks = "blocking")]
+async fn async_function() {}
+
+fn main() {}
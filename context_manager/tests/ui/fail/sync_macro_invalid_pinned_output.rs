@@ -0,0 +1,31 @@
+use context_manager::{AsyncWrapContext, SyncWrapContext};
+use context_manager_macro::wrap;
+
+struct Context;
+impl<T> SyncWrapContext<T> for Context {
+    fn new() -> Self {
+        Self
+    }
+}
+impl<T> AsyncWrapContext<T> for Context {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Context as SyncWrapContext<u8>)]
+fn mismatched() -> String {
+    String::new()
+}
+
+#[wrap(Context as AsyncWrapContext<u8>)]
+fn wrong_trait() -> u8 {
+    0
+}
+
+#[wrap(Context as Default)]
+fn unknown_trait() -> u8 {
+    0
+}
+
+fn main() {}
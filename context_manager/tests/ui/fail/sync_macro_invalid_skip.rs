@@ -0,0 +1,26 @@
+use context_manager::{CallerContext, SyncWrapContext};
+use context_manager_macro::wrap;
+
+struct Ctx;
+impl<T> SyncWrapContext<T> for Ctx {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Ctx, skip = "both")]
+fn invalid_value() {}
+
+#[wrap(Ctx, skip = "after", args)]
+fn with_args(value: u8) {
+    let _ = value;
+}
+
+#[wrap(Ctx, skip = "before", fields(value))]
+fn with_fields(value: u8) {
+    let _ = value;
+}
+
+fn main() {
+    let _ = CallerContext::new("main");
+}
@@ -0,0 +1,20 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Sync, span(level = "verbose"))]
+fn sync_foo() {}
+
+#[wrap(Sync, span(kind = "server"))]
+fn sync_bar() {}
+
+#[wrap(Sync, span(name = "a", name = "b"))]
+fn sync_baz() {}
+
+fn main() {}
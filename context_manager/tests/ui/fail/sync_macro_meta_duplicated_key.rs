@@ -0,0 +1,14 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Metrics;
+impl<T> SyncWrapContext<T> for Metrics {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Metrics, meta(service = "auth", service = "billing"))]
+fn login() {}
+
+fn main() {}
@@ -0,0 +1,59 @@
+#![deny(unused_must_use)]
+
+use context_manager::{AsyncWrapContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+struct Context;
+impl<T> SyncWrapContext<T> for Context {
+    fn new() -> Self {
+        Self
+    }
+}
+impl<T> AsyncWrapContext<T> for Context {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Context)]
+#[must_use]
+fn must_use_function() -> u8 {
+    42
+}
+
+#[wrap(Context)]
+fn must_use_type(value: &str) -> Result<u8, std::num::ParseIntError> {
+    value.parse()
+}
+
+#[wrap(Context)]
+#[must_use = "the answer should be used"]
+async fn must_use_async_function() -> u8 {
+    42
+}
+
+#[async_wrap(Context)]
+async fn must_use_async_type(value: &str) -> Result<u8, std::num::ParseIntError> {
+    value.parse()
+}
+
+struct Counter(u8);
+impl Counter {
+    #[wrap(Context)]
+    #[must_use]
+    fn incremented(&self) -> Self {
+        Self(self.0 + 1)
+    }
+}
+
+async fn caller() {
+    must_use_function();
+    must_use_type("1");
+    must_use_async_function().await;
+    must_use_async_type("1").await;
+    Counter(0).incremented();
+}
+
+fn main() {
+    let _ = caller();
+}
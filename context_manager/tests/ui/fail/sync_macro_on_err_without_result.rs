@@ -0,0 +1,18 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Sync, on = "err")]
+fn sync_foo(value: u8) -> u8 {
+    value
+}
+
+fn main() {
+    assert_eq!(sync_foo(10), 10);
+}
@@ -0,0 +1,21 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Sync, on = "error")]
+fn sync_foo(value: Result<u8, u8>) -> Result<u8, u8> {
+    value
+}
+
+#[wrap(Sync, when = "err")]
+fn sync_bar(value: Result<u8, u8>) -> Result<u8, u8> {
+    value
+}
+
+fn main() {}
@@ -0,0 +1,19 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Sync)]
+fn reference_to_local<'a>(value: &'a u8) -> &'a String {
+    let local = value.to_string();
+    &local
+}
+
+fn main() {
+    assert_eq!(reference_to_local(&10), "10");
+}
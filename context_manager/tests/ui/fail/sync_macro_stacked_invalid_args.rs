@@ -0,0 +1,19 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Ctx;
+impl<T> SyncWrapContext<T> for Ctx {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Ctx)]
+#[wrap(Ctx, hooks = "blocking")]
+fn stacked(value: u8) -> u8 {
+    value
+}
+
+fn main() {
+    assert_eq!(stacked(10), 10);
+}
@@ -0,0 +1,25 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+trait Value {
+    #[wrap(Sync)]
+    fn value(&self) -> usize;
+}
+
+struct Implementor;
+impl Value for Implementor {
+    fn value(&self) -> usize {
+        42
+    }
+}
+
+fn main() {
+    assert_eq!(Implementor.value(), 42);
+}
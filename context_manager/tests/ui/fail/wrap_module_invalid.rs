@@ -0,0 +1,19 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap_module;
+
+struct Ctx;
+impl<T> SyncWrapContext<T> for Ctx {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap_module(Ctx, alias = "instrumented")]
+mod aliased {
+    pub fn foo() {}
+}
+
+#[wrap_module(Ctx)]
+fn not_a_module() {}
+
+fn main() {}
@@ -0,0 +1,13 @@
+use context_manager_macro::{async_wrap, wrap};
+
+#[wrap]
+fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+#[async_wrap]
+async fn async_add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+fn main() {}
@@ -0,0 +1,18 @@
+use context_manager::contexts::test::{assert_async_context, assert_sync_context};
+use context_manager::contexts::{ErrorTrailContext, Trail};
+use context_manager::SyncWrapContext;
+
+struct SyncOnly;
+impl<T> SyncWrapContext<T> for SyncOnly {
+    fn new() -> Self {
+        Self
+    }
+}
+
+fn main() {
+    // ErrorTrailContext wraps only functions returning a `Result`
+    assert_sync_context::<ErrorTrailContext, u8>();
+    assert_sync_context::<ErrorTrailContext, Result<u8, Trail<String>>>();
+    // SyncOnly does not implement AsyncWrapContext
+    assert_async_context::<SyncOnly, u8>();
+}
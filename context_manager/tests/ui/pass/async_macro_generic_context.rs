@@ -0,0 +1,48 @@
+use context_manager::{AsyncWrapContext, CallerContext};
+use context_manager_macro::async_wrap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static FORMATTED: AtomicUsize = AtomicUsize::new(0);
+
+trait Format {
+    const NAME: &'static str;
+}
+
+struct Json;
+impl Format for Json {
+    const NAME: &'static str = "json";
+}
+
+mod ctx {
+    use super::*;
+
+    pub struct LoggingContext<F>(PhantomData<F>);
+    impl<F: Format, T> AsyncWrapContext<T> for LoggingContext<F> {
+        async fn new() -> Self {
+            Self(PhantomData)
+        }
+
+        async fn before(&self, _: &mut CallerContext) {
+            assert_eq!(F::NAME, "json");
+            FORMATTED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+#[async_wrap(ctx::LoggingContext<Json>)]
+async fn generic_context() -> usize {
+    1
+}
+
+#[async_wrap(crate::ctx::LoggingContext::<Json>)]
+async fn fully_qualified_path_with_turbofish() -> usize {
+    2
+}
+
+#[tokio::main]
+async fn main() {
+    assert_eq!(generic_context().await, 1);
+    assert_eq!(fully_qualified_path_with_turbofish().await, 2);
+    assert_eq!(FORMATTED.load(Ordering::Relaxed), 2);
+}
@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use context_manager::{default_new, AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+struct Labels {
+    service: &'static str,
+    values: HashMap<&'static str, u64>,
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Self {
+            service: "billing",
+            values: HashMap::from([("retries", 3)]),
+        }
+    }
+}
+
+#[default_new]
+impl<T> SyncWrapContext<T> for Labels {
+    fn before(&self, _: &mut CallerContext) {
+        assert_eq!(self.service, "billing");
+        assert_eq!(self.values.get("retries"), Some(&3));
+    }
+}
+
+#[default_new]
+impl<T> AsyncWrapContext<T> for Labels {
+    async fn before(&self, _: &mut CallerContext) {
+        assert_eq!(self.service, "billing");
+        assert_eq!(self.values.get("retries"), Some(&3));
+    }
+}
+
+#[wrap(Labels)]
+fn sync_charge() -> u64 {
+    42
+}
+
+#[async_wrap(Labels)]
+async fn async_charge() -> u64 {
+    42
+}
+
+#[tokio::main]
+async fn main() {
+    assert_eq!(sync_charge(), 42);
+    assert_eq!(async_charge().await, 42);
+}
@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+
+use context_manager::{CallerContext, SyncWrapContext};
+use context_manager_macro::wrap;
+
+thread_local! {
+    static CALLS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+fn recorded_calls() -> Vec<&'static str> {
+    CALLS.take()
+}
+
+struct RecordCalls;
+impl<T> SyncWrapContext<T> for RecordCalls {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        CALLS.with_borrow_mut(|calls| calls.push(caller_context.fn_name()));
+    }
+}
+
+/// Doubles the value
+#[wrap(RecordCalls, alias = "double_instrumented")]
+fn double(value: u8) -> u8 {
+    value * 2
+}
+
+struct Counter(u8);
+
+impl Counter {
+    #[wrap(RecordCalls, alias = "increment_instrumented")]
+    fn increment(&mut self) -> u8 {
+        self.0 += 1;
+        self.0
+    }
+}
+
+#[wrap(RecordCalls)]
+#[wrap(RecordCalls, alias = "fetch_instrumented")]
+async fn fetch() -> &'static str {
+    "fetched"
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(double(2), 4);
+    assert!(recorded_calls().is_empty());
+    assert_eq!(double_instrumented(2), 4);
+    assert_eq!(recorded_calls(), ["double_instrumented"]);
+
+    let mut counter = Counter(0);
+    assert_eq!(counter.increment(), 1);
+    assert_eq!(counter.increment_instrumented(), 2);
+    assert_eq!(recorded_calls(), ["increment_instrumented"]);
+
+    assert_eq!(fetch().await, "fetched");
+    assert!(recorded_calls().is_empty());
+    assert_eq!(fetch_instrumented().await, "fetched");
+    assert_eq!(
+        recorded_calls(),
+        ["fetch_instrumented", "fetch_instrumented"]
+    );
+}
@@ -0,0 +1,105 @@
+use std::cell::RefCell;
+use std::fmt::Debug;
+
+use context_manager::{CallerContext, SyncWrapContext, WrapContextWithArgs};
+use context_manager_macro::wrap;
+
+thread_local! {
+    static ARGS: RefCell<String> = const { RefCell::new(String::new()) };
+}
+
+fn recorded_args() -> String {
+    ARGS.with_borrow(Clone::clone)
+}
+
+struct RecordArgs;
+impl<T> SyncWrapContext<T> for RecordArgs {
+    fn new() -> Self {
+        Self
+    }
+}
+impl<Args: Debug, T> WrapContextWithArgs<Args, T> for RecordArgs {
+    fn before_with_args(&self, caller_context: &mut CallerContext, args: &Args) {
+        ARGS.set(format!("{}{args:?}", caller_context.fn_name()));
+    }
+}
+
+struct ReadArgs;
+impl<T> SyncWrapContext<T> for ReadArgs {
+    fn new() -> Self {
+        Self
+    }
+}
+impl<'a, T> WrapContextWithArgs<(u64, &'a str), T> for ReadArgs {
+    fn before_with_args(&self, _: &mut CallerContext, (id, name): &(u64, &'a str)) {
+        ARGS.set(format!("id={id} name={name}"));
+    }
+}
+
+struct Counter {
+    value: u32,
+}
+
+impl Counter {
+    #[wrap(RecordArgs, args)]
+    fn add(&mut self, amount: u32) -> u32 {
+        self.value += amount;
+        self.value
+    }
+}
+
+#[wrap(RecordArgs, args)]
+fn no_args() -> u8 {
+    42
+}
+
+#[wrap(ReadArgs, args)]
+fn greet(id: u64, name: &str) -> String {
+    format!("Hello {name} ({id})")
+}
+
+#[wrap(RecordArgs, args)]
+fn sum((a, b): (u8, u8), _: u8, mut total: u16) -> u16 {
+    total += u16::from(a) + u16::from(b);
+    total
+}
+
+#[wrap(RecordArgs, args)]
+fn consume(values: Vec<u8>) -> Vec<u8> {
+    values.into_iter().rev().collect()
+}
+
+#[wrap(RecordArgs, args, on = "ok")]
+fn parse(value: &str) -> Result<u8, std::num::ParseIntError> {
+    value.parse()
+}
+
+#[wrap(RecordArgs, args)]
+async fn async_length(value: String) -> usize {
+    value.len()
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(no_args(), 42);
+    assert_eq!(recorded_args(), "no_args()");
+
+    assert_eq!(greet(7, "Ada"), "Hello Ada (7)");
+    assert_eq!(recorded_args(), "id=7 name=Ada");
+
+    let mut counter = Counter { value: 1 };
+    assert_eq!(counter.add(2), 3);
+    assert_eq!(recorded_args(), "add(2,)");
+
+    assert_eq!(sum((1, 2), 0, 10), 13);
+    assert_eq!(recorded_args(), "sum(1, 2, 10)");
+
+    assert_eq!(consume(vec![1, 2, 3]), [3, 2, 1]);
+    assert_eq!(recorded_args(), "consume([1, 2, 3],)");
+
+    assert_eq!(parse("12"), Ok(12));
+    assert_eq!(recorded_args(), "parse(\"12\",)");
+
+    assert_eq!(async_length("four".to_string()).await, 4);
+    assert_eq!(recorded_args(), "async_length(\"four\",)");
+}
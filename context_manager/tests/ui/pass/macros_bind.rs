@@ -0,0 +1,156 @@
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext, WrapContextWithArgs};
+use context_manager_macro::{async_wrap, wrap};
+
+struct Noop;
+impl<T> SyncWrapContext<T> for Noop {
+    fn new() -> Self {
+        Self
+    }
+}
+impl<Args, T> WrapContextWithArgs<Args, T> for Noop {}
+impl<T> AsyncWrapContext<T> for Noop {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+// Inserts an extension in the caller context provided to the hooks
+struct Tag;
+impl<T> SyncWrapContext<T> for Tag {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        caller_context.insert_extension("sync");
+    }
+}
+impl<Args, T> WrapContextWithArgs<Args, T> for Tag {}
+impl<T> AsyncWrapContext<T> for Tag {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        caller_context.insert_extension("async");
+    }
+}
+
+#[wrap(Noop, bind = "ctx")]
+fn greet(name: &str) -> String {
+    format!("{}: Hello {name}", ctx.fn_name())
+}
+
+#[wrap(Noop, bind = "ctx", meta(service = "auth"))]
+fn service() -> &'static str {
+    ctx.meta("service").unwrap()
+}
+
+// The binding can be shadowed by the body
+#[wrap(Noop, bind = "ctx")]
+fn shadowed() -> u8 {
+    let ctx = ctx.arg_names().len() as u8 + 1;
+    ctx
+}
+
+// The macro generated code does not clash with the name of the binding
+#[wrap(Noop, bind = "caller_context", args)]
+fn with_args(value: u8) -> (&'static str, u8) {
+    (caller_context.fn_name(), value)
+}
+
+#[wrap(Noop, bind = "ctx")]
+async fn sync_hooks() -> bool {
+    ctx.is_async()
+}
+
+#[async_wrap(Noop, bind = "context")]
+async fn async_hooks() -> &'static [&'static str] {
+    tokio::task::yield_now().await;
+    context.arg_names()
+}
+
+// The bare function binds the caller context as well, describing itself
+#[wrap(Noop, bind = "ctx", alias = "aliased_instrumented")]
+fn aliased() -> &'static str {
+    ctx.fn_name()
+}
+
+struct Counter;
+
+impl Counter {
+    #[wrap(Noop, bind = "ctx")]
+    fn describe(&self, amount: u32) -> String {
+        format!("{}{:?}={amount}", ctx.fn_name(), ctx.arg_names())
+    }
+}
+
+// The binding is the caller context provided to the hooks, so the extensions inserted by them are visible
+#[wrap(Tag, bind = "ctx")]
+fn tagged() -> Option<&'static str> {
+    ctx.extension::<&str>().copied()
+}
+
+#[wrap(Tag, bind = "ctx", args)]
+fn tagged_with_args(value: u8) -> (Option<&'static str>, u8) {
+    (ctx.extension::<&str>().copied(), value)
+}
+
+#[wrap(Tag, bind = "ctx")]
+async fn tagged_sync_hooks() -> Option<&'static str> {
+    tokio::task::yield_now().await;
+    ctx.extension::<&str>().copied()
+}
+
+#[async_wrap(Tag, bind = "ctx")]
+async fn tagged_async_hooks() -> Option<&'static str> {
+    tokio::task::yield_now().await;
+    ctx.extension::<&str>().copied()
+}
+
+// With stacked macros the binding is the caller context of the layer providing it
+#[wrap(Noop, bind = "ctx")]
+#[wrap(Tag)]
+fn tagged_inner_layer() -> Option<&'static str> {
+    ctx.extension::<&str>().copied()
+}
+
+struct Slot(u8);
+
+impl Slot {
+    // References to the captured arguments can be returned
+    #[wrap(Tag, bind = "ctx")]
+    fn get_mut(&mut self) -> &mut u8 {
+        let _ = ctx.fn_name();
+        &mut self.0
+    }
+}
+
+fn check(ctx: &CallerContext) -> &'static str {
+    ctx.fn_name()
+}
+
+#[wrap(Noop, bind = "ctx")]
+fn forwarded() -> &'static str {
+    check(ctx)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(greet("Ada"), "greet: Hello Ada");
+    assert_eq!(service(), "auth");
+    assert_eq!(shadowed(), 1);
+    assert_eq!(with_args(3), ("with_args", 3));
+    assert!(sync_hooks().await);
+    assert_eq!(async_hooks().await, [] as [&str; 0]);
+    assert_eq!(Counter.describe(2), "describe[\"self\", \"amount\"]=2");
+    assert_eq!(forwarded(), "forwarded");
+    assert_eq!(aliased(), "aliased");
+    assert_eq!(aliased_instrumented(), "aliased_instrumented");
+    assert_eq!(tagged(), Some("sync"));
+    assert_eq!(tagged_with_args(1), (Some("sync"), 1));
+    assert_eq!(tagged_sync_hooks().await, Some("sync"));
+    assert_eq!(tagged_async_hooks().await, Some("async"));
+    assert_eq!(tagged_inner_layer(), None);
+    *Slot(1).get_mut() += 1;
+}
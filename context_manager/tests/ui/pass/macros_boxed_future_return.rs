@@ -0,0 +1,74 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use context_manager::{CallerContext, SyncWrapContext};
+use context_manager_macro::wrap;
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+static POLLED: AtomicBool = AtomicBool::new(false);
+static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Context observing the value returned by the wrapped function, which is the future itself
+struct RecordAfter;
+impl<T> SyncWrapContext<T> for RecordAfter {
+    fn new() -> Self {
+        Self
+    }
+
+    fn after(self, caller_context: &CallerContext, _: &T) {
+        EVENTS.lock().unwrap().push(format!(
+            "after {} (polled: {})",
+            caller_context.fn_name(),
+            POLLED.load(Ordering::Relaxed)
+        ));
+    }
+}
+
+// The function is not async, so the context wraps the creation of the future:
+// `after` receives the boxed future as result, before it is polled, and not its eventual output.
+#[wrap(RecordAfter)]
+fn fetch(id: u8) -> BoxFuture<u8> {
+    Box::pin(async move {
+        POLLED.store(true, Ordering::Relaxed);
+        id * 2
+    })
+}
+
+/// Context reading the boxed future returned by the wrapped function
+struct NotPolled;
+impl SyncWrapContext<Pin<Box<dyn Future<Output = u8>>>> for NotPolled {
+    fn new() -> Self {
+        Self
+    }
+
+    fn after(self, _: &CallerContext, future: &Pin<Box<dyn Future<Output = u8>>>) {
+        // The future can be inspected, but it cannot be awaited from the hook
+        let _: &dyn Future<Output = u8> = &**future;
+    }
+}
+
+trait Service {
+    fn call(&self, id: u8) -> Pin<Box<dyn Future<Output = u8> + '_>>;
+}
+
+struct Doubler;
+impl Service for Doubler {
+    #[wrap(NotPolled)]
+    fn call(&self, id: u8) -> Pin<Box<dyn Future<Output = u8> + '_>> {
+        Box::pin(async move { id * 2 })
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let future = fetch(21);
+    assert_eq!(*EVENTS.lock().unwrap(), ["after fetch (polled: false)"]);
+    assert_eq!(future.await, 42);
+    assert!(POLLED.load(Ordering::Relaxed));
+
+    let service: &dyn Service = &Doubler;
+    assert_eq!(service.call(4).await, 8);
+}
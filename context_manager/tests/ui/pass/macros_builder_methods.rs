@@ -0,0 +1,80 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static BEFORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountBefore;
+impl<T> SyncWrapContext<T> for CountBefore {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, _: &mut CallerContext) {
+        BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+impl<T> AsyncWrapContext<T> for CountBefore {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, _: &mut CallerContext) {
+        BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Request {
+    url: String,
+    retries: u32,
+    headers: Vec<(String, String)>,
+}
+
+impl Request {
+    #[wrap(CountBefore)]
+    fn with_url(mut self, url: &str) -> Self {
+        self.url = url.to_string();
+        self
+    }
+
+    #[wrap(CountBefore)]
+    fn with_retries(self, retries: u32) -> Self {
+        Self { retries, ..self }
+    }
+
+    #[wrap(CountBefore, args)]
+    fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    #[async_wrap(CountBefore)]
+    async fn resolved(mut self) -> Self {
+        self.url = self.url.replace("http://", "https://");
+        self
+    }
+}
+
+impl<T> context_manager::WrapContextWithArgs<T, Request> for CountBefore {}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let request = Request::default()
+        .with_url("http://example.com")
+        .with_retries(3)
+        .with_header("accept", "application/json")
+        .resolved()
+        .await;
+
+    assert_eq!(
+        request,
+        Request {
+            url: "https://example.com".to_string(),
+            retries: 3,
+            headers: vec![("accept".to_string(), "application/json".to_string())],
+        },
+    );
+    assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 4);
+}
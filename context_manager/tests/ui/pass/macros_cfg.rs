@@ -0,0 +1,114 @@
+use std::cell::RefCell;
+
+use context_manager::{CallerContext, SyncWrapContext};
+use context_manager_macro::wrap;
+
+thread_local! {
+    static CALLS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+}
+
+fn recorded_calls() -> Vec<&'static str> {
+    CALLS.take()
+}
+
+struct First;
+impl<T> SyncWrapContext<T> for First {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, _: &mut CallerContext) {
+        CALLS.with_borrow_mut(|calls| calls.push("first"));
+    }
+}
+
+struct Second;
+impl<T> SyncWrapContext<T> for Second {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, _: &mut CallerContext) {
+        CALLS.with_borrow_mut(|calls| calls.push("second"));
+    }
+}
+
+// `#[cfg]` listed before `#[wrap]`
+#[cfg(all())]
+#[wrap(First)]
+fn cfg_first() -> &'static str {
+    "enabled"
+}
+
+#[cfg(any())]
+#[wrap(First)]
+fn cfg_first() -> &'static str {
+    "disabled"
+}
+
+// `#[cfg]` listed after `#[wrap]`
+#[wrap(First)]
+#[cfg(any())]
+fn cfg_last() -> &'static str {
+    "disabled"
+}
+
+#[wrap(First)]
+#[cfg(all())]
+fn cfg_last() -> &'static str {
+    "enabled"
+}
+
+// `#[cfg]` listed between stacked macros
+#[wrap(First)]
+#[cfg(all())]
+#[wrap(Second)]
+fn cfg_between() -> &'static str {
+    "enabled"
+}
+
+#[wrap(First)]
+#[cfg(any())]
+#[wrap(Second)]
+fn cfg_between() -> &'static str {
+    "disabled"
+}
+
+// Macros enabled conditionally
+#[wrap(First)]
+#[cfg_attr(all(), wrap(Second))]
+#[cfg_attr(any(), wrap(Unknown))]
+fn cfg_attr() -> &'static str {
+    "enabled"
+}
+
+struct Methods;
+
+impl Methods {
+    #[cfg(all())]
+    #[wrap(First)]
+    fn method(&self) -> &'static str {
+        "enabled"
+    }
+
+    #[wrap(First)]
+    #[cfg(any())]
+    fn method(&self) -> &'static str {
+        "disabled"
+    }
+}
+
+fn main() {
+    assert_eq!(cfg_first(), "enabled");
+    assert_eq!(cfg_last(), "enabled");
+    assert_eq!(recorded_calls(), ["first", "first"]);
+
+    assert_eq!(cfg_between(), "enabled");
+    assert_eq!(recorded_calls(), ["first", "second"]);
+
+    assert_eq!(cfg_attr(), "enabled");
+    assert_eq!(recorded_calls(), ["first", "second"]);
+
+    assert_eq!(Methods.method(), "enabled");
+    assert_eq!(recorded_calls(), ["first"]);
+}
@@ -0,0 +1,90 @@
+use std::cell::Cell;
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+thread_local! {
+    static ARG_NAMES: Cell<&'static [&'static str]> = const { Cell::new(&[]) };
+}
+
+struct RecordArgNames;
+impl<T> SyncWrapContext<T> for RecordArgNames {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        ARG_NAMES.set(caller_context.arg_names());
+    }
+}
+impl<T> AsyncWrapContext<T> for RecordArgNames {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        ARG_NAMES.set(caller_context.arg_names());
+    }
+}
+
+struct Point {
+    x: i32,
+    y: i32,
+}
+
+struct Wrapper(Point);
+
+impl Point {
+    #[wrap(RecordArgNames)]
+    fn translate(&self, (dx, dy): (i32, i32)) -> Point {
+        Point {
+            x: self.x + dx,
+            y: self.y + dy,
+        }
+    }
+}
+
+#[wrap(RecordArgNames)]
+fn sum((a, b): (u8, u8), _: u8) -> u8 {
+    a + b
+}
+
+#[wrap(RecordArgNames)]
+fn manhattan(Point { x, y: ordinate }: Point, Wrapper(Point { x: x2, .. }): Wrapper) -> i32 {
+    x.abs() + ordinate.abs() + x2
+}
+
+#[wrap(RecordArgNames)]
+fn first_and_rest(&[first, ref rest @ ..]: &[u8; 3], mut r#type: u8) -> u8 {
+    r#type += first;
+    r#type + rest.iter().sum::<u8>()
+}
+
+#[async_wrap(RecordArgNames)]
+async fn async_sum((a, b): (u8, u8)) -> u8 {
+    a + b
+}
+
+fn assert_arg_names<T>(expected: &[&str], result: T) -> T {
+    assert_eq!(ARG_NAMES.get(), expected);
+    result
+}
+
+#[tokio::main]
+async fn main() {
+    assert_eq!(assert_arg_names(&["a", "b"], sum((1, 2), 3)), 3);
+    assert_eq!(
+        assert_arg_names(
+            &["x", "ordinate", "x2"],
+            manhattan(Point { x: -1, y: 2 }, Wrapper(Point { x: 3, y: 4 }))
+        ),
+        6
+    );
+    assert_eq!(
+        assert_arg_names(&["first", "rest", "type"], first_and_rest(&[1, 2, 3], 4)),
+        10
+    );
+    let translated = assert_arg_names(&["self", "dx", "dy"], Point { x: 1, y: 2 }.translate((3, 4)));
+    assert_eq!((translated.x, translated.y), (4, 6));
+    assert_eq!(assert_arg_names(&["a", "b"], async_sum((1, 2)).await), 3);
+}
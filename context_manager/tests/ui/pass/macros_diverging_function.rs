@@ -0,0 +1,72 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static BEFORE: AtomicUsize = AtomicUsize::new(0);
+static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+struct CountHooks;
+impl<T> SyncWrapContext<T> for CountHooks {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, _: &mut CallerContext) {
+        BEFORE.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn after(self, _: &CallerContext, _: &T) {
+        AFTER.fetch_add(1, Ordering::Relaxed);
+    }
+}
+impl<T> AsyncWrapContext<T> for CountHooks {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, _: &mut CallerContext) {
+        BEFORE.fetch_add(1, Ordering::Relaxed);
+    }
+
+    async fn after(self, _: &CallerContext, _: &T) {
+        AFTER.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// The loop can only be left by unwinding, as the function never returns
+#[wrap(CountHooks)]
+#[wrap(CountHooks)]
+fn event_loop(iterations: usize) -> ! {
+    let mut iteration = 0;
+    loop {
+        iteration += 1;
+        if iteration == iterations {
+            panic!("event loop stopped");
+        }
+    }
+}
+
+#[async_wrap(CountHooks)]
+async fn async_event_loop(iterations: usize) -> ! {
+    let mut iteration = 0;
+    loop {
+        iteration += 1;
+        if iteration == iterations {
+            panic!("async event loop stopped");
+        }
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    std::panic::set_hook(Box::new(|_| {}));
+
+    assert!(std::panic::catch_unwind(|| event_loop(3)).is_err());
+    assert_eq!(BEFORE.swap(0, Ordering::Relaxed), 2);
+
+    assert!(tokio::spawn(async_event_loop(3)).await.is_err());
+    assert_eq!(BEFORE.swap(0, Ordering::Relaxed), 1);
+
+    assert_eq!(AFTER.load(Ordering::Relaxed), 0);
+}
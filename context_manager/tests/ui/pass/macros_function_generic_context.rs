@@ -0,0 +1,117 @@
+use std::marker::PhantomData;
+use std::sync::Mutex;
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+trait Named {
+    const NAME: &'static str;
+}
+
+struct Order;
+impl Named for Order {
+    const NAME: &'static str = "order";
+}
+
+struct User;
+impl Named for User {
+    const NAME: &'static str = "user";
+}
+
+struct LoggingContext<F>(PhantomData<F>);
+impl<F: Named, T> SyncWrapContext<T> for LoggingContext<F> {
+    fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        LOGGED
+            .lock()
+            .unwrap()
+            .push(format!("{}:{}", caller_context.fn_name(), F::NAME));
+    }
+}
+impl<F: Named, T> AsyncWrapContext<T> for LoggingContext<F> {
+    async fn new() -> Self {
+        Self(PhantomData)
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        LOGGED
+            .lock()
+            .unwrap()
+            .push(format!("{}:{}", caller_context.fn_name(), F::NAME));
+    }
+}
+
+#[wrap(LoggingContext<T>)]
+fn load<T: Named>() -> &'static str {
+    T::NAME
+}
+
+#[wrap(LoggingContext::<T>, on = "err")]
+fn try_load<T: Named>(fail: bool) -> Result<&'static str, ()> {
+    if fail {
+        Err(())
+    } else {
+        Ok(T::NAME)
+    }
+}
+
+#[wrap(LoggingContext<T>)]
+async fn load_async<T: Named>() -> &'static str {
+    T::NAME
+}
+
+#[async_wrap(LoggingContext<T>)]
+async fn load_async_hooks<T>() -> &'static str
+where
+    T: Named,
+{
+    T::NAME
+}
+
+struct Repository<T>(PhantomData<T>);
+
+impl<T: Named> Repository<T> {
+    #[wrap(LoggingContext<T>)]
+    fn find(&self) -> &'static str {
+        T::NAME
+    }
+
+    #[wrap(LoggingContext<U>, alias = "find_other_instrumented")]
+    fn find_other<U: Named>(&self) -> (&'static str, &'static str) {
+        (T::NAME, U::NAME)
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(load::<Order>(), "order");
+    assert_eq!(load::<User>(), "user");
+    assert_eq!(try_load::<Order>(false), Ok("order"));
+    assert_eq!(try_load::<User>(true), Err(()));
+    assert_eq!(load_async::<Order>().await, "order");
+    assert_eq!(load_async_hooks::<User>().await, "user");
+
+    let repository = Repository::<Order>(PhantomData);
+    assert_eq!(repository.find(), "order");
+    assert_eq!(repository.find_other::<User>(), ("order", "user"));
+    assert_eq!(repository.find_other_instrumented::<User>(), ("order", "user"));
+
+    assert_eq!(
+        *LOGGED.lock().unwrap(),
+        [
+            "load:order",
+            "load:user",
+            "try_load:order",
+            "try_load:user",
+            "load_async:order",
+            "load_async_hooks:user",
+            "find:order",
+            "find_other_instrumented:user",
+        ]
+    );
+}
@@ -0,0 +1,55 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static ASYNC_HOOKS: AtomicBool = AtomicBool::new(false);
+
+struct RecordHooks;
+impl<T> SyncWrapContext<T> for RecordHooks {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, _: &mut CallerContext) {
+        ASYNC_HOOKS.store(false, Ordering::Relaxed);
+    }
+}
+impl<T> AsyncWrapContext<T> for RecordHooks {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, _: &mut CallerContext) {
+        ASYNC_HOOKS.store(true, Ordering::Relaxed);
+    }
+}
+
+#[wrap(RecordHooks)]
+async fn default_hooks() -> bool {
+    ASYNC_HOOKS.load(Ordering::Relaxed)
+}
+
+#[wrap(RecordHooks, hooks = "async")]
+async fn async_hooks() -> bool {
+    ASYNC_HOOKS.load(Ordering::Relaxed)
+}
+
+#[wrap(RecordHooks, hooks = "sync")]
+async fn explicit_sync_hooks() -> bool {
+    ASYNC_HOOKS.load(Ordering::Relaxed)
+}
+
+#[async_wrap(RecordHooks, hooks = "sync")]
+async fn async_wrap_sync_hooks() -> bool {
+    ASYNC_HOOKS.load(Ordering::Relaxed)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert!(!default_hooks().await);
+    assert!(async_hooks().await);
+    assert!(!explicit_sync_hooks().await);
+    assert!(async_hooks().await);
+    assert!(!async_wrap_sync_hooks().await);
+}
@@ -0,0 +1,110 @@
+use std::fmt::{Debug, Display};
+use std::future::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext, WrapContextWithArgs};
+use context_manager_macro::{async_wrap, wrap};
+
+static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountCalls;
+impl<T> SyncWrapContext<T> for CountCalls {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, _: &mut CallerContext) {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+impl<T> AsyncWrapContext<T> for CountCalls {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, _: &mut CallerContext) {
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl<Args: Debug, T> WrapContextWithArgs<Args, T> for CountCalls {
+    fn before_with_args(&self, _: &mut CallerContext, args: &Args) {
+        assert_eq!(format!("{args:?}"), "(1, \"a\")");
+        CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[wrap(CountCalls)]
+fn sum(values: impl Iterator<Item = u8>) -> u8 {
+    values.sum()
+}
+
+#[wrap(CountCalls)]
+fn join(values: impl IntoIterator<Item = impl Display>, separator: impl AsRef<str>) -> String {
+    values
+        .into_iter()
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(separator.as_ref())
+}
+
+#[wrap(CountCalls)]
+fn apply(value: u8, operation: impl FnOnce(u8) -> u8) -> u8 {
+    operation(value)
+}
+
+#[wrap(CountCalls, alias = "describe_instrumented")]
+fn describe(value: &impl Display) -> String {
+    format!("<{value}>")
+}
+
+#[wrap(CountCalls, args)]
+fn debug_args(first: impl Debug, second: impl Debug + Clone) -> String {
+    format!("{first:?} {:?}", second.clone())
+}
+
+#[wrap(CountCalls)]
+async fn sum_async(values: impl Iterator<Item = u8>) -> u8 {
+    values.sum()
+}
+
+#[async_wrap(CountCalls)]
+async fn join_async(values: impl IntoIterator<Item = u8>, load: impl Future<Output = u8>) -> u8 {
+    values.into_iter().sum::<u8>() + load.await
+}
+
+#[async_wrap(CountCalls)]
+async fn describe_async(value: &(impl Display + Sync)) -> String {
+    format!("<{value}>")
+}
+
+struct Formatter;
+
+impl Formatter {
+    #[wrap(CountCalls)]
+    fn format(&self, prefix: impl Display, value: impl Display) -> String {
+        format!("{prefix}{value}")
+    }
+
+    #[async_wrap(CountCalls)]
+    async fn format_async(&self, value: impl Display + Send) -> String {
+        value.to_string()
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(sum([1, 2, 3].into_iter()), 6);
+    assert_eq!(join([1, 2], ", "), "1, 2");
+    assert_eq!(join(vec!["a"], String::from("-")), "a");
+    assert_eq!(apply(2, |value| value * 3), 6);
+    assert_eq!(describe(&1), "<1>");
+    assert_eq!(describe_instrumented(&"a"), "<a>");
+    assert_eq!(debug_args(1, "a"), "1 \"a\"");
+    assert_eq!(sum_async(1..3).await, 3);
+    assert_eq!(join_async([1, 2], async { 3 }).await, 6);
+    assert_eq!(describe_async(&2).await, "<2>");
+    assert_eq!(Formatter.format('#', 1), "#1");
+    assert_eq!(Formatter.format_async(2).await, "2");
+    assert_eq!(CALLS.load(Ordering::Relaxed), 11);
+}
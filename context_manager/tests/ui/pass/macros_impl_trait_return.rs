@@ -0,0 +1,87 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+struct CountAfter;
+impl<T> SyncWrapContext<T> for CountAfter {
+    fn new() -> Self {
+        Self
+    }
+
+    fn after(self, _: &CallerContext, _: &T) {
+        AFTER.fetch_add(1, Ordering::Relaxed);
+    }
+}
+impl<T> AsyncWrapContext<T> for CountAfter {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn after(self, _: &CallerContext, _: &T) {
+        AFTER.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+struct AssertDisplay;
+impl<T: Display> SyncWrapContext<T> for AssertDisplay {
+    fn new() -> Self {
+        Self
+    }
+
+    fn after(self, _: &CallerContext, result: &T) {
+        assert_eq!(result.to_string(), "42");
+    }
+}
+
+#[wrap(CountAfter)]
+#[wrap(AssertDisplay)]
+fn display() -> impl Display {
+    42
+}
+
+#[wrap(CountAfter)]
+fn borrowed_iterator(values: &[u8]) -> impl Iterator<Item = u8> + '_ {
+    values.iter().map(|value| value * 2)
+}
+
+#[wrap(CountAfter)]
+fn adder(offset: u8) -> impl Fn(u8) -> u8 {
+    move |value| value + offset
+}
+
+#[wrap(CountAfter, on = "ok")]
+fn fallible(value: &str) -> Result<impl Display, std::num::ParseIntError> {
+    value.parse::<u8>()
+}
+
+#[wrap(CountAfter)]
+async fn sync_context_async_display() -> impl Display {
+    "sync"
+}
+
+#[async_wrap(CountAfter)]
+async fn async_display() -> impl Display {
+    "async"
+}
+
+#[async_wrap(CountAfter)]
+async fn async_boxed_display() -> Box<dyn Display + Send> {
+    Box::new(1)
+}
+
+#[tokio::main]
+async fn main() {
+    assert_eq!(display().to_string(), "42");
+    assert_eq!(borrowed_iterator(&[1, 2]).collect::<Vec<_>>(), [2, 4]);
+    assert_eq!(adder(1)(1), 2);
+    assert_eq!(fallible("1").unwrap().to_string(), "1");
+    assert!(fallible("NaN").is_err());
+    assert_eq!(sync_context_async_display().await.to_string(), "sync");
+    assert_eq!(async_display().await.to_string(), "async");
+    assert_eq!(async_boxed_display().await.to_string(), "1");
+    assert_eq!(AFTER.load(Ordering::Relaxed), 7);
+}
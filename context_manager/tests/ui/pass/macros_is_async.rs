@@ -0,0 +1,50 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static IS_ASYNC: AtomicBool = AtomicBool::new(false);
+
+struct RecordIsAsync;
+impl<T> SyncWrapContext<T> for RecordIsAsync {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        IS_ASYNC.store(caller_context.is_async(), Ordering::Relaxed);
+    }
+}
+impl<T> AsyncWrapContext<T> for RecordIsAsync {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        IS_ASYNC.store(caller_context.is_async(), Ordering::Relaxed);
+    }
+}
+
+#[wrap(RecordIsAsync)]
+fn sync_function() -> bool {
+    IS_ASYNC.load(Ordering::Relaxed)
+}
+
+#[wrap(RecordIsAsync)]
+async fn sync_context_async_function() -> bool {
+    IS_ASYNC.load(Ordering::Relaxed)
+}
+
+#[async_wrap(RecordIsAsync)]
+async fn async_function() -> bool {
+    IS_ASYNC.load(Ordering::Relaxed)
+}
+
+#[tokio::main]
+async fn main() {
+    assert!(!CallerContext::new("manual").is_async());
+    assert!(!sync_function());
+    assert!(sync_context_async_function().await);
+    assert!(!sync_function());
+    assert!(async_function().await);
+}
@@ -0,0 +1,94 @@
+use std::cell::RefCell;
+
+use context_manager::{CallerContext, SyncWrapContext};
+use context_manager_macro::wrap;
+
+thread_local! {
+    static CALLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn recorded_calls() -> Vec<String> {
+    CALLS.take()
+}
+
+struct RecordCalls;
+impl<T> SyncWrapContext<T> for RecordCalls {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        CALLS.with_borrow_mut(|calls| {
+            calls.push(format!(
+                "{}({})",
+                caller_context.fn_name(),
+                caller_context.arg_names().join(", ")
+            ));
+        });
+    }
+}
+
+// The name, the arguments and the context of the wrapped functions come from metavariables
+macro_rules! wrapped_getter {
+    ($vis:vis $name:ident, $ctx:ty, $arg:ident: $arg_type:ty => $value:expr) => {
+        #[wrap($ctx)]
+        $vis fn $name($arg: $arg_type) -> $arg_type {
+            let result = $value;
+            result
+        }
+    };
+}
+
+wrapped_getter!(pub double, RecordCalls, value: u8 => value * 2);
+wrapped_getter!(r#type, RecordCalls, r#in: u8 => r#in);
+
+// The attribute is provided to the macro, and the function is built by it
+macro_rules! with_attribute {
+    (#[$attribute:meta] $name:ident) => {
+        #[$attribute]
+        fn $name(&self, context: u8) -> u8 {
+            self.0 + context
+        }
+    };
+}
+
+struct Base(u8);
+
+impl Base {
+    with_attribute!(#[wrap(RecordCalls, args)] add);
+}
+
+impl<Args, T> context_manager::WrapContextWithArgs<Args, T> for RecordCalls {}
+
+// Locals of the macro do not clash with the ones generated by the attribute
+macro_rules! async_function {
+    ($name:ident) => {
+        #[wrap(RecordCalls)]
+        #[wrap(RecordCalls)]
+        async fn $name() -> u8 {
+            let caller_context = 1;
+            let context = 2;
+            caller_context + context
+        }
+    };
+}
+
+async_function!(nested);
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(double(2), 4);
+    assert_eq!(r#type(3), 3);
+    assert_eq!(Base(1).add(2), 3);
+    assert_eq!(nested().await, 3);
+    assert_eq!(
+        recorded_calls(),
+        [
+            "double(value)",
+            "type(in)",
+            "add(self, context)",
+            "nested()",
+            "nested()"
+        ]
+    );
+}
@@ -0,0 +1,59 @@
+use std::sync::Mutex;
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static LABELS: Mutex<Vec<(&str, Option<&str>, Option<&str>)>> = Mutex::new(Vec::new());
+
+struct Metrics;
+impl<T> SyncWrapContext<T> for Metrics {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        record(caller_context);
+    }
+}
+impl<T> AsyncWrapContext<T> for Metrics {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        record(caller_context);
+    }
+}
+
+fn record(caller_context: &CallerContext) {
+    LABELS.lock().unwrap().push((
+        caller_context.fn_name(),
+        caller_context.meta("service"),
+        caller_context.meta("tier"),
+    ));
+}
+
+#[wrap(Metrics, meta(service = "auth", tier = "edge"))]
+fn login() {}
+
+#[wrap(Metrics)]
+fn untagged() {}
+
+#[async_wrap(Metrics, meta(service = "billing"),)]
+async fn charge() {}
+
+#[tokio::main]
+async fn main() {
+    login();
+    untagged();
+    charge().await;
+
+    assert_eq!(
+        *LABELS.lock().unwrap(),
+        [
+            ("login", Some("auth"), Some("edge")),
+            ("untagged", None, None),
+            ("charge", Some("billing"), None),
+        ]
+    );
+}
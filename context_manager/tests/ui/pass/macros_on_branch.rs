@@ -0,0 +1,77 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+struct CountAfter;
+impl<T> SyncWrapContext<T> for CountAfter {
+    fn new() -> Self {
+        Self
+    }
+
+    fn after(self, _: &CallerContext, _: &T) {
+        AFTER.fetch_add(1, Ordering::Relaxed);
+    }
+}
+impl<T> AsyncWrapContext<T> for CountAfter {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn after(self, _: &CallerContext, _: &T) {
+        AFTER.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[wrap(CountAfter, on = "always")]
+fn on_always(value: Result<u8, u8>) -> Result<u8, u8> {
+    value
+}
+
+#[wrap(CountAfter, on = "ok")]
+fn on_ok(value: Result<u8, u8>) -> Result<u8, u8> {
+    value
+}
+
+#[wrap(CountAfter, on = "err")]
+fn on_err(value: Result<u8, u8>) -> std::result::Result<u8, u8> {
+    value
+}
+
+// Functions not returning a `Result` are always successful
+#[wrap(CountAfter, on = "ok")]
+fn on_ok_without_result(value: u8) -> u8 {
+    value
+}
+
+#[async_wrap(CountAfter, on = "err")]
+async fn async_on_err(value: Result<u8, u8>) -> Result<u8, u8> {
+    value
+}
+
+#[wrap(CountAfter, on = "ok")]
+async fn sync_context_async_on_ok(value: Result<u8, u8>) -> Result<u8, u8> {
+    value
+}
+
+fn assert_after_calls<T>(expected: usize, result: T) -> T {
+    assert_eq!(AFTER.swap(0, Ordering::Relaxed), expected);
+    result
+}
+
+#[tokio::main]
+async fn main() {
+    assert_eq!(assert_after_calls(1, on_always(Ok(1))), Ok(1));
+    assert_eq!(assert_after_calls(1, on_always(Err(1))), Err(1));
+    assert_eq!(assert_after_calls(1, on_ok(Ok(1))), Ok(1));
+    assert_eq!(assert_after_calls(0, on_ok(Err(1))), Err(1));
+    assert_eq!(assert_after_calls(0, on_err(Ok(1))), Ok(1));
+    assert_eq!(assert_after_calls(1, on_err(Err(1))), Err(1));
+    assert_eq!(assert_after_calls(1, on_ok_without_result(1)), 1);
+    assert_eq!(assert_after_calls(0, async_on_err(Ok(1)).await), Ok(1));
+    assert_eq!(assert_after_calls(1, async_on_err(Err(1)).await), Err(1));
+    assert_eq!(assert_after_calls(1, sync_context_async_on_ok(Ok(1)).await), Ok(1));
+    assert_eq!(assert_after_calls(0, sync_context_async_on_ok(Err(1)).await), Err(1));
+}
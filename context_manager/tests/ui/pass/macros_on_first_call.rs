@@ -0,0 +1,79 @@
+use std::sync::Mutex;
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn record(event: &str, caller_context: &CallerContext) {
+    EVENTS
+        .lock()
+        .unwrap()
+        .push(format!("{event}:{}", caller_context.fn_name()));
+}
+
+struct Registration;
+impl<T> SyncWrapContext<T> for Registration {
+    fn new() -> Self {
+        Self
+    }
+
+    fn on_first_call(&self, caller_context: &CallerContext) {
+        record("first_call", caller_context);
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        record("before", caller_context);
+    }
+}
+impl<T> AsyncWrapContext<T> for Registration {
+    async fn new() -> Self {
+        Self
+    }
+
+    fn on_first_call(&self, caller_context: &CallerContext) {
+        record("first_call", caller_context);
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        record("before", caller_context);
+    }
+}
+
+#[wrap(Registration)]
+fn foo() {}
+
+#[wrap(Registration)]
+fn bar() {}
+
+#[async_wrap(Registration)]
+async fn baz() {}
+
+#[tokio::main]
+async fn main() {
+    for _ in 0..3 {
+        foo();
+    }
+    bar();
+    baz().await;
+    baz().await;
+
+    assert_eq!(
+        *EVENTS.lock().unwrap(),
+        [
+            "first_call:foo",
+            "before:foo",
+            "before:foo",
+            "before:foo",
+            "first_call:bar",
+            "before:bar",
+            "first_call:baz",
+            "before:baz",
+            "before:baz",
+        ],
+    );
+
+    // Manually built caller contexts carry no guard, so the hook is never executed
+    Registration::run_sync(CallerContext::new("manual"), || {});
+    assert_eq!(EVENTS.lock().unwrap().last().unwrap(), "before:manual");
+}
@@ -0,0 +1,68 @@
+use std::fmt::Display;
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+struct Logging;
+impl<T: Display> SyncWrapContext<T> for Logging {
+    fn new() -> Self {
+        Self
+    }
+
+    fn after(self, caller_context: &CallerContext, result: &T) {
+        println!("{} returned {result}", caller_context.fn_name());
+    }
+}
+impl<T: Display> AsyncWrapContext<T> for Logging {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+// The output of the body can be inferred only from the pinned type
+#[wrap(Logging as SyncWrapContext<u32>)]
+fn parsed() -> impl Display {
+    "42".parse().unwrap()
+}
+
+#[wrap(Logging as context_manager::SyncWrapContext<String>)]
+fn collected() -> impl Display {
+    ['a', 'b'].into_iter().collect()
+}
+
+#[wrap(Logging as SyncWrapContext<u8>)]
+async fn defaulted() -> impl Display {
+    Default::default()
+}
+
+#[async_wrap(Logging as AsyncWrapContext<u16>)]
+async fn async_parsed() -> impl Display {
+    "7".parse().unwrap()
+}
+
+#[wrap(Logging as AsyncWrapContext<u64>, hooks = "async")]
+async fn async_hooks() -> impl Display {
+    "8".parse().unwrap()
+}
+
+struct Observe;
+impl<T> SyncWrapContext<T> for Observe {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Observe as SyncWrapContext<Result<u8, std::num::ParseIntError>>, on = "ok")]
+fn fallible(value: &str) -> Result<impl Display, std::num::ParseIntError> {
+    Ok(value.parse()?)
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(parsed().to_string(), "42");
+    assert_eq!(collected().to_string(), "ab");
+    assert_eq!(defaulted().await.to_string(), "0");
+    assert_eq!(async_parsed().await.to_string(), "7");
+    assert_eq!(async_hooks().await.to_string(), "8");
+    assert_eq!(fallible("3").unwrap().to_string(), "3");
+}
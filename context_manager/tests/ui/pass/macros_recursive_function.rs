@@ -0,0 +1,71 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context_manager::contexts::CounterContext;
+use context_manager::{current_fn_name, CallerContext, Reentrancy, SyncWrapContext};
+use context_manager_macro::wrap;
+
+static DEPTH: AtomicUsize = AtomicUsize::new(0);
+static MAX_DEPTH: AtomicUsize = AtomicUsize::new(0);
+static REENTRIES: AtomicUsize = AtomicUsize::new(0);
+
+struct TrackDepth;
+impl<T> SyncWrapContext<T> for TrackDepth {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, _: &mut CallerContext) {
+        let depth = DEPTH.fetch_add(1, Ordering::Relaxed) + 1;
+        MAX_DEPTH.fetch_max(depth, Ordering::Relaxed);
+    }
+
+    fn after(self, _: &CallerContext, _: &T) {
+        DEPTH.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+struct OutermostOnly;
+impl<T> SyncWrapContext<T> for OutermostOnly {
+    const REENTRANCY: Reentrancy = Reentrancy::Detect;
+
+    fn new() -> Self {
+        Self
+    }
+
+    fn on_reentry(&self, _: &CallerContext) {
+        REENTRIES.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[wrap(CounterContext)]
+#[wrap(TrackDepth)]
+fn factorial(n: u64) -> u64 {
+    assert_eq!(current_fn_name(), Some("factorial"));
+    if n <= 1 {
+        1
+    } else {
+        n * factorial(n - 1)
+    }
+}
+
+#[wrap(OutermostOnly)]
+fn fibonacci(n: u64) -> u64 {
+    if n <= 1 {
+        n
+    } else {
+        fibonacci(n - 1) + fibonacci(n - 2)
+    }
+}
+
+fn main() {
+    // Each recursive call is wrapped once per decorator, as the macro wraps the body only
+    assert_eq!(factorial(5), 120);
+    assert_eq!(CounterContext::count("factorial"), 5);
+    assert_eq!(MAX_DEPTH.load(Ordering::Relaxed), 5);
+    assert_eq!(DEPTH.load(Ordering::Relaxed), 0);
+    assert_eq!(current_fn_name(), None);
+
+    // Re-entrant calls are reported, all but the outermost one
+    assert_eq!(fibonacci(5), 5);
+    assert_eq!(REENTRIES.load(Ordering::Relaxed), 14);
+}
@@ -0,0 +1,81 @@
+use context_manager::{AsyncWrapContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+struct Noop;
+impl<T> SyncWrapContext<T> for Noop {
+    fn new() -> Self {
+        Self
+    }
+}
+impl<T> AsyncWrapContext<T> for Noop {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+#[wrap(Noop)]
+fn sync_early_return(value: i32) -> &'static str {
+    if value < 0 {
+        return "negative";
+    }
+    "non-negative"
+}
+
+#[wrap(Noop)]
+fn sync_nested_closure_return(values: &[i32]) -> Vec<i32> {
+    let doubled = values
+        .iter()
+        .map(|value| {
+            if *value < 0 {
+                // Returns from the closure only
+                return 0;
+            }
+            value * 2
+        })
+        .collect();
+    doubled
+}
+
+#[wrap(Noop)]
+async fn sync_context_async_early_return(value: i32) -> Result<i32, String> {
+    if value == 0 {
+        return Err("zero".to_string());
+    }
+    let inverse = |value: i32| -> Option<i32> {
+        if value == 1 {
+            return None;
+        }
+        Some(100 / value)
+    };
+    Ok(inverse(value).unwrap_or(-1))
+}
+
+#[async_wrap(Noop)]
+async fn async_early_return(value: i32) -> &'static str {
+    if value < 0 {
+        return "negative";
+    }
+    let classify = async move {
+        if value == 0 {
+            // Returns from the async block only
+            return "zero";
+        }
+        "positive"
+    };
+    classify.await
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(sync_early_return(-1), "negative");
+    assert_eq!(sync_early_return(1), "non-negative");
+    assert_eq!(sync_nested_closure_return(&[1, -2, 3]), [2, 0, 6]);
+
+    assert_eq!(sync_context_async_early_return(0).await, Err("zero".to_string()));
+    assert_eq!(sync_context_async_early_return(1).await, Ok(-1));
+    assert_eq!(sync_context_async_early_return(4).await, Ok(25));
+
+    assert_eq!(async_early_return(-1).await, "negative");
+    assert_eq!(async_early_return(0).await, "zero");
+    assert_eq!(async_early_return(1).await, "positive");
+}
@@ -0,0 +1,90 @@
+use std::sync::Mutex;
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static EVENTS: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+fn record(event: &'static str) {
+    EVENTS.lock().unwrap().push(event);
+}
+
+fn take_events() -> Vec<&'static str> {
+    std::mem::take(&mut *EVENTS.lock().unwrap())
+}
+
+struct RecordHooks;
+impl<T> SyncWrapContext<T> for RecordHooks {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, _: &mut CallerContext) {
+        record("before");
+    }
+
+    fn after(self, _: &CallerContext, _: &T) {
+        record("after");
+    }
+}
+impl<T> AsyncWrapContext<T> for RecordHooks {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, _: &mut CallerContext) {
+        record("before");
+    }
+
+    async fn after(self, _: &CallerContext, _: &T) {
+        record("after");
+    }
+}
+
+#[wrap(RecordHooks, skip = "after")]
+fn sync_skip_after() {
+    record("body");
+}
+
+#[wrap(RecordHooks, skip = "before")]
+fn sync_skip_before() {
+    record("body");
+}
+
+#[wrap(RecordHooks, skip = "after")]
+async fn async_skip_after() {
+    record("body");
+}
+
+#[wrap(RecordHooks, skip = "before")]
+async fn async_skip_before() {
+    record("body");
+}
+
+#[async_wrap(RecordHooks, skip = "after")]
+async fn async_wrap_skip_after() {
+    record("body");
+}
+
+#[async_wrap(RecordHooks, skip = "before")]
+async fn async_wrap_skip_before() {
+    record("body");
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    sync_skip_after();
+    assert_eq!(take_events(), ["before", "body"]);
+    sync_skip_before();
+    assert_eq!(take_events(), ["body", "after"]);
+
+    async_skip_after().await;
+    assert_eq!(take_events(), ["before", "body"]);
+    async_skip_before().await;
+    assert_eq!(take_events(), ["body", "after"]);
+
+    async_wrap_skip_after().await;
+    assert_eq!(take_events(), ["before", "body"]);
+    async_wrap_skip_before().await;
+    assert_eq!(take_events(), ["body", "after"]);
+}
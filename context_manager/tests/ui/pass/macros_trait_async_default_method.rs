@@ -0,0 +1,99 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static BEFORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+struct Count;
+impl<T> SyncWrapContext<T> for Count {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        assert!(caller_context.is_async());
+        BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+impl<T> AsyncWrapContext<T> for Count {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        assert!(caller_context.is_async());
+        BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[allow(async_fn_in_trait)]
+trait Repository {
+    async fn load(&self, id: u32) -> Option<String>;
+
+    #[wrap(Count)]
+    async fn load_or_default(&self, id: u32) -> String {
+        self.load(id).await.unwrap_or_default()
+    }
+
+    #[async_wrap(Count)]
+    async fn load_all(&self, ids: &[u32]) -> Vec<String> {
+        let mut values = Vec::with_capacity(ids.len());
+        for id in ids {
+            values.push(self.load_or_default(*id).await);
+        }
+        values
+    }
+
+    #[async_wrap(Count, on = "err")]
+    async fn try_load(&self, id: u32) -> Result<String, u32> {
+        self.load(id).await.ok_or(id)
+    }
+
+    // Associated functions without a receiver are supported as well
+    #[wrap(Count)]
+    async fn name() -> &'static str
+    where
+        Self: Sized,
+    {
+        "repository"
+    }
+}
+
+struct InMemory(Vec<String>);
+impl Repository for InMemory {
+    async fn load(&self, id: u32) -> Option<String> {
+        tokio::task::yield_now().await;
+        self.0.get(id as usize).cloned()
+    }
+
+    // Implementors can decorate their own async methods, overriding the default ones
+    #[async_wrap(Count)]
+    async fn try_load(&self, id: u32) -> Result<String, u32> {
+        self.load(id).await.ok_or(u32::MAX)
+    }
+}
+
+// The default methods are usable via generics and trait objects are not required
+async fn first<R: Repository>(repository: &R) -> String {
+    repository.load_or_default(0).await
+}
+
+#[tokio::main]
+async fn main() {
+    let repository = InMemory(vec!["a".to_string(), "b".to_string()]);
+    assert_eq!(first(&repository).await, "a");
+    assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 1);
+
+    assert_eq!(repository.load_all(&[1, 2]).await, ["b", ""]);
+    assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 4);
+
+    assert_eq!(repository.try_load(3).await, Err(u32::MAX));
+    assert_eq!(InMemory::name().await, "repository");
+    assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 6);
+
+    // The futures of the default methods are `Send` whenever the implementor allows it
+    tokio::spawn(async move { repository.load_all(&[0]).await })
+        .await
+        .unwrap();
+}
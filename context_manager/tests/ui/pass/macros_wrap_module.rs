@@ -0,0 +1,61 @@
+use std::sync::Mutex;
+
+use context_manager::{CallerContext, SyncWrapContext};
+use context_manager_macro::wrap_module;
+
+static CALLED: Mutex<Vec<&str>> = Mutex::new(Vec::new());
+
+struct RecordCalls;
+impl<T> SyncWrapContext<T> for RecordCalls {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        CALLED.lock().unwrap().push(caller_context.fn_name());
+    }
+}
+
+#[wrap_module(super::RecordCalls)]
+mod handlers {
+    pub struct User(pub u64);
+
+    pub fn get_user(id: u64) -> User {
+        User(id)
+    }
+
+    pub async fn list_users() -> Vec<u64> {
+        vec![get_user(1).0, get_user(2).0]
+    }
+
+    #[wrap_skip]
+    pub fn health_check() -> bool {
+        true
+    }
+
+    pub(crate) fn delete_user(id: u64) -> Result<u64, String> {
+        Ok(id)
+    }
+
+    pub const fn max_users() -> u64 {
+        100
+    }
+
+    pub extern "C" fn user_count() -> u64 {
+        max_users()
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(handlers::get_user(7).0, 7);
+    assert_eq!(handlers::list_users().await, [1, 2]);
+    assert!(handlers::health_check());
+    assert_eq!(handlers::delete_user(3), Ok(3));
+    assert_eq!(handlers::user_count(), 100);
+
+    assert_eq!(
+        *CALLED.lock().unwrap(),
+        ["get_user", "list_users", "get_user", "get_user", "delete_user"]
+    );
+}
@@ -0,0 +1,119 @@
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+use std::sync::Mutex;
+
+static EVENTS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+fn record(event: String) {
+    EVENTS.lock().unwrap().push(event);
+}
+
+fn take_events() -> Vec<String> {
+    std::mem::take(&mut *EVENTS.lock().unwrap())
+}
+
+struct A;
+impl<T> SyncWrapContext<T> for A {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        record(format!("A::before({})", caller_context.fn_name()));
+    }
+
+    fn after(self, caller_context: &CallerContext, _: &T) {
+        record(format!("A::after({})", caller_context.fn_name()));
+    }
+}
+
+struct B;
+impl<T> SyncWrapContext<T> for B {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        record(format!("B::before({})", caller_context.fn_name()));
+    }
+
+    fn after(self, caller_context: &CallerContext, _: &T) {
+        record(format!("B::after({})", caller_context.fn_name()));
+    }
+}
+
+struct C;
+impl<T> AsyncWrapContext<T> for C {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        record(format!("C::before({})", caller_context.fn_name()));
+    }
+
+    async fn after(self, caller_context: &CallerContext, _: &T) {
+        record(format!("C::after({})", caller_context.fn_name()));
+    }
+}
+
+#[wrap(A)]
+#[wrap(B)]
+fn sync_foo() -> usize {
+    record("body".to_string());
+    1
+}
+
+#[wrap(B)]
+#[inline]
+#[context_manager::wrap(A)]
+fn sync_bar() -> usize {
+    record("body".to_string());
+    2
+}
+
+#[async_wrap(C)]
+#[wrap(A)]
+async fn async_foo() -> usize {
+    record("body".to_string());
+    3
+}
+
+#[tokio::main]
+async fn main() {
+    assert_eq!(sync_foo(), 1);
+    assert_eq!(
+        take_events(),
+        [
+            "A::before(sync_foo)",
+            "B::before(sync_foo)",
+            "body",
+            "B::after(sync_foo)",
+            "A::after(sync_foo)",
+        ]
+    );
+
+    assert_eq!(sync_bar(), 2);
+    assert_eq!(
+        take_events(),
+        [
+            "B::before(sync_bar)",
+            "A::before(sync_bar)",
+            "body",
+            "A::after(sync_bar)",
+            "B::after(sync_bar)",
+        ]
+    );
+
+    assert_eq!(async_foo().await, 3);
+    assert_eq!(
+        take_events(),
+        [
+            "C::before(async_foo)",
+            "A::before(async_foo)",
+            "body",
+            "A::after(async_foo)",
+            "C::after(async_foo)",
+        ]
+    );
+}
@@ -0,0 +1,86 @@
+use context_manager::{CallerContext, SyncWrapContext};
+use context_manager_macro::wrap;
+use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static FORMATTED: AtomicUsize = AtomicUsize::new(0);
+
+trait Format {
+    const NAME: &'static str;
+}
+
+struct Json;
+impl Format for Json {
+    const NAME: &'static str = "json";
+}
+
+struct Text;
+impl Format for Text {
+    const NAME: &'static str = "text";
+}
+
+mod ctx {
+    use super::*;
+
+    pub struct LoggingContext<F>(PhantomData<F>);
+    impl<F: Format, T> SyncWrapContext<T> for LoggingContext<F> {
+        fn new() -> Self {
+            Self(PhantomData)
+        }
+
+        fn before(&self, caller_context: &mut CallerContext) {
+            assert!(!F::NAME.is_empty());
+            assert!(!caller_context.fn_name().is_empty());
+            FORMATTED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub struct PairContext<A, B>(PhantomData<(A, B)>);
+    impl<A: Format, B: Format, T> SyncWrapContext<T> for PairContext<A, B> {
+        fn new() -> Self {
+            Self(PhantomData)
+        }
+
+        fn before(&self, _: &mut CallerContext) {
+            assert_ne!(A::NAME, B::NAME);
+            FORMATTED.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+use ctx::{LoggingContext, PairContext};
+
+#[wrap(LoggingContext<Json>)]
+fn one_type_parameter() -> usize {
+    1
+}
+
+#[wrap(LoggingContext::<Text>)]
+fn turbofish_type_parameter() -> usize {
+    2
+}
+
+#[wrap(PairContext<Json, Text>)]
+fn two_type_parameters() -> usize {
+    3
+}
+
+#[wrap(crate::ctx::LoggingContext<Json>)]
+fn fully_qualified_path() -> usize {
+    4
+}
+
+#[wrap(self::ctx::PairContext::<Text, Json>)]
+async fn fully_qualified_path_with_turbofish() -> usize {
+    5
+}
+
+#[tokio::main]
+async fn main() {
+    assert_eq!(one_type_parameter(), 1);
+    assert_eq!(turbofish_type_parameter(), 2);
+    assert_eq!(two_type_parameters(), 3);
+    assert_eq!(fully_qualified_path(), 4);
+    assert_eq!(fully_qualified_path_with_turbofish().await, 5);
+    assert_eq!(FORMATTED.load(Ordering::Relaxed), 5);
+}
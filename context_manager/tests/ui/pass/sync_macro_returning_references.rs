@@ -0,0 +1,73 @@
+use context_manager::SyncWrapContext;
+use context_manager_macro::wrap;
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+struct Container {
+    default: u8,
+    values: Vec<u8>,
+}
+
+impl Container {
+    #[wrap(Sync)]
+    fn first<'a>(&'a self, xs: &'a [u8]) -> &'a u8 {
+        xs.first().unwrap_or(&self.default)
+    }
+
+    #[wrap(Sync)]
+    fn elided(&self) -> &[u8] {
+        &self.values
+    }
+
+    #[wrap(Sync)]
+    fn elided_mut(&mut self) -> &mut u8 {
+        &mut self.default
+    }
+
+    #[wrap(Sync)]
+    async fn async_elided_mut(&mut self) -> &mut Vec<u8> {
+        &mut self.values
+    }
+}
+
+#[wrap(Sync)]
+fn split<'a>(xs: &'a mut [u8]) -> (&'a mut [u8], &'a mut [u8]) {
+    xs.split_at_mut(1)
+}
+
+#[wrap(Sync)]
+fn longest<'a, 'b: 'a>(a: &'a str, b: &'b str) -> &'a str {
+    if a.len() >= b.len() {
+        a
+    } else {
+        b
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut container = Container {
+        default: 0,
+        values: vec![1, 2],
+    };
+    assert_eq!(*container.first(&[]), 0);
+    assert_eq!(*container.first(&[3]), 3);
+    assert_eq!(container.elided(), &[1, 2]);
+    *container.elided_mut() = 10;
+    assert_eq!(container.default, 10);
+    container.async_elided_mut().await.push(3);
+    assert_eq!(container.values, vec![1, 2, 3]);
+
+    let mut values = [1, 2, 3];
+    let (head, tail) = split(&mut values);
+    head[0] = 10;
+    tail[0] = 20;
+    assert_eq!(values, [10, 20, 3]);
+
+    assert_eq!(longest("ab", "a"), "ab");
+}
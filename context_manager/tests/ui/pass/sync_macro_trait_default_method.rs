@@ -0,0 +1,38 @@
+use context_manager::{CallerContext, SyncWrapContext};
+use context_manager_macro::wrap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static BEFORE_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        assert_eq!(caller_context.fn_name(), "doubled");
+        BEFORE_CALLS.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+trait Value {
+    fn value(&self) -> usize;
+
+    #[wrap(Sync)]
+    fn doubled(&self) -> usize {
+        self.value() * 2
+    }
+}
+
+struct Implementor(usize);
+impl Value for Implementor {
+    fn value(&self) -> usize {
+        self.0
+    }
+}
+
+fn main() {
+    assert_eq!(Implementor(21).doubled(), 42);
+    assert_eq!(BEFORE_CALLS.load(Ordering::Relaxed), 1);
+}
@@ -0,0 +1,63 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use context_manager::{wrap_fn, CallerContext, SyncWrapContext};
+use futures::StreamExt;
+
+static BEFORE: AtomicUsize = AtomicUsize::new(0);
+static AFTER: AtomicUsize = AtomicUsize::new(0);
+
+struct CountHooks;
+impl<T> SyncWrapContext<T> for CountHooks {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        assert_eq!(caller_context.fn_name(), "{closure}");
+        BEFORE.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn after(self, _: &CallerContext, _: &T) {
+        AFTER.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn assert_hooks_calls(expected: usize) {
+    assert_eq!(BEFORE.swap(0, Ordering::Relaxed), expected);
+    assert_eq!(AFTER.swap(0, Ordering::Relaxed), expected);
+}
+
+#[tokio::main]
+async fn main() {
+    // Closures are wrapped lazily, so nothing happens until they are called
+    let offset = 10;
+    let add_offset = wrap_fn!(CountHooks, |value: u64| -> u64 { value + offset });
+    assert_hooks_calls(0);
+    assert_eq!(add_offset(1), 11);
+    assert_hooks_calls(1);
+
+    let doubled: Vec<u64> = (1..=3).map(wrap_fn!(CountHooks, |value| value * 2)).collect();
+    assert_eq!(doubled, [2, 4, 6]);
+    assert_hooks_calls(3);
+
+    let names = vec![String::from("a"), String::from("b")];
+    let owned: Vec<String> = names
+        .into_iter()
+        .map(wrap_fn!(CountHooks, move |name: String| name + "!"))
+        .collect();
+    assert_eq!(owned, ["a!", "b!"]);
+    assert_hooks_calls(2);
+
+    let tripled: Vec<u64> = futures::stream::iter(1..=3)
+        .then(wrap_fn!(CountHooks, |value| async move {
+            value * 3
+        }))
+        .collect()
+        .await;
+    assert_eq!(tripled, [3, 6, 9]);
+    assert_hooks_calls(3);
+
+    let async_closure = wrap_fn!(CountHooks, async |value: u64| value + 1);
+    assert_eq!(async_closure(1).await, 2);
+    assert_hooks_calls(1);
+}
@@ -0,0 +1,91 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use context_manager::{AsyncWrapContext, CallerContext, SyncWrapContext};
+use context_manager_macro::{async_wrap, wrap};
+
+static DROPPED: AtomicBool = AtomicBool::new(false);
+
+/// Argument recording when it is dropped
+struct Guard(String);
+
+impl Drop for Guard {
+    fn drop(&mut self) {
+        DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+/// Context asserting that the arguments are dropped by the body, as for the unwrapped function
+struct Async;
+impl<T> AsyncWrapContext<T> for Async {
+    async fn new() -> Self {
+        DROPPED.store(false, Ordering::SeqCst);
+        Self
+    }
+
+    async fn after(self, _: &CallerContext, _: &T) {
+        assert!(DROPPED.load(Ordering::SeqCst));
+    }
+}
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        DROPPED.store(false, Ordering::SeqCst);
+        Self
+    }
+
+    fn after(self, _: &CallerContext, _: &T) {
+        assert!(DROPPED.load(Ordering::SeqCst));
+    }
+}
+
+#[async_wrap(Async)]
+async fn async_spawn(name: String, guard: Guard) -> String {
+    let handle = tokio::spawn(async move { name + "!" });
+    let _ = guard.0.len();
+    handle.await.unwrap()
+}
+
+#[wrap(Sync)]
+async fn sync_spawn(name: String, guard: Guard) -> String {
+    let handle = tokio::spawn(async move { name + "!" });
+    let _ = guard.0.len();
+    handle.await.unwrap()
+}
+
+#[async_wrap(Async)]
+async fn first_mut(values: &mut Vec<String>, _guard: Guard) -> Option<&mut String> {
+    values.first_mut()
+}
+
+struct Counter(Vec<String>);
+
+impl Counter {
+    #[async_wrap(Async)]
+    async fn push(&mut self, mut value: String, _guard: Guard) -> usize {
+        value.push('!');
+        self.0.push(value);
+        self.0.len()
+    }
+
+    #[wrap(Sync)]
+    async fn into_spawned(self, _guard: Guard) -> usize {
+        tokio::spawn(async move { self.0.len() }).await.unwrap()
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let guard = || Guard(String::from("guard"));
+    assert_eq!(async_spawn(String::from("async"), guard()).await, "async!");
+    assert_eq!(sync_spawn(String::from("sync"), guard()).await, "sync!");
+
+    let mut values = vec![String::from("first")];
+    first_mut(&mut values, guard()).await.unwrap().push('!');
+    assert_eq!(values, ["first!"]);
+
+    let mut counter = Counter(Vec::new());
+    assert_eq!(counter.push(String::from("a"), guard()).await, 1);
+    assert_eq!(counter.0, ["a!"]);
+    assert_eq!(counter.into_spawned(guard()).await, 1);
+}
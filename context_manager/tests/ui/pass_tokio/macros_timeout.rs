@@ -0,0 +1,45 @@
+use context_manager::{AsyncWrapContext, SyncWrapContext, TimeoutError};
+use context_manager_macro::{async_wrap, wrap};
+
+struct Async;
+impl<T> AsyncWrapContext<T> for Async {
+    async fn new() -> Self {
+        Self
+    }
+}
+
+struct Sync;
+impl<T> SyncWrapContext<T> for Sync {
+    fn new() -> Self {
+        Self
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Error {
+    Timeout,
+}
+
+impl From<TimeoutError> for Error {
+    fn from(_: TimeoutError) -> Self {
+        Self::Timeout
+    }
+}
+
+#[async_wrap(Async, timeout = "10ms")]
+async fn async_macro(sleep: u64) -> Result<u64, Error> {
+    tokio::time::sleep(std::time::Duration::from_millis(sleep)).await;
+    Ok(sleep)
+}
+
+#[wrap(Sync, timeout = "1m 30s")]
+async fn sync_macro() -> Result<(), TimeoutError> {
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() {
+    assert_eq!(async_macro(0).await, Ok(0));
+    assert_eq!(async_macro(1000).await, Err(Error::Timeout));
+    assert_eq!(sync_macro().await, Ok(()));
+}
@@ -0,0 +1,33 @@
+use context_manager_macro::{async_wrap, wrap};
+
+#[wrap]
+fn add(a: u8, b: u8) -> u8 {
+    a + b
+}
+
+#[wrap()]
+async fn sync_hooks() -> &'static str {
+    "sync"
+}
+
+#[async_wrap]
+async fn async_hooks() -> &'static str {
+    "async"
+}
+
+struct Calculator;
+
+impl Calculator {
+    #[wrap]
+    fn double(&self, value: u8) -> u8 {
+        value * 2
+    }
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    assert_eq!(add(1, 2), 3);
+    assert_eq!(Calculator.double(2), 4);
+    assert_eq!(sync_hooks().await, "sync");
+    assert_eq!(async_hooks().await, "async");
+}
@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+
+use context_manager::{
+    async_wrap, with_async_context, with_context, with_context_async, wrap, AsyncWrapContext,
+    CallerContext, SyncWrapContext,
+};
+
+thread_local! {
+    static EVENTS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn record(event: &str, caller_context: &CallerContext) {
+    EVENTS.with_borrow_mut(|events| {
+        events.push(format!("{event}:{}", caller_context.fn_name()));
+    });
+}
+
+fn recorded_events() -> Vec<String> {
+    EVENTS.take()
+}
+
+/// Context recording its hooks, both with the sync and the async traits
+struct Record;
+impl<T> SyncWrapContext<T> for Record {
+    fn new() -> Self {
+        Self
+    }
+
+    fn before(&self, caller_context: &mut CallerContext) {
+        record("before", caller_context);
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        record("finally", caller_context);
+    }
+
+    fn after(self, caller_context: &CallerContext, _: &T) {
+        record("after", caller_context);
+    }
+}
+impl<T> AsyncWrapContext<T> for Record {
+    async fn new() -> Self {
+        Self
+    }
+
+    async fn before(&self, caller_context: &mut CallerContext) {
+        record("async_before", caller_context);
+    }
+
+    fn finally(&self, caller_context: &CallerContext) {
+        record("async_finally", caller_context);
+    }
+
+    async fn after(self, caller_context: &CallerContext, _: &T) {
+        record("async_after", caller_context);
+    }
+}
+
+#[wrap(Record)]
+fn sync_foo() -> u8 {
+    42
+}
+
+#[wrap(Record)]
+async fn async_foo() -> u8 {
+    42
+}
+
+#[async_wrap(Record)]
+async fn async_wrap_foo() -> u8 {
+    42
+}
+
+#[test]
+fn with_context_matches_wrap() {
+    assert_eq!(sync_foo(), 42);
+    let expected = recorded_events();
+    assert_eq!(
+        expected,
+        ["before:sync_foo", "finally:sync_foo", "after:sync_foo"]
+    );
+
+    let output = with_context::<Record, _, _>(CallerContext::new("sync_foo"), || 42);
+    assert_eq!(output, 42);
+    assert_eq!(recorded_events(), expected);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn with_context_async_matches_wrap() {
+    assert_eq!(async_foo().await, 42);
+    let expected = recorded_events();
+    assert_eq!(
+        expected,
+        ["before:async_foo", "finally:async_foo", "after:async_foo"]
+    );
+
+    let output =
+        with_context_async::<Record, _, _>(CallerContext::new("async_foo"), async { 42 }).await;
+    assert_eq!(output, 42);
+    assert_eq!(recorded_events(), expected);
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn with_async_context_matches_async_wrap() {
+    assert_eq!(async_wrap_foo().await, 42);
+    let expected = recorded_events();
+    assert_eq!(
+        expected,
+        [
+            "async_before:async_wrap_foo",
+            "async_finally:async_wrap_foo",
+            "async_after:async_wrap_foo",
+        ]
+    );
+
+    let output =
+        with_async_context::<Record, _, _>(CallerContext::new("async_wrap_foo"), async { 42 })
+            .await;
+    assert_eq!(output, 42);
+    assert_eq!(recorded_events(), expected);
+}
@@ -13,120 +13,939 @@
 //! Implementation of the procedural macros exposed by [`context_manager`](https://crates.io/crates/context-manager) crate.
 
 use proc_macro::TokenStream;
+use proc_macro2::Span;
+use proc_macro2::TokenStream as TokenStream2;
+use proc_macro2::TokenTree;
+use quote::format_ident;
 use quote::quote;
+use quote::quote_spanned;
+use quote::ToTokens;
+use syn::ext::IdentExt;
+use syn::parenthesized;
 use syn::parse::Parse;
 use syn::parse::ParseStream;
 use syn::parse_macro_input;
 use syn::parse_quote;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::Abi;
+use syn::Attribute;
 use syn::Block;
 use syn::Error;
+use syn::Expr;
+use syn::ExprClosure;
+use syn::FnArg;
+use syn::Ident;
+use syn::ImplItem;
+use syn::Item;
 use syn::ItemFn;
+use syn::ItemImpl;
+use syn::ItemMod;
+use syn::LitStr;
+use syn::Pat;
+use syn::PatIdent;
+use syn::ReturnType;
+use syn::Signature;
+use syn::Token;
+use syn::TraitItemFn;
 use syn::Type;
+use syn::Visibility;
+
+/// Branch of the result on which the context `after` hooks are executed (`on = "..."` argument)
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum On {
+    Always,
+    Ok,
+    Err,
+}
 
 struct Args {
     context_type: Type,
+    on: On,
+    on_span: Span,
+    /// Arguments to record as fields of a tracing span (`fields(...)` argument)
+    fields: Option<Vec<Ident>>,
+    /// Metadata of the tracing span, overriding the `#[instrument]` defaults (`span(...)` argument)
+    span: Option<SpanArgs>,
+    /// Timeout of the body, in nanoseconds (`timeout = "..."` argument)
+    timeout: Option<(u64, Span)>,
+    /// Static key-value metadata attached to the caller context (`meta(key = "value", ...)` argument)
+    meta: Vec<(Ident, LitStr)>,
+    /// Whether the arguments of the function are provided to the context (`args` argument)
+    arguments: Option<Span>,
+    /// Trait providing the hooks of the context, overriding the one of the macro (`hooks = "..."` argument)
+    hooks: Option<(MacroKind, Span)>,
+    /// Name of the wrapped copy of the function, which is left bare (`alias = "..."` argument)
+    alias: Option<Ident>,
+    /// Name of the binding of the caller context in the body (`bind = "..."` argument)
+    bind: Option<Ident>,
+    /// Output type of the context, pinned via its trait (`Type as SyncWrapContext<Output>`)
+    output: Option<Output>,
+    /// Hook of the context not executed (`skip = "..."` argument)
+    skip: Option<(Skip, Span)>,
+}
+
+/// Hook of the context not executed, avoiding its cost entirely (`skip = "..."` argument)
+#[derive(Clone, Copy)]
+enum Skip {
+    Before,
+    After,
+}
+
+/// Metadata of the tracing span, like `span(name = "load", target = "db", level = "debug")`
+///
+/// The defaults match the ones of `#[tracing::instrument]`: the name of the function, its module and `INFO` level.
+#[derive(Default)]
+struct SpanArgs {
+    name: Option<LitStr>,
+    target: Option<LitStr>,
+    level: Option<Ident>,
+}
+
+impl SpanArgs {
+    fn parse_entries(entries: Punctuated<MetaEntry, Token![,]>) -> syn::Result<Self> {
+        let mut span = Self::default();
+        for MetaEntry { key, value } in entries {
+            let slot = if key == "name" {
+                &mut span.name
+            } else if key == "target" {
+                &mut span.target
+            } else if key == "level" {
+                let level = match value.value().to_ascii_lowercase().as_str() {
+                    "trace" => "TRACE",
+                    "debug" => "DEBUG",
+                    "info" => "INFO",
+                    "warn" => "WARN",
+                    "error" => "ERROR",
+                    _ => {
+                        return Err(Error::new_spanned(
+                            value,
+                            r#"Expected one of "trace", "debug", "info", "warn" or "error""#,
+                        ))
+                    }
+                };
+                if span
+                    .level
+                    .replace(Ident::new(level, value.span()))
+                    .is_some()
+                {
+                    return Err(Error::new_spanned(key, "Duplicated `span` key"));
+                }
+                continue;
+            } else {
+                return Err(Error::new_spanned(
+                    key,
+                    "Unknown `span` key, expected `name`, `target` or `level`",
+                ));
+            };
+            if slot.replace(value).is_some() {
+                return Err(Error::new_spanned(key, "Duplicated `span` key"));
+            }
+        }
+        Ok(span)
+    }
+}
+
+/// Output type of the context pinned in the attribute, like `String` in `#[wrap(Ctx as SyncWrapContext<String>)]`
+struct Output {
+    /// Trait providing the hooks of the context, as named in the attribute
+    kind: MacroKind,
+    trait_path: syn::Path,
+    ty: Type,
+}
+
+impl Parse for Output {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let trait_path = input.parse::<syn::Path>()?;
+        let invalid = || {
+            Error::new_spanned(
+                &trait_path,
+                "Expected `SyncWrapContext<Output>` or `AsyncWrapContext<Output>`",
+            )
+        };
+        let segment = trait_path.segments.last().ok_or_else(invalid)?;
+        let kind = match segment.ident.to_string().as_str() {
+            "SyncWrapContext" => MacroKind::Wrap,
+            "AsyncWrapContext" => MacroKind::AsyncWrap,
+            _ => return Err(invalid()),
+        };
+        let ty = match &segment.arguments {
+            syn::PathArguments::AngleBracketed(arguments) if arguments.args.len() == 1 => {
+                match &arguments.args[0] {
+                    syn::GenericArgument::Type(ty) => ty.clone(),
+                    _ => return Err(invalid()),
+                }
+            }
+            _ => return Err(invalid()),
+        };
+        Ok(Self {
+            kind,
+            trait_path,
+            ty,
+        })
+    }
 }
 
 impl Parse for Args {
     fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
-        if input.is_empty() {
-            Err(Error::new(
-                input.span(),
-                "Expected a type as argument: `#[wrap(Type)]` or `#[async_wrap(Type)]`",
-            ))
-        } else {
-            Ok(Self {
-                context_type: input.parse::<Type>()?,
-            })
+        let (context_type, output) = parse_context_type(input)?;
+
+        let mut args = Self {
+            context_type,
+            on: On::Always,
+            on_span: Span::call_site(),
+            fields: None,
+            span: None,
+            timeout: None,
+            meta: Vec::new(),
+            arguments: None,
+            hooks: None,
+            alias: None,
+            bind: None,
+            output,
+            skip: None,
+        };
+        while !input.is_empty() {
+            input.parse::<Token![,]>()?;
+            if input.is_empty() {
+                break;
+            }
+            let key = input.parse::<Ident>()?;
+            if key == "fields" {
+                let content;
+                parenthesized!(content in input);
+                let fields = Punctuated::<Ident, Token![,]>::parse_terminated(&content)?;
+                args.fields = Some(fields.into_iter().collect());
+                continue;
+            }
+            if key == "span" {
+                let content;
+                parenthesized!(content in input);
+                let entries = Punctuated::<MetaEntry, Token![,]>::parse_terminated(&content)?;
+                args.span = Some(SpanArgs::parse_entries(entries)?);
+                continue;
+            }
+            if key == "meta" {
+                let content;
+                parenthesized!(content in input);
+                let entries = Punctuated::<MetaEntry, Token![,]>::parse_terminated(&content)?;
+                for MetaEntry { key, value } in entries {
+                    if args.meta.iter().any(|(existing, _)| *existing == key) {
+                        return Err(Error::new_spanned(key, "Duplicated `meta` key"));
+                    }
+                    args.meta.push((key, value));
+                }
+                continue;
+            }
+            if key == "args" {
+                args.arguments = Some(key.span());
+                continue;
+            }
+            input.parse::<Token![=]>()?;
+            if key == "on" {
+                let value = input.parse::<LitStr>()?;
+                args.on = match value.value().as_str() {
+                    "always" => On::Always,
+                    "ok" => On::Ok,
+                    "err" => On::Err,
+                    _ => {
+                        return Err(Error::new_spanned(
+                            value,
+                            r#"Expected one of "ok", "err" or "always""#,
+                        ))
+                    }
+                };
+                args.on_span = value.span();
+            } else if key == "hooks" {
+                args.hooks = Some(parse_choice(
+                    input,
+                    &[("sync", MacroKind::Wrap), ("async", MacroKind::AsyncWrap)],
+                    r#"Expected one of "sync" or "async""#,
+                )?);
+            } else if key == "alias" {
+                args.alias = Some(parse_ident_str(input, "Expected a function name")?);
+            } else if key == "bind" {
+                args.bind = Some(parse_ident_str(input, "Expected a binding name")?);
+            } else if key == "skip" {
+                args.skip = Some(parse_choice(
+                    input,
+                    &[("before", Skip::Before), ("after", Skip::After)],
+                    r#"Expected one of "before" or "after""#,
+                )?);
+            } else if key == "timeout" {
+                let value = input.parse::<LitStr>()?;
+                let nanos = parse_duration(&value.value())
+                    .map_err(|message| Error::new_spanned(&value, message))?;
+                args.timeout = Some((nanos, value.span()));
+            } else {
+                return Err(Error::new_spanned(
+                    key,
+                    "Unknown argument, expected `on = \"ok\" | \"err\" | \"always\"`, `hooks = \"sync\" | \"async\"`, `fields(...)`, `span(...)`, `meta(...)`, `args`, `alias = \"...\"`, `bind = \"...\"`, `skip = \"before\" | \"after\"` or `timeout = \"...\"`",
+                ));
+            }
         }
+        Ok(args)
     }
 }
 
-/// Procedural macro that will decorate the incoming function with the provided context.
+/// Parse the type of the context, defaulting to `DefaultTraceContext` with the `trace` feature,
+/// optionally followed by its pinned output, like `Logging as SyncWrapContext<u32>`
+fn parse_context_type(input: ParseStream<'_>) -> syn::Result<(Type, Option<Output>)> {
+    let context_type = if !input.is_empty() {
+        input.parse::<Type>()?
+    } else if cfg!(feature = "trace") {
+        parse_quote! { ::context_manager::contexts::DefaultTraceContext }
+    } else {
+        return Err(Error::new(
+            input.span(),
+            "Expected a type as argument: `#[wrap(Type)]` or `#[async_wrap(Type)]` \
+             (omitting it requires the `trace` feature of `context_manager`)",
+        ));
+    };
+    let output = if input.peek(Token![as]) {
+        input.parse::<Token![as]>()?;
+        Some(input.parse::<Output>()?)
+    } else {
+        None
+    };
+    Ok((context_type, output))
+}
+
+/// Parse a string literal among the `choices`, returning the matching value and the span of the literal
+fn parse_choice<T: Copy>(
+    input: ParseStream<'_>,
+    choices: &[(&str, T)],
+    message: &str,
+) -> syn::Result<(T, Span)> {
+    let value = input.parse::<LitStr>()?;
+    choices
+        .iter()
+        .find(|(choice, _)| value.value() == *choice)
+        .map(|(_, choice)| (*choice, value.span()))
+        .ok_or_else(|| Error::new_spanned(&value, message))
+}
+
+/// Parse a string literal containing an identifier, like `"foo"`, failing with `message` otherwise
+fn parse_ident_str(input: ParseStream<'_>, message: &str) -> syn::Result<Ident> {
+    let value = input.parse::<LitStr>()?;
+    value
+        .parse::<Ident>()
+        .map_err(|_| Error::new_spanned(&value, message))
+}
+
+/// Entry of the `meta(...)` argument, like `service = "auth"`
+struct MetaEntry {
+    key: Ident,
+    value: LitStr,
+}
+
+impl Parse for MetaEntry {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let key = input.call(Ident::parse_any)?;
+        input.parse::<Token![=]>()?;
+        let value = input.parse::<LitStr>()?;
+        Ok(Self { key, value })
+    }
+}
+
+/// Parse a humantime-like duration (ie. `"500ms"` or `"1m 30s"`) into nanoseconds
+fn parse_duration(value: &str) -> Result<u64, String> {
+    const UNITS: [(&str, u64); 9] = [
+        ("ns", 1),
+        ("us", 1_000),
+        ("µs", 1_000),
+        ("ms", 1_000_000),
+        ("sec", 1_000_000_000),
+        ("s", 1_000_000_000),
+        ("min", 60_000_000_000),
+        ("m", 60_000_000_000),
+        ("h", 3_600_000_000_000),
+    ];
+    let invalid = || {
+        format!(
+            "Invalid duration `{value}`, expected a sequence of integers with units (ie. \"500ms\" or \"1m 30s\")"
+        )
+    };
+
+    let mut nanos: u64 = 0;
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return Err(invalid());
+    }
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let amount = rest[..digits].parse::<u64>().map_err(|_| invalid())?;
+        rest = &rest[digits..];
+        let (unit, factor) = UNITS
+            .iter()
+            .find(|(unit, _)| {
+                rest.strip_prefix(unit)
+                    .is_some_and(|after| !after.starts_with(|c: char| c.is_alphabetic()))
+            })
+            .ok_or_else(invalid)?;
+        nanos = amount
+            .checked_mul(*factor)
+            .and_then(|amount| nanos.checked_add(amount))
+            .ok_or_else(|| format!("Duration `{value}` is too long"))?;
+        rest = rest[unit.len()..].trim_start();
+    }
+    Ok(nanos)
+}
+
+/// Check whether the function returns a `Result`
 ///
-/// The context is expected to be a type that implements the `context_manager::SyncWrapContext` trait.
+/// The detection is syntactical, so aliases are recognised only if their name is `Result` (ie. `io::Result<T>`).
+fn returns_result(in_func: &ItemFn) -> bool {
+    match &in_func.sig.output {
+        ReturnType::Type(_, ty) => match ty.as_ref() {
+            Type::Path(type_path) => type_path
+                .path
+                .segments
+                .last()
+                .is_some_and(|segment| segment.ident == "Result"),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+/// Type of the context to use for the layer, selecting the `Result` branch if requested
+fn layer_context_type(args: &Args, returns_result: bool) -> syn::Result<TokenStream2> {
+    let context_type = &args.context_type;
+    let branch = match (args.on, returns_result) {
+        (On::Always, _) | (On::Ok, false) => return Ok(quote! { #context_type }),
+        (On::Ok, true) => quote! { ::context_manager::contexts::OkBranch },
+        (On::Err, true) => quote! { ::context_manager::contexts::ErrBranch },
+        (On::Err, false) => {
+            return Err(Error::new(
+                args.on_span,
+                r#"`on = "err"` requires the function to return a `Result`"#,
+            ))
+        }
+    };
+    Ok(quote! { ::context_manager::contexts::BranchContext<#context_type, #branch> })
+}
+
+/// Parse the decorated item as a function with a body.
 ///
-/// More documentation available [here](https://docs.rs/context_manager/latest/context_manager/attr.wrap.html)
-#[proc_macro_attribute]
-pub fn wrap(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let mut in_func = parse_macro_input!(item as ItemFn);
+/// Free functions and inherent methods are parsed as [`ItemFn`]. Trait methods are parsed as [`TraitItemFn`]
+/// and are accepted only if they provide a default body, as there is nothing to wrap otherwise.
+///
+/// On failure the returned [`TokenStream`] contains the compile error, followed by the original item
+/// (whenever possible) to avoid cascading errors on the rest of the code.
+fn parse_item_fn(item: TokenStream, macro_name: &str) -> Result<ItemFn, TokenStream> {
+    let item_fn_error = match syn::parse::<ItemFn>(item.clone()) {
+        Ok(item_fn) => return Ok(item_fn),
+        Err(error) => error,
+    };
 
-    if in_func.sig.constness.is_some() {
-        // Insert compile error at the begin of the function block.
-        // Doing so allows a clear compile failure, while allowing type inference to still work.
-        in_func.block.stmts.insert(
-            0,
-            parse_quote!(::std::compile_error!("#[wrap] cannot operate on const functions.");),
-        );
-        return quote! { #in_func }.into();
+    match syn::parse::<TraitItemFn>(item) {
+        Ok(TraitItemFn {
+            attrs,
+            sig,
+            default: Some(block),
+            ..
+        }) => Ok(ItemFn {
+            attrs,
+            vis: Visibility::Inherited,
+            sig,
+            block: Box::new(block),
+        }),
+        Ok(trait_item_fn) => {
+            let error = Error::new_spanned(
+                &trait_item_fn.sig,
+                format!("#[{macro_name}] cannot operate on trait methods without a default body. Please consider decorating the implementations instead."),
+            )
+            .to_compile_error();
+            Err(quote! { #error #trait_item_fn }.into())
+        }
+        Err(_) => Err(item_fn_error.to_compile_error().into()),
     }
+}
 
-    let args: Args = parse_macro_input!(attr);
+/// Collect the identifiers bound by `pat`, in declaration order
+fn collect_bindings<'a>(pat: &'a Pat, bindings: &mut Vec<&'a PatIdent>) {
+    match pat {
+        Pat::Ident(pat_ident) => {
+            bindings.push(pat_ident);
+            if let Some((_, subpat)) = &pat_ident.subpat {
+                collect_bindings(subpat, bindings);
+            }
+        }
+        Pat::Paren(pat_paren) => collect_bindings(&pat_paren.pat, bindings),
+        Pat::Reference(pat_reference) => collect_bindings(&pat_reference.pat, bindings),
+        Pat::Slice(pat_slice) => pat_slice
+            .elems
+            .iter()
+            .for_each(|elem| collect_bindings(elem, bindings)),
+        Pat::Struct(pat_struct) => pat_struct
+            .fields
+            .iter()
+            .for_each(|field| collect_bindings(&field.pat, bindings)),
+        Pat::Tuple(pat_tuple) => pat_tuple
+            .elems
+            .iter()
+            .for_each(|elem| collect_bindings(elem, bindings)),
+        Pat::TupleStruct(pat_tuple_struct) => pat_tuple_struct
+            .elems
+            .iter()
+            .for_each(|elem| collect_bindings(elem, bindings)),
+        Pat::Type(pat_type) => collect_bindings(&pat_type.pat, bindings),
+        _ => {}
+    }
+}
 
-    let context_type = &args.context_type;
+/// Identifiers bound by the patterns of the arguments of the function, excluding `self`
+fn arg_bindings(in_func: &ItemFn) -> Vec<&PatIdent> {
+    let mut bindings = Vec::with_capacity(in_func.sig.inputs.len());
+    for input in &in_func.sig.inputs {
+        if let FnArg::Typed(pat_type) = input {
+            collect_bindings(&pat_type.pat, &mut bindings);
+        }
+    }
+    bindings
+}
+
+/// Body of the function, taking ownership of all its arguments if the function is async
+///
+/// Async functions move their arguments into the returned future, dropping them once the body completes.
+/// The wrapped body is emitted as an `async move` block, which captures only the arguments it mentions,
+/// so the unused ones are mentioned too, to drop them as well before the `after` hooks are executed.
+fn async_body(in_func: &ItemFn) -> TokenStream2 {
     let block = &in_func.block;
+    if in_func.sig.asyncness.is_none() {
+        return quote! { #block };
+    }
+    let receiver = in_func.sig.receiver().map(|_| quote! { &self, });
+    let bindings = arg_bindings(in_func)
+        .into_iter()
+        .map(|binding| &binding.ident);
+    quote! {
+        {
+            #[allow(clippy::used_underscore_binding)]
+            let _ = (#receiver #(&#bindings,)*);
+            #block
+        }
+    }
+}
 
-    let ident = in_func.sig.ident.to_string();
-    let caller_context = quote! { ::context_manager::CallerContext::new(#ident) };
+/// Names of the arguments of the function, as the identifiers bound by their patterns
+fn arg_names(in_func: &ItemFn) -> Vec<String> {
+    let receiver = in_func.sig.receiver().map(|_| "self".to_string());
+    receiver
+        .into_iter()
+        .chain(
+            arg_bindings(in_func)
+                .into_iter()
+                .map(|binding| binding.ident.unraw().to_string()),
+        )
+        .collect()
+}
 
-    let new_body: TokenStream = if in_func.sig.asyncness.is_some() {
-        quote! {
+/// Generate the expression building the input of the context, if the layer requires one
+///
+/// The `fields(...)` and `span(...)` arguments lead to a tracing span, created eagerly as the arguments are moved into the body.
+fn layer_input(args: &Args, in_func: &ItemFn) -> syn::Result<Option<TokenStream2>> {
+    if args.fields.is_none() && args.span.is_none() {
+        return Ok(None);
+    }
+    let fields = args.fields.as_deref().unwrap_or_default();
+    let arg_names = arg_names(in_func);
+    if let Some(field) = fields
+        .iter()
+        .find(|field| !arg_names.contains(&field.unraw().to_string()))
+    {
+        return Err(Error::new_spanned(
+            field,
+            format!("`{field}` is not an argument of the function"),
+        ));
+    }
+    let span = args.span.as_ref();
+    let name = span.and_then(|span| span.name.as_ref()).map_or_else(
+        || in_func.sig.ident.unraw().to_string().into_token_stream(),
+        ToTokens::to_token_stream,
+    );
+    let target = span.and_then(|span| span.target.as_ref()).map_or_else(
+        || quote! { ::core::module_path!() },
+        ToTokens::to_token_stream,
+    );
+    let level = span
+        .and_then(|span| span.level.clone())
+        .unwrap_or_else(|| Ident::new("INFO", Span::call_site()));
+    Ok(Some(quote! {
+        ::context_manager::__private::tracing::span!(
+            target: #target,
+            ::context_manager::__private::tracing::Level::#level,
+            #name,
+            #(#fields = ?#fields),*
+        )
+    }))
+}
+
+/// Generate the expression describing the wrapped function as a `context_manager::CallerContext`
+///
+/// The expression does not attach the guard of the first call, see [`caller_context`].
+fn caller_description(in_func: &ItemFn, args: &Args) -> TokenStream2 {
+    let ident = in_func.sig.ident.unraw().to_string();
+    let arg_names = arg_names(in_func);
+    let is_async = in_func.sig.asyncness.is_some();
+    let meta_keys = args.meta.iter().map(|(key, _)| key.unraw().to_string());
+    let meta_values = args.meta.iter().map(|(_, value)| value);
+    quote! {
+        ::context_manager::CallerContext::new(#ident)
+            .with_location(::core::module_path!(), ::core::file!(), ::core::line!())
+            .with_arg_names(&[#(#arg_names),*])
+            .with_async(#is_async)
+            .with_meta(&[#((#meta_keys, #meta_values)),*])
+    }
+}
+
+/// Generate the expression building the `context_manager::CallerContext` of the wrapped function
+fn caller_context(in_func: &ItemFn, args: &Args) -> TokenStream2 {
+    let description = caller_description(in_func, args);
+    quote! {
+        {
+            static FIRST_CALL: ::context_manager::FirstCall = ::context_manager::FirstCall::new();
+            #description.with_first_call(&FIRST_CALL)
+        }
+    }
+}
+
+/// Attribute macros exposed by the crate
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum MacroKind {
+    /// `#[wrap]`, wrapping via `context_manager::SyncWrapContext`
+    Wrap,
+    /// `#[async_wrap]`, wrapping via `context_manager::AsyncWrapContext`
+    AsyncWrap,
+}
+
+impl MacroKind {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Wrap => "wrap",
+            Self::AsyncWrap => "async_wrap",
+        }
+    }
+
+    /// Detect whether `attribute` is one of the macros exposed by the crate
+    ///
+    /// The detection is based on the path of the attribute, so only the unqualified names
+    /// and the paths qualified via `context_manager` or `context_manager_macro` are recognised.
+    fn of_attribute(attribute: &Attribute) -> Option<Self> {
+        let path = attribute.path();
+        let mut segments = path
+            .segments
+            .iter()
+            .map(|segment| segment.ident.to_string());
+        let kind = match segments.next_back()?.as_str() {
+            "wrap" => Self::Wrap,
+            "async_wrap" => Self::AsyncWrap,
+            _ => return None,
+        };
+        match (segments.next_back(), segments.next_back()) {
+            (None, _) if path.leading_colon.is_none() => Some(kind),
+            (Some(krate), None)
+                if krate == "context_manager" || krate == "context_manager_macro" =>
             {
-                <#context_type as ::context_manager::SyncWrapContext<_>>::run_async(#caller_context, async #block).await
+                Some(kind)
             }
+            _ => None,
         }
-        .into()
-    } else {
-        quote! {
+    }
+}
+
+/// Wrap `body` with the context of a layer, as configured by `args`
+fn wrap_layer(
+    kind: MacroKind,
+    args: &Args,
+    in_func: &ItemFn,
+    body: TokenStream2,
+) -> syn::Result<TokenStream2> {
+    let is_async = in_func.sig.asyncness.is_some();
+    let kind = match args.hooks {
+        Some((MacroKind::AsyncWrap, span)) if !is_async => {
+            return Err(Error::new(
+                span,
+                r#"`hooks = "async"` requires an async function"#,
+            ))
+        }
+        Some((hooks, _)) => hooks,
+        None => kind,
+    };
+    let input = layer_input(args, in_func)?;
+    let output = match &args.output {
+        Some(output) if output.kind != kind => {
+            let expected = match kind {
+                MacroKind::Wrap => "SyncWrapContext",
+                MacroKind::AsyncWrap => "AsyncWrapContext",
+            };
+            return Err(Error::new_spanned(
+                &output.trait_path,
+                format!("The hooks of the context are provided by `{expected}`, expected `{expected}<Output>`"),
+            ));
+        }
+        Some(output) => {
+            let ty = &output.ty;
+            quote! { #ty }
+        }
+        None => quote! { _ },
+    };
+    let layer = wrap_layer_call(kind, args, in_func, body, input, &output)?;
+    Ok(match &args.output {
+        // Spanned on the pinned type, so that a mismatch with the return type of the function is reported on it
+        Some(Output { ty, .. }) => quote_spanned! {ty.span()=>
             {
-                <#context_type as ::context_manager::SyncWrapContext<_>>::run_sync(#caller_context, move || #block)
+                ::core::convert::identity::<#ty>(#layer)
             }
+        },
+        None => layer,
+    })
+}
+
+/// Generate the call wrapping `body` with the context of a layer, whose output is `output`
+fn wrap_layer_call(
+    kind: MacroKind,
+    args: &Args,
+    in_func: &ItemFn,
+    mut body: TokenStream2,
+    input: Option<TokenStream2>,
+    output: &TokenStream2,
+) -> syn::Result<TokenStream2> {
+    let caller_context = caller_context(in_func, args);
+    let is_async = in_func.sig.asyncness.is_some();
+    let returns_result = returns_result(in_func);
+    let context_type = layer_context_type(args, returns_result)?;
+
+    if let Some((nanos, span)) = args.timeout {
+        if !is_async || !returns_result {
+            return Err(Error::new(
+                span,
+                "`timeout` requires an async function returning a `Result`, whose error implements `From<context_manager::TimeoutError>`",
+            ));
         }
-        .into()
+        body = quote! {
+            {
+                ::context_manager::timeout(::core::time::Duration::from_nanos(#nanos), async move #body).await
+            }
+        };
+    }
+
+    if let (Some((_, span)), true) = (args.skip, input.is_some() || args.arguments.is_some()) {
+        return Err(Error::new(
+            span,
+            "`skip` cannot be combined with `fields(...)`, `span(...)` or `args`",
+        ));
+    }
+    // Suffix of the `run*` function of the context skipping the hook, if any
+    let suffix = match args.skip {
+        None => "",
+        Some((Skip::Before, _)) => "_after_only",
+        Some((Skip::After, _)) => "_before_only",
     };
+    let (sync_run, sync_run_async, async_run) = (
+        format_ident!("run_sync{}", suffix),
+        format_ident!("run_async{}", suffix),
+        format_ident!("run{}", suffix),
+    );
 
-    in_func.block.stmts = parse_macro_input!(new_body as Block).stmts;
+    if let Some(span) = args.arguments {
+        if input.is_some() {
+            return Err(Error::new(
+                span,
+                "`args` cannot be combined with `fields(...)` or `span(...)`",
+            ));
+        }
+        if kind == MacroKind::AsyncWrap {
+            return Err(Error::new(span, "`args` is supported only by #[wrap]"));
+        }
+        return Ok(wrap_layer_with_args(
+            in_func,
+            args.bind.as_ref(),
+            &caller_context,
+            &context_type,
+            output,
+            &body,
+        ));
+    }
 
-    quote! { #in_func }.into()
+    // The closures are accepted via `context_manager::SyncBlock` and `context_manager::AsyncBlock`, which do not drive
+    // their inference, so they are provided via helpers bounding them as `FnOnce`, with the output of the synchronous
+    // ones provided explicitly (ie. for unsized coercions). The binding of the caller context is emitted with the span
+    // of the provided name, so it resolves as if written at the start of the body
+    let block_output = block_output(in_func, output);
+    let (sync_block, async_block) = match &args.bind {
+        Some(bind) => (
+            quote! { ::context_manager::__private::sync_block_with_caller_context::<#block_output, _>(move |#bind: &::context_manager::CallerContext| #body) },
+            quote! { ::context_manager::__private::async_block_with_caller_context(async move |#bind: &::context_manager::CallerContext| #body) },
+        ),
+        None => (
+            quote! { ::context_manager::__private::sync_block::<#block_output, _>(move || #body) },
+            quote! { async move #body },
+        ),
+    };
+    Ok(match (kind, is_async, input) {
+        (MacroKind::Wrap, false, None) => quote! {
+            {
+                <#context_type as ::context_manager::SyncWrapContext<#output>>::#sync_run(#caller_context, #sync_block)
+            }
+        },
+        (MacroKind::Wrap, false, Some(input)) => quote! {
+            {
+                <#context_type as ::context_manager::SyncWrapContext<#output, _>>::run_sync_with_input(#caller_context, #input, #sync_block)
+            }
+        },
+        (MacroKind::Wrap, true, None) => quote! {
+            {
+                <#context_type as ::context_manager::SyncWrapContext<#output>>::#sync_run_async(#caller_context, #async_block).await
+            }
+        },
+        (MacroKind::Wrap, true, Some(input)) => quote! {
+            {
+                <#context_type as ::context_manager::SyncWrapContext<#output, _>>::run_async_with_input(#caller_context, #input, #async_block).await
+            }
+        },
+        (MacroKind::AsyncWrap, _, None) => quote! {
+            {
+                <#context_type as ::context_manager::AsyncWrapContext<#output>>::#async_run(#caller_context, #async_block).await
+            }
+        },
+        (MacroKind::AsyncWrap, _, Some(input)) => quote! {
+            {
+                <#context_type as ::context_manager::AsyncWrapContext<#output, _>>::run_with_input(#caller_context, #input, #async_block).await
+            }
+        },
+    })
 }
 
-/// Procedural macro that will decorate the incoming async function with the provided context.
+/// Output of the body wrapped by a layer whose output is `output`, or `_` if it cannot be named
 ///
-/// The context is expected to be a type that implements the `context_manager::AsyncWrapContext` trait.
+/// The return type of the function cannot be named if it contains `impl Trait` or if it is `!`.
+fn block_output(in_func: &ItemFn, output: &TokenStream2) -> TokenStream2 {
+    fn contains_impl(tokens: TokenStream2) -> bool {
+        tokens.into_iter().any(|token| match token {
+            TokenTree::Ident(ident) => ident == "impl",
+            TokenTree::Group(group) => contains_impl(group.stream()),
+            TokenTree::Punct(_) | TokenTree::Literal(_) => false,
+        })
+    }
+
+    if output.to_string() != "_" {
+        return output.clone();
+    }
+    match &in_func.sig.output {
+        ReturnType::Default => quote! { () },
+        ReturnType::Type(_, ty) if matches!(**ty, Type::Never(_)) => quote! { _ },
+        ReturnType::Type(_, ty) if contains_impl(ty.to_token_stream()) => quote! { _ },
+        ReturnType::Type(_, ty) => ty.to_token_stream(),
+    }
+}
+
+/// Wrap `body` with the context of a layer requesting the arguments of the function (`args` argument)
 ///
-/// More documentation available [here](https://docs.rs/context_manager/latest/context_manager/attr.async_wrap.html)
-#[proc_macro_attribute]
-pub fn async_wrap(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let mut in_func = parse_macro_input!(item as ItemFn);
+/// The identifiers bound by the arguments are moved into a tuple provided to the context, which moves
+/// it back into the body once the before hook is executed, re-binding the identifiers as in the signature.
+fn wrap_layer_with_args(
+    in_func: &ItemFn,
+    bind: Option<&Ident>,
+    caller_context: &TokenStream2,
+    context_type: &TokenStream2,
+    output: &TokenStream2,
+    body: &TokenStream2,
+) -> TokenStream2 {
+    let bindings = arg_bindings(in_func);
+    let idents = bindings.iter().map(|binding| &binding.ident);
+    let patterns = bindings.iter().map(|binding| {
+        let PatIdent {
+            mutability, ident, ..
+        } = binding;
+        quote! { #mutability #ident }
+    });
+    let arguments = quote! { (#(#idents,)*) };
+    let pattern = quote! { (#(#patterns,)*) };
+    // The caller context provided to the hooks is moved into the body alongside the arguments
+    let bind = bind.map_or_else(|| quote! { _ }, |bind| quote! { #bind });
+    if in_func.sig.asyncness.is_some() {
+        quote! {
+            {
+                <#context_type as ::context_manager::WrapContextWithArgs<_, #output>>::run_async_with_args(#caller_context, #arguments, async move |#pattern, #bind: &::context_manager::CallerContext| #body).await
+            }
+        }
+    } else {
+        quote! {
+            {
+                <#context_type as ::context_manager::WrapContextWithArgs<_, #output>>::run_sync_with_args(#caller_context, #arguments, move |#pattern, #bind: &::context_manager::CallerContext| #body)
+            }
+        }
+    }
+}
+
+/// Expand the `kind` macro, invoked with `attr` arguments, on the decorated `item`
+///
+/// Macros of the crate stacked below the expanded one are expanded at the same time,
+/// so that the top attribute is the outermost context (as in source order). Without
+/// doing so the bottom attribute, being expanded last, would be the outermost context.
+fn expand(kind: MacroKind, attr: TokenStream, item: TokenStream) -> TokenStream {
+    let mut in_func = match parse_item_fn(item, kind.name()) {
+        Ok(in_func) => in_func,
+        Err(error) => return error,
+    };
 
     if in_func.sig.constness.is_some() {
-        // This is not really possible, because "functions cannot be both `const` and `async`"
-        // but let's keep this check for future-proofing
         // Insert compile error at the begin of the function block.
         // Doing so allows a clear compile failure, while allowing type inference to still work.
-        in_func.block.stmts.insert(
-            0,
-            parse_quote!(::std::compile_error!("#[wrap] cannot operate on const functions.");),
-        );
+        let message = format!("#[{}] cannot operate on const functions.", kind.name());
+        in_func
+            .block
+            .stmts
+            .insert(0, parse_quote!(::std::compile_error!(#message);));
         return quote! { #in_func }.into();
     }
 
-    let args: Args = parse_macro_input!(attr);
-
-    let context_type = &args.context_type;
-    let block = &in_func.block;
-    if in_func.sig.asyncness.is_some() {
-        let ident = in_func.sig.ident.to_string();
-        let caller_context = quote! { ::context_manager::CallerContext::new(#ident) };
+    if let Some(abi) = non_rust_abi(&in_func.sig) {
+        // Emit the original function as well, so that its usages do not lead to further errors
+        let error = Error::new_spanned(
+            abi,
+            format!("#[{}] cannot operate on functions with a non-Rust ABI. Please consider decorating a Rust function and calling it from an `extern` shim instead.", kind.name()),
+        )
+        .to_compile_error();
+        return quote! { #error #in_func }.into();
+    }
 
-        let new_body: TokenStream = quote! {
-            {
-                <#context_type as ::context_manager::AsyncWrapContext<_>>::run(#caller_context, async #block).await
+    let mut layers = match syn::parse::<Args>(attr) {
+        Ok(args) => vec![(kind, args)],
+        Err(error) => {
+            // Emit the original function as well, so that its usages do not lead to further errors
+            let error = error.to_compile_error();
+            return quote! { #error #in_func }.into();
+        }
+    };
+    let mut stacked = Vec::new();
+    let mut attrs = Vec::with_capacity(in_func.attrs.len());
+    for attribute in std::mem::take(&mut in_func.attrs) {
+        match MacroKind::of_attribute(&attribute) {
+            Some(kind) => stacked.push((kind, attribute)),
+            None => attrs.push(attribute),
+        }
+    }
+    in_func.attrs = attrs;
+    for (kind, attribute) in stacked {
+        match attribute.parse_args::<Args>() {
+            Ok(args) => layers.push((kind, args)),
+            Err(error) => {
+                // Emit the original function as well (without the stacked macros, which would report the
+                // same error again), so that its usages do not lead to further errors
+                let error = error.to_compile_error();
+                return quote! { #error #in_func }.into();
             }
         }
-        .into();
-        in_func.block.stmts = parse_macro_input!(new_body as Block).stmts;
-    } else {
+    }
+
+    if in_func.sig.asyncness.is_none()
+        && layers.iter().any(|(kind, _)| *kind == MacroKind::AsyncWrap)
+    {
         // Insert compile error at the begin of the function block.
         // Doing so allows a clear compile failure, while allowing type inference to still work.
         in_func.block.stmts.insert(
@@ -135,7 +954,284 @@ pub fn async_wrap(attr: TokenStream, item: TokenStream) -> TokenStream {
                 "#[async_wrap] cannot operate on sync functions. Please consider using a #[wrap] macro or converting/wrapping the function to be async."
             )})
         );
+        return quote! { #in_func }.into();
+    }
+
+    // With an alias the function is emitted bare, and the alias is the wrapped copy of it
+    let mut aliases = layers.iter().filter_map(|(_, args)| args.alias.as_ref());
+    let alias = aliases.next().cloned();
+    if let Some(duplicated) = aliases.next() {
+        let error =
+            Error::new_spanned(duplicated, "`alias` can be provided only once").to_compile_error();
+        return quote! { #error #in_func }.into();
+    }
+    let bare = alias.map(|alias| {
+        let mut bare = in_func.clone();
+        // The body of the bare function can refer to the caller context bound via `bind = "..."` as well
+        for (_, args) in layers.iter().rev() {
+            if let Some(bind) = &args.bind {
+                let description = caller_description(&bare, args);
+                bare.block.stmts.insert(
+                    0,
+                    parse_quote! { let #bind: &::context_manager::CallerContext = &#description; },
+                );
+            }
+        }
+        in_func.sig.ident = alias;
+        bare
+    });
+
+    let mut body = async_body(&in_func);
+    for (kind, args) in layers.iter().rev() {
+        body = match wrap_layer(*kind, args, &in_func, body) {
+            Ok(body) => body,
+            Err(error) => {
+                let error = error.to_compile_error();
+                return quote! { #error #bare #in_func }.into();
+            }
+        };
+    }
+
+    let new_body: TokenStream = body.into();
+    in_func.block.stmts = parse_macro_input!(new_body as Block).stmts;
+
+    if layers.iter().any(|(_, args)| args.arguments.is_some()) {
+        // The arguments are moved into the context and re-bound in the body, so their `mut` is not used in the signature
+        for input in &mut in_func.sig.inputs {
+            if let FnArg::Typed(pat_type) = input {
+                pat_type.attrs.push(parse_quote!(#[allow(unused_mut)]));
+            }
+        }
+    }
+
+    quote! { #bare #in_func }.into()
+}
+
+/// Arguments of the `wrap_fn!` macro: the context type and the closure to wrap
+struct WrapFnArgs {
+    context_type: Type,
+    closure: ExprClosure,
+}
+
+impl Parse for WrapFnArgs {
+    fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
+        let context_type = input.parse::<Type>()?;
+        input.parse::<Token![,]>()?;
+        let closure = input.parse::<ExprClosure>()?;
+        // Allow a trailing comma, as for function calls
+        if input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+        }
+        Ok(Self {
+            context_type,
+            closure,
+        })
+    }
+}
+
+/// Expand the `wrap_fn!` macro, wrapping the body of the closure with the provided context
+///
+/// Async closures (`async |..| ..`) and closures whose body is an async block (`|..| async { .. }`)
+/// are wrapped via `run_async`, while any other closure is wrapped via `run_sync`.
+fn expand_wrap_fn(args: WrapFnArgs) -> TokenStream2 {
+    let WrapFnArgs {
+        context_type,
+        mut closure,
+    } = args;
+    let body = &closure.body;
+    let is_async = closure.asyncness.is_some() || matches!(body.as_ref(), Expr::Async(_));
+    let caller_context = quote! {
+        {
+            static FIRST_CALL: ::context_manager::FirstCall = ::context_manager::FirstCall::new();
+            ::context_manager::CallerContext::new("{closure}")
+                .with_location(::core::module_path!(), ::core::file!(), ::core::line!())
+                .with_async(#is_async)
+                .with_first_call(&FIRST_CALL)
+        }
+    };
+
+    let new_body: Expr = if closure.asyncness.is_some() {
+        parse_quote! {
+            {
+                <#context_type as ::context_manager::SyncWrapContext<_>>::run_async(#caller_context, async { #body }).await
+            }
+        }
+    } else if is_async {
+        parse_quote! {
+            {
+                <#context_type as ::context_manager::SyncWrapContext<_>>::run_async(#caller_context, #body)
+            }
+        }
+    } else {
+        parse_quote! {
+            {
+                <#context_type as ::context_manager::SyncWrapContext<_>>::run_sync(#caller_context, || #body)
+            }
+        }
+    };
+    *closure.body = new_body;
+    quote! { #closure }
+}
+
+/// Expand the `default_new` macro, adding to the `SyncWrapContext` or `AsyncWrapContext` implementation
+/// a `new` function delegating to `Default::default`
+fn expand_default_new(attr: TokenStream, item: TokenStream) -> syn::Result<TokenStream2> {
+    let attr = TokenStream2::from(attr);
+    if !attr.is_empty() {
+        return Err(Error::new_spanned(
+            attr,
+            "#[default_new] does not accept arguments",
+        ));
+    }
+
+    let mut item_impl: ItemImpl = syn::parse(item)?;
+    let is_async = match item_impl
+        .trait_
+        .as_ref()
+        .and_then(|(_, path, _)| path.segments.last())
+    {
+        Some(segment) if segment.ident == "SyncWrapContext" => false,
+        Some(segment) if segment.ident == "AsyncWrapContext" => true,
+        _ => {
+            return Err(Error::new_spanned(
+                &item_impl.self_ty,
+                "#[default_new] can only operate on implementations of `SyncWrapContext` or `AsyncWrapContext`",
+            ))
+        }
+    };
+
+    if let Some(new) = item_impl.items.iter().find_map(|item| match item {
+        ImplItem::Fn(impl_fn) if impl_fn.sig.ident == "new" => Some(&impl_fn.sig),
+        _ => None,
+    }) {
+        return Err(Error::new_spanned(
+            new,
+            "#[default_new] generates `new`, please remove the existing implementation",
+        ));
+    }
+
+    let asyncness = is_async.then(|| quote! { async });
+    item_impl.items.insert(
+        0,
+        parse_quote! {
+            #asyncness fn new() -> Self {
+                ::core::default::Default::default()
+            }
+        },
+    );
+    Ok(quote! { #item_impl })
+}
+
+/// ABI of the function, if it is not the Rust one (which the macros cannot operate on)
+fn non_rust_abi(sig: &Signature) -> Option<&Abi> {
+    sig.abi
+        .as_ref()
+        .filter(|abi| abi.name.as_ref().is_none_or(|name| name.value() != "Rust"))
+}
+
+/// Expand `#[wrap_module]`, decorating with `#[wrap]` all the functions of the module not marked with `#[wrap_skip]`
+fn expand_wrap_module(attr: &TokenStream, item: TokenStream) -> syn::Result<TokenStream2> {
+    let args: Args = syn::parse(attr.clone())?;
+    if let Some(alias) = args.alias {
+        return Err(Error::new_spanned(
+            alias,
+            "`alias` cannot be used with #[wrap_module], as the functions would share the same alias",
+        ));
     }
 
-    quote! { #in_func }.into()
+    let mut item_mod: ItemMod = syn::parse(item)?;
+    let Some((_, items)) = &mut item_mod.content else {
+        return Err(Error::new_spanned(
+            &item_mod,
+            "#[wrap_module] can only operate on inline modules (`mod name { ... }`)",
+        ));
+    };
+    for item in items.iter_mut() {
+        let Item::Fn(item_fn) = item else {
+            continue;
+        };
+        // The functions that `#[wrap]` cannot operate on are left bare, as the ones marked with `#[wrap_skip]`
+        if item_fn.sig.constness.is_some() || non_rust_abi(&item_fn.sig).is_some() {
+            continue;
+        }
+        let attrs_len = item_fn.attrs.len();
+        item_fn
+            .attrs
+            .retain(|attribute| !attribute.path().is_ident("wrap_skip"));
+        if item_fn.attrs.len() == attrs_len {
+            let wrapped = expand(
+                MacroKind::Wrap,
+                attr.clone(),
+                item_fn.to_token_stream().into(),
+            );
+            *item = Item::Verbatim(wrapped.into());
+        }
+    }
+    Ok(quote! { #item_mod })
+}
+
+/// Procedural macro that will decorate the incoming function with the provided context.
+///
+/// The context is expected to be a type that implements the `context_manager::SyncWrapContext` trait.
+///
+/// More documentation available [here](https://docs.rs/context_manager/latest/context_manager/attr.wrap.html)
+#[proc_macro_attribute]
+pub fn wrap(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand(MacroKind::Wrap, attr, item)
+}
+
+/// Procedural macro that will decorate the incoming async function with the provided context.
+///
+/// The context is expected to be a type that implements the `context_manager::AsyncWrapContext` trait.
+///
+/// More documentation available [here](https://docs.rs/context_manager/latest/context_manager/attr.async_wrap.html)
+#[proc_macro_attribute]
+pub fn async_wrap(attr: TokenStream, item: TokenStream) -> TokenStream {
+    expand(MacroKind::AsyncWrap, attr, item)
+}
+
+/// Procedural macro that will implement `new` of a context via its `Default` implementation.
+///
+/// The macro is expected to decorate an implementation of `context_manager::SyncWrapContext` or `context_manager::AsyncWrapContext`.
+///
+/// More documentation available [here](https://docs.rs/context_manager/latest/context_manager/attr.default_new.html)
+#[proc_macro_attribute]
+pub fn default_new(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let original = TokenStream2::from(item.clone());
+    match expand_default_new(attr, item) {
+        Ok(item_impl) => item_impl.into(),
+        Err(error) => {
+            // Emit the original implementation as well, so that its usages do not lead to further errors
+            let error = error.to_compile_error();
+            quote! { #error #original }.into()
+        }
+    }
+}
+
+/// Procedural macro that will decorate all the functions of the incoming module with the provided context.
+///
+/// The functions marked with `#[wrap_skip]` are left bare.
+///
+/// More documentation available [here](https://docs.rs/context_manager/latest/context_manager/attr.wrap_module.html)
+#[proc_macro_attribute]
+pub fn wrap_module(attr: TokenStream, item: TokenStream) -> TokenStream {
+    let original = TokenStream2::from(item.clone());
+    match expand_wrap_module(&attr, item) {
+        Ok(item_mod) => item_mod.into(),
+        Err(error) => {
+            // Emit the original module as well, so that its usages do not lead to further errors
+            let error = error.to_compile_error();
+            quote! { #error #original }.into()
+        }
+    }
+}
+
+/// Function-like macro that will wrap the incoming closure with the provided context.
+///
+/// The context is expected to be a type that implements the `context_manager::SyncWrapContext` trait.
+///
+/// More documentation available [here](https://docs.rs/context_manager/latest/context_manager/macro.wrap_fn.html)
+#[proc_macro]
+pub fn wrap_fn(input: TokenStream) -> TokenStream {
+    expand_wrap_fn(parse_macro_input!(input as WrapFnArgs)).into()
 }